@@ -0,0 +1,177 @@
+//! Archiving notes without deleting them.
+//!
+//! `nb.archive_note` moves a note into the notebook's `archive/` folder,
+//! keeping its folder path (`docs/plan.md` becomes `archive/docs/plan.md`),
+//! and tags it `#archived`. `nb.list` and `nb.search` then leave archived
+//! items out unless `include_archived` is set or the call targets the
+//! archive folder itself.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::nb::{NbClient, NbError};
+use crate::nb_index::{self, ItemResolver};
+use crate::notes;
+
+/// Folder, relative to the notebook root, holding archived notes.
+pub const FOLDER: &str = "archive";
+
+/// Tag added to archived notes.
+pub const TAG: &str = "archived";
+
+/// Result of `nb.archive_note`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Archived {
+    /// Note path before archiving, relative to the notebook root.
+    pub from: String,
+    /// Note path in the archive.
+    pub to: String,
+    /// Whether `#archived` was added (binary and already tagged notes are
+    /// not edited).
+    pub tagged: bool,
+    /// nb's confirmation of the move.
+    pub message: String,
+}
+
+/// Tags note `id` as archived and moves it under [`FOLDER`].
+pub async fn archive_note(
+    nb: &NbClient,
+    id: &str,
+    notebook: Option<&str>,
+) -> Result<Archived, NbError> {
+    let root = nb.notebook_path(notebook).await?;
+    let path = nb.show_path(id, notebook).await?;
+    let relative = path
+        .strip_prefix(&root)
+        .map_err(|_| {
+            NbError::CommandFailed(format!(
+                "note {id} is outside its notebook at {}",
+                root.display()
+            ))
+        })?
+        .to_path_buf();
+    if relative.starts_with(FOLDER) {
+        return Err(NbError::CommandFailed(format!(
+            "note {id} is already archived"
+        )));
+    }
+
+    let mut tagged = false;
+    if let Ok(content) = std::fs::read_to_string(&path) {
+        let updated = notes::add_hashtags(&content, &[TAG.to_string()]);
+        if updated != content {
+            nb.edit(id, &updated, notebook).await?;
+            tagged = true;
+        }
+    }
+
+    let folder = match relative
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        Some(parent) => Path::new(FOLDER).join(parent),
+        None => PathBuf::from(FOLDER),
+    };
+    let folder = folder.to_string_lossy().replace('\\', "/");
+    if !root.join(&folder).is_dir() {
+        nb.mkdir(&folder, notebook).await?;
+    }
+    let message = nb.move_note(id, &format!("{folder}/"), notebook).await?;
+    let file_name = relative.file_name().unwrap_or_default().to_string_lossy();
+    Ok(Archived {
+        from: relative.to_string_lossy().replace('\\', "/"),
+        to: format!("{folder}/{file_name}"),
+        tagged,
+        message: message.trim_end().to_string(),
+    })
+}
+
+/// Returns whether a listing of `folder` in the notebook at `root` hides
+/// archived items.
+pub fn hides(root: &Path, folder: Option<&str>, include_archived: bool) -> bool {
+    let in_archive =
+        folder.is_some_and(|folder| Path::new(folder.trim_matches('/')).starts_with(FOLDER));
+    !include_archived && !in_archive && root.join(FOLDER).is_dir()
+}
+
+/// Removes archived items from nb `list` or `search` output, along with the
+/// excerpt lines printed beneath them.
+pub fn exclude(output: &str, resolver: &mut ItemResolver, root: &Path) -> String {
+    let archive = root.join(FOLDER);
+    let mut kept = String::new();
+    let mut keeping = true;
+    for line in output.lines() {
+        if let Some((id, _)) = nb_index::split_item(line) {
+            let id = id.split_once(':').map_or(id, |(_, rest)| rest);
+            keeping = !Path::new(id).starts_with(FOLDER)
+                && !resolver
+                    .resolve(id)
+                    .is_some_and(|path| path.starts_with(&archive));
+        }
+        if keeping {
+            kept.push_str(line);
+            kept.push('\n');
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{archive_note, exclude, hides};
+    use crate::nb_index::ItemResolver;
+    use crate::{FakeExecutor, NbClient};
+
+    #[tokio::test]
+    async fn archive_note_tags_and_moves_keeping_folder_path() {
+        let root = std::env::temp_dir().join(format!("nb-mcp-archive-note-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        let note = root.join("docs/plan.md");
+        std::fs::write(&note, "# Plan\n\nShip it.\n").unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", root.to_str().unwrap())
+                .respond(&["show", "work:docs/1", "--path"], note.to_str().unwrap())
+                .respond(&["edit"], "Updated: [docs/1] docs/plan.md")
+                .respond(&["add", "folder"], "Added: [archive/1] archive/docs")
+                .respond(&["move"], "Moved to: [archive/docs/1] archive/docs/plan.md"),
+        );
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), false);
+        let archived = archive_note(&nb, "docs/1", None).await.unwrap();
+        assert_eq!(archived.from, "docs/plan.md");
+        assert_eq!(archived.to, "archive/docs/plan.md");
+        assert!(archived.tagged);
+        let calls = fake.calls();
+        let edit = calls.iter().find(|args| args[0] == "edit").unwrap();
+        assert_eq!(edit[3], "# Plan\n\n#archived\n\nShip it.\n");
+        assert!(
+            calls
+                .iter()
+                .any(|args| args == &["add", "folder", "work:archive/docs/"])
+        );
+        assert_eq!(
+            calls.last().unwrap(),
+            &["move", "work:docs/1", "archive/docs/", "--force"]
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn exclude_drops_archive_items_and_their_excerpts() {
+        let root = std::env::temp_dir().join(format!("nb-mcp-archive-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("archive")).unwrap();
+        std::fs::write(root.join(".index"), "plan.md\narchive\n").unwrap();
+        let output = "[1] Plan\n[2] 📂 archive\n[archive/docs/1] Old plan\n1: old line\n";
+        let mut resolver = ItemResolver::new(&root, None);
+        assert_eq!(exclude(output, &mut resolver, &root), "[1] Plan\n");
+
+        assert!(hides(&root, None, false));
+        assert!(hides(&root, Some("docs"), false));
+        assert!(!hides(&root, Some("archive/docs/"), false));
+        assert!(!hides(&root, None, true));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
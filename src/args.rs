@@ -0,0 +1,820 @@
+//! Argument structs for the `nb` tool's subcommands.
+//!
+//! Each struct is deserialized from the `args` object of an `nb` tool call
+//! and doubles as the JSON schema returned by the `help` tool.
+
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct StatusArgs {
+    /// Notebook to check status for (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct AddArgs {
+    /// Title for the note.
+    pub title: Option<String>,
+    /// Content of the note. Markdown is supported. Required unless `template`
+    /// is given, in which case it is appended to the filled-in template.
+    pub content: Option<String>,
+    /// Template to fill in, by name, from the notebook's `.templates/` folder
+    /// (e.g., "bug-report").
+    pub template: Option<String>,
+    /// Values for the template's variables, by name.
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    /// Snippet to append to the content, by name (see `snippet`).
+    pub insert_snippet: Option<String>,
+    /// Tags to apply (without # prefix).
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Folder to create the note in (overrides the server's default folder).
+    pub folder: Option<String>,
+    /// Skip the server's default folder and default tags for this note.
+    #[serde(default)]
+    pub no_defaults: bool,
+    /// Notebook to add to (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ShowArgs {
+    /// Note ID, filename, or title to show.
+    pub id: String,
+    /// Byte range to return, for reading notes larger than the output limit.
+    pub range: Option<ShowRange>,
+    /// Return binary files as base64 instead of metadata only.
+    #[serde(default)]
+    pub raw_base64: bool,
+    /// Notebook to read from (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ShowManyArgs {
+    /// Note IDs, filenames, or titles to show.
+    #[serde(default)]
+    pub ids: Vec<String>,
+    /// Files to show instead of `ids`, as a folder path and filename pattern
+    /// with `*` and `?` wildcards (e.g., "clients/*.md").
+    pub glob: Option<String>,
+    /// Combined size cap for all returned content, in bytes (capped at the
+    /// output limit).
+    pub max_bytes: Option<usize>,
+    /// Notebook to read from (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ShowFolderArgs {
+    /// Folder to read (reads the notebook root if not specified).
+    pub folder: Option<String>,
+    /// Also read the notes of subfolders.
+    #[serde(default)]
+    pub recursive: bool,
+    /// Order to read notes in: "name" (default), "created", or "modified".
+    pub sort: Option<String>,
+    /// "asc" or "desc" (default: ascending for names, newest first for
+    /// dates).
+    pub order: Option<String>,
+    /// Return one Markdown text with a `<!-- path -->` comment before each
+    /// note instead of a JSON list.
+    #[serde(default)]
+    pub concatenate: bool,
+    /// Combined size cap for all returned content, in bytes (capped at the
+    /// output limit).
+    pub max_bytes: Option<usize>,
+    /// Include notes in archive/ when reading the notebook root.
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Notebook to read from (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ShowRange {
+    /// Byte offset to start reading from (e.g., `next_offset` from a
+    /// truncation marker).
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of bytes to return (capped at the output limit).
+    pub length: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct EditArgs {
+    /// Note ID, filename, or title to edit.
+    pub id: String,
+    /// New content for the note (replaces existing content).
+    pub content: String,
+    /// Snippet to append to the new content, by name (see `snippet`).
+    pub insert_snippet: Option<String>,
+    /// Hash returned by `show`; the edit is refused if the note changed
+    /// since.
+    pub expected_hash: Option<String>,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct DeleteArgs {
+    /// Note ID, filename, or title to delete.
+    pub id: String,
+    /// Must be true to confirm deletion.
+    #[serde(default)]
+    pub confirm: bool,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct MoveArgs {
+    /// Note ID, filename, or title to move/rename.
+    pub id: String,
+    /// Destination path or new name. Can be a folder path (ending with /) or a new filename.
+    /// Examples: "new-folder/" (move to folder), "new-name.md" (rename), "folder/new-name.md" (move and rename).
+    pub destination: String,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct CopyToArgs {
+    /// Note ID, filename, or title to copy.
+    pub id: String,
+    /// Notebook to copy the note into.
+    pub target_notebook: String,
+    /// Folder in the target notebook (copies to root if not specified).
+    pub folder: Option<String>,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ArchiveNoteArgs {
+    /// Note ID, filename, or title to archive.
+    pub id: String,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct LinkArgs {
+    /// Note ID, filename, or title to add the link to.
+    pub from_id: String,
+    /// Note ID, filename, or title to link to.
+    pub to_id: String,
+    /// Link text (defaults to the target's title for Markdown links).
+    pub anchor_text: Option<String>,
+    /// Notebook containing both notes (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ListArgs {
+    /// Folder to list (lists root if not specified).
+    pub folder: Option<String>,
+    /// Filter by tags (without # prefix).
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Maximum number of items to return.
+    pub limit: Option<u32>,
+    /// Sort key: "created", "modified", "title", or "id" (nb's order if not
+    /// specified).
+    pub sort: Option<String>,
+    /// Sort direction: "asc" or "desc" (default: desc for dates, asc
+    /// otherwise).
+    pub order: Option<String>,
+    /// Boolean filter, e.g. `tag:client AND (tag:urgent OR title:sync)`.
+    pub filter: Option<String>,
+    /// Return only the number of matching items, by folder and tag.
+    #[serde(default)]
+    pub count_only: bool,
+    /// Include archived notes (see `archive_note`).
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Also save the results as a new note with this title; `{date}` is
+    /// replaced by today's date.
+    pub output_note: Option<String>,
+    /// Notebook to list from (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct SearchArgs {
+    /// Search query, matched as literal text unless `mode` is "regex".
+    pub query: String,
+    /// How to match the query: "literal" (default) or "regex" (extended
+    /// regular expression).
+    pub mode: Option<String>,
+    /// Filter by tags (without # prefix).
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Folder to search within (searches all if not specified).
+    pub folder: Option<String>,
+    /// Boolean filter applied to matching notes, e.g. `tag:a AND NOT tag:b`.
+    pub filter: Option<String>,
+    /// Return only the number of matching notes, by folder and tag.
+    #[serde(default)]
+    pub count_only: bool,
+    /// Include archived notes (see `archive_note`).
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Also save the results as a new note with this title; `{date}` is
+    /// replaced by today's date.
+    pub output_note: Option<String>,
+    /// Notebook to search in (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct TodoArgs {
+    /// Description of the todo item.
+    pub description: String,
+    /// Tags to apply (without # prefix).
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Folder to create the todo in (overrides the server's default folder).
+    pub folder: Option<String>,
+    /// Due date, e.g. "2024-05-01" or "2024-05-01 14:00" (see `tasks_ical`).
+    pub due: Option<String>,
+    /// Skip the server's default folder and default tags for this todo.
+    #[serde(default)]
+    pub no_defaults: bool,
+    /// Notebook to add todo to (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct TaskIdArgs {
+    /// Todo ID to mark as done/undone.
+    pub id: String,
+    /// Notebook containing the todo (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct TasksArgs {
+    /// Folder to list todos from (lists all if not specified).
+    pub folder: Option<String>,
+    /// Notebook to list todos from (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct TaskMetricsArgs {
+    /// Number of weeks to report, ending with the current one (default 8).
+    pub weeks: Option<u32>,
+    /// Notebook to report on (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct TasksIcalArgs {
+    /// Folder to take todos from (takes all if not specified).
+    pub folder: Option<String>,
+    /// File to write the feed to, relative to the notebook (the feed is
+    /// returned if not specified).
+    pub path: Option<String>,
+    /// Notebook to take todos from (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct TodotxtImportArgs {
+    /// todo.txt list to import, one task per line.
+    pub content: Option<String>,
+    /// todo.txt file to import, relative to the notebook (used when `content`
+    /// is not given).
+    pub path: Option<String>,
+    /// Folder to create the todos in (uses notebook root if not specified).
+    pub folder: Option<String>,
+    /// Notebook to import into (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct TodotxtExportArgs {
+    /// Folder to take todos from (takes all if not specified).
+    pub folder: Option<String>,
+    /// Include completed todos as `x` lines.
+    #[serde(default)]
+    pub include_done: bool,
+    /// File to write the list to, relative to the notebook (the list is
+    /// returned if not specified).
+    pub path: Option<String>,
+    /// Notebook to take todos from (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct BoardArgs {
+    /// Folder to take todos from (takes all if not specified).
+    pub folder: Option<String>,
+    /// Include todos in the archive folder.
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Notebook to take todos from (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct BoardMoveArgs {
+    /// Todo ID, filename, or path.
+    pub id: String,
+    /// Column (status tag, without #) to move the todo into.
+    pub column: String,
+    /// Notebook containing the todo (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ResolveArgs {
+    /// Title, partial filename, or URL to look up.
+    pub query: String,
+    /// What the query is: "title", "filename", or "url" (all three are
+    /// tried if not specified).
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    /// Folder to look in (the notebook root if not specified).
+    pub folder: Option<String>,
+    /// Notebook to look in (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct PinnedArgs {
+    /// Folder to list pinned items from (lists the notebook root if not
+    /// specified).
+    pub folder: Option<String>,
+    /// Notebook to list pinned items from (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct BookmarkArgs {
+    /// URL to bookmark.
+    pub url: String,
+    /// Title for the bookmark.
+    pub title: Option<String>,
+    /// Tags to apply (without # prefix).
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Comment or description.
+    pub comment: Option<String>,
+    /// Page text the client already has (Markdown or plain text). It is
+    /// stored as the bookmark's cached content and the URL is not fetched.
+    pub content: Option<String>,
+    /// Fetch the page on the server and store its title, description,
+    /// canonical URL, and preview image (`og:image`) in the bookmark's
+    /// frontmatter. Needs curl.
+    #[serde(default)]
+    pub enrich: bool,
+    /// Folder to create the bookmark in.
+    pub folder: Option<String>,
+    /// Notebook to add bookmark to (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct FoldersArgs {
+    /// Parent folder to list (lists root if not specified).
+    pub parent: Option<String>,
+    /// Notebook to list folders from (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct MkdirArgs {
+    /// Path of folder to create.
+    pub path: String,
+    /// Notebook to create folder in (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ImportArgs {
+    /// File path or URL to import.
+    pub source: String,
+    /// Folder to import into (imports to root if not specified).
+    pub folder: Option<String>,
+    /// Filename to use in notebook (uses original name if not specified).
+    pub filename: Option<String>,
+    /// Convert HTML content to Markdown.
+    #[serde(default)]
+    pub convert: bool,
+    /// Source format: omit to import the file as-is, "enex" for an Evernote
+    /// export, or "notion" for a Notion export zip. Exports are converted to
+    /// Markdown files in `folder` and committed together. With pandoc
+    /// installed, "docx", "odt", "org", and "rst" documents are converted to
+    /// a Markdown note.
+    pub format: Option<String>,
+    /// Notebook to import into (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ExportArgs {
+    /// Note ID, filename, or title to export.
+    pub id: String,
+    /// Target format (needs pandoc): "docx", "odt", "pdf", "org", or "rst".
+    pub format: String,
+    /// File to write, relative to the notebook. Required for docx, odt, and
+    /// pdf; org and rst are returned as text without it.
+    pub path: Option<String>,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct DumpArgs {
+    /// Folder to dump (dumps the whole notebook if not specified).
+    pub folder: Option<String>,
+    /// Notebook to dump (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct LoadArgs {
+    /// Dump document produced by `nb.dump` (object or JSON-encoded string).
+    #[serde(default)]
+    pub dump: serde_json::Value,
+    /// Folder to load notes into (preserves dumped paths if not specified).
+    pub folder: Option<String>,
+    /// Notebook to load into (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct PushArgs {
+    /// Remote to push to (uses the branch's upstream remote, or origin).
+    pub remote: Option<String>,
+    /// Branch to push (uses the current branch if not specified).
+    pub branch: Option<String>,
+    /// Notebook to push (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct PullArgs {
+    /// Remote to pull from (uses the branch's upstream remote, or origin).
+    pub remote: Option<String>,
+    /// Branch to pull (uses the current branch if not specified).
+    pub branch: Option<String>,
+    /// Only fast-forward; report divergence instead of merging.
+    #[serde(default)]
+    pub ff_only: bool,
+    /// Notebook to pull into (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct BlameArgs {
+    /// Note ID, filename, or title to trace.
+    pub id: String,
+    /// Group lines into paragraphs (separated by blank lines), each reported
+    /// with its most recent change.
+    #[serde(default)]
+    pub paragraphs: bool,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ChangesArgs {
+    /// Commit hash (e.g., `head` from an earlier call) or timestamp (e.g.,
+    /// "2025-06-01T09:00:00" or "2 days ago") to report changes since.
+    pub since: String,
+    /// Notebook to inspect (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct GitStatusArgs {
+    /// Fetch from the remote first so ahead/behind counts are current.
+    #[serde(default)]
+    pub fetch: bool,
+    /// Notebook to inspect (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct SummarizeArgs {
+    /// Note ID, filename, or title to summarize.
+    pub id: String,
+    /// Approximate maximum length of the summary in words (default: 60).
+    pub max_words: Option<u32>,
+    /// Write the summary into the note's `summary` frontmatter field.
+    #[serde(default)]
+    pub write: bool,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct SuggestTagsArgs {
+    /// Note ID, filename, or title to suggest tags for.
+    pub id: String,
+    /// Maximum number of tags to suggest (default: 5).
+    pub max_tags: Option<u32>,
+    /// Add the suggested tags to the note as hashtags.
+    #[serde(default)]
+    pub apply: bool,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct TagGraphArgs {
+    /// Folder to scan (scans the whole notebook if not specified).
+    pub folder: Option<String>,
+    /// Minimum number of shared notes for a tag pair to be listed
+    /// (default: 1).
+    pub min_count: Option<u32>,
+    /// Maximum number of tag pairs to return (default: 50).
+    pub limit: Option<u32>,
+    /// Include archived notes (see `archive_note`).
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Notebook to scan (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct InboxReportArgs {
+    /// Maximum number of notes listed per category (default: 100).
+    pub limit: Option<u32>,
+    /// Include archived notes (see `archive_note`).
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Also save the results as a new note with this title; `{date}` is
+    /// replaced by today's date.
+    pub output_note: Option<String>,
+    /// Notebook to scan (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct StaleArgs {
+    /// Also list notes not modified for more than this many days (by default
+    /// only notes past their `expires` or `review_by` date are listed).
+    pub days: Option<u32>,
+    /// Folder to scan (scans the whole notebook if not specified).
+    pub folder: Option<String>,
+    /// Maximum number of notes listed (default: 100).
+    pub limit: Option<u32>,
+    /// Include archived notes (see `archive_note`).
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Notebook to scan (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct DedupeArgs {
+    /// Folder to scan (scans the whole notebook if not specified).
+    pub folder: Option<String>,
+    /// Minimum similarity (0 to 1) for near-duplicates (default: 0.8).
+    pub threshold: Option<f64>,
+    /// Merge each cluster into its first note and delete the duplicates.
+    #[serde(default)]
+    pub merge: bool,
+    /// Must be true to confirm a merge.
+    #[serde(default)]
+    pub confirm: bool,
+    /// Notebook to scan (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct MergeArgs {
+    /// Note ID, filename, or title that receives the merged content.
+    pub target: String,
+    /// Notes to append to the target, in order.
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Delete the source notes after merging.
+    #[serde(default)]
+    pub delete_sources: bool,
+    /// Must be true to confirm deleting the sources.
+    #[serde(default)]
+    pub confirm: bool,
+    /// Notebook containing the notes (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct SplitArgs {
+    /// Note ID, filename, or title to split.
+    pub id: String,
+    /// Heading level whose sections become notes (default: 2).
+    pub level: Option<usize>,
+    /// Folder for the new notes, relative to the notebook root (default: a
+    /// folder named after the note, beside it).
+    pub folder: Option<String>,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct OutlineArgs {
+    /// Note ID, filename, or title.
+    pub id: String,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct TocArgs {
+    /// Note ID, filename, or title.
+    pub id: String,
+    /// Deepest heading level to list (default: 3).
+    pub max_level: Option<usize>,
+    /// Write the table of contents into the note between `<!-- toc -->` and
+    /// `<!-- tocstop -->` markers, adding them if missing, and commit.
+    #[serde(default)]
+    pub insert: bool,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct JournalStatsArgs {
+    /// Folder holding dated journal entries (default: `journal`).
+    pub folder: Option<String>,
+    /// First day of the range, as YYYY-MM-DD (default: 30 days ending with
+    /// `until`).
+    pub since: Option<String>,
+    /// Last day of the range, as YYYY-MM-DD (default: today).
+    pub until: Option<String>,
+    /// Notebook holding the journal (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct NoteStatsArgs {
+    /// Note ID, filename, or title to measure (measures every note of
+    /// `folder` if not specified).
+    pub id: Option<String>,
+    /// Folder to measure when `id` is not given (the whole notebook if not
+    /// specified).
+    pub folder: Option<String>,
+    /// Include archived notes when measuring a folder (see `archive_note`).
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Notebook containing the notes (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ReferencesArgs {
+    /// Note ID, filename, or title to scan (scans every note of `folder` if
+    /// not specified).
+    pub id: Option<String>,
+    /// Folder to scan when `id` is not given (the whole notebook if not
+    /// specified).
+    pub folder: Option<String>,
+    /// Include archived notes when scanning a folder (see `archive_note`).
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Notebook containing the notes (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct PreviewUrlArgs {
+    /// Note ID, filename, or title.
+    pub id: String,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ExtractTodosArgs {
+    /// Note to scan (scans `folder`, or the whole notebook, if not specified).
+    pub id: Option<String>,
+    /// Folder to scan when no note ID is given.
+    pub folder: Option<String>,
+    /// Tags to apply to the created todos (without # prefix).
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Folder to create the todos in (overrides the server's default folder).
+    pub todo_folder: Option<String>,
+    /// Skip the server's default folder and default tags for the created todos.
+    #[serde(default)]
+    pub no_defaults: bool,
+    /// Notebook to scan and add todos to (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ToggleCheckboxArgs {
+    /// Note ID, filename, or title containing the checklist.
+    pub id: String,
+    /// 1-based position of the checkbox among the note's checkboxes.
+    pub index: Option<usize>,
+    /// Case-insensitive text that identifies exactly one checkbox (used when
+    /// index is not given).
+    pub text: Option<String>,
+    /// State to set instead of flipping the current one.
+    pub checked: Option<bool>,
+    /// Notebook containing the note (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+/// A named list or search query, run with `nb.view`.
+///
+/// Views with a `query` run a search; the others run a list.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SavedView {
+    /// Search query; the view lists notes instead if not specified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// How to match the query: "literal" (default) or "regex".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Filter by tags (without # prefix).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Folder to list or search within.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// Boolean filter, e.g. `tag:client AND NOT tag:done`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// Sort key for list views: "created", "modified", "title", or "id".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// Sort direction for list views: "asc" or "desc".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+    /// Maximum number of items for list views.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ViewArgs {
+    /// View to run (lists the available views if not specified).
+    pub name: Option<String>,
+    /// Notebook to run the view in (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct SaveViewArgs {
+    /// Name of the view; an existing notebook view with this name is replaced.
+    pub name: String,
+    /// Query the view runs.
+    #[serde(flatten)]
+    pub view: SavedView,
+    /// Notebook to save the view in (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct SnippetArgs {
+    /// Snippet to return (lists the available snippets if not specified).
+    pub name: Option<String>,
+    /// Notebook holding the snippet (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct SaveSnippetArgs {
+    /// Name of the snippet; an existing snippet with this name is replaced.
+    pub name: String,
+    /// Text of the snippet.
+    pub text: String,
+    /// Notebook to save the snippet in (uses default if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct UseFolderArgs {
+    /// Folder later calls act in when they name none (clears the session
+    /// folder if not specified).
+    pub folder: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct UseNotebookArgs {
+    /// Notebook later calls use when they name none (clears the session
+    /// notebook if not specified).
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct NotebookCloneArgs {
+    /// Git URL of the notes repository (https, ssh, or a local path).
+    pub url: String,
+    /// Name for the new notebook (derived from the URL if not specified).
+    pub name: Option<String>,
+    /// Branch to check out (uses the remote's default branch if not specified).
+    pub branch: Option<String>,
+}
@@ -0,0 +1,269 @@
+//! Backlink sections maintained by the server.
+//!
+//! With `backlinks = true` in `.nb-mcp.toml`, every write is followed by a
+//! pass over the notebook's link graph: each note linked from other notes
+//! gets a `## Backlinks` section at its end listing them, and notes no
+//! longer linked lose theirs. Only notes whose section changes are
+//! rewritten, all in one commit, so the pass is idempotent and a write that
+//! leaves the graph alone commits nothing.
+//!
+//! Links are Markdown links to note files, relative to the linking note, and
+//! `[[wiki-links]]` naming a note's notebook path or title. Links inside a
+//! `## Backlinks` section are not part of the graph.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::Path,
+    sync::LazyLock,
+};
+
+use regex::Regex;
+
+use crate::links::{self, LinkStyle};
+use crate::nb::{NbClient, NbError};
+use crate::notes::{self, NoteFile};
+
+/// Heading of the maintained section.
+const SECTION: &str = "Backlinks";
+
+/// Commit message for backlink updates.
+const COMMIT_MESSAGE: &str = "[nb-mcp] Update backlinks";
+
+/// Matches a Markdown link's target: `[text](target)` or `[text](<target>)`.
+static MARKDOWN_LINK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[[^\]]*\]\((?:<([^>]+)>|([^)\s]+))[^)]*\)").unwrap());
+
+/// Matches a wiki-link's target: `[[target]]` or `[[target|text]]`.
+static WIKI_LINK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[\[([^\[\]|\n]+)(?:\|[^\[\]\n]*)?\]\]").unwrap());
+
+/// Regenerates the backlink sections of `notebook` and commits the notes
+/// that changed, returning their paths.
+pub async fn refresh(
+    nb: &NbClient,
+    style: LinkStyle,
+    notebook: Option<&str>,
+) -> Result<Vec<String>, NbError> {
+    let root = nb.notebook_path(notebook).await?;
+    let scan_root = root.clone();
+    let updated = tokio::task::spawn_blocking(move || -> std::io::Result<Vec<String>> {
+        let notes = notes::read_notes(&scan_root, None)?;
+        let updates = updates(&scan_root, &notes, style);
+        for (path, content) in &updates {
+            std::fs::write(scan_root.join(path), content)?;
+        }
+        Ok(updates.into_iter().map(|(path, _)| path).collect())
+    })
+    .await
+    .map_err(|err| NbError::CommandFailed(format!("backlink task failed: {err}")))??;
+    if !updated.is_empty() {
+        nb.checkpoint(COMMIT_MESSAGE, notebook).await?;
+    }
+    Ok(updated)
+}
+
+/// Returns the notes whose backlink section must change, with their new
+/// content.
+fn updates(root: &Path, notes: &[NoteFile], style: LinkStyle) -> Vec<(String, String)> {
+    let graph = backlinks(notes);
+    notes
+        .iter()
+        .filter_map(|note| {
+            let items = graph
+                .get(&note.path)
+                .into_iter()
+                .flatten()
+                .map(|source| {
+                    let content = notes
+                        .iter()
+                        .find(|other| &other.path == source)
+                        .map_or("", |other| other.content.as_str());
+                    let target =
+                        links::target(style, root, &root.join(&note.path), &root.join(source));
+                    let text = match style {
+                        LinkStyle::Markdown => links::title_of(Path::new(source), content),
+                        LinkStyle::Wiki => String::new(),
+                    };
+                    format!("- {}", links::format(style, &target, &text))
+                })
+                .collect::<Vec<_>>();
+            let updated = with_section(&note.content, &items.join("\n"));
+            (updated != note.content).then(|| (note.path.clone(), updated))
+        })
+        .collect()
+}
+
+/// Maps each linked note's path to the paths of the notes linking to it.
+fn backlinks(notes: &[NoteFile]) -> HashMap<String, BTreeSet<String>> {
+    let paths = notes
+        .iter()
+        .map(|note| note.path.as_str())
+        .collect::<BTreeSet<_>>();
+    let titles = notes
+        .iter()
+        .map(|note| {
+            let title = links::title_of(Path::new(&note.path), &note.content);
+            (title.to_lowercase(), note.path.as_str())
+        })
+        .collect::<BTreeMap<_, _>>();
+    let mut graph: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for note in notes {
+        let (before, section) = split_section(&note.content);
+        let body = format!("{before}{}", &note.content[before.len() + section.len()..]);
+        let body = body.as_str();
+        let folder = note.path.rsplit_once('/').map_or("", |(folder, _)| folder);
+        let markdown = MARKDOWN_LINK_REGEX
+            .captures_iter(body)
+            .filter_map(|captures| {
+                let target = captures.get(1).or_else(|| captures.get(2))?.as_str();
+                if target.contains("://") || target.starts_with(['#', '/']) || target.contains(':')
+                {
+                    return None;
+                }
+                let target = target.split('#').next().unwrap_or_default();
+                let joined = if folder.is_empty() {
+                    notes::percent_decode(target)
+                } else {
+                    format!("{folder}/{}", notes::percent_decode(target))
+                };
+                notes::normalize_path(&joined).filter(|path| paths.contains(path.as_str()))
+            });
+        let wiki = WIKI_LINK_REGEX.captures_iter(body).filter_map(|captures| {
+            let target = captures[1].trim();
+            // Links into other notebooks are out of scope.
+            if target.contains(':') {
+                return None;
+            }
+            let with_extension = format!("{target}.md");
+            [target, with_extension.as_str()]
+                .into_iter()
+                .find(|path| paths.contains(path))
+                .or_else(|| titles.get(&target.to_lowercase()).copied())
+                .map(String::from)
+        });
+        for target in markdown.chain(wiki) {
+            if target != note.path {
+                graph.entry(target).or_default().insert(note.path.clone());
+            }
+        }
+    }
+    graph
+}
+
+/// Splits `content` into the text outside the backlink section and the
+/// section itself (empty when there is none).
+fn split_section(content: &str) -> (&str, &str) {
+    let heading = format!("## {SECTION}");
+    let mut offset = 0;
+    let mut start = None;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        match start {
+            None if trimmed == heading => start = Some(offset),
+            Some(start) if trimmed.starts_with("# ") || trimmed.starts_with("## ") => {
+                return (&content[..start], &content[start..offset]);
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+    match start {
+        Some(start) => (&content[..start], &content[start..]),
+        None => (content, ""),
+    }
+}
+
+/// Replaces the backlink section of `content` with `items`, or removes it
+/// when `items` is empty. A new section goes at the end of the note.
+fn with_section(content: &str, items: &str) -> String {
+    let (before, section) = split_section(content);
+    if items.is_empty() && section.is_empty() {
+        return content.to_string();
+    }
+    let after = &content[before.len() + section.len()..];
+    let section = if items.is_empty() {
+        String::new()
+    } else {
+        format!("## {SECTION}\n\n{items}\n")
+    };
+    if section.is_empty() && !after.is_empty() {
+        return format!("{before}{after}");
+    }
+    let before = before.trim_end();
+    match (section.is_empty(), after.is_empty()) {
+        (true, _) if before.is_empty() => String::new(),
+        (true, _) => format!("{before}\n"),
+        (false, true) if before.is_empty() => section,
+        (false, true) => format!("{before}\n\n{section}"),
+        (false, false) => format!("{before}\n\n{section}\n{after}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{updates, with_section};
+    use crate::links::LinkStyle;
+    use crate::notes::NoteFile;
+
+    fn note(path: &str, content: &str) -> NoteFile {
+        NoteFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn updates_adds_and_removes_backlinks() {
+        let notes = [
+            note(
+                "ideas/cache.md",
+                "# Cache\n\n## Backlinks\n\n- [Old](../old.md)\n",
+            ),
+            note(
+                "projects/plan.md",
+                "# Plan\n\nSee [the cache](../ideas/cache.md).\n",
+            ),
+            note("journal.md", "# Journal\n\nWorked on [[Plan]].\n"),
+        ];
+        let root = Path::new("/notes/work");
+        let updates = updates(root, &notes, LinkStyle::Markdown);
+        assert_eq!(
+            updates,
+            vec![
+                (
+                    "ideas/cache.md".to_string(),
+                    "# Cache\n\n## Backlinks\n\n- [Plan](../projects/plan.md)\n".to_string()
+                ),
+                (
+                    "projects/plan.md".to_string(),
+                    "# Plan\n\nSee [the cache](../ideas/cache.md).\n\n## Backlinks\n\n\
+                     - [Journal](../journal.md)\n"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn updates_are_idempotent() {
+        let notes = [
+            note("a.md", "# A\n\nSee [[b.md]].\n"),
+            note("b.md", "# B\n\n## Backlinks\n\n- [[a.md]]\n"),
+        ];
+        assert!(updates(Path::new("/notes"), &notes, LinkStyle::Wiki).is_empty());
+    }
+
+    #[test]
+    fn with_section_keeps_following_sections() {
+        assert_eq!(
+            with_section("# A\n\n## Backlinks\n\n- x\n\n## Notes\n\nText\n", "- y"),
+            "# A\n\n## Backlinks\n\n- y\n\n## Notes\n\nText\n"
+        );
+        assert_eq!(
+            with_section("# A\n\nText\n\n## Backlinks\n\n- x\n", ""),
+            "# A\n\nText\n"
+        );
+    }
+}
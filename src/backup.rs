@@ -0,0 +1,434 @@
+//! Scheduled notebook backups.
+//!
+//! Users rarely run `nb.push` by hand. With a `[backup]` table in
+//! `.nb-mcp.toml`, a background task pushes the notebook to its remote (or
+//! writes a Git bundle to a directory) on a cron schedule in local time. The
+//! outcome of the latest run is reported by `server_info`.
+//!
+//! ```toml
+//! [backup]
+//! schedule = "0 */6 * * *"
+//! archive_dir = "/mnt/backups/notes"
+//! keep = 28
+//! ```
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use chrono::{
+    DateTime, Datelike, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::git_sync::{self, PushStatus};
+use crate::nb::{NbClient, NbError};
+use crate::shutdown::CallTracker;
+
+/// How far ahead to look for the next run before giving up (e.g., Feb 30).
+const SEARCH_DAYS: i64 = 5 * 366;
+
+/// Extension of archive files.
+const BUNDLE_EXTENSION: &str = "bundle";
+
+/// Backup settings from the `[backup]` table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackupConfig {
+    /// When to back up.
+    pub schedule: Schedule,
+    /// Directory for Git bundle archives; the notebook is pushed if unset.
+    pub archive_dir: Option<PathBuf>,
+    /// Number of archives to keep (all if unset).
+    pub keep: Option<usize>,
+    /// Remote to push to (the branch's upstream remote, or origin, if unset).
+    pub remote: Option<String>,
+    /// Notebook to back up (the default notebook if unset).
+    pub notebook: Option<String>,
+}
+
+/// A five-field cron schedule (minute, hour, day of month, month, day of
+/// week), evaluated in local time.
+///
+/// Fields accept `*`, numbers, ranges (`1-5`), steps (`*/15`, `0-30/10`), and
+/// comma-separated lists. Sunday is `0` or `7`. As in cron, when both day
+/// fields are restricted a time matches if either does. `@hourly`, `@daily`,
+/// `@weekly`, and `@monthly` are accepted as shorthands.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Schedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = String;
+
+    fn try_from(expression: String) -> Result<Self, String> {
+        Self::parse(&expression)
+    }
+}
+
+impl Schedule {
+    /// Parses a cron expression.
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "invalid schedule {expression:?}: expected five fields \
+                 (minute hour day month weekday)"
+            ));
+        };
+        let field = |text, min, max, name| {
+            parse_field(text, min, max)
+                .map_err(|err| format!("invalid schedule {expression:?}: {name}: {err}"))
+        };
+        let mut weekdays = field(weekday, 0, 7, "weekday")?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            expression: expression.trim().to_string(),
+            minutes: field(minute, 0, 59, "minute")?,
+            hours: field(hour, 0, 23, "hour")?,
+            days: field(day, 1, 31, "day")?,
+            months: field(month, 1, 12, "month")?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    /// Returns the expression the schedule was parsed from.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Returns the first matching minute strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after
+            .naive_local()
+            .with_second(0)?
+            .with_nanosecond(0)?
+            .checked_add_signed(Duration::minutes(1))?;
+        self.next_local(start)
+    }
+
+    fn next_local(&self, start: NaiveDateTime) -> Option<DateTime<Local>> {
+        let end = start.checked_add_signed(Duration::days(SEARCH_DAYS))?;
+        let mut time = start;
+        while time < end {
+            let date = time.date();
+            if !has(self.months, time.month()) {
+                let (year, month) = if time.month() == 12 {
+                    (time.year() + 1, 1)
+                } else {
+                    (time.year(), time.month() + 1)
+                };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(date) {
+                time = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !has(self.hours, time.hour()) {
+                time = date.and_hms_opt(time.hour(), 0, 0)? + Duration::hours(1);
+            } else if !has(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                match Local.from_local_datetime(&time) {
+                    LocalResult::Single(local) | LocalResult::Ambiguous(local, _) => {
+                        return Some(local);
+                    }
+                    // Skipped by a daylight saving change.
+                    LocalResult::None => time += Duration::minutes(1),
+                }
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parses one cron field into a bit set of the values it matches.
+fn parse_field(text: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut set = 0;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("invalid step {step:?}"))?;
+                if step == 0 {
+                    return Err("step must be positive".to_string());
+                }
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+        let value = |text: &str| -> Result<u32, String> {
+            let value = text
+                .parse()
+                .map_err(|_| format!("invalid value {text:?}"))?;
+            if value < min || value > max {
+                return Err(format!("{value} is outside {min}-{max}"));
+            }
+            Ok(value)
+        };
+        let (first, last) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((first, last)) => (value(first)?, value(last)?),
+                None => {
+                    let first = value(range)?;
+                    (first, if step.is_some() { max } else { first })
+                }
+            },
+        };
+        if first > last {
+            return Err(format!("range {range:?} is reversed"));
+        }
+        for value in (first..=last).step_by(step.unwrap_or(1) as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+/// Backup state reported by `server_info`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupStatus {
+    pub schedule: String,
+    /// "push" or the archive directory.
+    pub target: String,
+    /// Next scheduled run in RFC 3339 form, if any remains.
+    pub next_run: Option<String>,
+    pub last_run: Option<BackupRun>,
+    pub runs: u64,
+    pub failures: u64,
+}
+
+/// Outcome of one backup run.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupRun {
+    /// Start time in RFC 3339 form.
+    pub started: String,
+    pub success: bool,
+    /// What was pushed or written, or why the run failed.
+    pub detail: String,
+}
+
+/// Shared view of the backup task's state.
+#[derive(Debug)]
+pub struct BackupMonitor {
+    status: Mutex<BackupStatus>,
+}
+
+impl BackupMonitor {
+    pub fn snapshot(&self) -> BackupStatus {
+        self.status
+            .lock()
+            .expect("backup status lock poisoned")
+            .clone()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut BackupStatus)) {
+        change(&mut self.status.lock().expect("backup status lock poisoned"));
+    }
+}
+
+/// Starts the backup task and returns its monitor.
+///
+/// Runs are registered with `calls` so shutdown waits for a backup in
+/// progress, and no new run starts once shutdown has begun.
+pub(crate) fn spawn(
+    nb: NbClient,
+    config: BackupConfig,
+    calls: Arc<CallTracker>,
+) -> Arc<BackupMonitor> {
+    let monitor = Arc::new(BackupMonitor {
+        status: Mutex::new(BackupStatus {
+            schedule: config.schedule.expression.clone(),
+            target: config
+                .archive_dir
+                .as_ref()
+                .map_or_else(|| "push".to_string(), |dir| dir.display().to_string()),
+            next_run: None,
+            last_run: None,
+            runs: 0,
+            failures: 0,
+        }),
+    });
+    let task_monitor = monitor.clone();
+    tokio::spawn(async move {
+        let monitor = task_monitor;
+        loop {
+            let Some(next) = config.schedule.next_after(Local::now()) else {
+                tracing::warn!(
+                    schedule = %config.schedule.expression,
+                    "backup schedule never matches; backups stopped"
+                );
+                monitor.update(|status| status.next_run = None);
+                return;
+            };
+            monitor.update(|status| status.next_run = Some(next.to_rfc3339()));
+            let wait = (next - Local::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(wait).await;
+            let Some(_guard) = calls.begin() else {
+                return;
+            };
+            let started = Local::now();
+            let result = run(&nb, &config, started).await;
+            match &result {
+                Ok(detail) => tracing::info!(%detail, "backup finished"),
+                Err(err) => tracing::warn!(error = %err, "backup failed"),
+            }
+            monitor.update(|status| {
+                status.runs += 1;
+                status.failures += u64::from(result.is_err());
+                status.last_run = Some(BackupRun {
+                    started: started.to_rfc3339(),
+                    success: result.is_ok(),
+                    detail: result.unwrap_or_else(|err| err.to_string()),
+                });
+            });
+        }
+    });
+    monitor
+}
+
+/// Performs one backup, returning a description of what was done.
+async fn run(
+    nb: &NbClient,
+    config: &BackupConfig,
+    started: DateTime<Local>,
+) -> Result<String, NbError> {
+    let notebook = config.notebook.as_deref();
+    let Some(dir) = &config.archive_dir else {
+        let pushed = git_sync::push(nb, config.remote.as_deref(), None, notebook).await?;
+        let target = format!("{}/{}", pushed.remote, pushed.branch);
+        return match pushed.status {
+            PushStatus::Rejected => Err(NbError::CommandFailed(format!(
+                "push to {target} rejected: {}",
+                pushed.reason.unwrap_or_default()
+            ))),
+            PushStatus::UpToDate => Ok(format!("{target} already up to date")),
+            _ => Ok(format!("pushed to {target}")),
+        };
+    };
+    let name = nb.resolve_notebook(notebook).await?;
+    let repository = nb.notebook_path(Some(&name)).await?;
+    tokio::fs::create_dir_all(dir).await?;
+    let prefix = format!("{name}-");
+    let path = dir.join(format!(
+        "{prefix}{}.{BUNDLE_EXTENSION}",
+        started.format("%Y%m%dT%H%M%S")
+    ));
+    let path_arg = path.to_string_lossy();
+    let output = nb
+        .git(&repository, &["bundle", "create", &path_arg, "--all"])
+        .await?;
+    if !output.success {
+        return Err(NbError::CommandFailed(format!(
+            "git bundle failed: {}",
+            output.stderr.trim()
+        )));
+    }
+    if let Some(keep) = config.keep {
+        prune(dir, &prefix, keep.max(1)).await?;
+    }
+    Ok(format!("wrote {}", path.display()))
+}
+
+/// Removes the oldest archives with `prefix` beyond the newest `keep`.
+///
+/// Archive names end in a sortable timestamp, so name order is age order.
+async fn prune(dir: &Path, prefix: &str, keep: usize) -> Result<(), NbError> {
+    let mut archives = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with(prefix) && name.ends_with(&format!(".{BUNDLE_EXTENSION}")) {
+            archives.push(name);
+        }
+    }
+    archives.sort();
+    let excess = archives.len().saturating_sub(keep);
+    for name in &archives[..excess] {
+        tokio::fs::remove_file(dir.join(name)).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::Schedule;
+
+    fn at(day: u32, hour: u32, minute: u32) -> chrono::NaiveDateTime {
+        // 2026-03-02 is a Monday.
+        NaiveDate::from_ymd_opt(2026, 3, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn next_local_finds_matching_minute() {
+        let every_six_hours = Schedule::parse("15 */6 * * *").unwrap();
+        let next = every_six_hours.next_local(at(2, 6, 16)).unwrap();
+        assert_eq!(next.naive_local(), at(2, 12, 15));
+
+        let weekdays = Schedule::parse("0 9 * * 1-5").unwrap();
+        // Saturday evening rolls over to Monday morning.
+        let next = weekdays.next_local(at(7, 18, 0)).unwrap();
+        assert_eq!(next.naive_local(), at(9, 9, 0));
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either() {
+        // The 1st, or any Sunday.
+        let schedule = Schedule::parse("0 0 1 * 0").unwrap();
+        let next = schedule.next_local(at(2, 0, 0)).unwrap();
+        assert_eq!(next.naive_local(), at(8, 0, 0));
+    }
+
+    #[test]
+    fn parse_rejects_invalid_expressions() {
+        assert!(Schedule::parse("@daily").is_ok());
+        assert!(Schedule::parse("0 0 * *").is_err());
+        assert!(Schedule::parse("60 * * * *").is_err());
+        assert!(Schedule::parse("*/0 * * * *").is_err());
+        assert!(Schedule::parse("0 5-1 * * *").is_err());
+        assert!(
+            Schedule::parse("0 0 30 2 *")
+                .unwrap()
+                .next_local(at(2, 0, 0))
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Batched note creation with a single commit.
+//!
+//! Importers that create many notes write files directly into the notebook
+//! directory and then record one git checkpoint through nb, rather than
+//! paying for (and cluttering history with) one `nb add` commit per note.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// A note to be written as part of a batch.
+#[derive(Debug, Clone)]
+pub struct PendingNote {
+    /// Path relative to the batch folder (e.g., `projects/plan.md`).
+    pub path: String,
+    /// Full file content, including any frontmatter. Attachments may be binary.
+    pub content: Vec<u8>,
+    /// Original title, reported back to the caller.
+    pub title: Option<String>,
+}
+
+/// Outcome of writing a single note.
+#[derive(Debug, Serialize)]
+pub struct WrittenNote {
+    /// Path relative to the notebook root.
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Summary of a batch write.
+#[derive(Debug, Serialize)]
+pub struct BatchSummary {
+    pub created: usize,
+    pub failed: usize,
+    /// Whether the checkpoint commit succeeded.
+    pub committed: bool,
+    pub notes: Vec<WrittenNote>,
+}
+
+/// Writes `pending` notes beneath `folder` and commits them with `message`.
+///
+/// Existing files are never overwritten; colliding names receive a numeric
+/// suffix. Per-note failures are reported without aborting the batch.
+pub async fn write_notes(
+    nb: &NbClient,
+    pending: Vec<PendingNote>,
+    folder: Option<&str>,
+    message: &str,
+    notebook: Option<&str>,
+) -> Result<BatchSummary, NbError> {
+    let root = nb.notebook_path(notebook).await?;
+    let folder = folder
+        .map(|f| f.trim_matches('/').to_string())
+        .filter(|f| !f.is_empty());
+    let notes = tokio::task::spawn_blocking(move || write_all(&root, folder.as_deref(), pending))
+        .await
+        .map_err(|err| NbError::CommandFailed(format!("batch write task failed: {err}")))?;
+    let created = notes.iter().filter(|n| n.error.is_none()).count();
+    let committed = if created > 0 {
+        nb.checkpoint(message, notebook).await?;
+        true
+    } else {
+        false
+    };
+    Ok(BatchSummary {
+        created,
+        failed: notes.len() - created,
+        committed,
+        notes,
+    })
+}
+
+fn write_all(root: &Path, folder: Option<&str>, pending: Vec<PendingNote>) -> Vec<WrittenNote> {
+    pending
+        .into_iter()
+        .map(|note| {
+            let relative = match folder {
+                Some(f) => format!("{}/{}", f, note.path.trim_start_matches('/')),
+                None => note.path.trim_start_matches('/').to_string(),
+            };
+            match write_unique(root, &relative, &note.content) {
+                Ok(path) => WrittenNote {
+                    path,
+                    title: note.title,
+                    error: None,
+                },
+                Err(err) => WrittenNote {
+                    path: relative,
+                    title: note.title,
+                    error: Some(err.to_string()),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Writes `content` at `relative` (or a suffixed variant) and returns the path used.
+fn write_unique(root: &Path, relative: &str, content: &[u8]) -> std::io::Result<String> {
+    if relative.split('/').any(|part| part == "..") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "path escapes the notebook",
+        ));
+    }
+    let target = unique_path(&root.join(relative));
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&target, content)?;
+    Ok(notes::relative_path(root, &target))
+}
+
+/// Returns `path`, or the first `stem-N.ext` variant that does not exist.
+pub fn unique_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{}-{}{}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded suffix search always finds a free name")
+}
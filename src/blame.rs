@@ -0,0 +1,224 @@
+//! Per-line history of a note.
+//!
+//! `nb.blame` runs `git blame` on a note file so users can see when a fact
+//! entered a document and with which commit. Long notes can be reported by
+//! paragraph instead, each carrying the most recent change to any of its
+//! lines.
+
+use chrono::DateTime;
+use serde::Serialize;
+
+use crate::git_sync;
+use crate::nb::{NbClient, NbError};
+
+/// Commit git reports for lines that are not committed yet.
+const UNCOMMITTED: &str = "0000000000000000000000000000000000000000";
+
+/// Last change to a line or paragraph.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Origin {
+    /// Commit hash; absent for uncommitted changes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Author date, RFC 3339 in UTC.
+    pub date: String,
+    pub author: String,
+    /// First line of the commit message.
+    pub summary: String,
+    #[serde(skip)]
+    timestamp: i64,
+}
+
+/// A line of the note with its last change.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BlameLine {
+    /// Line number, starting at 1.
+    pub line: usize,
+    pub text: String,
+    #[serde(flatten)]
+    pub origin: Origin,
+}
+
+/// A paragraph of the note with the most recent change to its lines.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BlameParagraph {
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The paragraph's first line.
+    pub first_line: String,
+    #[serde(flatten)]
+    pub origin: Origin,
+}
+
+/// Result of `nb.blame`.
+#[derive(Debug, Serialize)]
+pub struct Blame {
+    /// Note file, relative to the notebook root.
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<Vec<BlameLine>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paragraphs: Option<Vec<BlameParagraph>>,
+}
+
+/// Runs `git blame` on note `id`, by line or by paragraph.
+pub async fn blame(
+    nb: &NbClient,
+    id: &str,
+    paragraphs: bool,
+    notebook: Option<&str>,
+) -> Result<Blame, NbError> {
+    let root = nb.notebook_path(notebook).await?;
+    let path = nb.show_path(id, notebook).await?;
+    let relative = path
+        .strip_prefix(&root)
+        .map_err(|_| {
+            NbError::CommandFailed(format!(
+                "note {id} is outside its notebook at {}",
+                root.display()
+            ))
+        })?
+        .to_string_lossy()
+        .into_owned();
+    let output =
+        git_sync::run_checked(nb, &root, &["blame", "--line-porcelain", "--", &relative]).await?;
+    let lines = parse_porcelain(&output);
+    let (lines, paragraphs) = if paragraphs {
+        (None, Some(group_paragraphs(lines)))
+    } else {
+        (Some(lines), None)
+    };
+    Ok(Blame {
+        path: relative,
+        lines,
+        paragraphs,
+    })
+}
+
+/// Parses `git blame --line-porcelain` output.
+fn parse_porcelain(output: &str) -> Vec<BlameLine> {
+    let mut lines = Vec::new();
+    let mut commit = "";
+    let mut line = 0;
+    let mut author = "";
+    let mut timestamp = 0;
+    let mut summary = "";
+    for row in output.lines() {
+        if let Some(text) = row.strip_prefix('\t') {
+            lines.push(BlameLine {
+                line,
+                text: text.to_string(),
+                origin: Origin {
+                    commit: (commit != UNCOMMITTED).then(|| commit.to_string()),
+                    date: DateTime::from_timestamp(timestamp, 0)
+                        .map(|date| date.to_rfc3339())
+                        .unwrap_or_default(),
+                    author: author.to_string(),
+                    summary: summary.to_string(),
+                    timestamp,
+                },
+            });
+        } else if let Some(value) = row.strip_prefix("author ") {
+            author = value;
+        } else if let Some(value) = row.strip_prefix("author-time ") {
+            timestamp = value.parse().unwrap_or(0);
+        } else if let Some(value) = row.strip_prefix("summary ") {
+            summary = value;
+        } else {
+            let mut fields = row.split(' ');
+            let hash = fields.next().unwrap_or_default();
+            if hash.len() == 40 && hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                commit = hash;
+                line = fields.nth(1).and_then(|n| n.parse().ok()).unwrap_or(0);
+            }
+        }
+    }
+    lines
+}
+
+/// Groups lines into blank-line-separated paragraphs, each with the most
+/// recent change among its lines.
+fn group_paragraphs(lines: Vec<BlameLine>) -> Vec<BlameParagraph> {
+    let mut paragraphs: Vec<BlameParagraph> = Vec::new();
+    let mut open = false;
+    for line in lines {
+        if line.text.trim().is_empty() {
+            open = false;
+            continue;
+        }
+        match paragraphs.last_mut() {
+            Some(paragraph) if open => {
+                paragraph.end_line = line.line;
+                if line.origin.timestamp > paragraph.origin.timestamp {
+                    paragraph.origin = line.origin;
+                }
+            }
+            _ => {
+                paragraphs.push(BlameParagraph {
+                    start_line: line.line,
+                    end_line: line.line,
+                    first_line: line.text,
+                    origin: line.origin,
+                });
+                open = true;
+            }
+        }
+    }
+    paragraphs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{group_paragraphs, parse_porcelain};
+
+    const OLD: &str = "1111111111111111111111111111111111111111";
+    const NEW: &str = "2222222222222222222222222222222222222222";
+
+    fn porcelain(entries: &[(&str, usize, i64, &str)]) -> String {
+        entries
+            .iter()
+            .map(|(commit, line, time, text)| {
+                format!(
+                    "{commit} {line} {line} 1\nauthor Ada\nauthor-mail <ada@example.com>\n\
+                     author-time {time}\nauthor-tz +0000\nsummary edit {line}\n\
+                     filename plan.md\n\t{text}\n"
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_porcelain_reads_each_line() {
+        let lines = parse_porcelain(&porcelain(&[
+            (OLD, 1, 0, "# Plan"),
+            (
+                "0000000000000000000000000000000000000000",
+                2,
+                86400,
+                "draft",
+            ),
+        ]));
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].line, 1);
+        assert_eq!(lines[0].text, "# Plan");
+        assert_eq!(lines[0].origin.commit.as_deref(), Some(OLD));
+        assert_eq!(lines[0].origin.date, "1970-01-01T00:00:00+00:00");
+        assert_eq!(lines[0].origin.summary, "edit 1");
+        assert_eq!(lines[1].origin.commit, None);
+    }
+
+    #[test]
+    fn group_paragraphs_keeps_the_latest_change() {
+        let lines = parse_porcelain(&porcelain(&[
+            (OLD, 1, 100, "# Plan"),
+            (OLD, 2, 100, ""),
+            (OLD, 3, 100, "Ship on Friday."),
+            (NEW, 4, 200, "Unless it rains."),
+        ]));
+        let paragraphs = group_paragraphs(lines);
+        assert_eq!(paragraphs.len(), 2);
+        assert_eq!((paragraphs[1].start_line, paragraphs[1].end_line), (3, 4));
+        assert_eq!(paragraphs[1].first_line, "Ship on Friday.");
+        assert_eq!(paragraphs[1].origin.commit.as_deref(), Some(NEW));
+    }
+}
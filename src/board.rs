@@ -0,0 +1,255 @@
+//! Kanban-style board over todos.
+//!
+//! `nb.board` groups a notebook's todos into columns by status tag (`#todo`,
+//! `#doing`, `#done` unless `board_columns` in `.nb-mcp.toml` names others).
+//! A todo without a status tag sits in the first column while open and in
+//! the last once done. Within a column, todos are ordered by priority tag
+//! (`#priority/a` first, as written by `nb.todotxt_import`), then due date,
+//! then path. `nb.board_move` retags a todo into another column.
+
+use serde::Serialize;
+
+use crate::archive;
+use crate::nb::{NbClient, NbError};
+use crate::notes::{self, NoteFile};
+
+/// Status tags used when none are configured.
+pub const DEFAULT_COLUMNS: &[&str] = &["todo", "doing", "done"];
+
+const PRIORITY_TAG: &str = "priority/";
+
+/// Result of `nb.board`.
+#[derive(Debug, Serialize)]
+pub struct Board {
+    pub columns: Vec<Column>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Column {
+    /// Status tag, without `#`.
+    pub tag: String,
+    pub tasks: Vec<Card>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Card {
+    /// Path relative to the notebook root, usable as an ID.
+    pub id: String,
+    pub title: String,
+    pub done: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+}
+
+/// Result of `nb.board_move`.
+#[derive(Debug, Serialize)]
+pub struct Moved {
+    pub id: String,
+    /// Status tag the todo had, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub to: String,
+    /// Whether the note was edited (false when already in the column).
+    pub changed: bool,
+}
+
+/// Returns the configured status tags, or the defaults.
+pub fn columns(configured: &[String]) -> Vec<String> {
+    if configured.is_empty() {
+        DEFAULT_COLUMNS.iter().map(|tag| tag.to_string()).collect()
+    } else {
+        configured
+            .iter()
+            .map(|tag| tag.trim_start_matches('#').to_string())
+            .collect()
+    }
+}
+
+/// Groups the todos of a notebook or folder into `columns`.
+pub async fn board(
+    nb: &NbClient,
+    columns: &[String],
+    folder: Option<&str>,
+    include_archived: bool,
+    notebook: Option<&str>,
+) -> Result<Board, NbError> {
+    let root = nb.notebook_path(notebook).await?;
+    let hide_archived = archive::hides(&root, folder, include_archived);
+    let folder = folder.map(String::from);
+    let files = tokio::task::spawn_blocking(move || notes::read_notes(&root, folder.as_deref()))
+        .await
+        .map_err(|err| NbError::CommandFailed(format!("board task failed: {err}")))??;
+    let files = files
+        .into_iter()
+        .filter(|file| !(hide_archived && file.path.split('/').next() == Some(archive::FOLDER)))
+        .collect::<Vec<_>>();
+    Ok(build(columns, &files))
+}
+
+/// Moves a todo into the column of `to` by replacing its status tag.
+pub async fn move_card(
+    nb: &NbClient,
+    columns: &[String],
+    id: &str,
+    to: &str,
+    notebook: Option<&str>,
+) -> Result<Moved, NbError> {
+    let to = to.trim_start_matches('#');
+    if !columns.iter().any(|column| column == to) {
+        return Err(NbError::CommandFailed(format!(
+            "unknown board column {to:?}; columns are {}",
+            columns.join(", ")
+        )));
+    }
+    let path = nb.show_path(id, notebook).await?;
+    let content = tokio::fs::read_to_string(&path).await?;
+    let tags = notes::extract_tags(&content);
+    let from = tags.iter().find(|tag| columns.contains(tag)).cloned();
+    let updated = match &from {
+        Some(from) => columns.iter().filter(|column| tags.contains(column)).fold(
+            notes::replace_hashtag(&content, from, Some(to)),
+            |note, column| {
+                if column == from || column == to {
+                    note
+                } else {
+                    notes::replace_hashtag(&note, column, None)
+                }
+            },
+        ),
+        None => notes::add_hashtags(&content, &[to.to_string()]),
+    };
+    let changed = updated != content;
+    if changed {
+        nb.edit(id, &updated, notebook).await?;
+    }
+    Ok(Moved {
+        id: id.to_string(),
+        from,
+        to: to.to_string(),
+        changed,
+    })
+}
+
+fn build(columns: &[String], files: &[NoteFile]) -> Board {
+    let mut grouped = columns
+        .iter()
+        .map(|tag| Column {
+            tag: tag.clone(),
+            tasks: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+    for file in files {
+        let Some((card, tags)) = card(file) else {
+            continue;
+        };
+        let index = columns
+            .iter()
+            .position(|column| tags.contains(column))
+            .unwrap_or(if card.done { columns.len() - 1 } else { 0 });
+        grouped[index].tasks.push(card);
+    }
+    for column in &mut grouped {
+        column.tasks.sort_by(|a, b| {
+            (
+                a.priority.is_none(),
+                &a.priority,
+                a.due.is_none(),
+                &a.due,
+                &a.id,
+            )
+                .cmp(&(
+                    b.priority.is_none(),
+                    &b.priority,
+                    b.due.is_none(),
+                    &b.due,
+                    &b.id,
+                ))
+        });
+    }
+    Board { columns: grouped }
+}
+
+/// Reads a todo file into a card and its tags.
+fn card(file: &NoteFile) -> Option<(Card, Vec<String>)> {
+    if !file.path.ends_with(".todo.md") {
+        return None;
+    }
+    let mut lines = file.content.lines().map(str::trim);
+    let heading = lines.by_ref().find(|line| !line.is_empty())?;
+    let (done, title) = if let Some(rest) = heading.strip_prefix("# [ ]") {
+        (false, rest)
+    } else {
+        (true, heading.strip_prefix("# [x]")?)
+    };
+    let due = if lines
+        .by_ref()
+        .any(|line| line.eq_ignore_ascii_case("## Due"))
+    {
+        lines.find(|line| !line.is_empty()).map(String::from)
+    } else {
+        None
+    };
+    let tags = notes::extract_tags(&file.content);
+    let priority = tags
+        .iter()
+        .find_map(|tag| tag.strip_prefix(PRIORITY_TAG))
+        .map(str::to_lowercase);
+    let card = Card {
+        id: file.path.clone(),
+        title: title.trim().to_string(),
+        done,
+        priority,
+        due,
+    };
+    Some((card, tags))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DEFAULT_COLUMNS, build, columns};
+    use crate::notes::NoteFile;
+
+    fn todo(path: &str, content: &str) -> NoteFile {
+        NoteFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn build_groups_by_status_tag_and_orders_by_priority_then_due() {
+        let columns = columns(&[]);
+        assert_eq!(columns, DEFAULT_COLUMNS);
+        let files = vec![
+            todo("1.todo.md", "# [ ] Untagged\n"),
+            todo(
+                "2.todo.md",
+                "# [ ] Later\n\n## Due\n\n2024-06-01\n\n## Tags\n\n#todo\n",
+            ),
+            todo(
+                "3.todo.md",
+                "# [ ] Sooner\n\n## Due\n\n2024-05-01\n\n## Tags\n\n#todo\n",
+            ),
+            todo(
+                "4.todo.md",
+                "# [ ] Urgent\n\n## Tags\n\n#todo #priority/a\n",
+            ),
+            todo("5.todo.md", "# [ ] Started\n\n## Tags\n\n#doing\n"),
+            todo("6.todo.md", "# [x] Finished\n"),
+            todo("notes.md", "# Not a todo #todo\n"),
+        ];
+        let board = build(&columns, &files);
+        let ids = |index: usize| {
+            board.columns[index]
+                .tasks
+                .iter()
+                .map(|card| card.id.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(0), ["4.todo.md", "3.todo.md", "2.todo.md", "1.todo.md"]);
+        assert_eq!(ids(1), ["5.todo.md"]);
+        assert_eq!(ids(2), ["6.todo.md"]);
+    }
+}
@@ -0,0 +1,106 @@
+//! Bookmarks from page content the client already has.
+//!
+//! `nb bookmark` downloads the page to cache its content. A client that
+//! already holds the page text (e.g., a browser extension) can pass it as
+//! `content`; the server then writes the bookmark file in nb's layout itself,
+//! with the text as the cached `## Content` section, and the URL is never
+//! requested. Enriched bookmarks (see `page_metadata`) are written the same
+//! way, after the server fetched the page.
+
+use chrono::Local;
+
+use crate::args::BookmarkArgs;
+use crate::nb::{NbClient, NbError};
+use crate::page_metadata::{self, PageMetadata};
+
+/// Returns a bookmark file name in nb's timestamp style.
+fn filename() -> String {
+    format!("{}.bookmark.md", Local::now().format("%Y%m%d%H%M%S"))
+}
+
+/// Returns the path of a new bookmark file in `folder`.
+pub fn path(folder: Option<&str>) -> String {
+    match folder.map(|folder| folder.trim_matches('/')) {
+        Some(folder) if !folder.is_empty() => format!("{folder}/{}", filename()),
+        _ => filename(),
+    }
+}
+
+/// Fetches the bookmarked page and adds the bookmark with the page's
+/// metadata in its frontmatter.
+///
+/// The page text is cached as the bookmark's content unless the client
+/// passed `content`; a `title` argument overrides the page's title in the
+/// heading only.
+pub async fn add_enriched(nb: &NbClient, args: &BookmarkArgs) -> Result<String, NbError> {
+    let html = page_metadata::fetch(&args.url).await?;
+    let metadata = PageMetadata::parse(&html, &args.url);
+    let content = match &args.content {
+        Some(content) => content.clone(),
+        None => page_metadata::text(&html),
+    };
+    let note = render(
+        &args.url,
+        args.title.as_deref().or(metadata.title.as_deref()),
+        args.comment.as_deref(),
+        &args.tags,
+        &content,
+    );
+    nb.add_at_path(
+        &path(args.folder.as_deref()),
+        &metadata.add_frontmatter(&note),
+        args.notebook.as_deref(),
+    )
+    .await
+}
+
+/// Renders a bookmark note in nb's layout.
+///
+/// Without `title`, the first heading of `content` or the URL is used.
+pub fn render(
+    url: &str,
+    title: Option<&str>,
+    comment: Option<&str>,
+    tags: &[String],
+    content: &str,
+) -> String {
+    let title = title
+        .map(String::from)
+        .or_else(|| crate::notes::derive_title(content))
+        .unwrap_or_else(|| url.to_string());
+    let mut note = format!("# {title}\n\n<{url}>\n");
+    if let Some(comment) = comment.map(str::trim).filter(|c| !c.is_empty()) {
+        note.push_str(&format!("\n## Comment\n\n{comment}\n"));
+    }
+    if !tags.is_empty() {
+        let tags = tags
+            .iter()
+            .map(|tag| format!("#{}", tag.trim_start_matches('#')))
+            .collect::<Vec<_>>()
+            .join(" ");
+        note.push_str(&format!("\n## Tags\n\n{tags}\n"));
+    }
+    note.push_str(&format!("\n## Content\n\n{}\n", content.trim()));
+    note
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render;
+
+    #[test]
+    fn render_uses_nb_sections() {
+        let note = render(
+            "https://example.com/post",
+            None,
+            Some("Worth a read"),
+            &["reading".to_string()],
+            "# Post title\n\nBody text.\n",
+        );
+        assert_eq!(
+            note,
+            "# Post title\n\n<https://example.com/post>\n\n## Comment\n\nWorth a read\n\n\
+             ## Tags\n\n#reading\n\n## Content\n\n# Post title\n\nBody text.\n"
+        );
+    }
+}
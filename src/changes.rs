@@ -0,0 +1,142 @@
+//! Notes changed since a commit or point in time.
+//!
+//! nb commits every change, so the notebook's Git history says exactly what
+//! happened while an agent was away. `nb.changes` diffs the notebook from a
+//! starting commit (given directly, or the last commit before a timestamp)
+//! to `HEAD`, so a resumed session can re-read only what changed. The
+//! returned `head` can be passed as `since` next time.
+
+use serde::Serialize;
+
+use crate::git_sync;
+use crate::nb::{NbClient, NbError};
+
+/// Git's empty tree, the base when `since` predates the notebook.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Result of `nb.changes`.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct Changes {
+    /// Commit the changes are counted from (absent when `since` predates the
+    /// notebook).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// Current commit; pass it as `since` to continue from here.
+    pub head: String,
+    /// Commits between `base` and `head`.
+    pub commits: usize,
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub deleted: Vec<String>,
+    pub renamed: Vec<Rename>,
+}
+
+/// A file moved or renamed between `base` and `head`.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Rename {
+    pub from: String,
+    pub to: String,
+}
+
+/// Reports the files that changed in the notebook since `since`.
+pub async fn since(nb: &NbClient, since: &str, notebook: Option<&str>) -> Result<Changes, NbError> {
+    let since = since.trim();
+    if since.is_empty() || since.starts_with('-') {
+        return Err(NbError::CommandFailed(format!(
+            "invalid since {since:?}; expected a commit hash or timestamp"
+        )));
+    }
+    let repository = nb.notebook_path(notebook).await?;
+    let head = git_sync::run_checked(nb, &repository, &["rev-parse", "HEAD"])
+        .await?
+        .trim()
+        .to_string();
+    let commit = format!("{since}^{{commit}}");
+    let resolved = nb
+        .git(&repository, &["rev-parse", "--verify", "--quiet", &commit])
+        .await?;
+    let base = if resolved.success {
+        Some(resolved.stdout.trim().to_string())
+    } else {
+        let before = format!("--before={since}");
+        let output =
+            git_sync::run_checked(nb, &repository, &["rev-list", "-1", &before, "HEAD"]).await?;
+        Some(output.trim().to_string()).filter(|base| !base.is_empty())
+    };
+    let from = base.as_deref().unwrap_or(EMPTY_TREE);
+    let diff = git_sync::run_checked(
+        nb,
+        &repository,
+        &["diff", "--name-status", "-M", "--no-color", from, &head],
+    )
+    .await?;
+    let range = match &base {
+        Some(base) => format!("{base}..{head}"),
+        None => head.clone(),
+    };
+    let commits = git_sync::run_checked(nb, &repository, &["rev-list", "--count", &range])
+        .await?
+        .trim()
+        .parse()
+        .unwrap_or(0);
+    let mut changes = parse_name_status(&diff);
+    changes.base = base;
+    changes.head = head;
+    changes.commits = commits;
+    Ok(changes)
+}
+
+/// Parses `git diff --name-status` output, skipping nb's hidden files (e.g.,
+/// `.index`).
+fn parse_name_status(output: &str) -> Changes {
+    let mut changes = Changes::default();
+    for line in output.lines() {
+        let mut fields = line.split('\t');
+        let (Some(status), Some(path)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let renamed_to = fields.next();
+        if is_hidden(path) && renamed_to.is_none_or(is_hidden) {
+            continue;
+        }
+        match (status.chars().next(), renamed_to) {
+            (Some('R'), Some(to)) => changes.renamed.push(Rename {
+                from: path.to_string(),
+                to: to.to_string(),
+            }),
+            (Some('C'), Some(to)) => changes.added.push(to.to_string()),
+            (Some('A'), _) => changes.added.push(path.to_string()),
+            (Some('D'), _) => changes.deleted.push(path.to_string()),
+            _ => changes.modified.push(path.to_string()),
+        }
+    }
+    changes
+}
+
+/// Returns whether any component of `path` is hidden.
+fn is_hidden(path: &str) -> bool {
+    path.split('/').any(|part| part.starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rename, parse_name_status};
+
+    #[test]
+    fn parse_name_status_groups_changes() {
+        let changes = parse_name_status(
+            "A\tideas/new.md\nM\tplan.md\nD\told.md\nR097\tdraft.md\tdocs/final.md\n\
+             M\t.index\nM\tdocs/.index\nT\tlink.md\n",
+        );
+        assert_eq!(changes.added, ["ideas/new.md"]);
+        assert_eq!(changes.modified, ["plan.md", "link.md"]);
+        assert_eq!(changes.deleted, ["old.md"]);
+        assert_eq!(
+            changes.renamed,
+            [Rename {
+                from: "draft.md".to_string(),
+                to: "docs/final.md".to_string(),
+            }]
+        );
+    }
+}
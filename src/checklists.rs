@@ -0,0 +1,319 @@
+//! Markdown checklist items inside ordinary notes.
+//!
+//! Meeting notes often collect action items as `- [ ]` checkboxes. These
+//! helpers find them so `nb.extract_todos` can turn them into nb todos that
+//! link back to the note they came from, and so `nb.toggle_checkbox` can tick
+//! one item without the agent rewriting the whole note.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::args::{ExtractTodosArgs, ToggleCheckboxArgs};
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// Matches a checklist item: indentation, bullet, box, and text.
+static CHECKBOX_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\s*[-*+]\s+\[)([ xX])(\]\s+)(.*?)\s*$").unwrap());
+
+/// Suffix nb gives todo files.
+const TODO_SUFFIX: &str = ".todo.md";
+
+/// A checklist item found in a note.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Checkbox {
+    /// 1-based line number.
+    pub line: usize,
+    pub text: String,
+    pub checked: bool,
+}
+
+/// Returns the checklist items in `content`, skipping fenced code blocks.
+pub fn checkboxes(content: &str) -> Vec<Checkbox> {
+    let mut in_fence = false;
+    let mut items = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some(captures) = CHECKBOX_REGEX.captures(line) {
+            let text = captures[4].to_string();
+            if !text.is_empty() {
+                items.push(Checkbox {
+                    line: index + 1,
+                    text,
+                    checked: &captures[2] != " ",
+                });
+            }
+        }
+    }
+    items
+}
+
+/// Sets the checkbox on 1-based `line` of `content` to `checked`.
+///
+/// Other lines, including their line endings, are left untouched.
+fn set_checkbox(content: &str, line: usize, checked: bool) -> String {
+    content
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(index, text)| {
+            if index + 1 != line {
+                return text.to_string();
+            }
+            let mark = if checked { "x" } else { " " };
+            CHECKBOX_REGEX
+                .replace(text, |captures: &regex::Captures| {
+                    format!("{}{}{}{}", &captures[1], mark, &captures[3], &captures[4])
+                })
+                .into_owned()
+                + if text.ends_with("\r\n") {
+                    "\r\n"
+                } else if text.ends_with('\n') {
+                    "\n"
+                } else {
+                    ""
+                }
+        })
+        .collect()
+}
+
+/// Picks the checkbox addressed by a 1-based `index` or a `text` match.
+fn select_checkbox(
+    items: Vec<Checkbox>,
+    index: Option<usize>,
+    text: Option<&str>,
+) -> Result<Checkbox, String> {
+    match (index, text) {
+        (Some(index), _) => {
+            let count = items.len();
+            items
+                .into_iter()
+                .nth(index.wrapping_sub(1))
+                .ok_or_else(|| format!("checkbox {index} not found; the note has {count}"))
+        }
+        (None, Some(text)) => {
+            let needle = text.to_lowercase();
+            let mut matches: Vec<Checkbox> = items
+                .into_iter()
+                .filter(|item| item.text.to_lowercase().contains(&needle))
+                .collect();
+            match matches.len() {
+                0 => Err(format!("no checkbox matches {text:?}")),
+                1 => Ok(matches.remove(0)),
+                n => Err(format!(
+                    "{n} checkboxes match {text:?}; use index or a more specific text"
+                )),
+            }
+        }
+        (None, None) => Err("toggle_checkbox requires index or text".to_string()),
+    }
+}
+
+/// Result of `nb.toggle_checkbox`.
+#[derive(Debug, Serialize)]
+pub struct ToggledCheckbox {
+    pub id: String,
+    pub line: usize,
+    pub text: String,
+    /// State after the change.
+    pub checked: bool,
+}
+
+/// Flips (or sets) one checkbox in a note and commits the edit through nb.
+pub async fn toggle_checkbox(
+    nb: &NbClient,
+    args: &ToggleCheckboxArgs,
+) -> Result<ToggledCheckbox, NbError> {
+    let notebook = args.notebook.as_deref();
+    let path = nb.show_path(&args.id, notebook).await?;
+    let content = String::from_utf8(tokio::fs::read(&path).await?)
+        .map_err(|_| NbError::CommandFailed(format!("note {} is not a text file", args.id)))?;
+    let item = select_checkbox(checkboxes(&content), args.index, args.text.as_deref())
+        .map_err(NbError::CommandFailed)?;
+    let checked = args.checked.unwrap_or(!item.checked);
+    if checked != item.checked {
+        let updated = set_checkbox(&content, item.line, checked);
+        nb.edit(&args.id, &updated, notebook).await?;
+    }
+    Ok(ToggledCheckbox {
+        id: args.id.clone(),
+        line: item.line,
+        text: item.text,
+        checked,
+    })
+}
+
+/// A todo created (or that failed to be created) from a checklist item.
+#[derive(Debug, Serialize)]
+pub struct ExtractedTodo {
+    pub description: String,
+    /// Note the item came from, relative to the notebook root.
+    pub source: String,
+    pub line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of `nb.extract_todos`.
+#[derive(Debug, Serialize)]
+pub struct ExtractSummary {
+    pub created: usize,
+    pub failed: usize,
+    /// Unchecked items skipped because a todo with the same text exists.
+    pub skipped_existing: usize,
+    pub todos: Vec<ExtractedTodo>,
+}
+
+/// Creates a todo for every unchecked item in a note or folder.
+///
+/// Items whose text matches an existing todo are skipped, so running the
+/// command again after adding items to a note only creates the new ones.
+/// `tags` and `folder` apply to the created todos.
+pub async fn extract_todos(
+    nb: &NbClient,
+    args: &ExtractTodosArgs,
+    tags: &[String],
+    folder: Option<&str>,
+) -> Result<ExtractSummary, NbError> {
+    let notebook = args.notebook.as_deref();
+    let root = nb.notebook_path(notebook).await?;
+    let source_id = args.id.clone();
+    let source_path = match &source_id {
+        Some(id) => Some(nb.show_path(id, notebook).await?),
+        None => None,
+    };
+    let scope = args.folder.clone();
+    let (sources, mut existing) = tokio::task::spawn_blocking(move || {
+        let all = notes::read_notes(&root, None)?;
+        let existing: Vec<String> = all
+            .iter()
+            .filter(|note| note.path.ends_with(TODO_SUFFIX))
+            .filter_map(|note| todo_title(&note.content))
+            .collect();
+        let sources: Vec<notes::NoteFile> = all
+            .into_iter()
+            .filter(|note| !note.path.ends_with(TODO_SUFFIX))
+            .filter(|note| match (&source_path, &scope) {
+                (Some(path), _) => root.join(&note.path) == *path,
+                (None, Some(scope)) => note
+                    .path
+                    .starts_with(&format!("{}/", scope.trim_matches('/'))),
+                (None, None) => true,
+            })
+            .collect();
+        Ok::<_, std::io::Error>((sources, existing))
+    })
+    .await
+    .map_err(|err| NbError::CommandFailed(format!("todo scan task failed: {err}")))??;
+    if let (Some(id), true) = (&source_id, sources.is_empty()) {
+        return Err(NbError::CommandFailed(format!(
+            "note {id} is not a text note in the notebook"
+        )));
+    }
+
+    let mut todos = Vec::new();
+    let mut skipped_existing = 0;
+    for source in &sources {
+        for item in checkboxes(&source.content)
+            .into_iter()
+            .filter(|item| !item.checked)
+        {
+            if existing.contains(&item.text) {
+                skipped_existing += 1;
+                continue;
+            }
+            let error = nb
+                .todo_with_related(
+                    &item.text,
+                    tags,
+                    folder,
+                    std::slice::from_ref(&source.path),
+                    notebook,
+                )
+                .await
+                .err()
+                .map(|err| err.to_string());
+            existing.push(item.text.clone());
+            todos.push(ExtractedTodo {
+                description: item.text,
+                source: source.path.clone(),
+                line: item.line,
+                error,
+            });
+        }
+    }
+    let failed = todos.iter().filter(|todo| todo.error.is_some()).count();
+    Ok(ExtractSummary {
+        created: todos.len() - failed,
+        failed,
+        skipped_existing,
+        todos,
+    })
+}
+
+/// Returns the title of an nb todo file (`# [ ] Title`).
+fn todo_title(content: &str) -> Option<String> {
+    let (_, body) = notes::split_frontmatter(content);
+    let first = body.lines().find(|line| !line.trim().is_empty())?;
+    let rest = first.trim().strip_prefix('#')?.trim_start();
+    let title = rest
+        .strip_prefix("[ ]")
+        .or_else(|| rest.strip_prefix("[x]"))
+        .or_else(|| rest.strip_prefix("[X]"))?;
+    Some(title.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{checkboxes, select_checkbox, set_checkbox, todo_title};
+
+    #[test]
+    fn checkboxes_find_items_outside_code() {
+        let content = "# Meeting\n- [ ] Email Sam\n  * [x] Book room\n```\n- [ ] not real\n```\n- [] malformed\n";
+        let items = checkboxes(content);
+        assert_eq!(items.len(), 2);
+        assert_eq!((items[0].line, items[0].text.as_str()), (2, "Email Sam"));
+        assert!(items[1].checked);
+    }
+
+    #[test]
+    fn todo_title_reads_nb_todo_heading() {
+        assert_eq!(
+            todo_title("# [ ] Email Sam\n\n## Related\n").as_deref(),
+            Some("Email Sam")
+        );
+        assert_eq!(todo_title("# Plain note\n"), None);
+    }
+
+    #[test]
+    fn set_checkbox_changes_only_the_addressed_line() {
+        let content = "- [ ] one\r\n- [ ] two\n";
+        assert_eq!(set_checkbox(content, 2, true), "- [ ] one\r\n- [x] two\n");
+        assert_eq!(set_checkbox("* [X] done", 1, false), "* [ ] done");
+    }
+
+    #[test]
+    fn select_checkbox_requires_unique_text_match() {
+        let items = checkboxes("- [ ] Email Sam\n- [ ] Email Ana\n- [ ] Book room\n");
+        assert_eq!(
+            select_checkbox(items.clone(), None, Some("book"))
+                .unwrap()
+                .line,
+            3
+        );
+        assert!(select_checkbox(items.clone(), None, Some("email")).is_err());
+        assert_eq!(
+            select_checkbox(items.clone(), Some(2), None).unwrap().line,
+            2
+        );
+        assert!(select_checkbox(items, Some(0), None).is_err());
+    }
+}
@@ -0,0 +1,41 @@
+//! Aliases for subcommands that models commonly guess.
+//!
+//! Models reach for `create`, `read`, or `rm` before they read the command
+//! list. Rather than failing with "unknown subcommand", the dispatcher runs
+//! the canonical command and says which one it ran, so the next call can use
+//! the real name. `view` is not an alias of `show` because it names the
+//! saved-view command.
+
+/// Alias and the canonical subcommand it stands for.
+const ALIASES: &[(&str, &str)] = &[
+    ("create", "add"),
+    ("new", "add"),
+    ("read", "show"),
+    ("get", "show"),
+    ("remove", "delete"),
+    ("rm", "delete"),
+    ("ls", "list"),
+    ("find", "search"),
+];
+
+/// Returns the canonical subcommand for `alias` (without `nb.`), if it is
+/// one.
+pub fn canonical(alias: &str) -> Option<&'static str> {
+    ALIASES
+        .iter()
+        .find(|(name, _)| *name == alias)
+        .map(|(_, canonical)| *canonical)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::canonical;
+
+    #[test]
+    fn maps_aliases_to_canonical_subcommands() {
+        assert_eq!(canonical("rm"), Some("delete"));
+        assert_eq!(canonical("find"), Some("search"));
+        assert_eq!(canonical("view"), None);
+        assert_eq!(canonical("add"), None);
+    }
+}
@@ -0,0 +1,228 @@
+//! Custom commit messages for notebook mutations.
+//!
+//! nb writes generic messages such as `[nb] Add: 12.md`. When a template is
+//! configured, the server rewrites the message of the commit a tool call just
+//! created by amending it. The commit is identified by comparing HEAD before
+//! and after the call, so commits made by anyone else are never touched.
+
+use std::path::Path;
+
+use crate::nb::{NbClient, NbError};
+use crate::output_note;
+
+/// Commands that create commits in the notebook repository.
+const MUTATING_COMMANDS: &[&str] = &[
+    "add",
+    "edit",
+    "delete",
+    "move",
+    "copy_to",
+    "archive_note",
+    "link",
+    "todo",
+    "do",
+    "undo",
+    "bookmark",
+    "mkdir",
+    "import",
+    "load",
+    "summarize",
+    "suggest_tags",
+    "dedupe",
+    "merge",
+    "split",
+    "extract_todos",
+    "todotxt_import",
+    "board_move",
+    "toggle_checkbox",
+    "toc",
+    "save_view",
+    "save_snippet",
+];
+
+/// Returns true if `command` is expected to commit to the notebook.
+pub fn is_mutation(command: &str) -> bool {
+    MUTATING_COMMANDS.contains(&command)
+}
+
+/// Returns true if a call of `command` with `args` is expected to commit,
+/// including queries saving their results with `output_note`.
+pub fn is_mutating_call(command: &str, args: &serde_json::Value) -> bool {
+    is_mutation(command) || output_note::requested(command, args)
+}
+
+/// A commit message template with `{placeholder}` substitution.
+///
+/// Supported placeholders: `{command}`, `{title}`, `{notebook}`, and
+/// `{message}` (nb's original commit subject).
+#[derive(Debug, Clone)]
+pub struct CommitMessageTemplate(String);
+
+impl CommitMessageTemplate {
+    pub fn new(template: impl Into<String>) -> Self {
+        Self(template.into())
+    }
+
+    /// Renders the template on one line, leaving unknown placeholders untouched.
+    pub fn render(&self, vars: &[(&str, &str)]) -> String {
+        let mut rendered = self.0.clone();
+        for (name, value) in vars {
+            rendered = rendered.replace(&format!("{{{}}}", name), value);
+        }
+        rendered.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Picks a human-readable subject for a command from its JSON arguments.
+pub fn subject_from_args(args: &serde_json::Value) -> String {
+    let parsed;
+    let args = match args {
+        serde_json::Value::String(raw) => {
+            parsed = serde_json::from_str(raw).unwrap_or(serde_json::Value::Null);
+            &parsed
+        }
+        other => other,
+    };
+    ["title", "description", "id", "url", "path", "source"]
+        .iter()
+        .find_map(|key| args.get(key).and_then(|v| v.as_str()))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Returns the current HEAD commit of the repository, if any.
+pub async fn head(nb: &NbClient, repository: &Path) -> Result<Option<String>, NbError> {
+    let output = nb
+        .git(repository, &["rev-parse", "--verify", "-q", "HEAD"])
+        .await?;
+    let head = output.stdout.trim();
+    Ok((output.success && !head.is_empty()).then(|| head.to_string()))
+}
+
+/// Rewrites the message of HEAD if it is a single new commit on top of
+/// `before` that was not pushed yet (as nb's auto-sync does right after
+/// committing); amending a pushed commit would diverge from the remote.
+///
+/// Returns true when the commit was amended.
+pub async fn amend_if_new(
+    nb: &NbClient,
+    repository: &Path,
+    before: Option<&str>,
+    template: &CommitMessageTemplate,
+    vars: &[(&str, &str)],
+) -> Result<bool, NbError> {
+    let Some(after) = head(nb, repository).await? else {
+        return Ok(false);
+    };
+    if before == Some(after.as_str()) {
+        return Ok(false);
+    }
+    let parent = nb
+        .git(repository, &["rev-parse", "--verify", "-q", "HEAD^"])
+        .await?;
+    let parent = parent.stdout.trim();
+    if before.is_some_and(|before| before != parent) {
+        // More than one commit appeared (or history moved); leave it alone.
+        return Ok(false);
+    }
+    let pushed = nb
+        .git(
+            repository,
+            &["merge-base", "--is-ancestor", "HEAD", "@{upstream}"],
+        )
+        .await?;
+    if pushed.success {
+        return Ok(false);
+    }
+    let original = nb.git(repository, &["log", "-1", "--format=%s"]).await?;
+    let mut vars = vars.to_vec();
+    vars.push(("message", original.stdout.trim()));
+    let message = template.render(&vars);
+    let amended = nb
+        .git(
+            repository,
+            &["commit", "--amend", "--only", "--no-verify", "-m", &message],
+        )
+        .await?;
+    if !amended.success {
+        return Err(NbError::CommandFailed(format!(
+            "failed to apply commit message template: {}",
+            amended.stderr.trim()
+        )));
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{CommitMessageTemplate, amend_if_new, head, subject_from_args};
+    use crate::executor::FakeExecutor;
+    use crate::nb::NbClient;
+
+    #[test]
+    fn render_substitutes_known_placeholders() {
+        let template = CommitMessageTemplate::new("nb-mcp: {command} {title} {other}");
+        assert_eq!(
+            template.render(&[("command", "add"), ("title", "Plan")]),
+            "nb-mcp: add Plan {other}"
+        );
+    }
+
+    #[test]
+    fn render_collapses_empty_placeholders() {
+        let template = CommitMessageTemplate::new("nb-mcp: {command} {title}");
+        assert_eq!(
+            template.render(&[("command", "mkdir"), ("title", "")]),
+            "nb-mcp: mkdir"
+        );
+    }
+
+    #[test]
+    fn subject_prefers_title_then_identifiers() {
+        let args = serde_json::json!({"id": "12", "content": "x"});
+        assert_eq!(subject_from_args(&args), "12");
+        let args = serde_json::json!(r#"{"title": "Plan", "id": "3"}"#);
+        assert_eq!(subject_from_args(&args), "Plan");
+    }
+
+    #[tokio::test]
+    async fn amend_if_new_leaves_pushed_commits_alone() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-amend-{}", std::process::id()));
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .current_dir(&dir)
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
+        std::fs::create_dir_all(&dir).unwrap();
+        git(&["init", "-q", "--bare", "remote.git"]);
+        git(&["clone", "-q", "remote.git", "notes"]);
+        let repository = dir.join("notes");
+        for (key, value) in [("user.name", "t"), ("user.email", "t@t")] {
+            git(&["-C", "notes", "config", key, value]);
+        }
+        git(&["-C", "notes", "commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&["-C", "notes", "push", "-q", "-u", "origin", "HEAD"]);
+        let nb = NbClient::with_executor(Arc::new(FakeExecutor::new()), None, false);
+        let template = CommitMessageTemplate::new("nb-mcp: {message}");
+
+        let before = head(&nb, &repository).await.unwrap();
+        git(&["-C", "notes", "commit", "-q", "--allow-empty", "-m", "Add"]);
+        git(&["-C", "notes", "push", "-q", "origin", "HEAD"]);
+        let amended = amend_if_new(&nb, &repository, before.as_deref(), &template, &[]);
+        assert!(!amended.await.unwrap());
+
+        let before = head(&nb, &repository).await.unwrap();
+        std::fs::write(repository.join("a.md"), "# A\n").unwrap();
+        git(&["-C", "notes", "add", "a.md"]);
+        git(&["-C", "notes", "commit", "-q", "-m", "Edit"]);
+        let amended = amend_if_new(&nb, &repository, before.as_deref(), &template, &[]);
+        assert!(amended.await.unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,43 @@
+//! Content hashes for optimistic concurrency.
+//!
+//! `show` reports a hash of the note file and `edit` accepts it back as
+//! `expected_hash`; if the file changed in between (e.g., the user edited
+//! it), the edit is refused instead of overwriting their change. The hash is
+//! the file's Git blob id, so it matches `git hash-object <file>`.
+
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+
+/// An `edit` refused because the note changed after it was read.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Conflict {
+    pub id: String,
+    pub expected_hash: String,
+    pub current_hash: String,
+    /// The note as it is now, for merging; absent for binary files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_content: Option<String>,
+}
+
+/// Returns the Git blob id of `content`.
+pub fn of(content: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("blob {}\0", content.len()).as_bytes());
+    hasher.update(content);
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::of;
+
+    #[test]
+    fn of_matches_git_blob_ids() {
+        assert_eq!(of(b""), "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391");
+        assert_eq!(of(b"hello\n"), "ce013625030ba8dba906f756967f9e9ca394464a");
+    }
+}
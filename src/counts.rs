@@ -0,0 +1,77 @@
+//! Match counts for list and search.
+//!
+//! With `count_only`, `nb.list` and `nb.search` return how many items matched
+//! and how they break down by folder and tag, instead of the entries. Agents
+//! use this to decide how to narrow a query without paying for the listing.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::nb_index::{self, ItemResolver};
+use crate::notes;
+
+/// Folder key for items at the notebook root.
+const ROOT_FOLDER: &str = ".";
+
+/// Number of matching items and their breakdown.
+#[derive(Debug, Default, Serialize)]
+pub struct Counts {
+    pub total: usize,
+    /// Items per folder, relative to the notebook root ("." for the root).
+    pub by_folder: BTreeMap<String, usize>,
+    /// Items per tag, without `#`. Items with several tags count once for
+    /// each; items without tags are not counted here.
+    pub by_tag: BTreeMap<String, usize>,
+}
+
+/// Counts the items in nb list or search output.
+///
+/// `folder` is the folder that was listed or searched; bare ids belong to it.
+pub fn count(output: &str, resolver: &mut ItemResolver, folder: Option<&str>) -> Counts {
+    let listed = folder
+        .map(|f| f.trim_matches('/'))
+        .filter(|f| !f.is_empty())
+        .unwrap_or(ROOT_FOLDER);
+    let mut counts = Counts::default();
+    for (id, _) in output.lines().filter_map(nb_index::split_item) {
+        counts.total += 1;
+        let local = id.split_once(':').map_or(id, |(_, rest)| rest);
+        let item_folder = local.rsplit_once('/').map_or(listed, |(dir, _)| dir);
+        *counts.by_folder.entry(item_folder.to_string()).or_default() += 1;
+        let tags = resolver
+            .resolve(id)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|content| notes::extract_tags(&content))
+            .unwrap_or_default();
+        for tag in tags {
+            *counts.by_tag.entry(tag).or_default() += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::count;
+    use crate::nb_index::ItemResolver;
+
+    #[test]
+    fn count_breaks_down_by_folder_and_tag() {
+        let root = std::env::temp_dir().join(format!("nb-mcp-counts-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join(".index"), "a.md\n").unwrap();
+        std::fs::write(root.join("docs/.index"), "b.md\n").unwrap();
+        std::fs::write(root.join("a.md"), "# A\n\n#client #urgent\n").unwrap();
+        std::fs::write(root.join("docs/b.md"), "# B\n\n#client\n").unwrap();
+        let output = "[1] A\n[docs/1] B\n-----\n3:#client\n";
+        let mut resolver = ItemResolver::new(&root, None);
+        let counts = count(output, &mut resolver, None);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(counts.total, 2);
+        assert_eq!(counts.by_folder["."], 1);
+        assert_eq!(counts.by_folder["docs"], 1);
+        assert_eq!(counts.by_tag["client"], 2);
+        assert_eq!(counts.by_tag["urgent"], 1);
+    }
+}
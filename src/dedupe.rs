@@ -0,0 +1,286 @@
+//! Duplicate note detection and merging.
+//!
+//! Notes are compared by their bodies with frontmatter removed, lowercased,
+//! and with whitespace collapsed. Identical bodies are found by hashing;
+//! near-duplicates by the Jaccard similarity of their three-word shingles.
+//! Each cluster is led by its first note by path, and holds the later notes
+//! that pass either test against that note itself, so a chain of notes that
+//! each resemble the next does not pull unrelated notes together. Merging
+//! appends the content of non-identical members to the kept note under a
+//! header and records one git checkpoint, then deletes the duplicates
+//! through `nb delete`.
+
+use std::{
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::args::DedupeArgs;
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// Default minimum similarity for near-duplicates.
+const DEFAULT_THRESHOLD: f64 = 0.8;
+
+/// Number of words per shingle.
+const SHINGLE_WORDS: usize = 3;
+
+/// A note found to duplicate the kept note of its cluster.
+#[derive(Debug, Serialize)]
+pub struct Duplicate {
+    pub path: String,
+    /// Jaccard similarity to the kept note, from 0 to 1.
+    pub similarity: f64,
+    /// Whether the normalized content is identical to the kept note.
+    pub exact: bool,
+}
+
+/// A group of duplicate notes.
+#[derive(Debug, Serialize)]
+pub struct DuplicateCluster {
+    /// Note that survives a merge.
+    pub keep: String,
+    pub duplicates: Vec<Duplicate>,
+}
+
+/// Result of `nb.dedupe`.
+#[derive(Debug, Serialize)]
+pub struct DedupeReport {
+    /// Number of notes compared.
+    pub scanned: usize,
+    pub clusters: Vec<DuplicateCluster>,
+    /// Whether the clusters were merged.
+    pub merged: bool,
+    /// Number of duplicate notes removed by the merge.
+    pub removed: usize,
+}
+
+/// A note prepared for comparison.
+struct Fingerprint {
+    hash: u64,
+    shingles: HashSet<u64>,
+}
+
+/// Finds duplicate notes and, if requested, merges them.
+///
+/// Callers must obtain confirmation before passing `merge: true`.
+pub async fn dedupe(nb: &NbClient, args: &DedupeArgs) -> Result<DedupeReport, NbError> {
+    let notebook = args.notebook.as_deref();
+    let root = nb.notebook_path(notebook).await?;
+    let folder = args.folder.clone();
+    let threshold = args.threshold.unwrap_or(DEFAULT_THRESHOLD).clamp(0.0, 1.0);
+    let merge = args.merge;
+    let (scanned, clusters) = tokio::task::spawn_blocking(move || {
+        let files = notes::read_notes(&root, folder.as_deref())?;
+        let clusters = find_clusters(&files, threshold);
+        if merge {
+            merge_clusters(&root, &files, &clusters)?;
+        }
+        Ok::<_, std::io::Error>((files.len(), clusters))
+    })
+    .await
+    .map_err(|err| NbError::CommandFailed(format!("dedupe task failed: {err}")))??;
+    let duplicates = clusters
+        .iter()
+        .flat_map(|cluster| &cluster.duplicates)
+        .map(|duplicate| duplicate.path.as_str())
+        .collect::<Vec<_>>();
+    let merged = merge && !duplicates.is_empty();
+    let mut removed = 0;
+    if merged {
+        nb.checkpoint(
+            &format!("[nb-mcp] Merge {} duplicate notes", duplicates.len()),
+            notebook,
+        )
+        .await?;
+        for path in duplicates {
+            nb.delete(path, notebook).await?;
+            removed += 1;
+        }
+    }
+    Ok(DedupeReport {
+        scanned,
+        clusters,
+        merged,
+        removed,
+    })
+}
+
+/// Normalizes a note body for comparison.
+fn normalize(content: &str) -> String {
+    let (_, body) = notes::split_frontmatter(content);
+    body.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn fingerprint(content: &str) -> Fingerprint {
+    let normalized = normalize(content);
+    let words: Vec<&str> = normalized.split(' ').collect();
+    let shingles = if normalized.is_empty() {
+        HashSet::new()
+    } else if words.len() < SHINGLE_WORDS {
+        HashSet::from([hash_of(&normalized)])
+    } else {
+        words.windows(SHINGLE_WORDS).map(hash_of).collect()
+    };
+    Fingerprint {
+        hash: hash_of(&normalized),
+        shingles,
+    }
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let shared = a.intersection(b).count();
+    let total = a.len() + b.len() - shared;
+    if total == 0 {
+        1.0
+    } else {
+        shared as f64 / total as f64
+    }
+}
+
+/// Returns true if two notes are identical or at least `threshold` similar.
+fn similar(a: &Fingerprint, b: &Fingerprint, threshold: f64) -> bool {
+    // Empty notes are not duplicates of each other.
+    if a.shingles.is_empty() || b.shingles.is_empty() {
+        return false;
+    }
+    let (small, large) = (
+        a.shingles.len().min(b.shingles.len()),
+        b.shingles.len().max(a.shingles.len()),
+    );
+    // Jaccard similarity can be no higher than the size ratio.
+    a.hash == b.hash
+        || (small as f64 / large as f64 >= threshold
+            && jaccard(&a.shingles, &b.shingles) >= threshold)
+}
+
+/// Groups notes whose content is identical or at least `threshold` similar
+/// to the first note of their group.
+///
+/// `files` must be sorted by path, so the first member of each cluster is
+/// the kept note.
+fn find_clusters(files: &[notes::NoteFile], threshold: f64) -> Vec<DuplicateCluster> {
+    let prints: Vec<Fingerprint> = files
+        .iter()
+        .map(|file| fingerprint(&file.content))
+        .collect();
+    let mut clustered = vec![false; prints.len()];
+    let mut clusters = Vec::new();
+    for keep in 0..prints.len() {
+        if clustered[keep] {
+            continue;
+        }
+        let mut duplicates = Vec::new();
+        for index in (keep + 1)..prints.len() {
+            if clustered[index] || !similar(&prints[keep], &prints[index], threshold) {
+                continue;
+            }
+            clustered[index] = true;
+            duplicates.push(Duplicate {
+                path: files[index].path.clone(),
+                similarity: (jaccard(&prints[keep].shingles, &prints[index].shingles) * 100.0)
+                    .round()
+                    / 100.0,
+                exact: prints[keep].hash == prints[index].hash,
+            });
+        }
+        if !duplicates.is_empty() {
+            clusters.push(DuplicateCluster {
+                keep: files[keep].path.clone(),
+                duplicates,
+            });
+        }
+    }
+    clusters
+}
+
+/// Appends the non-identical duplicates of each cluster to its kept note.
+fn merge_clusters(
+    root: &Path,
+    files: &[notes::NoteFile],
+    clusters: &[DuplicateCluster],
+) -> std::io::Result<()> {
+    let content_of = |path: &str| {
+        files
+            .iter()
+            .find(|file| file.path == path)
+            .map(|file| file.content.as_str())
+            .unwrap_or_default()
+    };
+    for cluster in clusters {
+        let mut merged = content_of(&cluster.keep).to_string();
+        for duplicate in cluster.duplicates.iter().filter(|d| !d.exact) {
+            let (_, body) = notes::split_frontmatter(content_of(&duplicate.path));
+            merged =
+                notes::append_section(&merged, &format!("Merged from {}", duplicate.path), body);
+        }
+        std::fs::write(root.join(&cluster.keep), merged)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_clusters;
+    use crate::notes::NoteFile;
+
+    fn note(path: &str, content: &str) -> NoteFile {
+        NoteFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn find_clusters_links_exact_and_similar_notes() {
+        let body = "the quick brown fox jumps over the lazy dog near the river bank today";
+        let files = vec![
+            note("a.md", body),
+            note(
+                "b.md",
+                &format!("---\ntitle: B\n---\n{}", body.to_uppercase()),
+            ),
+            note("c.md", &format!("{body} again")),
+            note("d.md", "something else entirely"),
+        ];
+        let clusters = find_clusters(&files, 0.8);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].keep, "a.md");
+        let duplicates = &clusters[0].duplicates;
+        assert_eq!(duplicates.len(), 2);
+        assert!(duplicates[0].exact);
+        assert!(!duplicates[1].exact && duplicates[1].similarity >= 0.8);
+    }
+
+    #[test]
+    fn find_clusters_does_not_chain_similar_notes() {
+        let words = (0..20).map(|n| format!("w{n}")).collect::<Vec<_>>();
+        // b shares most of a, and c most of b, but c little of a.
+        let files = vec![
+            note("a.md", &words[0..12].join(" ")),
+            note("b.md", &words[1..13].join(" ")),
+            note("c.md", &words[2..14].join(" ")),
+        ];
+        let clusters = find_clusters(&files, 0.75);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].keep, "a.md");
+        let paths = clusters[0]
+            .duplicates
+            .iter()
+            .map(|duplicate| duplicate.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["b.md"]);
+    }
+}
@@ -0,0 +1,168 @@
+//! Portable JSON dump and load of notebook contents.
+//!
+//! A dump is a single JSON document listing every text note in a notebook
+//! (or folder) with its path, frontmatter, body, and tags. Loading a dump
+//! recreates each note through `nb add` so that nb indexes and commits it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+use crate::session_folder;
+
+/// Current dump format version.
+pub const DUMP_VERSION: u32 = 1;
+
+/// A serialized notebook.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NotebookDump {
+    /// Dump format version.
+    #[serde(default = "default_version")]
+    pub version: u32,
+    /// Notes contained in the dump.
+    #[serde(default)]
+    pub notes: Vec<DumpedNote>,
+}
+
+/// A single note within a dump.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DumpedNote {
+    /// Path relative to the notebook root (e.g., `docs/design.md`).
+    pub path: String,
+    /// Raw YAML frontmatter without `---` delimiters, if present.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frontmatter: Option<String>,
+    /// Note body following the frontmatter.
+    #[serde(default)]
+    pub body: String,
+    /// Hashtags found in the note (informational; not used when loading).
+    #[serde(default, skip_deserializing)]
+    pub tags: Vec<String>,
+}
+
+fn default_version() -> u32 {
+    DUMP_VERSION
+}
+
+/// Outcome of loading a single note.
+#[derive(Debug, Serialize)]
+pub struct LoadResult {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Summary of a load operation.
+#[derive(Debug, Serialize)]
+pub struct LoadSummary {
+    pub created: usize,
+    pub failed: usize,
+    pub results: Vec<LoadResult>,
+}
+
+impl DumpedNote {
+    fn from_file(file: notes::NoteFile) -> Self {
+        let (frontmatter, body) = notes::split_frontmatter(&file.content);
+        Self {
+            tags: notes::extract_tags(&file.content),
+            frontmatter: frontmatter.map(String::from),
+            body: body.to_string(),
+            path: file.path,
+        }
+    }
+
+    fn content(&self) -> String {
+        notes::join_frontmatter(self.frontmatter.as_deref(), &self.body)
+    }
+}
+
+/// Produces a dump of all text notes in a notebook, optionally scoped to a folder.
+pub async fn dump(
+    nb: &NbClient,
+    folder: Option<&str>,
+    notebook: Option<&str>,
+) -> Result<NotebookDump, NbError> {
+    let root = nb.notebook_path(notebook).await?;
+    let folder = folder.map(String::from);
+    let files = tokio::task::spawn_blocking(move || notes::read_notes(&root, folder.as_deref()))
+        .await
+        .map_err(|err| NbError::CommandFailed(format!("dump task failed: {err}")))??;
+    Ok(NotebookDump {
+        version: DUMP_VERSION,
+        notes: files.into_iter().map(DumpedNote::from_file).collect(),
+    })
+}
+
+/// Recreates the notes in a dump, optionally beneath a target folder.
+///
+/// Notes are created one at a time; failures are recorded per note rather
+/// than aborting the whole load.
+pub async fn load(
+    nb: &NbClient,
+    dump: &NotebookDump,
+    folder: Option<&str>,
+    notebook: Option<&str>,
+) -> Result<LoadSummary, NbError> {
+    if dump.version > DUMP_VERSION {
+        return Err(NbError::CommandFailed(format!(
+            "unsupported dump version {}; expected {} or lower",
+            dump.version, DUMP_VERSION
+        )));
+    }
+    let mut results = Vec::with_capacity(dump.notes.len());
+    for note in &dump.notes {
+        let (path, error) = match target_path(folder, &note.path) {
+            Ok(path) => {
+                let error = match nb.add_at_path(&path, &note.content(), notebook).await {
+                    Ok(_) => None,
+                    Err(err) => Some(err.to_string()),
+                };
+                (path, error)
+            }
+            Err(message) => (note.path.clone(), Some(message)),
+        };
+        results.push(LoadResult { path, error });
+    }
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    Ok(LoadSummary {
+        created: results.len() - failed,
+        failed,
+        results,
+    })
+}
+
+/// Joins an optional target folder with a note path from a dump.
+///
+/// Returns why the path is refused if it is absolute or leaves the notebook.
+fn target_path(folder: Option<&str>, path: &str) -> Result<String, String> {
+    let path = notes::relative_file_path(path)?;
+    match session_folder::normalize(folder)? {
+        Some(folder) => Ok(format!("{folder}/{path}")),
+        None => Ok(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NotebookDump, target_path};
+
+    #[test]
+    fn target_path_prefixes_folder() {
+        assert_eq!(
+            target_path(Some("restored/"), "docs/a.md").as_deref(),
+            Ok("restored/docs/a.md")
+        );
+        assert_eq!(target_path(Some(""), "./a.md").as_deref(), Ok("a.md"));
+        assert!(target_path(None, "/docs/a.md").is_err());
+        assert!(target_path(None, "docs/../../a.md").is_err());
+        assert!(target_path(Some("../x"), "a.md").is_err());
+    }
+
+    #[test]
+    fn dump_parses_minimal_document() {
+        let dump: NotebookDump =
+            serde_json::from_str(r#"{"notes": [{"path": "a.md", "body": "hi"}]}"#).unwrap();
+        assert_eq!(dump.version, 1);
+        assert_eq!(dump.notes[0].content(), "hi");
+    }
+}
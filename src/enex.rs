@@ -0,0 +1,194 @@
+//! Evernote ENEX import.
+//!
+//! Parses `.enex` export files, converts each note's ENML body to Markdown,
+//! and writes the notes into the notebook with tags and timestamps preserved
+//! in frontmatter. All notes from one export land in a single commit.
+
+use quick_xml::{Reader, events::Event};
+
+use crate::batch::{self, BatchSummary, PendingNote};
+use crate::html;
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// A note parsed from an ENEX export.
+#[derive(Debug, Default, PartialEq)]
+pub struct EnexNote {
+    pub title: String,
+    /// ENML (XHTML) body.
+    pub content: String,
+    /// Creation time in RFC 3339 form.
+    pub created: Option<String>,
+    /// Last update time in RFC 3339 form.
+    pub updated: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Parses every `<note>` element in an ENEX document.
+pub fn parse(xml: &str) -> Result<Vec<EnexNote>, quick_xml::Error> {
+    let mut reader = Reader::from_str(xml);
+    let mut notes = Vec::new();
+    let mut current: Option<EnexNote> = None;
+    let mut field: Option<String> = None;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => {
+                let name = String::from_utf8_lossy(start.local_name().as_ref()).into_owned();
+                if name == "note" {
+                    current = Some(EnexNote::default());
+                } else if current.is_some() {
+                    field = Some(name);
+                    text.clear();
+                }
+            }
+            Event::Text(value) if field.is_some() => text.push_str(&value.unescape()?),
+            Event::CData(value) if field.is_some() => {
+                text.push_str(&String::from_utf8_lossy(&value))
+            }
+            Event::End(end) => {
+                let name = String::from_utf8_lossy(end.local_name().as_ref()).into_owned();
+                if name == "note" {
+                    notes.extend(current.take());
+                } else if let Some(note) = current.as_mut() {
+                    if field.as_deref() == Some(name.as_str()) {
+                        let value = std::mem::take(&mut text);
+                        match name.as_str() {
+                            "title" => note.title = value.trim().to_string(),
+                            "content" => note.content = value,
+                            "created" => note.created = timestamp(&value),
+                            "updated" => note.updated = timestamp(&value),
+                            "tag" => note.tags.push(value.trim().to_string()),
+                            _ => {}
+                        }
+                    }
+                    field = None;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(notes)
+}
+
+/// Converts an ENEX timestamp (`20130730T205204Z`) to RFC 3339.
+fn timestamp(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let valid = raw.len() == 16
+        && raw.as_bytes()[8] == b'T'
+        && raw.ends_with('Z')
+        && raw[..8].bytes().all(|b| b.is_ascii_digit())
+        && raw[9..15].bytes().all(|b| b.is_ascii_digit());
+    if !valid {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &raw[0..4],
+        &raw[4..6],
+        &raw[6..8],
+        &raw[9..11],
+        &raw[11..13],
+        &raw[13..15]
+    ))
+}
+
+/// Renders a parsed note as Markdown with YAML frontmatter.
+fn render(note: &EnexNote) -> String {
+    let mut frontmatter = vec![format!("title: {}", notes::yaml_string(&note.title))];
+    if let Some(created) = &note.created {
+        frontmatter.push(format!("created: {}", created));
+    }
+    if let Some(updated) = &note.updated {
+        frontmatter.push(format!("updated: {}", updated));
+    }
+    if !note.tags.is_empty() {
+        let tags = note
+            .tags
+            .iter()
+            .map(|t| notes::yaml_string(t))
+            .collect::<Vec<_>>()
+            .join(", ");
+        frontmatter.push(format!("tags: [{}]", tags));
+    }
+
+    let mut body = format!("# {}\n\n", note.title);
+    if !note.tags.is_empty() {
+        let hashtags = note
+            .tags
+            .iter()
+            .map(|t| format!("#{}", t.replace(char::is_whitespace, "-")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        body.push_str(&hashtags);
+        body.push_str("\n\n");
+    }
+    body.push_str(&html::to_markdown(&note.content));
+    notes::join_frontmatter(Some(&frontmatter.join("\n")), &body)
+}
+
+/// Imports every note in an ENEX file into the notebook.
+pub async fn import(
+    nb: &NbClient,
+    source: &str,
+    folder: Option<&str>,
+    notebook: Option<&str>,
+) -> Result<BatchSummary, NbError> {
+    let xml = tokio::fs::read_to_string(source).await?;
+    let parsed =
+        parse(&xml).map_err(|err| NbError::CommandFailed(format!("invalid ENEX file: {err}")))?;
+    let pending = parsed
+        .iter()
+        .map(|note| PendingNote {
+            path: format!("{}.md", notes::slugify(&note.title)),
+            content: render(note).into_bytes(),
+            title: Some(note.title.clone()),
+        })
+        .collect::<Vec<_>>();
+    let message = format!("[nb] Import {} notes from Evernote", pending.len());
+    batch::write_notes(nb, pending, folder, &message, notebook).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, render, timestamp};
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-export SYSTEM "http://xml.evernote.com/pub/evernote-export3.dtd">
+<en-export export-date="20240101T000000Z">
+  <note>
+    <title>Trip &amp; Plans</title>
+    <content><![CDATA[<?xml version="1.0" encoding="UTF-8"?><en-note><div>Pack <b>bags</b></div></en-note>]]></content>
+    <created>20130730T205204Z</created>
+    <tag>travel</tag>
+    <tag>to do</tag>
+  </note>
+</en-export>"#;
+
+    #[test]
+    fn parse_extracts_note_fields() {
+        let notes = parse(SAMPLE).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].title, "Trip & Plans");
+        assert_eq!(notes[0].created.as_deref(), Some("2013-07-30T20:52:04Z"));
+        assert_eq!(notes[0].tags, vec!["travel", "to do"]);
+    }
+
+    #[test]
+    fn render_writes_frontmatter_and_markdown() {
+        let notes = parse(SAMPLE).unwrap();
+        assert_eq!(
+            render(&notes[0]),
+            "---\ntitle: \"Trip & Plans\"\ncreated: 2013-07-30T20:52:04Z\n\
+             tags: [\"travel\", \"to do\"]\n---\n# Trip & Plans\n\n#travel #to-do\n\n\
+             Pack **bags**\n"
+        );
+    }
+
+    #[test]
+    fn timestamp_rejects_malformed_values() {
+        assert_eq!(timestamp("2013-07-30"), None);
+    }
+}
@@ -0,0 +1,439 @@
+//! Execution strategies for nb invocations.
+//!
+//! [`NbClient`](crate::nb::NbClient) builds argument vectors and delegates
+//! running them to an [`NbExecutor`]. [`ProcessExecutor`] spawns the real `nb`
+//! CLI; [`FakeExecutor`] answers from scripted responses so the client and
+//! MCP dispatch can be exercised without nb installed.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    process::Stdio,
+    sync::{LazyLock, Mutex},
+};
+
+use regex::Regex;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::nb::{NbError, apply_git_signing_env, redacted_args};
+
+/// Regex to match ANSI/ISO 2022 escape sequences.
+///
+/// Covers:
+/// - Fe sequences: `ESC [@-Z\-_]` (single byte after ESC)
+/// - CSI sequences: `ESC [ ... m` (SGR colors, cursor control, etc.)
+/// - nF sequences: `ESC [ -/]* [0-~]` (character set designation like `ESC ( B`)
+static ANSI_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1B(?:[@-Z\\-_]|\[[0-?]*[ -/]*[@-~]|[ -/]*[0-~])").unwrap());
+
+/// Strip ANSI escape sequences from text.
+fn strip_ansi(text: &str) -> String {
+    ANSI_REGEX.replace_all(text, "").into_owned()
+}
+
+/// Future returned by [`NbExecutor::exec`].
+pub type ExecFuture<'a> = Pin<Box<dyn Future<Output = Result<String, NbError>> + Send + 'a>>;
+
+/// Runs nb with a prepared argument vector and returns its stdout.
+///
+/// Implementations report a failed invocation as [`NbError::CommandFailed`]
+/// carrying nb's error message.
+pub trait NbExecutor: Send + Sync {
+    fn exec(&self, args: Vec<String>) -> ExecFuture<'_>;
+
+    /// Runs nb with `env` applied over the executor's own environment.
+    ///
+    /// Executors that do not spawn processes may ignore `env`.
+    fn exec_with_env(&self, args: Vec<String>, env: Vec<(String, EnvValue)>) -> ExecFuture<'_> {
+        let _ = env;
+        self.exec(args)
+    }
+
+    /// Runs nb like [`exec_with_env`](Self::exec_with_env), writing `input`
+    /// (a password for nb's prompt) to its standard input. `input` is zeroed
+    /// once written.
+    ///
+    /// Executors that do not spawn processes may ignore `input`.
+    fn exec_with_input(
+        &self,
+        args: Vec<String>,
+        env: Vec<(String, EnvValue)>,
+        mut input: Vec<u8>,
+    ) -> ExecFuture<'_> {
+        input.fill(0);
+        self.exec_with_env(args, env)
+    }
+}
+
+/// How an nb subprocess gets an environment variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvValue {
+    /// Set the variable to this value.
+    Set(String),
+    /// Remove the variable.
+    Remove,
+    /// Pass the server's own value through.
+    Inherit,
+}
+
+impl EnvValue {
+    /// Parses `KEY=VALUE` (set), `KEY=` (remove), or `KEY` (inherit).
+    pub fn parse_override(spec: &str) -> Result<(String, Self), NbError> {
+        let (key, value) = match spec.split_once('=') {
+            Some((key, "")) => (key, Self::Remove),
+            Some((key, value)) => (key, Self::Set(value.to_string())),
+            None => (spec, Self::Inherit),
+        };
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(NbError::CommandFailed(format!(
+                "invalid nb environment override {spec:?}; expected KEY=VALUE, KEY=, or KEY"
+            )));
+        }
+        Ok((key.to_string(), value))
+    }
+}
+
+/// Environment every nb subprocess starts from, so its output parses the same
+/// whatever the user's shell sets: English messages, no color, no editor or
+/// pager, and no sync on every write (the server has `push` and `pull`).
+const DEFAULT_ENV: &[(&str, Option<&str>)] = &[
+    ("LC_ALL", Some("C")),
+    ("NO_COLOR", Some("1")),
+    ("NB_AUTO_SYNC", Some("0")),
+    ("EDITOR", None),
+    ("VISUAL", None),
+    ("PAGER", None),
+];
+
+/// Executes nb as a subprocess.
+#[derive(Debug, Default)]
+pub struct ProcessExecutor {
+    /// Disable Git commit and tag signing for `nb` subprocesses.
+    disable_git_signing: bool,
+    /// Overrides of the default environment, by variable.
+    env_overrides: Vec<(String, EnvValue)>,
+}
+
+impl ProcessExecutor {
+    pub fn new(disable_git_signing: bool) -> Self {
+        Self {
+            disable_git_signing,
+            env_overrides: Vec::new(),
+        }
+    }
+
+    /// Applies `overrides` over the default nb environment; later entries win.
+    pub fn with_env_overrides(mut self, overrides: Vec<(String, EnvValue)>) -> Self {
+        self.env_overrides = overrides;
+        self
+    }
+
+    /// Returns the environment changes for an nb subprocess, in order, with
+    /// `extra` applied last.
+    fn environment(&self, extra: &[(String, EnvValue)]) -> Vec<(String, EnvValue)> {
+        let mut environment: Vec<(String, EnvValue)> = DEFAULT_ENV
+            .iter()
+            .map(|(key, value)| {
+                let value =
+                    value.map_or(EnvValue::Remove, |value| EnvValue::Set(value.to_string()));
+                (key.to_string(), value)
+            })
+            .collect();
+        for (key, value) in self.env_overrides.iter().chain(extra) {
+            environment.retain(|(existing, _)| existing != key);
+            environment.push((key.clone(), value.clone()));
+        }
+        environment
+    }
+}
+
+impl NbExecutor for ProcessExecutor {
+    fn exec(&self, args: Vec<String>) -> ExecFuture<'_> {
+        self.exec_with_env(args, Vec::new())
+    }
+
+    fn exec_with_env(&self, args: Vec<String>, env: Vec<(String, EnvValue)>) -> ExecFuture<'_> {
+        self.run(args, env, None)
+    }
+
+    fn exec_with_input(
+        &self,
+        args: Vec<String>,
+        env: Vec<(String, EnvValue)>,
+        input: Vec<u8>,
+    ) -> ExecFuture<'_> {
+        self.run(args, env, Some(input))
+    }
+}
+
+impl ProcessExecutor {
+    /// Spawns nb, feeding it `input` when given; stdin is otherwise closed.
+    fn run(
+        &self,
+        args: Vec<String>,
+        env: Vec<(String, EnvValue)>,
+        input: Option<Vec<u8>>,
+    ) -> ExecFuture<'_> {
+        Box::pin(async move {
+            tracing::debug!(args = ?redacted_args(&args), "executing nb command");
+            let started = std::time::Instant::now();
+            let mut command = Command::new("nb");
+            command
+                .args(&args)
+                // Prevent TTY hangs; a password is piped in instead.
+                .stdin(if input.is_some() {
+                    Stdio::piped()
+                } else {
+                    Stdio::null()
+                })
+                // Git credential prompts would block clones and syncs forever.
+                .env("GIT_TERMINAL_PROMPT", "0")
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                // Abandoned calls (e.g., at shutdown) must not orphan nb.
+                .kill_on_drop(true);
+            for (key, value) in self.environment(&env) {
+                match value {
+                    EnvValue::Set(value) => command.env(key, value),
+                    EnvValue::Remove => command.env_remove(key),
+                    EnvValue::Inherit => &mut command,
+                };
+            }
+            if self.disable_git_signing {
+                apply_git_signing_env(&mut command);
+            }
+            let mut child = command.spawn().map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    NbError::NotFound
+                } else {
+                    NbError::Io(e)
+                }
+            })?;
+            if let Some(mut input) = input {
+                let written = match child.stdin.take() {
+                    Some(mut stdin) => stdin.write_all(&input).await,
+                    None => Ok(()),
+                };
+                input.fill(0);
+                // nb may exit without reading, e.g. for a missing note.
+                if let Err(err) = written {
+                    tracing::debug!(%err, "nb did not read its input");
+                }
+            }
+            let output = child.wait_with_output().await?;
+            tracing::debug!(
+                args = ?redacted_args(&args),
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                status = ?output.status.code(),
+                "nb command finished"
+            );
+
+            if output.status.success() {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                Ok(strip_ansi(&stdout))
+            } else {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                // nb sometimes writes errors to stdout
+                let msg = if stderr.is_empty() {
+                    strip_ansi(&stdout)
+                } else {
+                    strip_ansi(&stderr)
+                };
+                Err(NbError::CommandFailed(msg))
+            }
+        })
+    }
+}
+
+/// A scripted response for [`FakeExecutor`].
+#[derive(Debug, Clone)]
+struct Response {
+    args: Vec<String>,
+    result: Result<String, String>,
+}
+
+/// In-memory executor returning scripted responses and recording calls.
+///
+/// Responses are matched by argument prefix; the most recently registered
+/// match wins. Unmatched invocations fail with [`NbError::CommandFailed`].
+#[derive(Debug, Default)]
+pub struct FakeExecutor {
+    responses: Mutex<Vec<Response>>,
+    calls: Mutex<Vec<Vec<String>>>,
+    environments: Mutex<Vec<Vec<(String, EnvValue)>>>,
+    inputs: Mutex<Vec<Option<String>>>,
+}
+
+impl FakeExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an existing notebook, answering `notebooks show <name> --path`.
+    pub fn notebook(self, name: &str, path: &str) -> Self {
+        self.respond(&["notebooks", "show", name, "--path"], path)
+    }
+
+    /// Answers invocations starting with `args` with `output`.
+    pub fn respond(self, args: &[&str], output: &str) -> Self {
+        self.push(args, Ok(output.to_string()));
+        self
+    }
+
+    /// Fails invocations starting with `args` with `message`.
+    pub fn fail(self, args: &[&str], message: &str) -> Self {
+        self.push(args, Err(message.to_string()));
+        self
+    }
+
+    /// Returns every argument vector executed so far.
+    pub fn calls(&self) -> Vec<Vec<String>> {
+        self.calls
+            .lock()
+            .expect("fake executor lock poisoned")
+            .clone()
+    }
+
+    /// Returns the per-call environment of every invocation, in call order.
+    pub fn environments(&self) -> Vec<Vec<(String, EnvValue)>> {
+        self.environments
+            .lock()
+            .expect("fake executor lock poisoned")
+            .clone()
+    }
+
+    /// Returns the standard input of every invocation, in call order.
+    pub fn inputs(&self) -> Vec<Option<String>> {
+        self.inputs
+            .lock()
+            .expect("fake executor lock poisoned")
+            .clone()
+    }
+
+    fn push(&self, args: &[&str], result: Result<String, String>) {
+        self.responses
+            .lock()
+            .expect("fake executor lock poisoned")
+            .push(Response {
+                args: args.iter().map(|a| a.to_string()).collect(),
+                result,
+            });
+    }
+}
+
+impl NbExecutor for FakeExecutor {
+    fn exec(&self, args: Vec<String>) -> ExecFuture<'_> {
+        self.exec_with_env(args, Vec::new())
+    }
+
+    fn exec_with_env(&self, args: Vec<String>, env: Vec<(String, EnvValue)>) -> ExecFuture<'_> {
+        self.record(args, env, None)
+    }
+
+    fn exec_with_input(
+        &self,
+        args: Vec<String>,
+        env: Vec<(String, EnvValue)>,
+        input: Vec<u8>,
+    ) -> ExecFuture<'_> {
+        self.record(
+            args,
+            env,
+            Some(String::from_utf8_lossy(&input).into_owned()),
+        )
+    }
+}
+
+impl FakeExecutor {
+    fn record(
+        &self,
+        args: Vec<String>,
+        env: Vec<(String, EnvValue)>,
+        input: Option<String>,
+    ) -> ExecFuture<'_> {
+        self.inputs
+            .lock()
+            .expect("fake executor lock poisoned")
+            .push(input);
+        self.environments
+            .lock()
+            .expect("fake executor lock poisoned")
+            .push(env);
+        let response = self
+            .responses
+            .lock()
+            .expect("fake executor lock poisoned")
+            .iter()
+            .rev()
+            .find(|r| args.starts_with(&r.args))
+            .map(|r| r.result.clone());
+        self.calls
+            .lock()
+            .expect("fake executor lock poisoned")
+            .push(args.clone());
+        Box::pin(async move {
+            match response {
+                Some(Ok(output)) => Ok(output),
+                Some(Err(message)) => Err(NbError::CommandFailed(message)),
+                None => Err(NbError::CommandFailed(format!(
+                    "no fake response for: nb {}",
+                    args.join(" ")
+                ))),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EnvValue, FakeExecutor, NbExecutor, ProcessExecutor, strip_ansi};
+
+    #[test]
+    fn environment_applies_overrides_over_defaults() {
+        let overrides = [
+            "NB_AUTO_SYNC",
+            "LC_ALL=C.UTF-8",
+            "NO_COLOR=",
+            "NB_DIR=/notes",
+        ]
+        .iter()
+        .map(|spec| EnvValue::parse_override(spec).unwrap())
+        .collect();
+        let environment = ProcessExecutor::new(false)
+            .with_env_overrides(overrides)
+            .environment(&[("NB_DIR".to_string(), EnvValue::Inherit)]);
+        let value = |key: &str| {
+            environment
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.clone())
+        };
+        assert_eq!(value("LC_ALL"), Some(EnvValue::Set("C.UTF-8".to_string())));
+        assert_eq!(value("NO_COLOR"), Some(EnvValue::Remove));
+        assert_eq!(value("NB_AUTO_SYNC"), Some(EnvValue::Inherit));
+        assert_eq!(value("PAGER"), Some(EnvValue::Remove));
+        assert_eq!(value("NB_DIR"), Some(EnvValue::Inherit));
+        assert!(EnvValue::parse_override("=1").is_err());
+    }
+
+    #[test]
+    fn strip_ansi_removes_color_codes() {
+        assert_eq!(strip_ansi("\x1b[1;32mok\x1b[0m"), "ok");
+    }
+
+    #[tokio::test]
+    async fn fake_executor_matches_latest_prefix() {
+        let fake = FakeExecutor::new()
+            .respond(&["list"], "first")
+            .respond(&["list", "work:"], "second");
+        let output = fake
+            .exec(vec!["list".to_string(), "work:".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(output, "second");
+        assert!(fake.exec(vec!["show".to_string()]).await.is_err());
+        assert_eq!(fake.calls().len(), 2);
+    }
+}
@@ -0,0 +1,171 @@
+//! Binary file detection for note entries.
+//!
+//! nb notebooks can hold imported PDFs, images, and other attachments. `nb
+//! show` decodes those lossily into garbage text, so `show` first checks the
+//! entry's extension and leading bytes and reports binary files as metadata.
+
+use std::io::Read;
+use std::path::Path;
+
+use serde::Serialize;
+
+/// Number of leading bytes inspected when sniffing content.
+const SNIFF_BYTES: usize = 8000;
+
+/// Known binary extensions and their media types.
+const BINARY_EXTENSIONS: &[(&str, &str)] = &[
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("bmp", "image/bmp"),
+    ("ico", "image/x-icon"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("heic", "image/heic"),
+    ("mp3", "audio/mpeg"),
+    ("m4a", "audio/mp4"),
+    ("wav", "audio/wav"),
+    ("ogg", "audio/ogg"),
+    ("mp4", "video/mp4"),
+    ("mov", "video/quicktime"),
+    ("webm", "video/webm"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("7z", "application/x-7z-compressed"),
+    ("epub", "application/epub+zip"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("doc", "application/msword"),
+    ("xls", "application/vnd.ms-excel"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+];
+
+/// Known text extensions and their media types.
+const TEXT_EXTENSIONS: &[(&str, &str)] = &[
+    ("md", "text/markdown"),
+    ("markdown", "text/markdown"),
+    ("txt", "text/plain"),
+    ("org", "text/org"),
+    ("adoc", "text/asciidoc"),
+    ("rst", "text/x-rst"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("csv", "text/csv"),
+    ("json", "application/json"),
+    ("toml", "application/toml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+];
+
+/// Metadata returned in place of binary note content.
+#[derive(Debug, Serialize)]
+pub struct BinaryNote {
+    pub binary: bool,
+    /// Media type guessed from the extension or content.
+    #[serde(rename = "type")]
+    pub media_type: &'static str,
+    /// File size in bytes.
+    pub size: u64,
+    pub path: String,
+    /// File content, present only when requested with `raw_base64` and
+    /// within the output size limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_base64: Option<String>,
+    /// Set when `raw_base64` was requested but the content exceeds the
+    /// output size limit.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub too_large: bool,
+}
+
+/// Returns the media type of `content` if it should be treated as binary.
+pub fn binary_type(path: &Path, content: &[u8]) -> Option<&'static str> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if let Some((_, media_type)) = BINARY_EXTENSIONS.iter().find(|(ext, _)| *ext == extension) {
+        return Some(media_type);
+    }
+    is_binary(content).then_some("application/octet-stream")
+}
+
+/// Returns the media type of a file, from its extension or, failing that,
+/// its leading bytes.
+pub fn media_type(path: &Path, content: &[u8]) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    TEXT_EXTENSIONS
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, media_type)| *media_type)
+        .or_else(|| binary_type(path, content))
+        .unwrap_or("text/plain")
+}
+
+/// Returns the media type of the file at `path`, reading at most its
+/// leading bytes.
+pub fn sniff_media_type(path: &Path) -> std::io::Result<&'static str> {
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    std::fs::File::open(path)?
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut head)?;
+    Ok(media_type(path, &head))
+}
+
+/// Sniffs leading bytes for NULs or invalid UTF-8.
+fn is_binary(content: &[u8]) -> bool {
+    let head = &content[..content.len().min(SNIFF_BYTES)];
+    if head.contains(&0) {
+        return true;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => false,
+        // A multi-byte character cut off at the sniff boundary is still text.
+        Err(err) => err.error_len().is_some(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::binary_type;
+
+    #[test]
+    fn binary_type_uses_extension_first() {
+        assert_eq!(
+            binary_type(Path::new("scan.PDF"), b"%PDF-1.7"),
+            Some("application/pdf")
+        );
+    }
+
+    #[test]
+    fn binary_type_sniffs_unknown_extensions() {
+        assert_eq!(binary_type(Path::new("notes.md"), "héllo".as_bytes()), None);
+        assert_eq!(
+            binary_type(Path::new("blob"), &[0x7f, 0x45, 0x00, 0x01]),
+            Some("application/octet-stream")
+        );
+        assert_eq!(
+            binary_type(Path::new("blob"), &[0xff, 0xfe, 0x41]),
+            Some("application/octet-stream")
+        );
+    }
+}
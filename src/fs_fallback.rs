@@ -0,0 +1,215 @@
+//! Read-only notes access for machines without nb.
+//!
+//! With `--no-nb-fallback <DIR>`, a server that cannot find nb at startup
+//! answers `status`, `list`, `show`, and `search` by reading the notes
+//! directory directly instead of refusing to start. Items are identified by
+//! their path relative to the directory, and every other command fails with
+//! an error explaining that it needs nb.
+
+use std::path::{Path, PathBuf};
+
+use regex::RegexBuilder;
+
+use crate::args::{ListArgs, SearchArgs};
+use crate::nb::{self, NbError};
+use crate::notes::{self, NoteFile};
+
+/// Commands served without nb.
+pub const COMMANDS: &[&str] = &["status", "list", "show", "search"];
+
+/// A notes directory read without nb.
+#[derive(Debug)]
+pub struct FsNotebook {
+    root: PathBuf,
+}
+
+impl FsNotebook {
+    /// Opens the notes directory, which must exist.
+    pub fn open(root: &Path) -> Result<Self, NbError> {
+        if !root.is_dir() {
+            return Err(NbError::CommandFailed(format!(
+                "notes directory {} does not exist",
+                root.display()
+            )));
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+        })
+    }
+
+    /// Returns the error for commands that need nb, or `None` if served.
+    pub fn unavailable(subcommand: &str) -> Option<String> {
+        (!COMMANDS.contains(&subcommand)).then(|| {
+            format!(
+                "nb.{subcommand} is unavailable: nb is not installed and the server is in \
+                 read-only filesystem mode (available: {})",
+                COMMANDS.join(", ")
+            )
+        })
+    }
+
+    /// Describes the mode and the notes directory.
+    pub async fn status(&self) -> Result<String, NbError> {
+        let count = self.read(None).await?.len();
+        Ok(format!(
+            "filesystem-only mode: nb is not installed\n\
+             notes directory: {}\n\
+             notes: {count}\n\
+             available commands: {}",
+            self.root.display(),
+            COMMANDS.join(", ")
+        ))
+    }
+
+    /// Lists notes beneath a folder, one `[path] title` line each.
+    pub async fn list(&self, args: &ListArgs) -> Result<String, NbError> {
+        if args.sort.is_some() || args.filter.is_some() || args.count_only {
+            return Err(unsupported("sort, filter, and count_only"));
+        }
+        let notes = self.read(args.folder.as_deref()).await?;
+        let lines = notes
+            .iter()
+            .filter(|note| has_tags(note, &args.tags))
+            .take(args.limit.map_or(usize::MAX, |limit| limit as usize))
+            .map(entry)
+            .collect::<Vec<_>>();
+        Ok(lines.join("\n"))
+    }
+
+    /// Shows a note by path, file name, or title.
+    pub async fn show(&self, id: &str) -> Result<String, NbError> {
+        let id = id.trim_matches('/');
+        let notes = self.read(None).await?;
+        notes
+            .iter()
+            .find(|note| note.path == id)
+            .or_else(|| {
+                notes
+                    .iter()
+                    .find(|note| note.path.rsplit('/').next() == Some(id))
+            })
+            .or_else(|| {
+                notes
+                    .iter()
+                    .find(|note| notes::derive_title(&note.content).as_deref() == Some(id))
+            })
+            .map(|note| note.content.clone())
+            .ok_or_else(|| NbError::CommandFailed(format!("note not found: {id}")))
+    }
+
+    /// Searches note contents case-insensitively, listing matching lines.
+    pub async fn search(&self, args: &SearchArgs) -> Result<String, NbError> {
+        if args.filter.is_some() || args.count_only {
+            return Err(unsupported("filter and count_only"));
+        }
+        let pattern = nb::search_pattern(&args.query, args.mode.as_deref())?;
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|err| NbError::CommandFailed(format!("invalid search pattern: {err}")))?;
+        let mut output = Vec::new();
+        for note in self.read(args.folder.as_deref()).await? {
+            if !has_tags(&note, &args.tags) {
+                continue;
+            }
+            let matches = note
+                .content
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| regex.is_match(line))
+                .map(|(index, line)| format!("{}: {}", index + 1, line.trim()))
+                .collect::<Vec<_>>();
+            if !matches.is_empty() {
+                output.push(entry(&note));
+                output.extend(matches);
+            }
+        }
+        Ok(output.join("\n"))
+    }
+
+    async fn read(&self, folder: Option<&str>) -> Result<Vec<NoteFile>, NbError> {
+        let root = self.root.clone();
+        let folder = folder.map(str::to_string);
+        tokio::task::spawn_blocking(move || notes::read_notes(&root, folder.as_deref()))
+            .await
+            .map_err(|err| NbError::CommandFailed(format!("notes scan task failed: {err}")))?
+            .map_err(NbError::from)
+    }
+}
+
+fn unsupported(options: &str) -> NbError {
+    NbError::CommandFailed(format!(
+        "{options} are not supported in filesystem-only mode"
+    ))
+}
+
+fn has_tags(note: &NoteFile, tags: &[String]) -> bool {
+    let note_tags = notes::extract_tags(&note.content);
+    tags.iter()
+        .all(|tag| note_tags.contains(&tag.trim_start_matches('#').to_string()))
+}
+
+fn entry(note: &NoteFile) -> String {
+    let title = notes::derive_title(&note.content)
+        .unwrap_or_else(|| note.path.rsplit('/').next().unwrap_or_default().to_string());
+    format!("[{}] {}", note.path, title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FsNotebook;
+    use crate::args::{ListArgs, SearchArgs};
+
+    fn notebook(name: &str) -> FsNotebook {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-fs-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("inbox")).unwrap();
+        std::fs::write(dir.join("plan.md"), "# Plan\n\nShip v2. #work\n").unwrap();
+        std::fs::write(dir.join("inbox/idea.md"), "# Idea\n\nTry a.b\n").unwrap();
+        FsNotebook::open(&dir).unwrap()
+    }
+
+    #[tokio::test]
+    async fn list_and_show_use_relative_paths() {
+        let notebook = notebook("list");
+        let listed = notebook
+            .list(&ListArgs {
+                tags: vec!["work".to_string()],
+                ..ListArgs::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(listed, "[plan.md] Plan");
+        assert!(
+            notebook
+                .show("inbox/idea.md")
+                .await
+                .unwrap()
+                .contains("a.b")
+        );
+        assert!(notebook.show("Plan").await.unwrap().contains("Ship"));
+    }
+
+    #[tokio::test]
+    async fn search_matches_literal_text() {
+        let notebook = notebook("search");
+        let search = |query: &str| SearchArgs {
+            query: query.to_string(),
+            ..SearchArgs::default()
+        };
+        assert_eq!(
+            notebook.search(&search("A.B")).await.unwrap(),
+            "[inbox/idea.md] Idea\n3: Try a.b"
+        );
+        assert_eq!(notebook.search(&search("a.c")).await.unwrap(), "");
+    }
+
+    #[test]
+    fn mutations_are_unavailable() {
+        assert!(
+            FsNotebook::unavailable("add")
+                .unwrap()
+                .contains("read-only")
+        );
+        assert!(FsNotebook::unavailable("search").is_none());
+    }
+}
@@ -1,85 +1,98 @@
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::{Context, Result, anyhow};
-use tokio::process::Command;
 
 use crate::{Config, nb::NbClient};
 
+/// How long to wait for another process holding the configuration lock.
+const LOCK_WAIT: Duration = Duration::from_secs(5);
+
 pub async fn disable_commit_signing(config: &Config) -> Result<Option<PathBuf>> {
-    let nb_client = NbClient::new(config.notebook.as_deref(), config.create_notebook, true)
-        .context("create nb client for commit signing update")?;
+    let nb_client = NbClient::new(
+        config.notebook.as_deref(),
+        config.notebook_derivation,
+        config.create_notebook,
+        true,
+    )
+    .context("create nb client for commit signing update")?
+    .with_nb_env(config.nb_env.clone());
     let path = nb_client
-        .notebook_path(config.notebook.as_deref())
+        .notebook_path(None)
         .await
         .context("fetch notebook path for commit signing update")?;
     disable_signing_for_path(&path).await.map(Some)
 }
 
 async fn disable_signing_for_path(path: &Path) -> Result<PathBuf> {
-    let root = resolve_git_root(path).await?;
-    apply_signing_config(&root).await?;
-    Ok(root)
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let repository =
+            gix::discover(&path).context("open notebook repository to resolve its root")?;
+        let root = repository
+            .workdir()
+            .ok_or_else(|| anyhow!("notebook repository has no worktree"))?
+            .to_path_buf();
+        apply_signing_config(&repository.common_dir().join("config"))?;
+        Ok(root)
+    })
+    .await
+    .context("commit signing update task failed")?
 }
 
-async fn resolve_git_root(path: &Path) -> Result<PathBuf> {
-    let output = Command::new("git")
-        .arg("-C")
-        .arg(path)
-        .args(["rev-parse", "--show-toplevel"])
-        .output()
-        .await
-        .context("run git rev-parse to resolve notebook repository root")?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let message = select_output(&stderr, &stdout);
-        return Err(anyhow!(
-            "git rev-parse failed for notebook repository: {}",
-            message.trim()
-        ));
+/// Sets the signing overrides in the repository's local configuration file.
+///
+/// The file is read and replaced under `config.lock`, the lock git itself
+/// takes, so concurrent git processes neither see a half-written file nor
+/// lose their own updates.
+fn apply_signing_config(config_path: &Path) -> Result<()> {
+    let mut lock = gix::lock::File::acquire_to_update_resource(
+        config_path,
+        gix::lock::acquire::Fail::AfterDurationWithBackoff(LOCK_WAIT),
+        None,
+    )
+    .with_context(|| format!("lock {}", config_path.display()))?;
+    let mut config = gix::config::File::from_path_no_includes(
+        config_path.to_path_buf(),
+        gix::config::Source::Local,
+    )
+    .with_context(|| format!("read {}", config_path.display()))?;
+    for section in ["commit", "tag"] {
+        config
+            .set_raw_value_by(section, None, "gpgsign", "false")
+            .with_context(|| format!("set {section}.gpgsign for notebook repository"))?;
     }
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let root = stdout.trim();
-    if root.is_empty() {
-        return Err(anyhow!(
-            "git rev-parse returned an empty notebook repository path"
-        ));
-    }
-    Ok(PathBuf::from(root))
-}
-
-async fn apply_signing_config(path: &Path) -> Result<()> {
-    run_git_config(path, "commit.gpgsign", "false").await?;
-    run_git_config(path, "tag.gpgsign", "false").await?;
+    config
+        .write_to(&mut lock)
+        .with_context(|| format!("write {}", config_path.display()))?;
+    lock.commit()
+        .map_err(|err| err.error)
+        .with_context(|| format!("write {}", config_path.display()))?;
     Ok(())
 }
 
-async fn run_git_config(path: &Path, key: &str, value: &str) -> Result<()> {
-    let output = Command::new("git")
-        .arg("-C")
-        .arg(path)
-        .arg("config")
-        .arg(key)
-        .arg(value)
-        .output()
-        .await
-        .with_context(|| format!("run git config {key} for notebook repository"))?;
-    if output.status.success() {
-        return Ok(());
-    }
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let message = select_output(&stderr, &stdout);
-    Err(anyhow!(
-        "git config failed for {key} in notebook repository: {}",
-        message.trim()
-    ))
-}
+#[cfg(test)]
+mod tests {
+    use super::apply_signing_config;
 
-fn select_output<'a>(stderr: &'a str, stdout: &'a str) -> &'a str {
-    if stderr.trim().is_empty() {
-        stdout
-    } else {
-        stderr
+    #[test]
+    fn apply_signing_config_keeps_existing_settings() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-signing-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config");
+        std::fs::write(
+            &path,
+            "[core]\n\tbare = false\n[commit]\n\tgpgsign = true\n",
+        )
+        .unwrap();
+        apply_signing_config(&path).unwrap();
+        let config =
+            gix::config::File::from_path_no_includes(path.clone(), gix::config::Source::Local)
+                .unwrap();
+        assert_eq!(config.boolean("commit.gpgsign"), Some(Ok(false)));
+        assert_eq!(config.boolean("tag.gpgsign"), Some(Ok(false)));
+        assert_eq!(config.boolean("core.bare"), Some(Ok(false)));
+        assert!(!dir.join("config.lock").exists());
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
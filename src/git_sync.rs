@@ -0,0 +1,447 @@
+//! Explicit push, pull, and status for the notebook repository.
+//!
+//! `nb sync` bundles fetch, merge, and push into one opaque step. These
+//! commands run each half separately with git and report structured results
+//! (ahead/behind counts, fast-forward versus merge, rejection reasons) so an
+//! agent can decide what to do next.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::nb::{NbClient, NbError};
+
+/// Outcome of a push.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PushStatus {
+    Pushed,
+    Forced,
+    Created,
+    UpToDate,
+    Rejected,
+}
+
+/// Result of `nb.push`.
+#[derive(Debug, Serialize)]
+pub struct PushResult {
+    pub remote: String,
+    pub branch: String,
+    pub status: PushStatus,
+    /// Local commits not on the remote before pushing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ahead: Option<u32>,
+    /// Remote commits not present locally before pushing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub behind: Option<u32>,
+    /// Why the push was rejected (e.g., `fetch first`, `non-fast-forward`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Outcome of a pull.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PullStatus {
+    UpToDate,
+    FastForward,
+    Merged,
+    /// Local and remote diverged and `ff_only` prevented a merge.
+    Diverged,
+    /// The merge conflicted and was aborted; the repository is unchanged.
+    Conflict,
+}
+
+/// Result of `nb.pull`.
+#[derive(Debug, Serialize)]
+pub struct PullResult {
+    pub remote: String,
+    pub branch: String,
+    pub status: PullStatus,
+    pub ahead: u32,
+    pub behind: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<String>,
+}
+
+/// Result of `nb.git_status`.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct GitStatus {
+    /// Current branch (absent when HEAD is detached).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Upstream tracking branch, if configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    /// True when there are no staged, modified, untracked, or conflicted files.
+    pub clean: bool,
+    pub staged: Vec<String>,
+    pub modified: Vec<String>,
+    pub untracked: Vec<String>,
+    pub conflicted: Vec<String>,
+}
+
+/// Reports working-tree and remote-tracking state for the notebook repository.
+///
+/// With `fetch`, remote-tracking refs are updated first so ahead/behind counts
+/// reflect the remote rather than the last fetch.
+pub async fn status(
+    nb: &NbClient,
+    fetch: bool,
+    notebook: Option<&str>,
+) -> Result<GitStatus, NbError> {
+    let repository = nb.notebook_path(notebook).await?;
+    if fetch {
+        let fetched = nb.git(&repository, &["fetch", "--quiet"]).await?;
+        if !fetched.success {
+            return Err(NbError::CommandFailed(format!(
+                "git fetch failed: {}",
+                select_output(&fetched.stderr, &fetched.stdout).trim()
+            )));
+        }
+    }
+    let output = run_checked(
+        nb,
+        &repository,
+        &[
+            "status",
+            "--porcelain=v1",
+            "--branch",
+            "--untracked-files=all",
+        ],
+    )
+    .await?;
+    Ok(parse_status(&output))
+}
+
+/// Parses `git status --porcelain=v1 --branch` output.
+fn parse_status(output: &str) -> GitStatus {
+    let mut status = GitStatus::default();
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix("## ") {
+            parse_branch_header(header, &mut status);
+            continue;
+        }
+        if line.len() < 4 {
+            continue;
+        }
+        let (code, path) = line.split_at(3);
+        // Renames are reported as `old -> new`; the new path is what exists.
+        let path = path.rsplit(" -> ").next().unwrap_or(path).to_string();
+        let mut code = code.chars();
+        let (index, worktree) = (code.next().unwrap_or(' '), code.next().unwrap_or(' '));
+        match (index, worktree) {
+            ('?', '?') => status.untracked.push(path),
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => status.conflicted.push(path),
+            _ => {
+                if index != ' ' {
+                    status.staged.push(path.clone());
+                }
+                if worktree != ' ' {
+                    status.modified.push(path);
+                }
+            }
+        }
+    }
+    status.clean = status.staged.is_empty()
+        && status.modified.is_empty()
+        && status.untracked.is_empty()
+        && status.conflicted.is_empty();
+    status
+}
+
+/// Parses `main...origin/main [ahead 1, behind 2]` into `status`.
+fn parse_branch_header(header: &str, status: &mut GitStatus) {
+    let (refs, tracking) = match header.split_once(" [") {
+        Some((refs, tracking)) => (refs, tracking.trim_end_matches(']')),
+        None => (header, ""),
+    };
+    let (branch, upstream) = match refs.split_once("...") {
+        Some((branch, upstream)) => (branch, Some(upstream)),
+        None => (refs, None),
+    };
+    let branch = branch.strip_prefix("No commits yet on ").unwrap_or(branch);
+    if !branch.starts_with("HEAD (no branch)") {
+        status.branch = Some(branch.to_string());
+    }
+    status.upstream = upstream.map(String::from);
+    for part in tracking.split(", ") {
+        if let Some(count) = part.strip_prefix("ahead ") {
+            status.ahead = count.parse().unwrap_or(0);
+        } else if let Some(count) = part.strip_prefix("behind ") {
+            status.behind = count.parse().unwrap_or(0);
+        }
+    }
+}
+
+/// Pushes the notebook's current branch.
+pub async fn push(
+    nb: &NbClient,
+    remote: Option<&str>,
+    branch: Option<&str>,
+    notebook: Option<&str>,
+) -> Result<PushResult, NbError> {
+    let repository = nb.notebook_path(notebook).await?;
+    let (remote, branch) = resolve_target(nb, &repository, remote, branch).await?;
+
+    // Fetch first so the counts reflect the remote; a failure here surfaces
+    // again (with a better message) from the push itself.
+    nb.git(&repository, &["fetch", "--quiet", &remote]).await?;
+    let counts = ahead_behind(nb, &repository, &format!("{}/{}", remote, branch)).await?;
+
+    let refspec = format!("HEAD:refs/heads/{}", branch);
+    let output = nb
+        .git(&repository, &["push", "--porcelain", &remote, &refspec])
+        .await?;
+    let Some((flag, summary)) = parse_push_porcelain(&output.stdout) else {
+        return Err(NbError::CommandFailed(format!(
+            "git push failed: {}",
+            select_output(&output.stderr, &output.stdout).trim()
+        )));
+    };
+    let status = match flag {
+        '=' => PushStatus::UpToDate,
+        '+' => PushStatus::Forced,
+        '*' => PushStatus::Created,
+        '!' => PushStatus::Rejected,
+        _ => PushStatus::Pushed,
+    };
+    let reason = (status == PushStatus::Rejected).then(|| rejection_reason(&summary));
+    Ok(PushResult {
+        remote,
+        branch,
+        status,
+        ahead: counts.map(|(ahead, _)| ahead),
+        behind: counts.map(|(_, behind)| behind),
+        reason,
+    })
+}
+
+/// Fetches and integrates the remote branch into the notebook.
+///
+/// Conflicting merges are aborted so the notebook is never left mid-merge.
+pub async fn pull(
+    nb: &NbClient,
+    remote: Option<&str>,
+    branch: Option<&str>,
+    ff_only: bool,
+    notebook: Option<&str>,
+) -> Result<PullResult, NbError> {
+    let repository = nb.notebook_path(notebook).await?;
+    let (remote, branch) = resolve_target(nb, &repository, remote, branch).await?;
+
+    let fetch = nb
+        .git(&repository, &["fetch", "--quiet", &remote, &branch])
+        .await?;
+    if !fetch.success {
+        return Err(NbError::CommandFailed(format!(
+            "git fetch failed: {}",
+            select_output(&fetch.stderr, &fetch.stdout).trim()
+        )));
+    }
+    let upstream = format!("{}/{}", remote, branch);
+    let (ahead, behind) = ahead_behind(nb, &repository, &upstream)
+        .await?
+        .unwrap_or((0, 0));
+
+    let mut result = PullResult {
+        remote,
+        branch,
+        status: PullStatus::UpToDate,
+        ahead,
+        behind,
+        conflicts: Vec::new(),
+    };
+    if behind == 0 {
+        return Ok(result);
+    }
+    if ahead == 0 {
+        run_checked(nb, &repository, &["merge", "--ff-only", &upstream]).await?;
+        result.status = PullStatus::FastForward;
+        return Ok(result);
+    }
+    if ff_only {
+        result.status = PullStatus::Diverged;
+        return Ok(result);
+    }
+
+    let merge = nb
+        .git(&repository, &["merge", "--no-edit", &upstream])
+        .await?;
+    if merge.success {
+        result.status = PullStatus::Merged;
+        return Ok(result);
+    }
+    let conflicted = nb
+        .git(&repository, &["diff", "--name-only", "--diff-filter=U"])
+        .await?;
+    result.conflicts = conflicted
+        .stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect();
+    run_checked(nb, &repository, &["merge", "--abort"]).await?;
+    result.status = PullStatus::Conflict;
+    Ok(result)
+}
+
+/// Determines the remote and branch, defaulting to the current branch's upstream.
+async fn resolve_target(
+    nb: &NbClient,
+    repository: &Path,
+    remote: Option<&str>,
+    branch: Option<&str>,
+) -> Result<(String, String), NbError> {
+    let branch = match branch {
+        Some(branch) => branch.to_string(),
+        None => run_checked(nb, repository, &["rev-parse", "--abbrev-ref", "HEAD"])
+            .await?
+            .trim()
+            .to_string(),
+    };
+    let remote = match remote {
+        Some(remote) => remote.to_string(),
+        None => {
+            let key = format!("branch.{}.remote", branch);
+            let configured = nb.git(repository, &["config", "--get", &key]).await?;
+            let configured = configured.stdout.trim();
+            if configured.is_empty() {
+                "origin".to_string()
+            } else {
+                configured.to_string()
+            }
+        }
+    };
+    Ok((remote, branch))
+}
+
+/// Counts commits on HEAD but not `upstream`, and vice versa.
+///
+/// Returns `None` when `upstream` does not exist (e.g., never pushed).
+pub(crate) async fn ahead_behind(
+    nb: &NbClient,
+    repository: &Path,
+    upstream: &str,
+) -> Result<Option<(u32, u32)>, NbError> {
+    let range = format!("HEAD...{}", upstream);
+    let output = nb
+        .git(repository, &["rev-list", "--left-right", "--count", &range])
+        .await?;
+    if !output.success {
+        return Ok(None);
+    }
+    Ok(parse_counts(&output.stdout))
+}
+
+fn parse_counts(output: &str) -> Option<(u32, u32)> {
+    let mut parts = output.split_whitespace();
+    let ahead = parts.next()?.parse().ok()?;
+    let behind = parts.next()?.parse().ok()?;
+    Some((ahead, behind))
+}
+
+/// Runs git and returns stdout, treating a non-zero exit as an error.
+pub async fn run_checked(
+    nb: &NbClient,
+    repository: &Path,
+    args: &[&str],
+) -> Result<String, NbError> {
+    let output = nb.git(repository, args).await?;
+    if output.success {
+        Ok(output.stdout)
+    } else {
+        Err(NbError::CommandFailed(format!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            select_output(&output.stderr, &output.stdout).trim()
+        )))
+    }
+}
+
+/// Extracts the flag and summary for the pushed ref from `git push --porcelain`.
+fn parse_push_porcelain(stdout: &str) -> Option<(char, String)> {
+    stdout.lines().find_map(|line| {
+        let mut fields = line.splitn(3, '\t');
+        let flag = fields.next()?;
+        let _refs = fields.next()?;
+        let summary = fields.next()?;
+        let mut chars = flag.chars();
+        let flag = chars.next()?;
+        chars.next().is_none().then(|| (flag, summary.to_string()))
+    })
+}
+
+/// Extracts the parenthesized reason from a rejected ref summary.
+fn rejection_reason(summary: &str) -> String {
+    summary
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(reason, _)| reason.to_string())
+        .unwrap_or_else(|| summary.trim().to_string())
+}
+
+fn select_output<'a>(stderr: &'a str, stdout: &'a str) -> &'a str {
+    if stderr.trim().is_empty() {
+        stdout
+    } else {
+        stderr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_counts, parse_push_porcelain, parse_status, rejection_reason};
+
+    #[test]
+    fn parse_push_porcelain_reads_rejections() {
+        let stdout = "To example.com:notes.git\n\
+                      !\trefs/heads/main:refs/heads/main\t[rejected] (fetch first)\nDone\n";
+        let (flag, summary) = parse_push_porcelain(stdout).unwrap();
+        assert_eq!(flag, '!');
+        assert_eq!(rejection_reason(&summary), "fetch first");
+    }
+
+    #[test]
+    fn parse_push_porcelain_reads_fast_forward() {
+        let stdout =
+            "To example.com:notes.git\n \trefs/heads/main:refs/heads/main\tabc..def\nDone\n";
+        assert_eq!(
+            parse_push_porcelain(stdout),
+            Some((' ', "abc..def".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_counts_reads_left_right_output() {
+        assert_eq!(parse_counts("3\t1\n"), Some((3, 1)));
+        assert_eq!(parse_counts(""), None);
+    }
+
+    #[test]
+    fn parse_status_reports_changes_and_tracking() {
+        let status = parse_status(
+            "## main...origin/main [ahead 1, behind 2]\n M notes/a.md\nA  b.md\n\
+             ?? scratch.md\nR  old.md -> new.md\nUU c.md\n",
+        );
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.ahead, status.behind), (1, 2));
+        assert_eq!(status.modified, vec!["notes/a.md"]);
+        assert_eq!(status.staged, vec!["b.md", "new.md"]);
+        assert_eq!(status.untracked, vec!["scratch.md"]);
+        assert_eq!(status.conflicted, vec!["c.md"]);
+        assert!(!status.clean);
+    }
+
+    #[test]
+    fn parse_status_without_upstream_is_clean() {
+        let status = parse_status("## main\n");
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.upstream, None);
+        assert!(status.clean);
+    }
+}
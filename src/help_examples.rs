@@ -0,0 +1,194 @@
+//! Example invocations for `help` responses.
+//!
+//! Models follow a worked example far more reliably than a JSON Schema, so
+//! each command's help lists one or two `args` objects it would accept.
+//! Common commands have hand-written samples; every command also gets one
+//! built from its schema's required properties, so a new command is never
+//! left without an example.
+
+use serde_json::{Value, json};
+
+/// Maximum examples listed per command.
+const MAX_EXAMPLES: usize = 2;
+
+/// Returns example `nb` tool parameters for `command` (e.g., `nb.add`).
+pub fn examples(command: &str, schema: &Value) -> Vec<Value> {
+    let subcommand = command.strip_prefix("nb.").unwrap_or(command);
+    let mut args = curated(subcommand);
+    let generated = from_schema(schema);
+    if !args.contains(&generated) {
+        args.push(generated);
+    }
+    args.into_iter()
+        .take(MAX_EXAMPLES)
+        .map(|args| json!({"command": command, "args": args}))
+        .collect()
+}
+
+/// Hand-written samples for commonly used commands.
+fn curated(subcommand: &str) -> Vec<Value> {
+    match subcommand {
+        "add" => vec![
+            json!({"title": "Meeting notes", "content": "# Meeting notes\n\n- Ship on Friday", "tags": ["meeting"]}),
+            json!({"content": "Idea: cache the search index", "folder": "ideas"}),
+        ],
+        "show" => vec![
+            json!({"id": "3"}),
+            json!({"id": "Meeting notes", "range": {"offset": 0, "length": 4000}}),
+        ],
+        "show_folder" => vec![
+            json!({"folder": "projects/acme"}),
+            json!({"folder": "journal", "sort": "created", "order": "asc", "concatenate": true}),
+        ],
+        "stale" => vec![json!({}), json!({"days": 180, "folder": "docs"})],
+        "show_many" => vec![
+            json!({"ids": ["3", "7", "Meeting notes"]}),
+            json!({"glob": "clients/*.md", "max_bytes": 20000}),
+        ],
+        "edit" => vec![json!({"id": "3", "content": "# Meeting notes\n\n- Ship on Monday"})],
+        "delete" => vec![json!({"id": "3", "confirm": true})],
+        "resolve" => vec![
+            json!({"query": "quarterly plan"}),
+            json!({"query": "https://example.com/article", "type": "url"}),
+        ],
+        "link" => vec![
+            json!({"from_id": "3", "to_id": "7"}),
+            json!({"from_id": "Meeting notes", "to_id": "Quarterly plan", "anchor_text": "the plan"}),
+        ],
+        "move" => vec![json!({"id": "3", "destination": "archive/meeting-notes.md"})],
+        "list" => vec![
+            json!({"folder": "projects", "limit": 20}),
+            json!({"tags": ["meeting"], "sort": "modified"}),
+        ],
+        "search" => vec![
+            json!({"query": "roadmap"}),
+            json!({"query": "TODO|FIXME", "mode": "regex", "folder": "projects"}),
+        ],
+        "todo" => vec![
+            json!({"description": "Review the draft", "tags": ["review"], "due": "2025-07-01"}),
+        ],
+        "do" | "undo" => vec![json!({"id": "5"})],
+        "blame" => vec![json!({"id": "3"}), json!({"id": "3", "paragraphs": true})],
+        "changes" => vec![json!({"since": "2 days ago"}), json!({"since": "3f2a9c1"})],
+        "bookmark" => vec![
+            json!({"url": "https://example.com/article", "tags": ["reading"]}),
+            json!({"url": "https://example.com/article", "enrich": true}),
+        ],
+        "board_move" => vec![json!({"id": "5", "column": "doing"})],
+        "merge" => vec![json!({"target": "3", "sources": ["7", "8"]})],
+        "split" => vec![
+            json!({"id": "3"}),
+            json!({"id": "3", "level": 1, "folder": "projects/launch"}),
+        ],
+        "toc" => vec![
+            json!({"id": "3"}),
+            json!({"id": "3", "insert": true, "max_level": 2}),
+        ],
+        "journal_stats" => vec![
+            json!({}),
+            json!({"folder": "daily", "since": "2025-01-01", "until": "2025-03-31"}),
+        ],
+        "references" => vec![json!({"folder": "research"}), json!({"id": "3"})],
+        "toggle_checkbox" => vec![json!({"id": "3", "text": "Ship on Friday", "checked": true})],
+        "view" => vec![json!({}), json!({"name": "open-issues"})],
+        "snippet" => vec![json!({}), json!({"name": "meeting-header"})],
+        "save_snippet" => vec![json!({
+            "name": "meeting-header",
+            "text": "## Attendees\n\n## Decisions\n\n## Actions\n"
+        })],
+        "use_folder" => vec![json!({"folder": "projects/acme"}), json!({})],
+        "use_notebook" => vec![json!({"notebook": "acme"}), json!({})],
+        _ => Vec::new(),
+    }
+}
+
+/// Builds an `args` object holding every required property of `schema`.
+fn from_schema(schema: &Value) -> Value {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let required = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str);
+    let mut args = serde_json::Map::new();
+    for name in required {
+        let property = properties
+            .and_then(|properties| properties.get(name))
+            .unwrap_or(&Value::Null);
+        args.insert(name.to_string(), sample(name, property));
+    }
+    Value::Object(args)
+}
+
+/// Returns a plausible value for the property `name`.
+fn sample(name: &str, property: &Value) -> Value {
+    let kind = match property.get("type") {
+        Some(Value::String(kind)) => kind.as_str(),
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null")
+            .unwrap_or("string"),
+        _ if property.get("$ref").is_some() => "object",
+        _ => "string",
+    };
+    match kind {
+        "integer" | "number" => json!(1),
+        "boolean" => json!(true),
+        "object" => json!({}),
+        "array" => {
+            let items = property.get("items").unwrap_or(&Value::Null);
+            json!([sample(name.trim_end_matches('s'), items)])
+        }
+        _ => Value::String(sample_string(name).to_string()),
+    }
+}
+
+fn sample_string(name: &str) -> &'static str {
+    match name {
+        "id" | "target" | "source" => "3",
+        "title" => "Meeting notes",
+        "content" => "# Meeting notes",
+        "description" => "Review the draft",
+        "query" => "roadmap",
+        "folder" | "path" => "projects",
+        "url" => "https://example.com",
+        "notebook" | "target_notebook" => "work",
+        "name" => "open-issues",
+        "column" => "doing",
+        "tag" => "meeting",
+        _ => "example",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::examples;
+
+    #[test]
+    fn generates_required_properties_for_uncurated_commands() {
+        let schema = json!({
+            "properties": {
+                "id": {"type": "string"},
+                "sources": {"type": "array", "items": {"type": "string"}},
+                "limit": {"type": ["integer", "null"]},
+            },
+            "required": ["id", "sources"],
+        });
+        assert_eq!(
+            examples("nb.frobnicate", &schema),
+            vec![json!({"command": "nb.frobnicate", "args": {"id": "3", "sources": ["3"]}})]
+        );
+    }
+
+    #[test]
+    fn prefers_curated_samples() {
+        let schema = json!({"properties": {"id": {"type": "string"}}, "required": ["id"]});
+        let examples = examples("nb.show", &schema);
+        assert_eq!(examples.len(), 2);
+        assert_eq!(examples[0]["args"], json!({"id": "3"}));
+    }
+}
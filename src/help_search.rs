@@ -0,0 +1,180 @@
+//! Keyword search over the help catalog.
+//!
+//! A `help` query that is not a command name ("how do I tag a note") is
+//! split into keywords and matched against each command's name, description,
+//! and argument names and docs. Matches in the name count most and matches in
+//! argument docs least, so the ranking favors the command that is about the
+//! topic over commands that merely accept it as an option.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Maximum candidates returned for a query.
+const MAX_MATCHES: usize = 5;
+
+/// Words too common in questions to say anything about the command.
+const STOPWORDS: &[&str] = &[
+    "a", "all", "an", "and", "are", "can", "do", "does", "for", "from", "get", "how", "i", "in",
+    "into", "is", "it", "me", "my", "of", "on", "or", "the", "to", "use", "what", "which", "with",
+];
+
+/// A command as seen by the search.
+pub struct Entry {
+    command: String,
+    description: String,
+    /// Argument names and their docs, lowercased.
+    arguments: String,
+}
+
+impl Entry {
+    /// Describes `command` by its help description and argument schema.
+    pub fn new(command: &str, description: &str, schema: &Value) -> Self {
+        let arguments = schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .map(|(name, property)| {
+                let docs = property
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                format!("{} {docs}", name.replace('_', " "))
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        Self {
+            command: command.to_string(),
+            description: description.to_string(),
+            arguments,
+        }
+    }
+}
+
+/// A candidate command for a query.
+#[derive(Debug, Serialize)]
+pub struct Match {
+    pub command: String,
+    pub description: String,
+    pub score: u32,
+}
+
+/// Returns the commands matching any keyword of `query`, best first.
+pub fn search(query: &str, entries: &[Entry]) -> Vec<Match> {
+    let keywords = keywords(query);
+    let mut matches = entries
+        .iter()
+        .filter_map(|entry| {
+            let name = entry
+                .command
+                .strip_prefix("nb.")
+                .unwrap_or(&entry.command)
+                .replace('_', " ");
+            let description = entry.description.to_lowercase();
+            let score = keywords
+                .iter()
+                .map(|keyword| {
+                    let mut score = 0;
+                    if name == *keyword {
+                        score += 6;
+                    } else if name.contains(keyword.as_str()) {
+                        score += 3;
+                    }
+                    if description.contains(keyword.as_str()) {
+                        score += 2;
+                    }
+                    if entry.arguments.contains(keyword.as_str()) {
+                        score += 1;
+                    }
+                    score
+                })
+                .sum::<u32>();
+            (score > 0).then(|| Match {
+                command: entry.command.clone(),
+                description: entry.description.clone(),
+                score,
+            })
+        })
+        .collect::<Vec<_>>();
+    // Stable, so equal scores keep catalog order.
+    matches.sort_by_key(|candidate| std::cmp::Reverse(candidate.score));
+    matches.truncate(MAX_MATCHES);
+    matches
+}
+
+/// Splits `query` into lowercase stems, dropping stopwords.
+fn keywords(query: &str) -> Vec<String> {
+    let mut keywords = Vec::new();
+    for word in query
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| !word.is_empty() && !STOPWORDS.contains(&word.as_str()))
+    {
+        let stem = stem(&word).to_string();
+        if !keywords.contains(&stem) {
+            keywords.push(stem);
+        }
+    }
+    keywords
+}
+
+/// Strips common English suffixes so "tagging" matches "tag" and "tags".
+fn stem(word: &str) -> &str {
+    for suffix in ["ing", "ed", "es", "s"] {
+        let Some(stem) = word.strip_suffix(suffix) else {
+            continue;
+        };
+        if stem.len() < 3 {
+            break;
+        }
+        // "tagg" from "tagging" or "tagged".
+        let bytes = stem.as_bytes();
+        let last = bytes[bytes.len() - 1];
+        return if bytes.len() > 3 && last.is_ascii_alphabetic() && last == bytes[bytes.len() - 2] {
+            &stem[..stem.len() - 1]
+        } else {
+            stem
+        };
+    }
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{Entry, search};
+
+    #[test]
+    fn ranks_commands_by_keyword_matches() {
+        let entries = [
+            Entry::new(
+                "nb.add",
+                "Create a new note",
+                &json!({"properties": {"tags": {"description": "Tags to apply"}}}),
+            ),
+            Entry::new(
+                "nb.tag_graph",
+                "Report tag usage and co-occurring tags",
+                &json!({}),
+            ),
+            Entry::new("nb.delete", "Delete a note", &json!({})),
+        ];
+        let matches = search("How do I tag a note?", &entries);
+        let commands = matches
+            .iter()
+            .map(|candidate| candidate.command.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(commands, ["nb.tag_graph", "nb.add", "nb.delete"]);
+        assert!(search("frobnicate", &entries).is_empty());
+    }
+
+    #[test]
+    fn stems_keywords() {
+        let entries = [Entry::new("nb.todo", "Create a todo item", &json!({}))];
+        assert_eq!(search("todos", &entries)[0].command, "nb.todo");
+        let entries = [Entry::new("nb.tag_graph", "Report tag usage", &json!({}))];
+        assert_eq!(search("tagging", &entries)[0].command, "nb.tag_graph");
+    }
+}
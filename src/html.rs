@@ -0,0 +1,368 @@
+//! Minimal HTML to Markdown conversion for imported documents.
+//!
+//! Handles the subset of HTML produced by note-taking exports (Evernote ENML,
+//! Notion HTML): headings, paragraphs, emphasis, links, images, lists,
+//! checkboxes, code, blockquotes, and simple tables. Unknown elements are
+//! unwrapped so their text is preserved.
+
+use quick_xml::{
+    Reader,
+    escape::resolve_html5_entity,
+    events::{BytesStart, Event},
+};
+
+/// Converts an HTML (or XHTML) fragment to Markdown.
+///
+/// Parsing is lenient: mismatched or unclosed tags are tolerated, and
+/// conversion stops quietly at the first unrecoverable syntax error.
+pub fn to_markdown(html: &str) -> String {
+    let mut reader = Reader::from_str(html);
+    let config = reader.config_mut();
+    config.check_end_names = false;
+    config.allow_unmatched_ends = true;
+
+    let mut writer = Writer::default();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(start)) => writer.start(&start, false),
+            Ok(Event::Empty(start)) => writer.start(&start, true),
+            Ok(Event::End(end)) => {
+                writer.end(&String::from_utf8_lossy(end.local_name().as_ref()).to_lowercase())
+            }
+            Ok(Event::Text(text)) => {
+                let raw = String::from_utf8_lossy(&text);
+                let decoded = quick_xml::escape::unescape_with(&raw, resolve_html5_entity)
+                    .map(|cow| cow.into_owned())
+                    .unwrap_or_else(|_| raw.to_string());
+                writer.text(&decoded);
+            }
+            Ok(Event::CData(data)) => writer.text(&String::from_utf8_lossy(&data)),
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+    writer.finish()
+}
+
+/// Tracks rendering state while walking HTML events.
+#[derive(Default)]
+struct Writer {
+    /// Output buffers; nested blockquotes render into their own buffer.
+    buffers: Vec<String>,
+    /// Open list kinds, with the next item number for ordered lists.
+    lists: Vec<Option<u32>>,
+    /// Link targets for open anchors.
+    links: Vec<Option<String>>,
+    /// Depth of elements whose text is discarded (script, style, head).
+    skip_depth: usize,
+    /// Depth of open `pre` elements.
+    pre_depth: usize,
+    /// Cells emitted in the current table row.
+    row_cells: usize,
+    /// Rows emitted in the current table.
+    table_rows: usize,
+}
+
+impl Writer {
+    fn out(&mut self) -> &mut String {
+        if self.buffers.is_empty() {
+            self.buffers.push(String::new());
+        }
+        self.buffers.last_mut().expect("buffer stack is non-empty")
+    }
+
+    fn start(&mut self, start: &BytesStart<'_>, empty: bool) {
+        let name = String::from_utf8_lossy(start.local_name().as_ref()).to_lowercase();
+        if self.skip_depth > 0 {
+            if !empty {
+                self.skip_depth += 1;
+            }
+            return;
+        }
+        match name.as_str() {
+            "script" | "style" | "head" | "title" if !empty => self.skip_depth = 1,
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                self.block_break();
+                self.out().push_str(&"#".repeat(level));
+                self.out().push(' ');
+            }
+            "p" | "div" | "section" | "article" | "en-note" | "table" => {
+                self.block_break();
+                if name == "table" {
+                    self.table_rows = 0;
+                }
+            }
+            "br" => self.out().push('\n'),
+            "hr" => {
+                self.block_break();
+                self.out().push_str("---");
+                self.block_break();
+            }
+            "strong" | "b" => self.out().push_str("**"),
+            "em" | "i" => self.out().push('_'),
+            "s" | "del" | "strike" => self.out().push_str("~~"),
+            "code" if self.pre_depth == 0 => self.out().push('`'),
+            "pre" => {
+                self.block_break();
+                self.out().push_str("```\n");
+                self.pre_depth += 1;
+            }
+            "blockquote" => {
+                self.block_break();
+                self.buffers.push(String::new());
+            }
+            "ul" | "ol" => {
+                if self.lists.is_empty() {
+                    self.block_break();
+                }
+                self.lists.push((name == "ol").then_some(1));
+            }
+            "li" => {
+                self.line_break();
+                let depth = self.lists.len().saturating_sub(1);
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        let marker = format!("{}. ", n);
+                        *n += 1;
+                        marker
+                    }
+                    _ => "- ".to_string(),
+                };
+                let indent = "  ".repeat(depth);
+                self.out().push_str(&indent);
+                self.out().push_str(&marker);
+            }
+            "a" => {
+                let href = attribute(start, "href");
+                if href.is_some() {
+                    self.out().push('[');
+                }
+                self.links.push(href);
+            }
+            "img" => {
+                let src = attribute(start, "src").unwrap_or_default();
+                let alt = attribute(start, "alt").unwrap_or_default();
+                let image = format!("![{}]({})", alt, src);
+                self.out().push_str(&image);
+            }
+            "en-todo" => {
+                let checked = attribute(start, "checked").is_some_and(|v| v == "true");
+                self.out().push_str(if checked { "[x] " } else { "[ ] " });
+            }
+            "input" if attribute(start, "type").is_some_and(|t| t == "checkbox") => {
+                let checked = attribute(start, "checked").is_some();
+                self.out().push_str(if checked { "[x] " } else { "[ ] " });
+            }
+            "tr" => {
+                self.line_break();
+                self.out().push('|');
+                self.row_cells = 0;
+            }
+            "td" | "th" => self.out().push(' '),
+            _ => {}
+        }
+        if empty {
+            self.end_inline(&name);
+        }
+    }
+
+    fn end(&mut self, name: &str) {
+        if self.skip_depth > 0 {
+            self.skip_depth -= 1;
+            return;
+        }
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "p" | "div" | "section" | "article"
+            | "table" => self.block_break(),
+            "pre" => {
+                self.pre_depth = self.pre_depth.saturating_sub(1);
+                if !self.out().ends_with('\n') {
+                    self.out().push('\n');
+                }
+                self.out().push_str("```");
+                self.block_break();
+            }
+            "blockquote" => {
+                if self.buffers.len() > 1 {
+                    let inner = self.buffers.pop().unwrap_or_default();
+                    let quoted = inner
+                        .trim()
+                        .lines()
+                        .map(|line| {
+                            if line.is_empty() {
+                                ">".to_string()
+                            } else {
+                                format!("> {}", line)
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    self.out().push_str(&quoted);
+                    self.block_break();
+                }
+            }
+            "ul" | "ol" => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.block_break();
+                }
+            }
+            "tr" => {
+                self.table_rows += 1;
+                if self.table_rows == 1 {
+                    let separator = format!("\n|{}", " --- |".repeat(self.row_cells.max(1)));
+                    self.out().push_str(&separator);
+                }
+            }
+            "td" | "th" => {
+                self.out().push_str(" |");
+                self.row_cells += 1;
+            }
+            _ => self.end_inline(name),
+        }
+    }
+
+    /// Closes inline markup; also used for self-closing elements.
+    fn end_inline(&mut self, name: &str) {
+        match name {
+            "strong" | "b" => self.out().push_str("**"),
+            "em" | "i" => self.out().push('_'),
+            "s" | "del" | "strike" => self.out().push_str("~~"),
+            "code" if self.pre_depth == 0 => self.out().push('`'),
+            "a" => {
+                if let Some(Some(href)) = self.links.pop() {
+                    let target = format!("]({})", href);
+                    self.out().push_str(&target);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.skip_depth > 0 {
+            return;
+        }
+        if self.pre_depth > 0 {
+            self.out().push_str(text);
+            return;
+        }
+        let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if collapsed.is_empty() {
+            if text.chars().next().is_some_and(char::is_whitespace) {
+                self.space();
+            }
+            return;
+        }
+        if text.starts_with(char::is_whitespace) {
+            self.space();
+        }
+        self.out().push_str(&collapsed);
+        if text.ends_with(char::is_whitespace) {
+            self.space();
+        }
+    }
+
+    /// Emits a single space unless the output already ends in whitespace.
+    fn space(&mut self) {
+        let out = self.out();
+        if !out.is_empty() && !out.ends_with(char::is_whitespace) {
+            out.push(' ');
+        }
+    }
+
+    fn line_break(&mut self) {
+        let out = self.out();
+        let trimmed = out.trim_end_matches(' ').len();
+        out.truncate(trimmed);
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+
+    fn block_break(&mut self) {
+        if !self.lists.is_empty() {
+            self.line_break();
+            return;
+        }
+        self.line_break();
+        let out = self.out();
+        if !out.is_empty() && !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+    }
+
+    fn finish(mut self) -> String {
+        while self.buffers.len() > 1 {
+            let inner = self.buffers.pop().unwrap_or_default();
+            self.out().push_str(&inner);
+        }
+        let out = self.buffers.pop().unwrap_or_default();
+        let mut result = String::with_capacity(out.len());
+        let mut blank_lines = 0;
+        for line in out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                blank_lines += 1;
+                if blank_lines > 1 {
+                    continue;
+                }
+            } else {
+                blank_lines = 0;
+            }
+            result.push_str(line);
+            result.push('\n');
+        }
+        let trimmed = result.trim();
+        if trimmed.is_empty() {
+            String::new()
+        } else {
+            format!("{}\n", trimmed)
+        }
+    }
+}
+
+/// Returns the unescaped value of an attribute, tolerating unquoted HTML values.
+fn attribute(start: &BytesStart<'_>, name: &str) -> Option<String> {
+    start
+        .html_attributes()
+        .flatten()
+        .find(|attr| {
+            attr.key
+                .local_name()
+                .as_ref()
+                .eq_ignore_ascii_case(name.as_bytes())
+        })
+        .map(|attr| {
+            attr.unescape_value()
+                .map(|value| value.into_owned())
+                .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).into_owned())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_markdown;
+
+    #[test]
+    fn converts_headings_paragraphs_and_emphasis() {
+        let markdown = to_markdown("<h1>Title</h1><p>Some <b>bold</b> and <i>italic</i>.</p>");
+        assert_eq!(markdown, "# Title\n\nSome **bold** and _italic_.\n");
+    }
+
+    #[test]
+    fn converts_links_lists_and_checkboxes() {
+        let markdown = to_markdown(
+            "<en-note><ul><li><a href=\"https://example.com\">Site</a></li>\
+             <li><en-todo checked=\"true\"/>Done</li></ul></en-note>",
+        );
+        assert_eq!(markdown, "- [Site](https://example.com)\n- [x] Done\n");
+    }
+
+    #[test]
+    fn tolerates_html_void_elements_and_entities() {
+        let markdown = to_markdown("<p>a&nbsp;&amp; b<br>c</p><hr><pre>x  y</pre>");
+        assert_eq!(markdown, "a & b\nc\n\n---\n\n```\nx  y\n```\n");
+    }
+}
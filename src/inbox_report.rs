@@ -0,0 +1,119 @@
+//! Notes waiting for triage.
+//!
+//! `nb.inbox_report` lists notes without tags (neither hashtags nor a
+//! `tags` frontmatter field) and notes at the notebook root, outside any
+//! folder, so a weekly-review agent can tag and file them.
+
+use serde::Serialize;
+
+use crate::archive;
+use crate::args::InboxReportArgs;
+use crate::nb::{NbClient, NbError};
+use crate::notes::{self, NoteFile};
+
+/// Default number of notes listed per category.
+const DEFAULT_LIMIT: usize = 100;
+
+/// Result of `nb.inbox_report`.
+#[derive(Debug, Serialize)]
+pub struct InboxReport {
+    /// Number of notes scanned.
+    pub scanned: usize,
+    /// Notes without tags.
+    pub untagged: Category,
+    /// Notes at the notebook root.
+    pub unfiled: Category,
+}
+
+/// Notes in one triage category.
+#[derive(Debug, Default, Serialize)]
+pub struct Category {
+    /// Number of notes in the category, including those not listed.
+    pub total: usize,
+    /// Listed notes, by path.
+    pub notes: Vec<InboxNote>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InboxNote {
+    /// Path relative to the notebook root.
+    pub path: String,
+    pub title: String,
+}
+
+/// Lists the untagged and unfiled notes of a notebook.
+pub async fn inbox_report(nb: &NbClient, args: &InboxReportArgs) -> Result<InboxReport, NbError> {
+    let root = nb.notebook_path(args.notebook.as_deref()).await?;
+    let hide_archived = archive::hides(&root, None, args.include_archived);
+    let files = tokio::task::spawn_blocking(move || notes::read_notes(&root, None))
+        .await
+        .map_err(|err| NbError::CommandFailed(format!("inbox report task failed: {err}")))??;
+    let files = files
+        .into_iter()
+        .filter(|note| !(hide_archived && note.path.split('/').next() == Some(archive::FOLDER)))
+        .collect::<Vec<_>>();
+    Ok(report(
+        &files,
+        args.limit.map_or(DEFAULT_LIMIT, |limit| limit as usize),
+    ))
+}
+
+fn report(files: &[NoteFile], limit: usize) -> InboxReport {
+    let mut report = InboxReport {
+        scanned: files.len(),
+        untagged: Category::default(),
+        unfiled: Category::default(),
+    };
+    for note in files {
+        let untagged = notes::extract_tags(&note.content).is_empty()
+            && notes::frontmatter_tags(&note.content).is_empty();
+        let unfiled = !note.path.contains('/');
+        for (matches, category) in [
+            (untagged, &mut report.untagged),
+            (unfiled, &mut report.unfiled),
+        ] {
+            if !matches {
+                continue;
+            }
+            category.total += 1;
+            if category.notes.len() < limit {
+                category.notes.push(InboxNote {
+                    path: note.path.clone(),
+                    title: notes::derive_title(&note.content).unwrap_or_else(|| note.path.clone()),
+                });
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::report;
+    use crate::notes::NoteFile;
+
+    fn note(path: &str, content: &str) -> NoteFile {
+        NoteFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn report_lists_untagged_and_root_notes() {
+        let files = vec![
+            note("docs/tagged.md", "# Filed\n\n#plan\n"),
+            note("docs/meta.md", "---\ntags: [plan]\n---\n# Meta\n"),
+            note("idea.md", "# Idea\n\nNo tags yet.\n"),
+            note("loose.md", "# Loose\n\n#todo\n"),
+            note("docs/bare.md", "Just text\n"),
+        ];
+        let report = report(&files, 1);
+        assert_eq!(report.scanned, 5);
+        assert_eq!(report.untagged.total, 2);
+        assert_eq!(report.untagged.notes[0].path, "idea.md");
+        assert_eq!(report.untagged.notes.len(), 1);
+        assert_eq!(report.unfiled.total, 2);
+        assert_eq!(report.unfiled.notes[0].title, "Idea");
+    }
+}
@@ -0,0 +1,206 @@
+//! Journal streaks and statistics.
+//!
+//! `nb.journal_stats` reads the dates of journal entries from their paths in
+//! the journal folder (`2025-06-01.md`, `2025/06/01.md`, or nb's
+//! `20250601093000.md` timestamps) and reports which days of a range have
+//! entries, the current and longest streaks of consecutive days, and the
+//! average entry length in words. Files without a date in their path are
+//! not entries.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::LazyLock;
+
+use chrono::{Days, NaiveDate};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::nb::{NbClient, NbError};
+use crate::note_stats;
+use crate::notes;
+
+/// Journal folder when the call does not name one.
+pub const DEFAULT_FOLDER: &str = "journal";
+
+/// Days reported, ending with `until`, when `since` is not given.
+pub const DEFAULT_DAYS: u64 = 30;
+
+/// Matches a date in a path: `YYYY-MM-DD`, `YYYY/MM/DD`, or `YYYYMMDD`, not
+/// preceded by a digit.
+static DATE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[^0-9])(\d{4})[-/]?(\d{2})[-/]?(\d{2})").unwrap());
+
+/// Result of `nb.journal_stats`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct JournalStats {
+    pub folder: String,
+    pub since: String,
+    pub until: String,
+    /// Days in the range with at least one entry, oldest first.
+    pub entry_days: Vec<String>,
+    /// Days in the range without an entry.
+    pub missed_days: usize,
+    /// Entries dated within the range.
+    pub entries: usize,
+    /// Consecutive days with entries ending at `until`, or the day before
+    /// when `until` has no entry yet.
+    pub current_streak: usize,
+    /// Longest run of consecutive days with entries within the range.
+    pub longest_streak: usize,
+    /// Mean words per entry within the range.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_words: Option<f64>,
+}
+
+/// Reports journal activity in `folder` between `since` and `until`,
+/// inclusive.
+pub async fn journal_stats(
+    nb: &NbClient,
+    folder: &str,
+    since: NaiveDate,
+    until: NaiveDate,
+    notebook: Option<&str>,
+) -> Result<JournalStats, NbError> {
+    let root = nb.notebook_path(notebook).await?;
+    let scan_folder = folder.to_string();
+    let files = tokio::task::spawn_blocking(move || notes::read_notes(&root, Some(&scan_folder)))
+        .await
+        .map_err(|err| NbError::CommandFailed(format!("journal stats task failed: {err}")))?
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => NbError::CommandFailed(format!(
+                "no {folder} folder in the notebook; pass the journal folder as `folder`"
+            )),
+            _ => err.into(),
+        })?;
+    let entries = files
+        .iter()
+        .filter_map(|note| {
+            let relative = note.path.strip_prefix(folder).unwrap_or(&note.path);
+            let date = date_of(relative)?;
+            Some((date, note_stats::stats(&note.content).words))
+        })
+        .collect::<Vec<_>>();
+    Ok(summarize(folder, &entries, since, until))
+}
+
+/// Returns the date in a journal entry's path, if any.
+fn date_of(path: &str) -> Option<NaiveDate> {
+    DATE_REGEX.captures_iter(path).find_map(|captures| {
+        NaiveDate::from_ymd_opt(
+            captures[1].parse().ok()?,
+            captures[2].parse().ok()?,
+            captures[3].parse().ok()?,
+        )
+    })
+}
+
+/// Computes the statistics of dated `entries` (date and word count).
+fn summarize(
+    folder: &str,
+    entries: &[(NaiveDate, usize)],
+    since: NaiveDate,
+    until: NaiveDate,
+) -> JournalStats {
+    let all_days = entries
+        .iter()
+        .map(|(date, _)| *date)
+        .collect::<BTreeSet<_>>();
+    let mut words_by_day: BTreeMap<NaiveDate, Vec<usize>> = BTreeMap::new();
+    for (date, words) in entries {
+        if (since..=until).contains(date) {
+            words_by_day.entry(*date).or_default().push(*words);
+        }
+    }
+    let words = words_by_day.values().flatten().collect::<Vec<_>>();
+    let average_words = (!words.is_empty()).then(|| {
+        let mean = words.iter().copied().sum::<usize>() as f64 / words.len() as f64;
+        (mean * 10.0).round() / 10.0
+    });
+
+    // The streak may run back past `since`; today's entry may not be
+    // written yet, so a streak ending yesterday is still current.
+    let mut day = if all_days.contains(&until) {
+        Some(until)
+    } else {
+        until.checked_sub_days(Days::new(1))
+    };
+    let mut current_streak = 0;
+    while let Some(date) = day.filter(|date| all_days.contains(date)) {
+        current_streak += 1;
+        day = date.checked_sub_days(Days::new(1));
+    }
+
+    let mut longest_streak = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for date in words_by_day.keys() {
+        run = match previous {
+            Some(previous) if previous.checked_add_days(Days::new(1)) == Some(*date) => run + 1,
+            _ => 1,
+        };
+        longest_streak = longest_streak.max(run);
+        previous = Some(*date);
+    }
+
+    let range_days = (until - since).num_days() as usize + 1;
+    JournalStats {
+        folder: folder.to_string(),
+        since: since.to_string(),
+        until: until.to_string(),
+        entry_days: words_by_day.keys().map(NaiveDate::to_string).collect(),
+        missed_days: range_days - words_by_day.len(),
+        entries: words.len(),
+        current_streak,
+        longest_streak,
+        average_words,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{date_of, summarize};
+
+    fn day(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn date_of_reads_common_journal_names() {
+        assert_eq!(date_of("/2025-06-01.md"), Some(day("2025-06-01")));
+        assert_eq!(date_of("/2025/06/01.md"), Some(day("2025-06-01")));
+        assert_eq!(date_of("/20250601093000.md"), Some(day("2025-06-01")));
+        assert_eq!(date_of("/standup-2025-06-01.md"), Some(day("2025-06-01")));
+        assert_eq!(date_of("/ideas.md"), None);
+        assert_eq!(date_of("/2025-13-01.md"), None);
+    }
+
+    #[test]
+    fn summarize_counts_streaks_and_gaps() {
+        let entries = [
+            (day("2025-05-30"), 10),
+            (day("2025-06-01"), 100),
+            (day("2025-06-01"), 50),
+            (day("2025-06-02"), 30),
+            (day("2025-06-04"), 20),
+            (day("2025-06-05"), 40),
+            (day("2025-06-06"), 60),
+        ];
+        let stats = summarize("journal", &entries, day("2025-06-01"), day("2025-06-07"));
+        assert_eq!(
+            stats.entry_days,
+            [
+                "2025-06-01",
+                "2025-06-02",
+                "2025-06-04",
+                "2025-06-05",
+                "2025-06-06"
+            ]
+        );
+        assert_eq!(stats.missed_days, 2);
+        assert_eq!(stats.entries, 6);
+        assert_eq!(stats.current_streak, 3);
+        assert_eq!(stats.longest_streak, 3);
+        assert_eq!(stats.average_words, Some(50.0));
+    }
+}
@@ -0,0 +1,280 @@
+//! MCP server wrapping the [nb](https://xwmx.github.io/nb/) CLI.
+//!
+//! The `nb-mcp` binary is a thin wrapper around this crate. Embedders can
+//! drive notebooks directly through [`NbClient`], or run the MCP server over
+//! their own transport with [`mcp::serve`].
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! let config = nb_mcp_server::Config::default();
+//! nb_mcp_server::mcp::serve(config, rmcp::transport::stdio()).await
+//! # }
+//! ```
+
+// stdout belongs to the stdio transport; see `stdout_guard`.
+#![deny(clippy::print_stdout)]
+// The `nb` help listing is a single `json!` literal with one entry per command.
+#![recursion_limit = "256"]
+
+mod archive;
+mod backlinks;
+mod batch;
+mod blame;
+mod board;
+mod bookmark;
+mod changes;
+mod checklists;
+mod command_alias;
+mod commit_message;
+mod content_hash;
+mod counts;
+mod dedupe;
+mod dump;
+mod enex;
+mod file_type;
+mod fs_fallback;
+mod git_signing;
+mod git_sync;
+mod help_examples;
+mod help_search;
+mod html;
+mod inbox_report;
+mod journal_stats;
+mod links;
+mod list_cache;
+mod list_dates;
+mod list_sort;
+mod merge;
+mod nb_index;
+mod nb_version;
+mod note_cache;
+mod note_stats;
+mod notebook_clone;
+mod notebook_lock;
+mod notebook_scope;
+mod notes;
+mod notion;
+mod outline;
+mod output;
+mod output_note;
+mod page_metadata;
+mod pandoc;
+mod password;
+mod percent;
+mod preflight;
+mod preview;
+mod query;
+mod rate_limit;
+mod references;
+mod reload;
+mod remediation;
+mod request_id;
+mod resolve;
+mod resources;
+mod sampling;
+mod saved_note;
+mod server_info;
+mod session_folder;
+mod show_folder;
+mod show_many;
+mod shutdown;
+mod snippets;
+mod split;
+mod stale;
+mod stdout_guard;
+mod structured;
+mod tag_graph;
+mod task_log;
+mod tasks_ical;
+mod templates;
+mod toc;
+mod todotxt;
+mod unknown_args;
+mod usage;
+mod views;
+
+pub mod args;
+pub mod backup;
+pub mod executor;
+pub mod mcp;
+pub mod model;
+pub mod nb;
+pub mod notebook_dir;
+pub mod paths;
+pub mod project_config;
+pub mod scaffold;
+
+pub use executor::{EnvValue, FakeExecutor, NbExecutor, ProcessExecutor};
+pub use links::LinkStyle;
+pub use nb::{NbClient, NbError};
+
+/// Default threshold for slow-call warnings.
+pub const DEFAULT_SLOW_CALL_THRESHOLD: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Default number of nb and git subprocesses run at once.
+pub const DEFAULT_MAX_SUBPROCESSES: usize = 4;
+
+/// Configuration for the MCP server.
+pub struct Config {
+    /// Default notebook or comma-separated fallback chain (CLI --notebook
+    /// overrides NB_MCP_NOTEBOOK env var).
+    pub notebook: Option<String>,
+    /// Existing directory to serve as the default notebook, registered with
+    /// nb by [`notebook_dir::apply`] before serving (CLI --notebook-path
+    /// overrides NB_MCP_NOTEBOOK_PATH).
+    pub notebook_path: Option<std::path::PathBuf>,
+    /// How to derive the notebook name when none is configured.
+    pub notebook_derivation: nb::NotebookDerivation,
+    /// Disable commit and tag signing in the notebook repository.
+    pub commit_signing_disabled: bool,
+    /// Automatically create missing notebooks.
+    pub create_notebook: bool,
+    /// Never create notebooks, even for calls passing `allow_create: true`
+    /// (CLI --strict-notebooks or NB_MCP_STRICT_NOTEBOOKS).
+    pub strict_notebooks: bool,
+    /// Show notebook and state paths, then exit.
+    pub show_paths: bool,
+    /// Log level or `RUST_LOG`-style filter directives (CLI --log-level
+    /// overrides NB_MCP_LOG_LEVEL); `RUST_LOG` applies when unset.
+    pub log_level: Option<String>,
+    /// Log file replacing the per-project file in the state directory (CLI
+    /// --log-file overrides NB_MCP_LOG_FILE).
+    pub log_file: Option<std::path::PathBuf>,
+    /// Write logs to a file (disabled by CLI --no-file-log or
+    /// NB_MCP_NO_FILE_LOG).
+    pub file_log: bool,
+    /// Write logs to stderr (disabled by CLI --no-stderr-log or
+    /// NB_MCP_NO_STDERR_LOG).
+    pub stderr_log: bool,
+    /// Notes directory served read-only when nb is not installed (CLI
+    /// --no-nb-fallback overrides NB_MCP_NO_NB_FALLBACK).
+    pub nb_fallback_dir: Option<std::path::PathBuf>,
+    /// Directory the `path` of file imports and exports is resolved in (CLI
+    /// --export-dir overrides NB_MCP_EXPORT_DIR); the notebook's folder if
+    /// `None`.
+    pub export_dir: Option<std::path::PathBuf>,
+    /// Overrides of the environment nb runs with (CLI --nb-env overrides
+    /// NB_MCP_NB_ENV).
+    pub nb_env: Vec<(String, executor::EnvValue)>,
+    /// Port of the local note preview server, if enabled (CLI --preview-port
+    /// overrides NB_MCP_PREVIEW_PORT).
+    pub preview_port: Option<u16>,
+    /// Check nb, git, and the default notebook before serving (disabled by
+    /// CLI --no-preflight or NB_MCP_NO_PREFLIGHT).
+    pub preflight: bool,
+    /// Commit message template for mutations (CLI --commit-message overrides
+    /// NB_MCP_COMMIT_MESSAGE env var).
+    pub commit_message_template: Option<String>,
+    /// Maximum response size in bytes (CLI --max-output overrides
+    /// NB_MCP_MAX_OUTPUT env var).
+    pub max_output_bytes: usize,
+    /// `nb` calls admitted per minute (CLI --max-calls-per-minute overrides
+    /// NB_MCP_MAX_CALLS_PER_MINUTE and `.nb-mcp.toml`); `None` or 0 is
+    /// unlimited.
+    pub max_calls_per_minute: Option<u32>,
+    /// Mutating `nb` calls admitted per session (CLI --max-mutations
+    /// overrides NB_MCP_MAX_MUTATIONS and `.nb-mcp.toml`); `None` or 0 is
+    /// unlimited.
+    pub max_mutations: Option<u32>,
+    /// nb and git invocations slower than this are logged as warnings (CLI
+    /// --slow-call-ms overrides NB_MCP_SLOW_CALL_MS; 0 disables).
+    pub slow_call_threshold: Option<std::time::Duration>,
+    /// nb and git subprocesses run at once, with later ones queued (CLI
+    /// --max-subprocesses overrides NB_MCP_MAX_SUBPROCESSES; 0 disables).
+    pub max_subprocesses: Option<usize>,
+    /// Folder for notes and todos created without one (CLI --default-folder
+    /// overrides NB_MCP_DEFAULT_FOLDER env var).
+    pub default_folder: Option<String>,
+    /// Tags added to every note and todo (CLI --default-tag overrides
+    /// NB_MCP_DEFAULT_TAGS env var).
+    pub default_tags: Vec<String>,
+    /// Notebooks served to clients; when non-empty, every call must target
+    /// one of them (CLI --serve-notebook overrides NB_MCP_SERVE_NOTEBOOKS).
+    pub served_notebooks: Vec<String>,
+    /// Derive titles for untitled notes from their content (CLI --auto-title
+    /// or NB_MCP_AUTO_TITLE).
+    pub auto_title: bool,
+    /// Sync with the notebook remote after every write (CLI --auto-sync or
+    /// NB_MCP_AUTO_SYNC); otherwise writes stay local until `nb.push`.
+    pub auto_sync: bool,
+    /// Keep `server_usage` counts in the state directory across restarts
+    /// (CLI --persist-usage or NB_MCP_PERSIST_USAGE).
+    pub persist_usage: bool,
+    /// Subcommands clients may call (from `.nb-mcp.toml`); `None` allows all.
+    pub allowed_commands: Option<Vec<String>>,
+    /// Saved views from `.nb-mcp.toml`, by name.
+    pub views: std::collections::BTreeMap<String, args::SavedView>,
+    /// Server instructions from `.nb-mcp.toml`, replacing the built-in ones.
+    pub instructions: Option<String>,
+    /// `nb` tool description from `.nb-mcp.toml`, replacing the built-in one.
+    pub tool_description: Option<String>,
+    /// Help descriptions from `.nb-mcp.toml`, by subcommand.
+    pub help: std::collections::BTreeMap<String, String>,
+    /// Per-subcommand permissions from `.nb-mcp.toml`; unlisted subcommands
+    /// are allowed.
+    pub permissions: std::collections::BTreeMap<String, project_config::Permission>,
+    /// Structure written into automatically created notebooks (CLI
+    /// --scaffold, NB_MCP_SCAFFOLD, or `.nb-mcp.toml`); `None` disables it.
+    pub scaffold: Option<scaffold::Scaffold>,
+    /// Scheduled backups (from `.nb-mcp.toml`); `None` disables them.
+    pub backup: Option<backup::BackupConfig>,
+    /// Status tags naming the `nb.board` columns, in order (from
+    /// `.nb-mcp.toml`); empty uses `todo`, `doing`, and `done`.
+    pub board_columns: Vec<String>,
+    /// Syntax of links written by `nb.link` (from `.nb-mcp.toml`).
+    pub link_style: links::LinkStyle,
+    /// Maintain `## Backlinks` sections after every write (from
+    /// `.nb-mcp.toml`).
+    pub backlinks: bool,
+    /// Shell command printing the password of encrypted notes, with
+    /// `{notebook}` replaced by the notebook name (CLI --password-command
+    /// overrides NB_MCP_PASSWORD_COMMAND).
+    pub password_command: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            notebook: None,
+            notebook_derivation: nb::NotebookDerivation::default(),
+            commit_signing_disabled: false,
+            create_notebook: true,
+            strict_notebooks: false,
+            show_paths: false,
+            log_level: None,
+            log_file: None,
+            file_log: true,
+            stderr_log: true,
+            nb_fallback_dir: None,
+            export_dir: None,
+            preview_port: None,
+            notebook_path: None,
+            nb_env: Vec::new(),
+            preflight: true,
+            commit_message_template: None,
+            max_output_bytes: output::DEFAULT_MAX_OUTPUT_BYTES,
+            max_calls_per_minute: None,
+            max_mutations: None,
+            slow_call_threshold: Some(DEFAULT_SLOW_CALL_THRESHOLD),
+            max_subprocesses: Some(DEFAULT_MAX_SUBPROCESSES),
+            default_folder: None,
+            default_tags: Vec::new(),
+            served_notebooks: Vec::new(),
+            auto_title: false,
+            auto_sync: false,
+            persist_usage: false,
+            allowed_commands: None,
+            views: std::collections::BTreeMap::new(),
+            instructions: None,
+            tool_description: None,
+            help: std::collections::BTreeMap::new(),
+            permissions: std::collections::BTreeMap::new(),
+            scaffold: None,
+            backup: None,
+            board_columns: Vec::new(),
+            link_style: links::LinkStyle::default(),
+            backlinks: false,
+            password_command: None,
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Links between notes.
+//!
+//! `nb.link` adds a link from one note to another under a `## Links`
+//! section, creating the section at the end of the note if needed. Links use
+//! Markdown syntax with a path relative to the linking note, or nb's
+//! `[[wiki-link]]` syntax with the target's notebook path when
+//! `link_style = "wiki"` is set in `.nb-mcp.toml`.
+
+use std::path::{Component, Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// Heading of the section links are added under.
+const SECTION: &str = "Links";
+
+/// Syntax of links written between notes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStyle {
+    /// `[Title](relative/path.md)`.
+    #[default]
+    Markdown,
+    /// `[[folder/file.md]]`, or `[[folder/file.md|anchor]]` with anchor text.
+    Wiki,
+}
+
+/// Result of `nb.link`.
+#[derive(Debug, Serialize)]
+pub struct Linked {
+    pub from: String,
+    pub to: String,
+    /// The link as written in the note.
+    pub link: String,
+    /// False when the note already linked to the target.
+    pub changed: bool,
+}
+
+/// Adds a link to note `to_id` in note `from_id` and saves it through nb.
+pub async fn link(
+    nb: &NbClient,
+    style: LinkStyle,
+    from_id: &str,
+    to_id: &str,
+    anchor_text: Option<&str>,
+    notebook: Option<&str>,
+) -> Result<Linked, NbError> {
+    let root = nb.notebook_path(notebook).await?;
+    let from_path = nb.show_path(from_id, notebook).await?;
+    let to_path = nb.show_path(to_id, notebook).await?;
+    if from_path == to_path {
+        return Err(NbError::CommandFailed(format!(
+            "note {from_id} cannot link to itself"
+        )));
+    }
+    let content = tokio::fs::read_to_string(&from_path).await?;
+    let target = target(style, &root, &from_path, &to_path);
+    let link = match (style, anchor_text) {
+        (LinkStyle::Markdown, None) => markdown(&title(&to_path).await, &target),
+        (_, anchor) => format(style, &target, anchor.unwrap_or_default()),
+    };
+    let changed = !links_to(&content, &target);
+    if changed {
+        nb.edit(
+            from_id,
+            &add_to_section(&content, &format!("- {link}")),
+            notebook,
+        )
+        .await?;
+    }
+    Ok(Linked {
+        from: from_id.to_string(),
+        to: to_id.to_string(),
+        link,
+        changed,
+    })
+}
+
+/// Returns how the note at `from` refers to the note at `to` in `style`:
+/// a path relative to `from`'s folder, or to the notebook `root` for wiki
+/// links.
+pub fn target(style: LinkStyle, root: &Path, from: &Path, to: &Path) -> String {
+    match style {
+        LinkStyle::Markdown => relative_to(from.parent().unwrap_or(root), to),
+        LinkStyle::Wiki => notes::relative_path(root, to),
+    }
+}
+
+/// Formats a link to `target` with `text`, which wiki links omit when empty.
+pub fn format(style: LinkStyle, target: &str, text: &str) -> String {
+    match style {
+        LinkStyle::Markdown => markdown(text, target),
+        LinkStyle::Wiki if text.is_empty() => format!("[[{target}]]"),
+        LinkStyle::Wiki => format!("[[{target}|{text}]]"),
+    }
+}
+
+fn markdown(text: &str, target: &str) -> String {
+    if target.contains([' ', '(', ')']) {
+        format!("[{text}](<{target}>)")
+    } else {
+        format!("[{text}]({target})")
+    }
+}
+
+/// Returns the title of the note at `path`, or its file name.
+async fn title(path: &Path) -> String {
+    let content = tokio::fs::read_to_string(path).await.unwrap_or_default();
+    title_of(path, &content)
+}
+
+/// Returns the title of a note with `content`, or the file name of `path`.
+pub fn title_of(path: &Path, content: &str) -> String {
+    notes::derive_title(content).unwrap_or_else(|| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    })
+}
+
+/// Returns whether `content` already links to `target`, in either syntax.
+fn links_to(content: &str, target: &str) -> bool {
+    content.contains(&format!("]({target})"))
+        || content.contains(&format!("](<{target}>)"))
+        || content.contains(&format!("[[{target}]]"))
+        || content.contains(&format!("[[{target}|"))
+}
+
+/// Appends `item` to the end of the `## Links` section, adding the section
+/// at the end of the note if it has none.
+fn add_to_section(content: &str, item: &str) -> String {
+    let lines = content.lines().collect::<Vec<_>>();
+    let Some(start) = lines
+        .iter()
+        .position(|line| line.trim_end() == format!("## {SECTION}"))
+    else {
+        return notes::append_section(content, SECTION, item);
+    };
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.starts_with("# ") || line.starts_with("## "))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    // Insert after the section's last non-blank line.
+    let last = (start..end)
+        .rev()
+        .find(|index| !lines[*index].trim().is_empty())
+        .unwrap_or(start);
+    let mut updated = lines[..=last].to_vec();
+    if last == start {
+        updated.push("");
+    }
+    updated.push(item);
+    updated.extend(&lines[last + 1..]);
+    let mut updated = updated.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated
+}
+
+/// Renders `path` relative to the directory `from`, using `..` as needed.
+fn relative_to(from: &Path, path: &Path) -> String {
+    let from = from.components().collect::<Vec<_>>();
+    let path = path.components().collect::<Vec<_>>();
+    let common = from.iter().zip(&path).take_while(|(a, b)| a == b).count();
+    std::iter::repeat_n("..".to_string(), from.len() - common)
+        .chain(path[common..].iter().map(|component| match component {
+            Component::Normal(name) => name.to_string_lossy().into_owned(),
+            other => other.as_os_str().to_string_lossy().into_owned(),
+        }))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{add_to_section, links_to, relative_to};
+
+    #[test]
+    fn relative_to_walks_up_shared_folders() {
+        let root = Path::new("/notes/work");
+        assert_eq!(
+            relative_to(&root.join("projects"), &root.join("projects/plan.md")),
+            "plan.md"
+        );
+        assert_eq!(
+            relative_to(&root.join("projects/a"), &root.join("ideas/cache.md")),
+            "../../ideas/cache.md"
+        );
+    }
+
+    #[test]
+    fn add_to_section_appends_to_an_existing_section() {
+        let note = "# Plan\n\n## Links\n\n- [Old](old.md)\n\n## Notes\n\nText\n";
+        assert_eq!(
+            add_to_section(note, "- [New](new.md)"),
+            "# Plan\n\n## Links\n\n- [Old](old.md)\n- [New](new.md)\n\n## Notes\n\nText\n"
+        );
+        assert_eq!(
+            add_to_section("# Plan\n", "- [New](new.md)"),
+            "# Plan\n\n## Links\n\n- [New](new.md)\n"
+        );
+    }
+
+    #[test]
+    fn links_to_recognizes_both_syntaxes() {
+        assert!(links_to("See [Plan](plan.md).", "plan.md"));
+        assert!(links_to("See [[plan.md|the plan]].", "plan.md"));
+        assert!(!links_to("See [Plan](plan.md).", "ideas.md"));
+    }
+}
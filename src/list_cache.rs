@@ -0,0 +1,234 @@
+//! Warm cache of note listings.
+//!
+//! On large notebooks every `nb.list` pays for two nb invocations: the
+//! notebook's path, then the listing. Listings are kept here per notebook,
+//! folder, tags, and limit, and the notebook paths per notebook, so repeated
+//! listings answer from memory. A listing is served while the listed
+//! folder's modification time is unchanged (catching notes added or removed
+//! outside the server) and for at most a minute (catching titles edited
+//! outside it). Writes through the server drop the listings of the written
+//! notebook and then refresh them in the background, so the next listing is
+//! warm again without ever showing the state before the write.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Age after which a listing is fetched again.
+const MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Listings kept at most.
+const CAPACITY: usize = 32;
+
+/// Notebook a listing was requested for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NotebookKey {
+    /// Notebook named by the call.
+    pub requested: Option<String>,
+    /// Default notebook in effect when the call names none, so listings of
+    /// an earlier workspace root are not reused.
+    pub default: Option<String>,
+}
+
+impl NotebookKey {
+    /// Returns the name of the listed notebook, if known; `None` is nb's own
+    /// default.
+    pub fn name(&self) -> Option<&str> {
+        self.requested.as_deref().or(self.default.as_deref())
+    }
+}
+
+/// Arguments of a cached `nb list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListKey {
+    pub notebook: NotebookKey,
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    key: ListKey,
+    output: String,
+    /// Modification time of the listed folder before the listing ran.
+    folder_modified: Option<SystemTime>,
+    fetched: Instant,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    roots: HashMap<NotebookKey, PathBuf>,
+    /// Listings, most recently used first.
+    entries: VecDeque<Entry>,
+    /// Bumped by every invalidation, so listings fetched before a write are
+    /// not cached after it.
+    generation: u64,
+}
+
+/// Listings and notebook paths of the session.
+#[derive(Debug, Default)]
+pub struct ListCache {
+    state: Mutex<State>,
+}
+
+impl ListCache {
+    /// Returns the cached path of `notebook`.
+    pub fn root(&self, notebook: &NotebookKey) -> Option<PathBuf> {
+        self.lock().roots.get(notebook).cloned()
+    }
+
+    /// Caches the path of `notebook`.
+    pub fn set_root(&self, notebook: NotebookKey, root: PathBuf) {
+        self.lock().roots.insert(notebook, root);
+    }
+
+    /// Returns the current generation, to be passed back to `insert`.
+    pub fn generation(&self) -> u64 {
+        self.lock().generation
+    }
+
+    /// Returns the listing for `key` if it is younger than a minute and its
+    /// folder's modification time is still `folder_modified`.
+    pub fn get(
+        &self,
+        key: &ListKey,
+        folder_modified: Option<SystemTime>,
+        now: Instant,
+    ) -> Option<String> {
+        let mut state = self.lock();
+        let index = state.entries.iter().position(|entry| entry.key == *key)?;
+        let entry = state.entries.remove(index)?;
+        if folder_modified.is_none()
+            || entry.folder_modified != folder_modified
+            || now.duration_since(entry.fetched) >= MAX_AGE
+        {
+            return None;
+        }
+        let output = entry.output.clone();
+        state.entries.push_front(entry);
+        Some(output)
+    }
+
+    /// Caches the listing for `key`, fetched at `fetched` in `generation`,
+    /// unless the cache was invalidated since.
+    pub fn insert(
+        &self,
+        key: ListKey,
+        output: String,
+        folder_modified: Option<SystemTime>,
+        fetched: Instant,
+        generation: u64,
+    ) {
+        let mut state = self.lock();
+        if state.generation != generation {
+            return;
+        }
+        state.entries.retain(|entry| entry.key != key);
+        state.entries.push_front(Entry {
+            key,
+            output,
+            folder_modified,
+            fetched,
+        });
+        state.entries.truncate(CAPACITY);
+    }
+
+    /// Drops the listings of the `written` notebooks (every listing if
+    /// `None`), returning their keys, most recently used first, for
+    /// refreshing. Listings of nb's own default notebook are always dropped,
+    /// as it may be any of them.
+    pub fn invalidate(&self, written: Option<&[String]>) -> Vec<ListKey> {
+        let mut state = self.lock();
+        state.generation += 1;
+        let (dropped, kept) = state.entries.drain(..).partition(|entry: &Entry| {
+            match (written, entry.key.notebook.name()) {
+                (Some(written), Some(name)) => written.iter().any(|written| written == name),
+                _ => true,
+            }
+        });
+        state.entries = kept;
+        dropped.into_iter().map(|entry: Entry| entry.key).collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("list cache lock poisoned")
+    }
+}
+
+/// Returns the modification time of `folder` (the notebook root if `None`)
+/// of the notebook at `root`.
+pub async fn folder_modified(root: &Path, folder: Option<&str>) -> Option<SystemTime> {
+    let dir = match folder {
+        Some(folder) => root.join(folder.trim_matches('/')),
+        None => root.to_path_buf(),
+    };
+    tokio::fs::metadata(dir).await.ok()?.modified().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant, SystemTime};
+
+    use super::{ListCache, ListKey, NotebookKey};
+
+    fn key(folder: &str) -> ListKey {
+        ListKey {
+            notebook: NotebookKey {
+                requested: None,
+                default: Some("work".to_string()),
+            },
+            folder: Some(folder.to_string()),
+            tags: Vec::new(),
+            limit: None,
+        }
+    }
+
+    #[test]
+    fn get_serves_fresh_listings_of_unchanged_folders() {
+        let cache = ListCache::default();
+        let modified = Some(SystemTime::UNIX_EPOCH);
+        let now = Instant::now();
+        let generation = cache.generation();
+        cache.insert(key("inbox"), "[1] a".to_string(), modified, now, generation);
+        assert_eq!(
+            cache.get(&key("inbox"), modified, now).as_deref(),
+            Some("[1] a")
+        );
+        assert!(cache.get(&key("work"), modified, now).is_none());
+        let later = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+        assert!(cache.get(&key("inbox"), later, now).is_none());
+
+        cache.insert(key("inbox"), "[1] a".to_string(), modified, now, generation);
+        let stale = now + Duration::from_secs(60);
+        assert!(cache.get(&key("inbox"), modified, stale).is_none());
+    }
+
+    #[test]
+    fn invalidate_drops_listings_and_late_inserts() {
+        let cache = ListCache::default();
+        let modified = Some(SystemTime::UNIX_EPOCH);
+        let now = Instant::now();
+        let generation = cache.generation();
+        cache.insert(key("inbox"), "[1] a".to_string(), modified, now, generation);
+        assert_eq!(cache.invalidate(None), [key("inbox")]);
+        assert!(cache.get(&key("inbox"), modified, now).is_none());
+        cache.insert(key("inbox"), "[1] a".to_string(), modified, now, generation);
+        assert!(cache.get(&key("inbox"), modified, now).is_none());
+    }
+
+    #[test]
+    fn invalidate_keeps_listings_of_other_notebooks() {
+        let cache = ListCache::default();
+        let modified = Some(SystemTime::UNIX_EPOCH);
+        let now = Instant::now();
+        cache.insert(key("inbox"), "[1] a".to_string(), modified, now, 0);
+        assert!(cache.invalidate(Some(&["home".to_string()])).is_empty());
+        assert!(cache.get(&key("inbox"), modified, now).is_some());
+        assert_eq!(
+            cache.invalidate(Some(&["work".to_string()])),
+            [key("inbox")]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! Creation and modification dates for listed items.
+//!
+//! nb's list output carries no dates, yet agents need recency to rank notes.
+//! The structured result of `nb.list` gives each item a `created` date, taken
+//! from the commit that added its file (or the file itself while it is
+//! uncommitted), and a `modified` date from the file's modification time.
+//! Reading the dates the files were added walks the whole history, so they
+//! are cached per folder until the notebook's HEAD commit changes.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::Value;
+
+use crate::git_sync;
+use crate::nb::{NbClient, NbError};
+use crate::nb_index::ItemResolver;
+
+/// Folders whose dates are cached at most.
+const CAPACITY: usize = 32;
+
+/// Times files were added, by path.
+type Additions = Arc<HashMap<String, i64>>;
+
+/// Times files were added, per notebook root and folder, with the HEAD
+/// commit they were read at.
+#[derive(Debug, Default)]
+pub struct CreatedDates {
+    folders: Mutex<HashMap<(PathBuf, String), (String, Additions)>>,
+}
+
+impl CreatedDates {
+    /// Returns the times the files in `pathspec` of the notebook at `root`
+    /// were added, reading the history only when HEAD moved.
+    async fn get(&self, nb: &NbClient, root: &Path, pathspec: &str) -> Additions {
+        let head = git_sync::run_checked(nb, root, &["rev-parse", "HEAD"])
+            .await
+            .ok()
+            .map(|head| head.trim().to_string());
+        let key = (root.to_path_buf(), pathspec.to_string());
+        if let Some(head) = &head {
+            let folders = self.lock();
+            if let Some((cached, added)) = folders.get(&key) {
+                if cached == head {
+                    return added.clone();
+                }
+            }
+        }
+        let added = Arc::new(
+            git_sync::run_checked(
+                nb,
+                root,
+                &[
+                    "-c",
+                    "core.quotePath=false",
+                    "log",
+                    "--no-renames",
+                    "--diff-filter=A",
+                    "--name-only",
+                    "--format=%x00%at",
+                    "--",
+                    pathspec,
+                ],
+            )
+            .await
+            .map(|output| parse_additions(&output))
+            .unwrap_or_default(),
+        );
+        if let Some(head) = head {
+            let mut folders = self.lock();
+            if folders.len() >= CAPACITY && !folders.contains_key(&key) {
+                folders.clear();
+            }
+            folders.insert(key, (head, added.clone()));
+        }
+        added
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(PathBuf, String), (String, Additions)>> {
+        self.folders.lock().expect("created dates lock poisoned")
+    }
+}
+
+/// Adds `created` and `modified` to each item of a structured listing of
+/// `folder`.
+///
+/// Items whose file cannot be found are left without dates, and an
+/// unavailable git history falls back to file times.
+pub async fn annotate(
+    nb: &NbClient,
+    dates: &CreatedDates,
+    listing: &mut Value,
+    folder: Option<&str>,
+    notebook: Option<&str>,
+) -> Result<(), NbError> {
+    let Some(items) = listing.get_mut("items").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+    let root = nb.notebook_path(notebook).await?;
+    let pathspec = folder.map_or(".", |folder| folder.trim_matches('/'));
+    let added = dates.get(nb, &root, pathspec).await;
+    let mut resolver = ItemResolver::new(&root, folder);
+    for item in items {
+        let Some(path) = item
+            .get("id")
+            .and_then(Value::as_str)
+            .and_then(|id| resolver.resolve(id))
+        else {
+            continue;
+        };
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        let modified = metadata.modified().ok();
+        let created = relative(&path, &root)
+            .and_then(|relative| added.get(&relative).copied())
+            .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+            .or_else(|| metadata.created().ok().or(modified).map(DateTime::from));
+        if let Some(item) = item.as_object_mut() {
+            if let Some(created) = created {
+                item.insert("created".to_string(), Value::String(format_date(created)));
+            }
+            if let Some(modified) = modified {
+                let modified = format_date(DateTime::<Utc>::from(modified));
+                item.insert("modified".to_string(), Value::String(modified));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Maps each path in `git log --name-only --format=%x00%at` output to the
+/// time of its earliest listed commit.
+fn parse_additions(output: &str) -> HashMap<String, i64> {
+    let mut added = HashMap::new();
+    // Commits are listed newest first, so later ones overwrite.
+    for commit in output.split('\0').filter(|commit| !commit.is_empty()) {
+        let mut lines = commit.lines();
+        let Some(timestamp) = lines.next().and_then(|line| line.trim().parse().ok()) else {
+            continue;
+        };
+        for path in lines.filter(|line| !line.is_empty()) {
+            added.insert(path.to_string(), timestamp);
+        }
+    }
+    added
+}
+
+fn relative(path: &Path, root: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+fn format_date(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{CreatedDates, parse_additions};
+    use crate::executor::FakeExecutor;
+    use crate::nb::NbClient;
+
+    #[tokio::test]
+    async fn created_dates_are_read_again_when_head_moves() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-created-{}", std::process::id()));
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .current_dir(&dir)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
+        std::fs::create_dir_all(&dir).unwrap();
+        git(&["init", "-q"]);
+        std::fs::write(dir.join("a.md"), "# A\n").unwrap();
+        git(&["add", "a.md"]);
+        git(&["commit", "-q", "-m", "a"]);
+        let nb = NbClient::with_executor(Arc::new(FakeExecutor::new()), None, false);
+        let dates = CreatedDates::default();
+
+        let first = dates.get(&nb, &dir, ".").await;
+        assert!(first.contains_key("a.md"));
+        assert!(Arc::ptr_eq(&first, &dates.get(&nb, &dir, ".").await));
+
+        std::fs::write(dir.join("b.md"), "# B\n").unwrap();
+        git(&["add", "b.md"]);
+        git(&["commit", "-q", "-m", "b"]);
+        assert!(dates.get(&nb, &dir, ".").await.contains_key("b.md"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn parse_additions_keeps_the_earliest_commit() {
+        let output = "\x00200\n\nnotes/b.md\nnotes/a.md\n\x00100\n\nnotes/a.md\n";
+        let added = parse_additions(output);
+        assert_eq!(added["notes/a.md"], 100);
+        assert_eq!(added["notes/b.md"], 200);
+    }
+}
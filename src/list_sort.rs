@@ -0,0 +1,175 @@
+//! Server-side ordering of `nb list` output.
+//!
+//! nb lists items in its own order and offers no sort keys matching what
+//! agents usually ask for ("most recently modified first"). When `sort` is
+//! given, the server lists everything, maps each item's id to its file, and
+//! reorders the item lines by the requested key before applying the limit.
+
+use std::{cmp::Ordering, time::SystemTime};
+
+use crate::nb::NbError;
+use crate::nb_index::{self, ItemResolver};
+
+/// Sort key for listed items.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Created,
+    Modified,
+    Title,
+    Id,
+}
+
+impl SortKey {
+    pub fn parse(value: &str) -> Result<Self, NbError> {
+        match value {
+            "created" => Ok(Self::Created),
+            "modified" => Ok(Self::Modified),
+            "title" => Ok(Self::Title),
+            "id" => Ok(Self::Id),
+            other => Err(NbError::CommandFailed(format!(
+                "unknown sort {other:?}; expected created, modified, title, or id"
+            ))),
+        }
+    }
+
+    /// Dates sort newest first by default; names and ids ascending.
+    fn default_descending(self) -> bool {
+        matches!(self, Self::Created | Self::Modified)
+    }
+}
+
+/// Parses an `order` argument into "descending?", defaulting per key.
+pub fn descending(order: Option<&str>, key: SortKey) -> Result<bool, NbError> {
+    match order {
+        None => Ok(key.default_descending()),
+        Some("asc") => Ok(false),
+        Some("desc") => Ok(true),
+        Some(other) => Err(NbError::CommandFailed(format!(
+            "unknown order {other:?}; expected asc or desc"
+        ))),
+    }
+}
+
+/// A listed item with the attributes it may be sorted by.
+struct Entry<'a> {
+    line: &'a str,
+    id: &'a str,
+    number: Option<u64>,
+    title: String,
+    created: Option<SystemTime>,
+    modified: Option<SystemTime>,
+}
+
+/// Reorders the item lines of `output` and keeps at most `limit` of them.
+///
+/// Lines that are not items (such as nb's hints) are dropped. Items whose
+/// file cannot be found sort last for date keys.
+pub fn sort_listing(
+    output: &str,
+    resolver: &mut ItemResolver,
+    key: SortKey,
+    descending: bool,
+    limit: Option<u32>,
+) -> String {
+    let mut entries: Vec<Entry> = output
+        .lines()
+        .filter_map(|line| {
+            let (id, title) = nb_index::split_item(line)?;
+            let local = id.rsplit([':', '/']).next().unwrap_or(id);
+            let number = local.parse::<u64>().ok();
+            let metadata = resolver
+                .resolve(id)
+                .and_then(|path| std::fs::metadata(path).ok());
+            let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+            Some(Entry {
+                line,
+                id,
+                number,
+                title: title.trim().to_lowercase(),
+                created: metadata
+                    .as_ref()
+                    .and_then(|m| m.created().ok())
+                    .or(modified),
+                modified,
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Created => compare_dates(a.created, b.created, descending),
+            SortKey::Modified => compare_dates(a.modified, b.modified, descending),
+            SortKey::Title => a.title.cmp(&b.title),
+            SortKey::Id => a.number.cmp(&b.number).then_with(|| a.id.cmp(b.id)),
+        };
+        // Dates already account for direction so missing ones stay last.
+        if descending && !matches!(key, SortKey::Created | SortKey::Modified) {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    let limit = limit.map_or(entries.len(), |limit| limit as usize);
+    let mut sorted: String = entries
+        .iter()
+        .take(limit)
+        .map(|entry| format!("{}\n", entry.line))
+        .collect();
+    if sorted.is_empty() {
+        sorted = output.to_string();
+    }
+    sorted
+}
+
+fn compare_dates(a: Option<SystemTime>, b: Option<SystemTime>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SortKey, sort_listing};
+    use crate::nb_index::ItemResolver;
+
+    #[test]
+    fn sort_listing_orders_by_title_and_id() {
+        let output = "[2] beta\n[10] Alpha\n[3] gamma\nAdd a note: nb add\n";
+        let mut resolver = ItemResolver::new(std::path::Path::new("/nonexistent"), None);
+        assert_eq!(
+            sort_listing(output, &mut resolver, SortKey::Title, false, None),
+            "[10] Alpha\n[2] beta\n[3] gamma\n"
+        );
+        assert_eq!(
+            sort_listing(output, &mut resolver, SortKey::Id, true, Some(2)),
+            "[10] Alpha\n[3] gamma\n"
+        );
+    }
+
+    #[test]
+    fn sort_listing_uses_index_for_modification_times() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-sort-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(".index"), "old.md\nnew.md\n").unwrap();
+        std::fs::write(dir.join("old.md"), "old").unwrap();
+        let old = std::fs::File::options()
+            .write(true)
+            .open(dir.join("old.md"))
+            .unwrap();
+        old.set_modified(std::time::SystemTime::UNIX_EPOCH).unwrap();
+        std::fs::write(dir.join("new.md"), "new").unwrap();
+        let mut resolver = ItemResolver::new(&dir, None);
+        let sorted = sort_listing(
+            "[1] Old\n[2] New\n",
+            &mut resolver,
+            SortKey::Modified,
+            true,
+            None,
+        );
+        assert_eq!(sorted, "[2] New\n[1] Old\n");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,54 +1,126 @@
-use anyhow::Result;
-use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+//! `nb-mcp` binary. stdout carries the MCP stdio transport, so all
+//! human-facing output, including `--version` and `--show-paths`, goes to
+//! stderr.
 
-mod git_signing;
-mod mcp;
-mod nb;
-mod paths;
-
-/// Command-line configuration for the MCP server.
-pub struct Config {
-    /// Default notebook (CLI --notebook overrides NB_MCP_NOTEBOOK env var).
-    pub notebook: Option<String>,
-    /// Disable commit and tag signing in the notebook repository.
-    pub commit_signing_disabled: bool,
-    /// Automatically create missing notebooks.
-    pub create_notebook: bool,
-    /// Show notebook and state paths, then exit.
-    pub show_paths: bool,
-}
+#![deny(clippy::print_stdout)]
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            notebook: None,
-            commit_signing_disabled: false,
-            create_notebook: true,
-            show_paths: false,
-        }
-    }
-}
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use nb_mcp_server::{
+    Config, EnvValue, mcp, nb, notebook_dir, paths, project_config, scaffold::Scaffold,
+};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
-fn parse_args() -> Config {
+fn parse_args() -> Result<Config> {
     let mut config = Config::default();
     let mut args = std::env::args().skip(1);
+    let mut max_output = None;
+    let mut slow_call_ms = None;
+    let mut max_subprocesses = None;
+    let mut max_calls_per_minute = None;
+    let mut max_mutations = None;
+    let mut nb_env = Vec::new();
+    let mut preview_port = None;
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--notebook" | "-n" => {
                 config.notebook = args.next();
             }
+            "--worktree-notebooks" => {
+                config.notebook_derivation.per_worktree = true;
+            }
+            "--directory-notebook" => {
+                config.notebook_derivation.directory_fallback = true;
+            }
             "--no-commit-signing" => {
                 config.commit_signing_disabled = true;
             }
             "--no-create-notebook" => {
                 config.create_notebook = false;
             }
+            "--strict-notebooks" => {
+                config.strict_notebooks = true;
+            }
+            "--notebook-path" => {
+                config.notebook_path = args.next().map(Into::into);
+            }
+            "--no-nb-fallback" => {
+                config.nb_fallback_dir = args.next().map(Into::into);
+            }
+            "--export-dir" => {
+                config.export_dir = args.next().map(Into::into);
+            }
+            "--preview-port" => {
+                preview_port = args.next();
+            }
+            "--nb-env" => {
+                nb_env.extend(args.next());
+            }
+            "--no-preflight" => {
+                config.preflight = false;
+            }
+            "--log-level" => {
+                config.log_level = args.next();
+            }
+            "--log-file" => {
+                config.log_file = args.next().map(Into::into);
+            }
+            "--no-file-log" => {
+                config.file_log = false;
+            }
+            "--no-stderr-log" => {
+                config.stderr_log = false;
+            }
             "--show-paths" => {
                 config.show_paths = true;
             }
+            "--commit-message" => {
+                config.commit_message_template = args.next();
+            }
+            "--password-command" => {
+                config.password_command = args.next();
+            }
+            "--default-folder" => {
+                config.default_folder = args.next();
+            }
+            "--default-tag" => {
+                config.default_tags.extend(args.next());
+            }
+            "--serve-notebook" => {
+                config.served_notebooks.extend(args.next());
+            }
+            "--auto-title" => {
+                config.auto_title = true;
+            }
+            "--auto-sync" => {
+                config.auto_sync = true;
+            }
+            "--persist-usage" => {
+                config.persist_usage = true;
+            }
+            "--scaffold" => {
+                config.scaffold = Some(Scaffold::default());
+            }
+            "--max-output" => {
+                max_output = args.next();
+            }
+            "--slow-call-ms" => {
+                slow_call_ms = args.next();
+            }
+            "--max-subprocesses" => {
+                max_subprocesses = args.next();
+            }
+            "--max-calls-per-minute" => {
+                max_calls_per_minute = args.next();
+            }
+            "--max-mutations" => {
+                max_mutations = args.next();
+            }
             "--version" => {
-                println!("nb-mcp {}", env!("CARGO_PKG_VERSION"));
+                eprintln!("nb-mcp {}", env!("CARGO_PKG_VERSION"));
                 std::process::exit(0);
             }
             "--help" | "-h" => {
@@ -57,10 +129,91 @@ fn parse_args() -> Config {
                 eprintln!("Usage: nb-mcp [OPTIONS]");
                 eprintln!();
                 eprintln!("Options:");
-                eprintln!("  -n, --notebook <NAME>  Default notebook (overrides NB_MCP_NOTEBOOK)");
+                eprintln!("  -n, --notebook <NAME>  Default notebook (overrides NB_MCP_NOTEBOOK);");
+                eprintln!("                         comma-separated names form a fallback chain");
+                eprintln!("      --worktree-notebooks  Derive a separate notebook per Git");
+                eprintln!("                            worktree (project--worktree)");
+                eprintln!("      --directory-notebook  Outside Git, name the notebook after");
+                eprintln!("                            the current directory");
                 eprintln!("      --no-commit-signing  Disable commit and tag signing");
                 eprintln!("                            in notebook repo");
                 eprintln!("      --no-create-notebook  Disable automatic notebook creation");
+                eprintln!("      --strict-notebooks  Never create notebooks, even when a call");
+                eprintln!("                         passes allow_create");
+                eprintln!("      --notebook-path <DIR>");
+                eprintln!("                         Serve an existing notes directory as the");
+                eprintln!("                         default notebook, registering it with nb");
+                eprintln!("                         (overrides NB_MCP_NOTEBOOK_PATH)");
+                eprintln!("      --no-nb-fallback <DIR>");
+                eprintln!("                         Without nb, serve list, show, and search");
+                eprintln!("                         read-only from DIR (overrides");
+                eprintln!("                         NB_MCP_NO_NB_FALLBACK)");
+                eprintln!("      --export-dir <DIR>");
+                eprintln!("                         Resolve file paths of imports and exports");
+                eprintln!("                         in DIR instead of the notebook (overrides");
+                eprintln!("                         NB_MCP_EXPORT_DIR)");
+                eprintln!("      --preview-port <PORT>");
+                eprintln!("                         Serve rendered notes on 127.0.0.1:PORT");
+                eprintln!("                         for nb.preview_url (0 picks a port;");
+                eprintln!("                         overrides NB_MCP_PREVIEW_PORT)");
+                eprintln!("      --nb-env <KEY=VALUE>");
+                eprintln!("                         Override nb's environment; KEY= unsets,");
+                eprintln!("                         KEY inherits. Repeatable (overrides");
+                eprintln!("                         NB_MCP_NB_ENV)");
+                eprintln!("      --no-preflight     Skip the startup checks of nb, git, and");
+                eprintln!("                         the default notebook");
+                eprintln!("      --commit-message <TEMPLATE>");
+                eprintln!("                         Commit message template for mutations");
+                eprintln!("                         (overrides NB_MCP_COMMIT_MESSAGE)");
+                eprintln!("      --password-command <COMMAND>");
+                eprintln!("                         Shell command printing the password of");
+                eprintln!("                         encrypted notes; {{notebook}} is replaced");
+                eprintln!("                         (overrides NB_MCP_PASSWORD_COMMAND)");
+                eprintln!("      --default-folder <FOLDER>");
+                eprintln!("                         Folder for new notes and todos");
+                eprintln!("                         (overrides NB_MCP_DEFAULT_FOLDER)");
+                eprintln!("      --default-tag <TAG>  Tag added to new notes and todos;");
+                eprintln!("                         repeatable (overrides NB_MCP_DEFAULT_TAGS)");
+                eprintln!("      --serve-notebook <NAME>");
+                eprintln!("                         Serve this notebook; repeatable. Calls must");
+                eprintln!("                         target a served notebook (overrides");
+                eprintln!("                         NB_MCP_SERVE_NOTEBOOKS)");
+                eprintln!("      --auto-title       Title untitled notes from their first");
+                eprintln!("                         heading or sentence");
+                eprintln!("      --auto-sync        Sync the notebook remote after every");
+                eprintln!("                         write instead of on nb.push");
+                eprintln!("      --persist-usage    Keep server_usage counts in the state");
+                eprintln!("                         directory across restarts");
+                eprintln!("      --scaffold         Seed new notebooks with standard folders,");
+                eprintln!("                         a README note, and saved views");
+                eprintln!("      --max-output <BYTES>");
+                eprintln!("                         Maximum response size (overrides");
+                eprintln!("                         NB_MCP_MAX_OUTPUT, default 65536)");
+                eprintln!("      --slow-call-ms <MS>");
+                eprintln!("                         Warn about nb or git invocations slower");
+                eprintln!("                         than MS (overrides NB_MCP_SLOW_CALL_MS,");
+                eprintln!("                         default 5000; 0 disables)");
+                eprintln!("      --max-subprocesses <N>");
+                eprintln!("                         Run at most N nb or git processes at once,");
+                eprintln!("                         queuing the rest (overrides");
+                eprintln!(
+                    "                         NB_MCP_MAX_SUBPROCESSES, default 4; 0 disables)"
+                );
+                eprintln!("      --max-calls-per-minute <N>");
+                eprintln!("                         Refuse nb calls beyond N per minute; 0 is");
+                eprintln!("                         unlimited (overrides");
+                eprintln!("                         NB_MCP_MAX_CALLS_PER_MINUTE)");
+                eprintln!("      --max-mutations <N>");
+                eprintln!("                         Refuse writes after N in a session; 0 is");
+                eprintln!("                         unlimited (overrides NB_MCP_MAX_MUTATIONS)");
+                eprintln!("      --log-level <LEVEL>");
+                eprintln!("                         Log level (error, warn, info, debug, trace)");
+                eprintln!("                         or RUST_LOG-style directives (overrides");
+                eprintln!("                         NB_MCP_LOG_LEVEL and RUST_LOG)");
+                eprintln!("      --log-file <PATH>  Write the log file to PATH (overrides");
+                eprintln!("                         NB_MCP_LOG_FILE)");
+                eprintln!("      --no-file-log      Do not write a log file");
+                eprintln!("      --no-stderr-log    Do not log to stderr");
                 eprintln!("      --show-paths       Show notebook path and state directory");
                 eprintln!("      --version          Show version");
                 eprintln!("  -h, --help             Show this help");
@@ -72,41 +225,253 @@ fn parse_args() -> Config {
         }
     }
 
-    config
+    if let Some(path) = project_config::discover() {
+        project_config::load(&path)?.merge_into(&mut config);
+    }
+
+    if env_flag("NB_MCP_WORKTREE_NOTEBOOKS") {
+        config.notebook_derivation.per_worktree = true;
+    }
+    if env_flag("NB_MCP_DIRECTORY_NOTEBOOK") {
+        config.notebook_derivation.directory_fallback = true;
+    }
+    if env_flag("NB_MCP_STRICT_NOTEBOOKS") {
+        config.strict_notebooks = true;
+    }
+    if env_flag("NB_MCP_NO_FILE_LOG") {
+        config.file_log = false;
+    }
+    if env_flag("NB_MCP_NO_STDERR_LOG") {
+        config.stderr_log = false;
+    }
+    if config.log_level.is_none() {
+        config.log_level = std::env::var("NB_MCP_LOG_LEVEL")
+            .ok()
+            .filter(|level| !level.trim().is_empty());
+    }
+    if config.log_file.is_none() {
+        config.log_file = std::env::var("NB_MCP_LOG_FILE")
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .map(Into::into);
+    }
+    if env_flag("NB_MCP_NO_PREFLIGHT") {
+        config.preflight = false;
+    }
+    if env_flag("NB_MCP_AUTO_TITLE") {
+        config.auto_title = true;
+    }
+    if env_flag("NB_MCP_PERSIST_USAGE") {
+        config.persist_usage = true;
+    }
+    if env_flag("NB_MCP_AUTO_SYNC") {
+        config.auto_sync = true;
+    }
+    if config.scaffold.is_none() && env_flag("NB_MCP_SCAFFOLD") {
+        config.scaffold = Some(Scaffold::default());
+    }
+
+    if config.commit_message_template.is_none() {
+        config.commit_message_template = std::env::var("NB_MCP_COMMIT_MESSAGE")
+            .ok()
+            .filter(|template| !template.trim().is_empty());
+    }
+
+    if config.password_command.is_none() {
+        config.password_command = std::env::var("NB_MCP_PASSWORD_COMMAND")
+            .ok()
+            .filter(|command| !command.trim().is_empty());
+    }
+
+    if config.default_folder.is_none() {
+        config.default_folder = std::env::var("NB_MCP_DEFAULT_FOLDER")
+            .ok()
+            .filter(|folder| !folder.trim().is_empty());
+    }
+    if config.default_tags.is_empty() {
+        config.default_tags = std::env::var("NB_MCP_DEFAULT_TAGS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(String::from)
+            .collect();
+    }
+
+    if config.notebook_path.is_none() {
+        config.notebook_path = std::env::var("NB_MCP_NOTEBOOK_PATH")
+            .ok()
+            .filter(|dir| !dir.trim().is_empty())
+            .map(Into::into);
+    }
+
+    if config.export_dir.is_none() {
+        config.export_dir = std::env::var("NB_MCP_EXPORT_DIR")
+            .ok()
+            .filter(|dir| !dir.trim().is_empty())
+            .map(Into::into);
+    }
+
+    if config.nb_fallback_dir.is_none() {
+        config.nb_fallback_dir = std::env::var("NB_MCP_NO_NB_FALLBACK")
+            .ok()
+            .filter(|dir| !dir.trim().is_empty())
+            .map(Into::into);
+    }
+
+    if config.served_notebooks.is_empty() {
+        config.served_notebooks = std::env::var("NB_MCP_SERVE_NOTEBOOKS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect();
+    }
+
+    if nb_env.is_empty() {
+        nb_env = std::env::var("NB_MCP_NB_ENV")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|spec| !spec.is_empty())
+            .map(String::from)
+            .collect();
+    }
+    config.nb_env = nb_env
+        .iter()
+        .map(|spec| EnvValue::parse_override(spec))
+        .collect::<Result<_, _>>()?;
+
+    if let Some(port) = preview_port
+        .or_else(|| std::env::var("NB_MCP_PREVIEW_PORT").ok())
+        .filter(|port| !port.trim().is_empty())
+    {
+        config.preview_port = Some(
+            port.trim()
+                .parse()
+                .with_context(|| format!("invalid preview port {port:?}"))?,
+        );
+    }
+
+    if let Some(bytes) = max_output
+        .or_else(|| std::env::var("NB_MCP_MAX_OUTPUT").ok())
+        .and_then(|bytes| bytes.trim().parse().ok())
+        .filter(|bytes| *bytes > 0)
+    {
+        config.max_output_bytes = bytes;
+    }
+
+    if let Some(limit) = max_calls_per_minute
+        .or_else(|| std::env::var("NB_MCP_MAX_CALLS_PER_MINUTE").ok())
+        .filter(|limit| !limit.trim().is_empty())
+    {
+        config.max_calls_per_minute = Some(
+            limit
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid calls-per-minute limit {limit:?}"))?,
+        );
+    }
+
+    if let Some(limit) = max_mutations
+        .or_else(|| std::env::var("NB_MCP_MAX_MUTATIONS").ok())
+        .filter(|limit| !limit.trim().is_empty())
+    {
+        config.max_mutations = Some(
+            limit
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid mutation limit {limit:?}"))?,
+        );
+    }
+
+    if let Some(ms) = slow_call_ms
+        .or_else(|| std::env::var("NB_MCP_SLOW_CALL_MS").ok())
+        .filter(|ms| !ms.trim().is_empty())
+    {
+        let ms: u64 = ms
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid slow-call threshold {ms:?}"))?;
+        config.slow_call_threshold = (ms > 0).then(|| std::time::Duration::from_millis(ms));
+    }
+
+    if let Some(limit) = max_subprocesses
+        .or_else(|| std::env::var("NB_MCP_MAX_SUBPROCESSES").ok())
+        .filter(|limit| !limit.trim().is_empty())
+    {
+        let limit: usize = limit
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid subprocess limit {limit:?}"))?;
+        config.max_subprocesses = (limit > 0).then_some(limit);
+    }
+
+    Ok(config)
+}
+
+/// Returns true if the environment variable is set to `1`, `true`, or `yes`.
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|value| {
+        matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes"
+        )
+    })
 }
 
 async fn show_paths(config: &Config) -> Result<()> {
     let nb = nb::NbClient::new(
         config.notebook.as_deref(),
+        config.notebook_derivation,
         config.create_notebook,
         config.commit_signing_disabled,
-    )?;
-    let notebook_path = nb.notebook_path(config.notebook.as_deref()).await?;
+    )?
+    .with_nb_env(config.nb_env.clone())
+    .with_strict_notebooks(config.strict_notebooks)
+    .with_scaffold(config.scaffold.clone());
+    let notebook_path = nb.notebook_path(None).await?;
     let log_path = paths::get_log_path();
     let state_dir = log_path.parent().unwrap_or(log_path.as_path());
-    println!("notebook_path: {}", notebook_path.display());
-    println!("state_dir: {}", state_dir.display());
+    eprintln!("notebook_path: {}", notebook_path.display());
+    eprintln!("state_dir: {}", state_dir.display());
     Ok(())
 }
 
-/// Set up logging to both stderr and a file.
+/// Set up logging to stderr and a file, as enabled in `config`.
 ///
 /// - Stderr: For immediate feedback during development
-/// - File: For persistent logs in `~/.local/state/nb-mcp/{project}--{worktree}.log`
-fn setup_logging() {
-    let env_filter = EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into());
+/// - File: For persistent logs, by default in
+///   `~/.local/state/nb-mcp/{project}--{worktree}.log`
+///
+/// Returns the file actually logged to, if any, and the file writer's guard;
+/// dropping the guard flushes buffered log lines.
+fn setup_logging(config: &Config) -> Result<(Option<PathBuf>, Option<WorkerGuard>)> {
+    let env_filter = match config.log_level.as_deref() {
+        Some(level) => EnvFilter::try_new(level.trim())
+            .with_context(|| format!("invalid log level {level:?}"))?,
+        None => EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()),
+    };
 
     // Stderr layer (compact, for console)
-    let stderr_layer = fmt::layer().with_writer(std::io::stderr).compact();
+    let stderr_layer = config
+        .stderr_log
+        .then(|| fmt::layer().with_writer(std::io::stderr).compact());
 
     // File layer (with timestamps, for debugging)
-    let file_layer = match setup_file_logging() {
-        Some((writer, guard)) => {
-            // Keep the guard alive by leaking it (file logger lives for process lifetime)
-            std::mem::forget(guard);
-            Some(fmt::layer().with_writer(writer).with_ansi(false))
-        }
-        None => None,
+    let log_path = config.log_file.clone().unwrap_or_else(paths::get_log_path);
+    let (file_layer, guard, log_path) = match config
+        .file_log
+        .then(|| setup_file_logging(&log_path))
+        .flatten()
+    {
+        Some((writer, guard)) => (
+            Some(fmt::layer().with_writer(writer).with_ansi(false)),
+            Some(guard),
+            Some(log_path),
+        ),
+        None => (None, None, None),
     };
 
     tracing_subscriber::registry()
@@ -114,17 +479,19 @@ fn setup_logging() {
         .with(stderr_layer)
         .with(file_layer)
         .init();
+    Ok((log_path, guard))
 }
 
-/// Set up file logging, returning the writer and guard.
+/// Set up file logging at `log_path`, returning the writer and guard.
 ///
 /// Returns `None` if the log directory cannot be created.
-fn setup_file_logging() -> Option<(
-    tracing_appender::non_blocking::NonBlocking,
-    tracing_appender::non_blocking::WorkerGuard,
-)> {
-    let log_path = paths::get_log_path();
-    let log_dir = log_path.parent()?;
+fn setup_file_logging(
+    log_path: &Path,
+) -> Option<(tracing_appender::non_blocking::NonBlocking, WorkerGuard)> {
+    let log_dir = log_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
     let log_filename = log_path.file_name()?.to_str()?;
 
     // Ensure log directory exists
@@ -139,16 +506,30 @@ fn setup_file_logging() -> Option<(
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = parse_args();
+    let mut config = parse_args()?;
+    notebook_dir::apply(&mut config).await?;
     if config.show_paths {
         show_paths(&config).await?;
         return Ok(());
     }
 
-    setup_logging();
-
-    let log_path = paths::get_log_path();
-    tracing::info!(log_file = %log_path.display(), "logging initialized");
+    let (log_path, log_guard) = setup_logging(&config)?;
+    match log_path {
+        Some(path) => tracing::info!(log_file = %path.display(), "logging initialized"),
+        None => tracing::info!("logging initialized without a log file"),
+    }
 
-    mcp::run(config).await
+    let stderr_log = config.stderr_log;
+    let result = mcp::run(config).await;
+    if let Err(err) = &result {
+        tracing::error!(error = %err, "nb-mcp server failed");
+        if stderr_log {
+            eprintln!("Error: {err:?}");
+        }
+    }
+    // Flush buffered file log lines before exiting.
+    drop(log_guard);
+    // Exit directly: the runtime would otherwise wait on the blocking stdin
+    // reader, which never returns while the client keeps the pipe open.
+    std::process::exit(if result.is_ok() { 0 } else { 1 })
 }
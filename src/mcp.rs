@@ -1,23 +1,179 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
 use anyhow::Result;
+use base64::prelude::{BASE64_STANDARD, Engine as _};
 use rmcp::{
-    ErrorData as McpError, ServiceExt,
+    ErrorData as McpError, Peer, RoleServer, ServiceExt,
     handler::server::router::tool::ToolRouter,
     handler::server::wrapper::Parameters,
-    model::{CallToolResult, Content, ServerCapabilities, ServerInfo},
+    model::{CallToolResult, Content, ProgressToken, ServerCapabilities, ServerInfo},
+    model::{CreateElicitationRequestParam, ElicitationAction},
+    model::{
+        ListResourceTemplatesResult, ListResourcesResult, PaginatedRequestParam,
+        ReadResourceRequestParam, ReadResourceResult,
+    },
+    service::{NotificationContext, RequestContext},
     tool, tool_handler, tool_router,
-    transport::stdio,
+    transport::IntoTransport,
 };
 use schemars::JsonSchema;
 use serde::Deserialize;
-use tracing::{info, warn};
+use tracing::{Instrument, debug, info, warn};
 
 use crate::Config;
+use crate::archive;
+use crate::args::*;
+use crate::backlinks;
+use crate::backup::{self, BackupMonitor};
+use crate::blame;
+use crate::board;
+use crate::bookmark;
+use crate::changes;
+use crate::checklists;
+use crate::command_alias;
+use crate::commit_message::{self, CommitMessageTemplate};
+use crate::content_hash::{self, Conflict};
+use crate::counts;
+use crate::dedupe;
+use crate::dump;
+use crate::enex;
+use crate::file_type::{self, BinaryNote};
+use crate::fs_fallback::{self, FsNotebook};
 use crate::git_signing;
-use crate::nb::NbClient;
+use crate::git_sync;
+use crate::help_examples;
+use crate::help_search;
+use crate::inbox_report;
+use crate::journal_stats;
+use crate::links::{self, LinkStyle};
+use crate::list_cache::{self, ListCache, ListKey, NotebookKey};
+use crate::list_dates::{self, CreatedDates};
+use crate::list_sort;
+use crate::merge;
+use crate::nb::{self, NbClient, NbError};
+use crate::nb_index::ItemResolver;
+use crate::nb_version::Compatibility;
+use crate::note_cache::{CachedNote, NoteCache};
+use crate::note_stats;
+use crate::notebook_clone;
+use crate::notebook_lock::{self, NotebookLock};
+use crate::notebook_scope;
+use crate::notes;
+use crate::notion;
+use crate::outline;
+use crate::output;
+use crate::output_note;
+use crate::pandoc;
+use crate::password;
+use crate::paths;
+use crate::preflight;
+use crate::preview::{self, Preview};
+use crate::project_config::Permission;
+use crate::query;
+use crate::rate_limit::{self, RateLimiter};
+use crate::references;
+use crate::reload::{self, Policy, ReloadReport};
+use crate::remediation::{self, Remediation};
+use crate::request_id;
+use crate::resolve;
+use crate::resources;
+use crate::sampling;
+use crate::saved_note;
+use crate::server_info::{self, CallStats};
+use crate::session_folder;
+use crate::show_folder;
+use crate::show_many::{self, ShownNotes};
+use crate::shutdown::{self, CallTracker};
+use crate::snippets;
+use crate::split;
+use crate::stale;
+use crate::stdout_guard::{self, ProtocolWriter};
+use crate::structured;
+use crate::tag_graph;
+use crate::task_log;
+use crate::tasks_ical;
+use crate::templates;
+use crate::toc;
+use crate::todotxt;
+use crate::unknown_args;
+use crate::usage::UsageTracker;
+use crate::views;
+
+/// Guidance attached to truncated `show` output.
+const SHOW_LIMIT_HINT: &str =
+    "Output truncated. Call nb.show again with range.offset set to next_offset to continue.";
+
+/// Server instructions sent at initialize, unless the project replaces them.
+const DEFAULT_INSTRUCTIONS: &str = "MCP server wrapping nb CLI for LLM-friendly note-taking. \
+     Handles markdown escaping and notebook qualification automatically.";
+
+/// Guidance attached to other truncated output.
+const LIMIT_HINT: &str =
+    "Output truncated. Narrow the request (e.g., folder, tags, or limit) to see more.";
 
 #[derive(Clone)]
 struct McpServer {
     nb: NbClient,
+    /// Template for rewriting commit messages of mutating commands.
+    commit_message_template: Option<CommitMessageTemplate>,
+    /// Maximum response size in bytes.
+    max_output_bytes: usize,
+    /// Folder for created notes and todos that do not name one.
+    default_folder: Option<String>,
+    /// Tags added to created notes and todos.
+    default_tags: Vec<String>,
+    /// Notebooks calls may target; empty when the server is not scoped.
+    served_notebooks: Vec<String>,
+    /// Served notebook the client chose at initialize, shared between clones.
+    session_notebook: Arc<RwLock<Option<String>>>,
+    /// Folder set with `nb.use_folder`, shared between clones.
+    session_folder: Arc<RwLock<Option<String>>>,
+    /// Allowlist and views, replaced when the configuration is reloaded.
+    policy: Arc<RwLock<Policy>>,
+    /// Derive a title from the content when `add` is called without one.
+    auto_title: bool,
+    /// Status tags naming the board columns, in order.
+    board_columns: Vec<String>,
+    /// Directory file imports and exports resolve `path` in; the notebook's
+    /// folder if `None`.
+    export_dir: Option<PathBuf>,
+    /// Syntax of links written by `link` and in backlink sections.
+    link_style: LinkStyle,
+    /// Regenerate backlink sections after every write.
+    backlinks: bool,
+    /// Installed nb checked against required versions, once probed.
+    compatibility: Option<Compatibility>,
+    /// Calls served, shared between clones.
+    stats: Arc<CallStats>,
+    /// Per-command usage, persisted across restarts if configured.
+    usage: Arc<UsageTracker>,
+    /// Recently shown notes, shared between clones.
+    note_cache: Arc<NoteCache>,
+    /// Recent listings and notebook paths, shared between clones.
+    list_cache: Arc<ListCache>,
+    /// Creation dates of listed files, shared between clones.
+    created_dates: Arc<CreatedDates>,
+    /// Background refresh of the listings after the latest write.
+    listing_refresh: Arc<std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// Call rate and mutation limits of the session, shared between clones.
+    rate_limiter: Arc<RateLimiter>,
+    /// In-flight calls, drained on shutdown.
+    calls: Arc<CallTracker>,
+    /// Scheduled backup task, if configured.
+    backup: Option<Arc<BackupMonitor>>,
+    /// Notes directory served read-only because nb is not installed.
+    fallback: Option<Arc<FsNotebook>>,
+    /// Local server rendering notes, if enabled.
+    preview: Option<Arc<Preview>>,
+    /// Project replacements for the instructions, the `nb` tool description,
+    /// and help descriptions.
+    instructions: Option<String>,
+    tool_description: Option<String>,
+    help: Arc<BTreeMap<String, String>>,
     tool_router: ToolRouter<Self>,
 }
 
@@ -29,6 +185,10 @@ struct NbCall {
     /// Arguments for the subcommand as a JSON object.
     #[serde(default)]
     args: serde_json::Value,
+    /// Progress token from the request metadata, if the client sent one.
+    #[serde(skip)]
+    #[schemars(skip)]
+    progress_token: Option<ProgressToken>,
 }
 
 /// Parameters for the help tool.
@@ -38,220 +198,220 @@ struct HelpParams {
     query: String,
 }
 
-// Command-specific argument structs
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct StatusArgs {
-    /// Notebook to check status for (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct AddArgs {
-    /// Title for the note.
-    title: Option<String>,
-    /// Content of the note. Markdown is supported.
-    content: String,
-    /// Tags to apply (without # prefix).
-    #[serde(default)]
-    tags: Vec<String>,
-    /// Folder to create the note in.
-    folder: Option<String>,
-    /// Notebook to add to (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct ShowArgs {
-    /// Note ID, filename, or title to show.
-    id: String,
-    /// Notebook to read from (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct EditArgs {
-    /// Note ID, filename, or title to edit.
-    id: String,
-    /// New content for the note (replaces existing content).
-    content: String,
-    /// Notebook containing the note (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct DeleteArgs {
-    /// Note ID, filename, or title to delete.
-    id: String,
-    /// Must be true to confirm deletion.
-    #[serde(default)]
-    confirm: bool,
-    /// Notebook containing the note (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct MoveArgs {
-    /// Note ID, filename, or title to move/rename.
-    id: String,
-    /// Destination path or new name. Can be a folder path (ending with /) or a new filename.
-    /// Examples: "new-folder/" (move to folder), "new-name.md" (rename), "folder/new-name.md" (move and rename).
-    destination: String,
-    /// Notebook containing the note (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct ListArgs {
-    /// Folder to list (lists root if not specified).
-    folder: Option<String>,
-    /// Filter by tags (without # prefix).
-    #[serde(default)]
-    tags: Vec<String>,
-    /// Maximum number of items to return.
-    limit: Option<u32>,
-    /// Notebook to list from (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct SearchArgs {
-    /// Search query (supports regex).
-    query: String,
-    /// Filter by tags (without # prefix).
-    #[serde(default)]
-    tags: Vec<String>,
-    /// Folder to search within (searches all if not specified).
-    folder: Option<String>,
-    /// Notebook to search in (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct TodoArgs {
-    /// Description of the todo item.
-    description: String,
-    /// Tags to apply (without # prefix).
-    #[serde(default)]
-    tags: Vec<String>,
-    /// Folder to create the todo in.
-    folder: Option<String>,
-    /// Notebook to add todo to (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct TaskIdArgs {
-    /// Todo ID to mark as done/undone.
-    id: String,
-    /// Notebook containing the todo (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct TasksArgs {
-    /// Folder to list todos from (lists all if not specified).
-    folder: Option<String>,
-    /// Notebook to list todos from (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct BookmarkArgs {
-    /// URL to bookmark.
-    url: String,
-    /// Title for the bookmark.
-    title: Option<String>,
-    /// Tags to apply (without # prefix).
-    #[serde(default)]
-    tags: Vec<String>,
-    /// Comment or description.
-    comment: Option<String>,
-    /// Folder to create the bookmark in.
-    folder: Option<String>,
-    /// Notebook to add bookmark to (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct FoldersArgs {
-    /// Parent folder to list (lists root if not specified).
-    parent: Option<String>,
-    /// Notebook to list folders from (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct MkdirArgs {
-    /// Path of folder to create.
-    path: String,
-    /// Notebook to create folder in (uses default if not specified).
-    notebook: Option<String>,
-}
-
-#[derive(Debug, Default, Deserialize, JsonSchema)]
-struct ImportArgs {
-    /// File path or URL to import.
-    source: String,
-    /// Folder to import into (imports to root if not specified).
-    folder: Option<String>,
-    /// Filename to use in notebook (uses original name if not specified).
-    filename: Option<String>,
-    /// Convert HTML content to Markdown.
-    #[serde(default)]
-    convert: bool,
-    /// Notebook to import into (uses default if not specified).
-    notebook: Option<String>,
-}
-
 #[tool_router]
 impl McpServer {
     fn new(config: &Config) -> Result<Self> {
         let nb = NbClient::new(
             config.notebook.as_deref(),
+            config.notebook_derivation,
             config.create_notebook,
             config.commit_signing_disabled,
-        )?;
-        Ok(Self {
+        )?
+        .with_nb_env(config.nb_env.clone())
+        .with_strict_notebooks(config.strict_notebooks)
+        .with_scaffold(config.scaffold.clone())
+        .with_slow_call_threshold(config.slow_call_threshold)
+        .with_max_subprocesses(config.max_subprocesses)
+        .with_password_command(config.password_command.clone());
+        // Without the flag, `--nb-env NB_AUTO_SYNC=...` still applies.
+        let nb = if config.auto_sync {
+            nb.with_auto_sync(true)
+        } else {
+            nb
+        };
+        Ok(Self::with_client(nb, config))
+    }
+
+    /// Creates a server around an existing client (e.g., one with a fake executor).
+    fn with_client(nb: NbClient, config: &Config) -> Self {
+        Self {
             nb,
+            commit_message_template: config
+                .commit_message_template
+                .as_deref()
+                .map(CommitMessageTemplate::new),
+            max_output_bytes: config.max_output_bytes,
+            default_folder: config.default_folder.clone(),
+            default_tags: config.default_tags.clone(),
+            served_notebooks: config.served_notebooks.clone(),
+            session_notebook: Arc::default(),
+            session_folder: Arc::default(),
+            policy: Arc::new(RwLock::new(Policy::from_config(config))),
+            auto_title: config.auto_title,
+            board_columns: board::columns(&config.board_columns),
+            export_dir: config.export_dir.clone(),
+            link_style: config.link_style,
+            backlinks: config.backlinks,
+            compatibility: None,
+            stats: Arc::default(),
+            usage: Arc::new(if config.persist_usage {
+                UsageTracker::persisted(paths::get_usage_path())
+            } else {
+                UsageTracker::default()
+            }),
+            note_cache: Arc::default(),
+            list_cache: Arc::default(),
+            created_dates: Arc::default(),
+            listing_refresh: Arc::default(),
+            rate_limiter: Arc::new(RateLimiter::new(
+                config.max_calls_per_minute,
+                config.max_mutations,
+            )),
+            calls: Arc::default(),
+            backup: None,
+            fallback: None,
+            preview: None,
+            instructions: config.instructions.clone(),
+            tool_description: config.tool_description.clone(),
+            help: Arc::new(config.help.clone()),
             tool_router: Self::tool_router(),
-        })
+        }
     }
 
     #[tool(
-        description = "nb note-taking tool. Commands: status, add, show, edit, delete, list, search, todo, do, undo, tasks, bookmark, folders, mkdir, notebooks, import. Use `help` for schemas."
+        description = "nb note-taking tool. Commands: status, add, show, show_many, show_folder, edit, delete, resolve, copy_to, archive_note, link, list, search, todo, do, undo, tasks, task_metrics, tasks_ical, todotxt_import, todotxt_export, board, board_move, bookmark, folders, pinned, preview_url, mkdir, notebooks, notebook_clone, import, export, dump, load, push, pull, changes, blame, git_status, summarize, suggest_tags, tag_graph, inbox_report, stale, dedupe, merge, split, outline, toc, note_stats, journal_stats, references, extract_todos, toggle_checkbox, view, save_view, snippet, save_snippet, use_folder, use_notebook. Use `help` for schemas."
     )]
-    async fn nb(&self, Parameters(call): Parameters<NbCall>) -> Result<CallToolResult, McpError> {
-        self.dispatch_nb(call).await
+    async fn nb(
+        &self,
+        Parameters(mut call): Parameters<NbCall>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let Some(_guard) = self.calls.begin() else {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "nb-mcp is shutting down; no new commands are accepted",
+            )]));
+        };
+        if let Some(cancelled) = self.confirm_with_user(&mut call, &context.peer).await? {
+            return Ok(cancelled);
+        }
+        call.progress_token = context.meta.get_progress_token();
+        self.dispatch_nb(call, Some(&context.peer)).await
     }
 
     #[tool(
-        description = "Return sub-command help and JSON schemas. Query 'nb' for command list or 'nb.<command>' for details."
+        description = "Return sub-command help and JSON schemas. Query 'nb' for command list or 'nb.<command>' for details, or describe a task (e.g., 'tag a note') to find matching commands."
     )]
     async fn help(
         &self,
         Parameters(params): Parameters<HelpParams>,
     ) -> Result<CallToolResult, McpError> {
-        help_tool(params)
+        help_tool(params, &self.help)
+    }
+
+    #[tool(
+        description = "Report nb, git, and pandoc versions, the default notebook and its path, commit signing, uptime, and call counts. Use it to diagnose environment problems before working."
+    )]
+    async fn server_info(&self) -> Result<CallToolResult, McpError> {
+        let report = server_info::report(&self.nb, &self.stats, self.backup.as_deref()).await;
+        Ok(CallToolResult::success(vec![Content::json(report)?]))
+    }
+
+    #[tool(
+        description = "Report calls and error rates per nb subcommand, most used first. Counts accumulate across restarts when the server runs with --persist-usage."
+    )]
+    async fn server_usage(&self) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::json(
+            self.usage.report(),
+        )?]))
+    }
+
+    #[tool(
+        description = "Reload allowed_commands, permissions, and views from .nb-mcp.toml without restarting. Sends tools/list_changed when the allowed or denied commands change."
+    )]
+    async fn server_reload(
+        &self,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        match self.reload(Some(&context.peer)).await {
+            Ok(report) => Ok(CallToolResult::success(vec![Content::json(report)?])),
+            Err(err) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "reload failed; keeping the current configuration: {err:#}"
+            ))])),
+        }
     }
 }
 
-#[tool_handler]
+#[tool_handler(router = self.tools())]
 impl rmcp::ServerHandler for McpServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             instructions: Some(
-                "MCP server wrapping nb CLI for LLM-friendly note-taking. \
-                 Handles markdown escaping and notebook qualification automatically."
-                    .to_string(),
+                self.instructions
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_INSTRUCTIONS.to_string()),
             ),
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .enable_resources()
+                .build(),
             ..Default::default()
         }
     }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        self.apply_session_notebook(&context.peer);
+        // The handshake awaits this handler, so roots must be requested from
+        // a separate task or the request would never be read.
+        let server = self.clone();
+        tokio::spawn(async move { server.apply_workspace_roots(&context.peer).await });
+    }
+
+    async fn on_roots_list_changed(&self, context: NotificationContext<RoleServer>) {
+        self.apply_workspace_roots(&context.peer).await;
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let resources = resources::list(&self.nb, &self.served_notebooks, &self.policy).await?;
+        Ok(ListResourcesResult::with_all_items(resources))
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        Ok(ListResourceTemplatesResult::with_all_items(
+            resources::templates(),
+        ))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let contents =
+            resources::read(&self.nb, &self.served_notebooks, &self.policy, &request.uri).await?;
+        Ok(ReadResourceResult {
+            contents: vec![contents],
+        })
+    }
 }
 
+/// Runs the MCP server over stdio until the client disconnects.
 pub async fn run(config: Config) -> Result<()> {
+    #[cfg(unix)]
+    let stdout = stdout_guard::take_stdout()?;
+    #[cfg(not(unix))]
+    let stdout = tokio::io::stdout();
+    serve(config, (tokio::io::stdin(), ProtocolWriter::new(stdout))).await
+}
+
+/// Runs the MCP server over an arbitrary transport until it closes.
+///
+/// On SIGINT or SIGTERM, new calls are refused and running ones get
+/// [`SHUTDOWN_GRACE`](crate::shutdown::SHUTDOWN_GRACE) to finish before the
+/// service is cancelled.
+pub async fn serve<T, E, A>(config: Config, transport: T) -> Result<()>
+where
+    T: IntoTransport<RoleServer, E, A>,
+    E: std::error::Error + Send + Sync + 'static,
+{
     if config.commit_signing_disabled {
         match git_signing::disable_commit_signing(&config).await {
             Ok(Some(path)) => {
@@ -271,19 +431,448 @@ pub async fn run(config: Config) -> Result<()> {
             }
         }
     }
-    let server = McpServer::new(&config)?;
+    let mut server = McpServer::new(&config)?;
+    if let Some(dir) = &config.nb_fallback_dir {
+        if let Err(NbError::NotFound) = server.nb.version().await {
+            warn!(
+                notes = %dir.display(),
+                "nb not found; serving the notes directory read-only"
+            );
+            server.fallback = Some(Arc::new(FsNotebook::open(dir)?));
+        }
+    }
+    if server.fallback.is_none() {
+        let nb_version = if config.preflight {
+            Some(preflight::run(&server.nb, &server.served_notebooks).await?)
+        } else {
+            None
+        };
+        server.check_nb_version(nb_version).await;
+    }
+    if let Some(port) = config.preview_port {
+        if server.fallback.is_some() {
+            warn!("the preview server needs nb and is disabled in filesystem-only mode");
+        } else {
+            let preview = preview::spawn(
+                server.nb.clone(),
+                port,
+                server.served_notebooks.clone(),
+                server.policy.clone(),
+            )
+            .await?;
+            info!(url = preview.base_url(), "preview server listening");
+            server.preview = Some(preview);
+        }
+    }
+    if let (Some(backup), true) = (config.backup.clone(), server.fallback.is_some()) {
+        warn!(
+            schedule = backup.schedule.expression(),
+            "scheduled backups need nb and are disabled in filesystem-only mode"
+        );
+    } else if let Some(backup) = config.backup.clone() {
+        info!(
+            schedule = backup.schedule.expression(),
+            "scheduled backups enabled"
+        );
+        server.backup = Some(backup::spawn(
+            server.nb.clone(),
+            backup,
+            server.calls.clone(),
+        ));
+    }
     info!("starting nb-mcp server");
     if let Some(ref nb) = config.notebook {
         info!(notebook = %nb, "using configured notebook");
     }
-    let service = server.serve(stdio()).await?;
+    let calls = server.calls.clone();
+    let reloader = server.clone();
+    // Listen before the handshake so an early signal also exits cleanly.
+    let mut signal = tokio::spawn(shutdown::signal());
+    let service = tokio::select! {
+        service = server.serve(transport) => service?,
+        _ = &mut signal => {
+            info!("shutdown requested before the client initialized");
+            return Ok(());
+        }
+    };
     info!("nb-mcp server ready");
-    service.waiting().await?;
+    #[cfg(unix)]
+    if let Some(mut hangups) = reload::hangups() {
+        let peer = service.peer().clone();
+        tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                if let Err(err) = reloader.reload(Some(&peer)).await {
+                    warn!(error = %err, "reload failed; keeping the current configuration");
+                }
+            }
+        });
+    }
+    #[cfg(not(unix))]
+    drop(reloader);
+    let cancellation = service.cancellation_token();
+    tokio::spawn(async move {
+        if signal.await.is_err() {
+            return;
+        }
+        info!(active = calls.active(), "shutdown requested");
+        if !calls.drain(shutdown::SHUTDOWN_GRACE).await {
+            warn!(
+                active = calls.active(),
+                "in-flight calls did not finish before shutdown"
+            );
+        }
+        cancellation.cancel();
+    });
+    let reason = service.waiting().await?;
+    info!(reason = ?reason, "nb-mcp server stopped");
     Ok(())
 }
 
 impl McpServer {
-    async fn dispatch_nb(&self, call: NbCall) -> Result<CallToolResult, McpError> {
+    /// Returns the tools to advertise, with the `nb` tool's command list
+    /// narrowed to the current allowlist and, without nb, to the commands
+    /// served from the filesystem.
+    fn tools(&self) -> ToolRouter<Self> {
+        let mut router = self.tool_router.clone();
+        if let (Some(description), Some(route)) = (&self.tool_description, router.map.get_mut("nb"))
+        {
+            route.attr.description = Some(description.clone().into());
+        }
+        let policy = self.policy.read().expect("policy lock poisoned");
+        let allowed = match (&policy.allowed_commands, &self.fallback) {
+            (allowed, Some(_)) => Some(
+                fs_fallback::COMMANDS
+                    .iter()
+                    .map(|name| name.to_string())
+                    .filter(|name| {
+                        allowed.as_ref().is_none_or(|allowed| {
+                            allowed.iter().any(|allowed| {
+                                allowed.strip_prefix("nb.").unwrap_or(allowed) == name
+                            })
+                        })
+                    })
+                    .collect(),
+            ),
+            (allowed, None) => allowed.clone(),
+        };
+        if let (Some(allowed), Some(route)) = (&allowed, router.map.get_mut("nb")) {
+            if let Some(description) = &route.attr.description {
+                route.attr.description =
+                    Some(reload::restrict_description(description, allowed).into());
+            }
+        }
+        let denied = policy.denied();
+        if let (false, Some(route)) = (denied.is_empty(), router.map.get_mut("nb")) {
+            if let Some(description) = &route.attr.description {
+                route.attr.description =
+                    Some(reload::remove_from_description(description, &denied).into());
+            }
+        }
+        router
+    }
+
+    /// Rereads the project configuration and applies its allowlist,
+    /// permissions, and views.
+    ///
+    /// Notifies `peer` when the allowlist, and so the tool list, changed.
+    async fn reload(&self, peer: Option<&Peer<RoleServer>>) -> Result<ReloadReport> {
+        let (config_file, policy) = reload::read()?;
+        let report = ReloadReport {
+            config_file,
+            allowed_commands: policy.allowed_commands.clone(),
+            permissions: policy.permissions.clone(),
+            views: policy.views.keys().cloned().collect(),
+            tools_changed: {
+                let mut current = self.policy.write().expect("policy lock poisoned");
+                let changed = current.allowed_commands != policy.allowed_commands
+                    || current.denied() != policy.denied();
+                *current = policy;
+                changed
+            },
+        };
+        info!(
+            tools_changed = report.tools_changed,
+            "project configuration reloaded"
+        );
+        if let (true, Some(peer)) = (report.tools_changed, peer) {
+            if let Err(err) = peer.notify_tool_list_changed().await {
+                warn!(error = %err, "failed to send tools/list_changed");
+            }
+        }
+        Ok(report)
+    }
+
+    /// Probes the installed nb, unless preflight already did, and warns if
+    /// it is too old.
+    async fn check_nb_version(&mut self, output: Option<String>) {
+        let output = match output {
+            Some(output) => output,
+            None => match self.nb.version().await {
+                Ok(output) => output,
+                Err(err) => {
+                    warn!(error = %err, "could not determine nb version");
+                    return;
+                }
+            },
+        };
+        let compatibility = Compatibility::check(&output);
+        if !compatibility.supported {
+            warn!(
+                version = %output,
+                minimum = %compatibility.minimum,
+                unavailable = ?compatibility.unavailable_commands,
+                "installed nb is older than supported; some commands are disabled"
+            );
+        }
+        self.compatibility = Some(compatibility);
+    }
+
+    /// Derives the default notebook from the client's first workspace root.
+    ///
+    /// Clients that start the server from an unrelated directory (as many
+    /// IDEs do) would otherwise get a notebook derived from the wrong place.
+    /// Adopts the session notebook the client requested, if it is served.
+    fn apply_session_notebook(&self, peer: &Peer<RoleServer>) {
+        let Some(requested) = peer
+            .peer_info()
+            .and_then(|info| notebook_scope::requested_notebook(&info.capabilities))
+        else {
+            return;
+        };
+        if notebook_scope::check_served(&self.served_notebooks, &requested).is_err() {
+            warn!(notebook = %requested, "client requested a notebook that is not served");
+            return;
+        }
+        info!(notebook = %requested, "using session notebook");
+        *self
+            .session_notebook
+            .write()
+            .expect("session notebook lock poisoned") = Some(requested);
+    }
+
+    async fn apply_workspace_roots(&self, peer: &Peer<RoleServer>) {
+        let supports_roots = peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.roots.is_some());
+        if !supports_roots {
+            return;
+        }
+        let roots = match peer.list_roots().await {
+            Ok(result) => result.roots,
+            Err(err) => {
+                warn!(error = %err, "failed to list client roots");
+                return;
+            }
+        };
+        let Some(root) = roots.first().and_then(|root| root_path(&root.uri)) else {
+            return;
+        };
+        let notebooks = self.nb.set_workspace_root(&root);
+        info!(
+            root = %root.display(),
+            notebooks = ?notebooks,
+            "default notebook derived from client root"
+        );
+    }
+
+    /// Asks the user to confirm a destructive command, or one the project
+    /// permissions mark `confirm`, through elicitation.
+    ///
+    /// When the client supports elicitation, the user's answer replaces the
+    /// model-supplied `confirm` flag: acceptance sets it, and declining
+    /// returns the tool result to send instead of running the command.
+    /// Without elicitation support (or if the request fails), the call is
+    /// left untouched and the `confirm` flag rule applies as before.
+    async fn confirm_with_user(
+        &self,
+        call: &mut NbCall,
+        peer: &Peer<RoleServer>,
+    ) -> Result<Option<CallToolResult>, McpError> {
+        let command = call.command.trim();
+        let subcommand = command.strip_prefix("nb.").unwrap_or(command);
+        let subcommand = command_alias::canonical(subcommand).unwrap_or(subcommand);
+        let required = self
+            .policy
+            .read()
+            .expect("policy lock poisoned")
+            .permission(subcommand)
+            == Permission::Confirm;
+        if !(required || matches!(subcommand, "delete" | "dedupe" | "merge"))
+            || !peer.supports_elicitation()
+        {
+            return Ok(None);
+        }
+        let mut args: serde_json::Map<String, serde_json::Value> = parse_args(call.args.clone())?;
+        let prompt = self
+            .confirmation_prompt(subcommand, serde_json::Value::Object(args.clone()))
+            .await?;
+        let Some((message, declined)) = prompt.or_else(|| {
+            required.then(|| {
+                (
+                    format!(
+                        "Run nb.{subcommand} with {}?",
+                        serde_json::Value::Object(args.clone())
+                    ),
+                    format!("nb.{subcommand} was not confirmed by the user."),
+                )
+            })
+        }) else {
+            return Ok(None);
+        };
+        let request = CreateElicitationRequestParam {
+            message,
+            requested_schema: confirmation_schema(),
+        };
+        let response = match peer.create_elicitation(request).await {
+            Ok(response) => response,
+            Err(err) => {
+                warn!(error = %err, "elicitation failed; falling back to confirm flag");
+                return Ok(None);
+            }
+        };
+        let confirmed = response.action == ElicitationAction::Accept
+            && response
+                .content
+                .as_ref()
+                .and_then(|content| content.get("confirm"))
+                .and_then(|confirm| confirm.as_bool())
+                .unwrap_or(true);
+        if !confirmed {
+            return Ok(Some(CallToolResult::error(vec![Content::text(declined)])));
+        }
+        args.insert("confirm".to_string(), serde_json::Value::Bool(true));
+        call.args = serde_json::Value::Object(args);
+        Ok(None)
+    }
+
+    /// Returns the question to ask the user before running a destructive
+    /// call, and the error to report if they decline. `None` means the call
+    /// needs no confirmation.
+    async fn confirmation_prompt(
+        &self,
+        subcommand: &str,
+        args: serde_json::Value,
+    ) -> Result<Option<(String, String)>, McpError> {
+        // Per-call overrides such as `allow_create` are still in `args`; the
+        // arguments are checked strictly when the call runs.
+        match subcommand {
+            "delete" => {
+                let delete: DeleteArgs = parse_value(args)?;
+                let message = match self.nb.title(&delete.id, delete.notebook.as_deref()).await {
+                    Ok(title) if !title.is_empty() => {
+                        format!("Delete note {}: '{}'?", delete.id, title)
+                    }
+                    _ => format!("Delete note {}?", delete.id),
+                };
+                let declined = format!(
+                    "Deletion of note {} was not confirmed by the user.",
+                    delete.id
+                );
+                Ok(Some((message, declined)))
+            }
+            "merge" => {
+                let merge: MergeArgs = parse_value(args)?;
+                if !merge.delete_sources {
+                    return Ok(None);
+                }
+                Ok(Some((
+                    format!(
+                        "Merge notes {} into note {} and delete them?",
+                        merge.sources.join(", "),
+                        merge.target
+                    ),
+                    "Deleting the merged notes was not confirmed by the user.".to_string(),
+                )))
+            }
+            "dedupe" => {
+                let dedupe: DedupeArgs = parse_value(args)?;
+                if !dedupe.merge {
+                    return Ok(None);
+                }
+                let scope = match &dedupe.folder {
+                    Some(folder) => format!("folder {folder}"),
+                    None => "the notebook".to_string(),
+                };
+                Ok(Some((
+                    format!(
+                        "Merge duplicate notes in {scope}? Each duplicate's content is \
+                         appended to the first note of its group and the duplicate is deleted."
+                    ),
+                    "Merging duplicate notes was not confirmed by the user.".to_string(),
+                )))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Runs a call and records it in the call statistics.
+    ///
+    /// An aliased subcommand (e.g., `rm`) runs as its canonical command, which
+    /// the response names.
+    async fn dispatch_nb(
+        &self,
+        mut call: NbCall,
+        peer: Option<&Peer<RoleServer>>,
+    ) -> Result<CallToolResult, McpError> {
+        let command = call.command.trim();
+        let alias = command.strip_prefix("nb.").unwrap_or(command);
+        let canonical =
+            command_alias::canonical(alias).map(|canonical| (alias.to_string(), canonical));
+        if let Some((_, canonical)) = &canonical {
+            call.command = canonical.to_string();
+        }
+        let command = call.command.trim();
+        let subcommand = command.strip_prefix("nb.").unwrap_or(command).to_string();
+        let request_id = request_id::next();
+        let span = tracing::info_span!("call", request_id = %request_id, command = %subcommand);
+        let started = std::time::Instant::now();
+        let decoded = decode_string_args(call.args.clone());
+        let mutation = commit_message::is_mutating_call(&subcommand, &decoded);
+        let budgeted = rate_limit::is_budgeted(&subcommand, mutation);
+        let admitted = self.rate_limiter.admit(budgeted, started);
+        let spent = budgeted && admitted.is_ok();
+        let result = match admitted {
+            Ok(()) => self.execute_call(call, peer).instrument(span.clone()).await,
+            Err(limited) => {
+                span.in_scope(|| warn!(limit = limited.limit, "call rate limited"));
+                let mut result = CallToolResult::error(vec![Content::text(limited.message())]);
+                result.structured_content = serde_json::to_value(&limited).ok();
+                Ok(result)
+            }
+        };
+        // Any write may change what a cached id shows or a folder lists.
+        if mutation || subcommand == "pull" {
+            self.note_cache.clear();
+            self.refresh_listings(self.written_notebooks(&decoded).as_deref());
+        }
+        let failed = result
+            .as_ref()
+            .map_or(true, |result| result.is_error == Some(true));
+        // Mutations count toward the budget only once they succeed.
+        if spent && failed {
+            self.rate_limiter.refund();
+        }
+        span.in_scope(|| {
+            debug!(
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                failed, "call finished"
+            );
+        });
+        self.stats.record(&subcommand, failed, started.elapsed());
+        self.usage.record(&subcommand, failed);
+        self.usage.persist().await;
+        let result = match canonical {
+            Some((alias, canonical)) => with_canonical_command(result, &alias, canonical),
+            None => result,
+        };
+        with_request_id(result, &request_id)
+    }
+
+    async fn execute_call(
+        &self,
+        call: NbCall,
+        peer: Option<&Peer<RoleServer>>,
+    ) -> Result<CallToolResult, McpError> {
         let command = call.command.trim();
         if command.is_empty() {
             return Err(McpError::invalid_params("command must be non-empty", None));
@@ -292,134 +881,1588 @@ impl McpServer {
         // Strip "nb." prefix if present.
         let subcommand = command.strip_prefix("nb.").unwrap_or(command);
 
-        let result = match subcommand {
-            "status" => {
-                let args: StatusArgs = parse_args(call.args)?;
-                self.nb.status(args.notebook.as_deref()).await
+        let mut args = decode_string_args(call.args);
+        // Saving results with `output_note` adds a note, so `add`'s policy
+        // applies as well.
+        let mut checked = vec![subcommand];
+        if output_note::requested(subcommand, &args) {
+            checked.push("add");
+        }
+        let (allowed_commands, permission) = {
+            let policy = self.policy.read().expect("policy lock poisoned");
+            (
+                policy.allowed_commands.clone(),
+                checked
+                    .iter()
+                    .map(|name| policy.permission(name))
+                    .max()
+                    .unwrap_or_default(),
+            )
+        };
+        if let Some(allowed) = &allowed_commands {
+            if !checked.iter().all(|checked| {
+                allowed
+                    .iter()
+                    .any(|name| name.strip_prefix("nb.").unwrap_or(name) == *checked)
+            }) {
+                return Err(McpError::invalid_params(
+                    "command not allowed by project configuration",
+                    Some(serde_json::json!({
+                        "command": command,
+                        "allowed": allowed,
+                    })),
+                ));
             }
-            "notebooks" => self.nb.notebooks().await,
-            "add" => {
-                let args: AddArgs = parse_args(call.args)?;
-                self.nb
-                    .add(
-                        args.title.as_deref(),
-                        &args.content,
-                        &args.tags,
-                        args.folder.as_deref(),
-                        args.notebook.as_deref(),
-                    )
-                    .await
+        }
+        if permission == Permission::Deny {
+            return Err(McpError::invalid_params(
+                "command denied by project configuration",
+                Some(serde_json::json!({"command": command})),
+            ));
+        }
+
+        if let Some(message) = self
+            .fallback
+            .as_ref()
+            .and_then(|_| FsNotebook::unavailable(subcommand))
+        {
+            return Ok(self.error_result(message, None, None).await);
+        }
+
+        if let Some(message) = self
+            .compatibility
+            .as_ref()
+            .and_then(|compatibility| compatibility.unavailable(subcommand))
+        {
+            return Ok(self.error_result(message, None, None).await);
+        }
+
+        if permission == Permission::Confirm {
+            if args.get("confirm").and_then(|confirm| confirm.as_bool()) != Some(true) {
+                return Err(McpError::invalid_params(
+                    format!("{command} requires confirm: true by project configuration"),
+                    Some(serde_json::json!({
+                        "hint": "Ask the user to approve this call, then retry with confirm: true.",
+                    })),
+                ));
             }
-            "show" => {
-                let args: ShowArgs = parse_args(call.args)?;
-                self.nb.show(&args.id, args.notebook.as_deref()).await
+            // Other commands carry `confirm` only for the policy.
+            if !matches!(subcommand, "delete" | "dedupe" | "merge") {
+                take_bool_arg(&mut args, "confirm")?;
             }
-            "edit" => {
-                let args: EditArgs = parse_args(call.args)?;
-                self.nb
-                    .edit(&args.id, &args.content, args.notebook.as_deref())
-                    .await
+        }
+        let args = {
+            let session = self
+                .session_notebook
+                .read()
+                .expect("session notebook lock poisoned")
+                .clone();
+            notebook_scope::scope_args(&self.served_notebooks, session.as_deref(), subcommand, args)
+                .map_err(|message| McpError::invalid_params(message, None))?
+        };
+        let mut args = {
+            let folder = self
+                .session_folder
+                .read()
+                .expect("session folder lock poisoned");
+            session_folder::scope_args(folder.as_deref(), subcommand, args)
+        };
+        session_folder::check_folder(subcommand, &args)
+            .map_err(|message| McpError::invalid_params(message, None))?;
+
+        let mutation = commit_message::is_mutating_call(subcommand, &args);
+        let selector = args.get("id").and_then(|id| id.as_str()).map(String::from);
+        let notebook = args
+            .get("notebook")
+            .and_then(|notebook| notebook.as_str())
+            .map(String::from);
+        let folder = args
+            .get("folder")
+            .and_then(|folder| folder.as_str())
+            .map(String::from);
+
+        // Per-call `allow_create` and `auto_sync` run the call with its own
+        // creation and sync policies.
+        let allow_create = take_bool_arg(&mut args, "allow_create")?;
+        let auto_sync = take_bool_arg(&mut args, "auto_sync")?;
+        let scoped;
+        let server = if allow_create.is_some() || auto_sync.is_some() {
+            let mut nb = match allow_create {
+                Some(allow) => self
+                    .nb
+                    .with_notebook_creation(allow)
+                    .map_err(|err| McpError::invalid_params(err.to_string(), None))?,
+                None => self.nb.clone(),
+            };
+            if let Some(auto_sync) = auto_sync {
+                nb = nb.with_auto_sync(auto_sync);
             }
-            "delete" => {
-                let args: DeleteArgs = parse_args(call.args)?;
-                if !args.confirm {
-                    return Err(McpError::invalid_params(
-                        "delete requires confirm: true",
-                        Some(serde_json::json!({
-                            "hint": "Set confirm: true to delete the note.",
-                            "id": args.id,
-                        })),
-                    ));
+            scoped = Self { nb, ..self.clone() };
+            &scoped
+        } else {
+            self
+        };
+
+        // Writes wait for other instances serving the same notebook.
+        let _locks = if mutation || subcommand == "pull" {
+            match server.lock_notebooks(&args).await {
+                Ok(locks) => locks,
+                Err(err) => {
+                    return Ok(self
+                        .error_result(err.to_string(), selector.as_deref(), notebook.as_deref())
+                        .await);
                 }
-                self.nb.delete(&args.id, args.notebook.as_deref()).await
-            }
-            "move" => {
-                let args: MoveArgs = parse_args(call.args)?;
-                self.nb
-                    .move_note(&args.id, &args.destination, args.notebook.as_deref())
-                    .await
             }
-            "list" => {
-                let args: ListArgs = parse_args(call.args)?;
-                self.nb
-                    .list(
-                        args.folder.as_deref(),
-                        &args.tags,
-                        args.limit,
-                        args.notebook.as_deref(),
-                    )
-                    .await
+        } else {
+            Vec::new()
+        };
+
+        // `show` reports the note's content hash alongside its text.
+        if subcommand == "show" && server.fallback.is_none() {
+            let args: ShowArgs = parse_args(args)?;
+            return Ok(match server.show(args).await {
+                Ok((text, hash)) => show_result(text, hash),
+                Err(err) => {
+                    self.error_result(err.to_string(), selector.as_deref(), notebook.as_deref())
+                        .await
+                }
+            });
+        }
+        if subcommand == "edit" {
+            let edit: EditArgs = parse_args(args.clone())?;
+            match server.edit_conflict(&edit).await {
+                Ok(None) => {}
+                Ok(Some(conflict)) => return Ok(conflict_result(conflict)),
+                Err(err) => {
+                    return Ok(self
+                        .error_result(err.to_string(), selector.as_deref(), notebook.as_deref())
+                        .await);
+                }
             }
-            "search" => {
-                let args: SearchArgs = parse_args(call.args)?;
-                self.nb
-                    .search(
-                        &args.query,
-                        &args.tags,
-                        args.folder.as_deref(),
-                        args.notebook.as_deref(),
+        }
+
+        let result = match server.commit_message_template.as_ref().filter(|_| mutation) {
+            Some(template) => {
+                server
+                    .run_with_commit_message(
+                        command,
+                        subcommand,
+                        args,
+                        template,
+                        peer,
+                        call.progress_token.as_ref(),
                     )
-                    .await
+                    .await?
             }
-            "todo" => {
-                let args: TodoArgs = parse_args(call.args)?;
-                self.nb
-                    .todo(
-                        &args.description,
-                        &args.tags,
-                        args.folder.as_deref(),
-                        args.notebook.as_deref(),
+            None => {
+                server
+                    .run_command(
+                        command,
+                        subcommand,
+                        args,
+                        peer,
+                        call.progress_token.as_ref(),
                     )
-                    .await
+                    .await?
             }
-            "do" => {
-                let args: TaskIdArgs = parse_args(call.args)?;
-                self.nb.do_task(&args.id, args.notebook.as_deref()).await
+        };
+
+        let result = match result {
+            Ok(text) if matches!(subcommand, "add" | "edit") => {
+                Ok(saved_note::describe(&server.nb, text, notebook.as_deref()).await)
             }
-            "undo" => {
-                let args: TaskIdArgs = parse_args(call.args)?;
-                self.nb.undo_task(&args.id, args.notebook.as_deref()).await
+            other => other,
+        };
+
+        // After describing the saved note, so its `commit` is the write's own.
+        if server.backlinks && result.is_ok() && mutation {
+            match backlinks::refresh(&server.nb, server.link_style, notebook.as_deref()).await {
+                Ok(updated) if !updated.is_empty() => {
+                    debug!(notes = updated.len(), "updated backlinks");
+                }
+                Ok(_) => {}
+                Err(err) => warn!(error = %err, "could not update backlinks"),
             }
-            "tasks" => {
-                let args: TasksArgs = parse_args(call.args)?;
+        }
+
+        match result {
+            // `show` applies the limit itself so it can honor `range`, and
+            // `show_many` shares it between notes.
+            Ok(text) if matches!(subcommand, "show" | "show_many") => {
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Ok(text) => {
+                let text = output::limit(text, self.max_output_bytes, LIMIT_HINT);
+                let mut structured_content = structured::parse(subcommand, &text);
+                if let Some(listing) = structured_content
+                    .as_mut()
+                    .filter(|_| subcommand == "list" && server.fallback.is_none())
+                {
+                    let dated = list_dates::annotate(
+                        &server.nb,
+                        &server.created_dates,
+                        listing,
+                        folder.as_deref(),
+                        notebook.as_deref(),
+                    );
+                    if let Err(err) = dated.await {
+                        debug!(error = %err, "could not date listed items");
+                    }
+                }
+                let mut result = CallToolResult::success(vec![Content::text(text)]);
+                result.structured_content = structured_content;
+                Ok(result)
+            }
+            Err(err) => Ok(self
+                .error_result(err.to_string(), selector.as_deref(), notebook.as_deref())
+                .await),
+        }
+    }
+
+    /// Builds a tool error, adding a `remediation` hint when the failure is
+    /// recognized.
+    ///
+    /// The hint is returned as `structuredContent` and, for clients that only
+    /// read content, as a second JSON text block. Unknown selectors are
+    /// matched against titles in the selector's folder to suggest candidates.
+    async fn error_result(
+        &self,
+        message: String,
+        selector: Option<&str>,
+        notebook: Option<&str>,
+    ) -> CallToolResult {
+        let mut result = CallToolResult::error(vec![Content::text(message.clone())]);
+        let Some(mut remediation) = remediation::classify(&message, selector) else {
+            return result;
+        };
+        if let Remediation::ResolveSelector {
+            selector,
+            candidates,
+        } = &mut remediation
+        {
+            let folder = selector.rsplit_once('/').map(|(folder, _)| folder);
+            if let Ok(entries) = self.nb.list_entries(folder, &[], None, notebook).await {
+                *candidates = remediation::candidates(&entries, selector);
+            }
+        }
+        let payload = serde_json::json!({
+            "error": message,
+            "remediation": remediation,
+        });
+        result.content.push(Content::text(payload.to_string()));
+        result.structured_content = Some(payload);
+        result
+    }
+
+    /// Lists notes, applying `filter`, `sort`, and `count_only` server-side
+    /// and hiding archived notes.
+    ///
+    /// Without any of them, this is a plain `nb list`. Otherwise every
+    /// matching item is listed, then filtered, counted or sorted, and limited
+    /// here.
+    async fn list(&self, args: &ListArgs) -> Result<String, NbError> {
+        let folder = args.folder.as_deref();
+        let notebook = self.notebook_key(args.notebook.as_deref());
+        let root = self.cached_notebook_path(&notebook).await?;
+        let hide_archived = archive::hides(&root, folder, args.include_archived);
+        let plain =
+            args.filter.is_none() && args.sort.is_none() && !args.count_only && !hide_archived;
+        let key = ListKey {
+            notebook,
+            folder: args.folder.clone(),
+            tags: args.tags.clone(),
+            limit: args.limit.filter(|_| plain),
+        };
+        if plain {
+            return self.cached_list(key, &root).await;
+        }
+        let filter = args.filter.as_deref().map(query::parse).transpose()?;
+        let sort = args
+            .sort
+            .as_deref()
+            .map(list_sort::SortKey::parse)
+            .transpose()?;
+        let output = self.cached_list(key, &root).await?;
+        let mut resolver = ItemResolver::new(&root, folder);
+        let output = if hide_archived {
+            archive::exclude(&output, &mut resolver, &root)
+        } else {
+            output
+        };
+        let output = match &filter {
+            Some(filter) => query::filter_output(&output, &mut resolver, filter),
+            None => output,
+        };
+        if args.count_only {
+            return Ok(to_json_text(&counts::count(&output, &mut resolver, folder)));
+        }
+        match sort {
+            Some(key) => {
+                let descending = list_sort::descending(args.order.as_deref(), key)?;
+                Ok(list_sort::sort_listing(
+                    &output,
+                    &mut resolver,
+                    key,
+                    descending,
+                    args.limit,
+                ))
+            }
+            None => Ok(output
+                .lines()
+                .take(args.limit.map_or(usize::MAX, |limit| limit as usize))
+                .map(|line| format!("{line}\n"))
+                .collect()),
+        }
+    }
+
+    /// Identifies the notebook a call names, or the default one.
+    fn notebook_key(&self, notebook: Option<&str>) -> NotebookKey {
+        NotebookKey {
+            requested: notebook.map(String::from),
+            default: match notebook {
+                Some(_) => None,
+                None => self.nb.default_notebook(),
+            },
+        }
+    }
+
+    /// Returns the path of `notebook`, asking nb only the first time.
+    async fn cached_notebook_path(&self, notebook: &NotebookKey) -> Result<PathBuf, NbError> {
+        if let Some(root) = self.list_cache.root(notebook) {
+            return Ok(root);
+        }
+        let root = self.nb.notebook_path(notebook.requested.as_deref()).await?;
+        self.list_cache.set_root(notebook.clone(), root.clone());
+        Ok(root)
+    }
+
+    /// Lists notes, answering from the listing cache while it is current.
+    async fn cached_list(&self, key: ListKey, root: &Path) -> Result<String, NbError> {
+        let modified = list_cache::folder_modified(root, key.folder.as_deref()).await;
+        if let Some(output) = self
+            .list_cache
+            .get(&key, modified, std::time::Instant::now())
+        {
+            return Ok(output);
+        }
+        self.fetch_listing(key, root).await
+    }
+
+    /// Lists notes through nb and caches the listing.
+    async fn fetch_listing(&self, key: ListKey, root: &Path) -> Result<String, NbError> {
+        let generation = self.list_cache.generation();
+        let modified = list_cache::folder_modified(root, key.folder.as_deref()).await;
+        let fetched = std::time::Instant::now();
+        let output = self
+            .nb
+            .list(
+                key.folder.as_deref(),
+                &key.tags,
+                key.limit,
+                key.notebook.requested.as_deref(),
+            )
+            .await?;
+        self.list_cache
+            .insert(key, output.clone(), modified, fetched, generation);
+        Ok(output)
+    }
+
+    /// Returns the notebooks a write with `args` may change, or `None` if
+    /// they are not known.
+    ///
+    /// Calls without `notebook` write to the session notebook, if one was
+    /// chosen, before the default.
+    fn written_notebooks(&self, args: &serde_json::Value) -> Option<Vec<String>> {
+        let session = self
+            .session_notebook
+            .read()
+            .expect("session notebook lock poisoned")
+            .clone();
+        self.written_notebook_selectors(args)
+            .into_iter()
+            .map(|notebook| {
+                notebook
+                    .or_else(|| session.clone())
+                    .or_else(|| self.nb.default_notebook())
+            })
+            .collect()
+    }
+
+    /// Returns the notebooks a write with `args` may change, `None` standing
+    /// for the default notebook.
+    fn written_notebook_selectors(&self, args: &serde_json::Value) -> Vec<Option<String>> {
+        let arg = |name: &str| args.get(name).and_then(|value| value.as_str());
+        let mut written = vec![arg("notebook").map(String::from)];
+        // `copy_to` writes to its target notebook.
+        written.extend(arg("target_notebook").map(|notebook| Some(notebook.to_string())));
+        // Qualified ids (`home:12`) write to the notebook they name.
+        written.extend(
+            arg("id")
+                .and_then(|id| id.split_once(':'))
+                .map(|(notebook, _)| Some(notebook.to_string())),
+        );
+        written
+    }
+
+    /// Drops the cached listings of the `written` notebooks (all of them if
+    /// `None`) after a write and fetches them again in the background.
+    fn refresh_listings(&self, written: Option<&[String]>) {
+        let keys = self.list_cache.invalidate(written);
+        if keys.is_empty() {
+            return;
+        }
+        let server = self.clone();
+        let refresh = tokio::spawn(async move {
+            for key in keys {
+                let Some(root) = server.list_cache.root(&key.notebook) else {
+                    continue;
+                };
+                if let Err(err) = server.fetch_listing(key, &root).await {
+                    debug!(error = %err, "listing refresh failed");
+                }
+            }
+        });
+        *self
+            .listing_refresh
+            .lock()
+            .expect("listing refresh lock poisoned") = Some(refresh);
+    }
+
+    /// Waits for the background refresh of the listings to finish.
+    #[cfg(test)]
+    async fn listings_refreshed(&self) {
+        let refresh = self
+            .listing_refresh
+            .lock()
+            .expect("listing refresh lock poisoned")
+            .take();
+        if let Some(refresh) = refresh {
+            refresh.await.unwrap();
+        }
+    }
+
+    /// Searches notes, keeping only those matching `filter` when given and
+    /// returning counts instead with `count_only`.
+    async fn search(&self, args: &SearchArgs) -> Result<String, NbError> {
+        let folder = args.folder.as_deref();
+        let notebook = args.notebook.as_deref();
+        let pattern = nb::search_pattern(&args.query, args.mode.as_deref())?;
+        let filter = args.filter.as_deref().map(query::parse).transpose()?;
+        let output = self
+            .nb
+            .search(&pattern, &args.tags, folder, notebook)
+            .await?;
+        let root = self.nb.notebook_path(notebook).await?;
+        let hide_archived = archive::hides(&root, folder, args.include_archived);
+        if filter.is_none() && !args.count_only && !hide_archived {
+            return Ok(output);
+        }
+        let mut resolver = ItemResolver::new(&root, folder);
+        let output = if hide_archived {
+            archive::exclude(&output, &mut resolver, &root)
+        } else {
+            output
+        };
+        let output = match &filter {
+            Some(filter) => query::filter_output(&output, &mut resolver, filter),
+            None => output,
+        };
+        if args.count_only {
+            return Ok(to_json_text(&counts::count(&output, &mut resolver, folder)));
+        }
+        Ok(output)
+    }
+
+    /// Runs a saved view, or lists the available views without a name.
+    async fn view(&self, args: ViewArgs) -> Result<String, NbError> {
+        let notebook = args.notebook.as_deref();
+        let configured = self
+            .policy
+            .read()
+            .expect("policy lock poisoned")
+            .views
+            .clone();
+        let views = views::load(&self.nb, &configured, notebook).await?;
+        let Some(name) = args.name else {
+            return Ok(to_json_text(&views));
+        };
+        let Some(view) = views.get(&name) else {
+            return Err(NbError::CommandFailed(format!(
+                "unknown view {name:?}; available: {}",
+                views.keys().cloned().collect::<Vec<_>>().join(", ")
+            )));
+        };
+        match &view.query {
+            Some(query) => {
+                self.search(&SearchArgs {
+                    query: query.clone(),
+                    mode: view.mode.clone(),
+                    tags: view.tags.clone(),
+                    folder: view.folder.clone(),
+                    filter: view.filter.clone(),
+                    count_only: false,
+                    include_archived: false,
+                    output_note: None,
+                    notebook: args.notebook.clone(),
+                })
+                .await
+            }
+            None => {
+                self.list(&ListArgs {
+                    folder: view.folder.clone(),
+                    tags: view.tags.clone(),
+                    limit: view.limit,
+                    sort: view.sort.clone(),
+                    order: view.order.clone(),
+                    filter: view.filter.clone(),
+                    count_only: false,
+                    include_archived: false,
+                    output_note: None,
+                    notebook: args.notebook.clone(),
+                })
+                .await
+            }
+        }
+    }
+
+    /// Applies the default folder and tags to a create command's arguments.
+    ///
+    /// An explicit folder wins over the default; default tags are merged
+    /// with the call's tags. `no_defaults` leaves the arguments untouched.
+    fn with_defaults(
+        &self,
+        folder: Option<String>,
+        mut tags: Vec<String>,
+        no_defaults: bool,
+    ) -> (Option<String>, Vec<String>) {
+        if no_defaults {
+            return (folder, tags);
+        }
+        for tag in &self.default_tags {
+            let tag = tag.trim_start_matches('#');
+            if !tags.iter().any(|t| t.trim_start_matches('#') == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+        (folder.or_else(|| self.default_folder.clone()), tags)
+    }
+
+    /// Adds successful query `result` as a new note titled `output_note`, if
+    /// given, formatting the note with `render`, and reports the added note
+    /// after the results.
+    async fn save_output_note(
+        &self,
+        result: Result<String, NbError>,
+        output_note: Option<&str>,
+        notebook: Option<&str>,
+        render: impl FnOnce(&str) -> String,
+    ) -> Result<String, NbError> {
+        let (Some(template), Ok(text)) = (output_note, &result) else {
+            return result;
+        };
+        let title = output_note::title(template, chrono::Local::now().date_naive());
+        if title.is_empty() {
+            return Err(NbError::CommandFailed(
+                "output_note title is empty".to_string(),
+            ));
+        }
+        let folder = self
+            .session_folder
+            .read()
+            .expect("session folder lock poisoned")
+            .clone();
+        let (folder, tags) = self.with_defaults(folder, Vec::new(), false);
+        let added = self
+            .nb
+            .add(
+                Some(&title),
+                &render(text),
+                &tags,
+                folder.as_deref(),
+                notebook,
+            )
+            .await?;
+        // Cut the results here so the size limit cannot drop the marker.
+        let marker = output_note::marker(&added);
+        let max = self.max_output_bytes.saturating_sub(marker.len());
+        Ok(format!(
+            "{}{marker}",
+            output::limit(text.clone(), max, LIMIT_HINT)
+        ))
+    }
+
+    /// Shows a note, reporting binary files as metadata rather than text.
+    ///
+    /// Also returns the content hash of the note file, if it is a file.
+    async fn show(&self, args: ShowArgs) -> Result<(String, Option<String>), NbError> {
+        let cache_notebook = args.notebook.clone().or_else(|| self.nb.default_notebook());
+        let cached = match &cache_notebook {
+            Some(cache_notebook) => self.note_cache.get(cache_notebook, &args.id).await,
+            None => None,
+        };
+        let (text, hash) = match cached {
+            Some(cached) => (cached.text, Some(cached.hash)),
+            None => match self.show_uncached(&args, cache_notebook.as_deref()).await? {
+                // Binary metadata fits the limit already, and cutting it
+                // would leave invalid JSON.
+                (text, hash, true) => return Ok((text, hash)),
+                (text, hash, false) => (text, hash),
+            },
+        };
+        let range = args.range.unwrap_or_default();
+        let text = output::window(
+            &text,
+            range.offset,
+            range.length,
+            self.max_output_bytes,
+            SHOW_LIMIT_HINT,
+        );
+        Ok((text, hash))
+    }
+
+    /// Shows a note through nb, caching text notes for later calls.
+    ///
+    /// Also returns whether the note is binary, shown as JSON metadata.
+    async fn show_uncached(
+        &self,
+        args: &ShowArgs,
+        cache_notebook: Option<&str>,
+    ) -> Result<(String, Option<String>, bool), NbError> {
+        let notebook = args.notebook.as_deref();
+        let path = self.nb.show_path(&args.id, notebook).await?;
+        // Taken before reading, so a write racing the read invalidates the
+        // cached text rather than hiding behind a newer time.
+        let metadata = tokio::fs::metadata(&path).await.ok();
+        // Entries nb can show but not read as files (e.g., folders) fall
+        // through to `nb show`.
+        let content = match tokio::fs::read(&path).await {
+            Ok(content) => content,
+            Err(_) => return Ok((self.nb.show(&args.id, notebook).await?, None, false)),
+        };
+        let hash = content_hash::of(&content);
+        if let Some(media_type) = file_type::binary_type(&path, &content) {
+            let mut note = BinaryNote {
+                binary: true,
+                media_type,
+                size: content.len() as u64,
+                path: path.display().to_string(),
+                content_base64: args.raw_base64.then(|| BASE64_STANDARD.encode(&content)),
+                too_large: false,
+            };
+            let mut text = to_json_text(&note);
+            if text.len() > self.max_output_bytes && note.content_base64.is_some() {
+                note.content_base64 = None;
+                note.too_large = true;
+                text = to_json_text(&note);
+            }
+            return Ok((text, Some(hash), true));
+        }
+        let text = self.nb.show(&args.id, notebook).await?;
+        // Decrypted notes are not kept in memory.
+        if let (Some(cache_notebook), Some(metadata)) = (cache_notebook, metadata) {
+            if let Ok(modified) = metadata.modified() {
+                if !password::is_encrypted(&path.to_string_lossy()) {
+                    let note = CachedNote {
+                        text: text.clone(),
+                        hash: hash.clone(),
+                    };
+                    self.note_cache.insert(
+                        cache_notebook,
+                        &args.id,
+                        &path,
+                        modified,
+                        metadata.len(),
+                        note,
+                    );
+                }
+            }
+        }
+        Ok((text, Some(hash), false))
+    }
+
+    /// Reads several notes into one JSON response keyed by id, within one
+    /// size budget.
+    async fn show_many(&self, args: ShowManyArgs) -> Result<String, NbError> {
+        let notebook = args.notebook.as_deref();
+        let ids = match &args.glob {
+            Some(glob) => show_many::glob_ids(&self.nb, glob, notebook).await?,
+            None => args.ids,
+        };
+        if ids.len() > show_many::MAX_NOTES {
+            return Err(NbError::CommandFailed(format!(
+                "show_many reads at most {} notes; {} were requested",
+                show_many::MAX_NOTES,
+                ids.len()
+            )));
+        }
+        let budget = args
+            .max_bytes
+            .unwrap_or(self.max_output_bytes)
+            .min(self.max_output_bytes);
+        let mut shown = ShownNotes::new(budget);
+        for id in ids {
+            if !shown.has_room() {
+                shown.omit(&id);
+                continue;
+            }
+            let show = ShowArgs {
+                id: id.clone(),
+                notebook: args.notebook.clone(),
+                ..ShowArgs::default()
+            };
+            match self.show(show).await {
+                Ok((content, hash)) => shown.push(&id, content, hash, SHOW_LIMIT_HINT),
+                Err(err) => shown.push_error(&id, err.to_string()),
+            }
+        }
+        Ok(to_json_text(&shown))
+    }
+
+    /// Returns the conflict an edit would cause, if it passes an
+    /// `expected_hash` that no longer matches the note file.
+    async fn edit_conflict(&self, args: &EditArgs) -> Result<Option<Conflict>, NbError> {
+        let Some(expected_hash) = &args.expected_hash else {
+            return Ok(None);
+        };
+        let path = self
+            .nb
+            .show_path(&args.id, args.notebook.as_deref())
+            .await?;
+        let content = tokio::fs::read(&path).await.map_err(|err| {
+            NbError::CommandFailed(format!("failed to read {}: {err}", path.display()))
+        })?;
+        let current_hash = content_hash::of(&content);
+        if current_hash.eq_ignore_ascii_case(expected_hash.trim()) {
+            return Ok(None);
+        }
+        Ok(Some(Conflict {
+            id: args.id.clone(),
+            expected_hash: expected_hash.clone(),
+            current_hash,
+            current_content: String::from_utf8(content).ok(),
+        }))
+    }
+
+    /// Runs a read-only subcommand against the notes directory.
+    async fn run_fallback(
+        &self,
+        fallback: &FsNotebook,
+        subcommand: &str,
+        args: serde_json::Value,
+    ) -> Result<Result<String, NbError>, McpError> {
+        Ok(match subcommand {
+            "status" => fallback.status().await,
+            "list" => {
+                let args: ListArgs = parse_args(args)?;
+                match args.output_note {
+                    Some(_) => Err(fallback_output_note()),
+                    None => fallback.list(&args).await,
+                }
+            }
+            "search" => {
+                let args: SearchArgs = parse_args(args)?;
+                match args.output_note {
+                    Some(_) => Err(fallback_output_note()),
+                    None => fallback.search(&args).await,
+                }
+            }
+            "show" => {
+                let args: ShowArgs = parse_args(args)?;
+                let range = args.range.unwrap_or_default();
+                fallback.show(&args.id).await.map(|text| {
+                    output::window(
+                        &text,
+                        range.offset,
+                        range.length,
+                        self.max_output_bytes,
+                        SHOW_LIMIT_HINT,
+                    )
+                })
+            }
+            _ => Err(NbError::CommandFailed(
+                FsNotebook::unavailable(subcommand).unwrap_or_default(),
+            )),
+        })
+    }
+
+    /// Resolves the `path` argument of a file import or export in the export
+    /// directory, or else the notebook's folder.
+    async fn file_path(
+        &self,
+        path: Option<&str>,
+        notebook: Option<&str>,
+    ) -> Result<Result<Option<PathBuf>, NbError>, McpError> {
+        let Some(path) = path else {
+            return Ok(Ok(None));
+        };
+        let relative = notes::relative_file_path(path)
+            .map_err(|message| McpError::invalid_params(message, None))?;
+        let base = match &self.export_dir {
+            Some(dir) => dir.clone(),
+            None => match self.nb.notebook_path(notebook).await {
+                Ok(root) => root,
+                Err(err) => return Ok(Err(err)),
+            },
+        };
+        Ok(Ok(Some(base.join(relative))))
+    }
+
+    /// Runs a subcommand, returning the nb result or an MCP argument error.
+    async fn run_command(
+        &self,
+        command: &str,
+        subcommand: &str,
+        args: serde_json::Value,
+        peer: Option<&Peer<RoleServer>>,
+        progress_token: Option<&ProgressToken>,
+    ) -> Result<Result<String, NbError>, McpError> {
+        if let Some(fallback) = &self.fallback {
+            return self.run_fallback(fallback, subcommand, args).await;
+        }
+        let result = match subcommand {
+            "status" => {
+                let args: StatusArgs = parse_args(args)?;
+                self.nb.status(args.notebook.as_deref()).await
+            }
+            "notebooks" => self.nb.notebooks().await,
+            "notebook_clone" => {
+                let args: NotebookCloneArgs = parse_args(args)?;
+                notebook_clone::clone(&self.nb, peer, progress_token, &args)
+                    .await
+                    .map(|cloned| to_json_text(&cloned))
+            }
+            "add" => {
+                let args: AddArgs = parse_args(args)?;
+                let content = match (&args.template, args.content) {
+                    (Some(name), content) => {
+                        let template =
+                            match templates::load(&self.nb, name, args.notebook.as_deref()).await {
+                                Ok(template) => template,
+                                Err(err) => return Ok(Err(err)),
+                            };
+                        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+                        let rendered = template.render(&args.vars, &date).map_err(|err| {
+                            McpError::invalid_params(
+                                err.message(&template),
+                                Some(serde_json::json!({"variables": template.variables})),
+                            )
+                        })?;
+                        match content {
+                            Some(content) => format!("{}\n\n{}\n", rendered.trim_end(), content),
+                            None => rendered,
+                        }
+                    }
+                    (None, Some(content)) => content,
+                    (None, None) if args.insert_snippet.is_some() => String::new(),
+                    (None, None) => {
+                        return Err(McpError::invalid_params(
+                            "add needs content, template, or insert_snippet",
+                            None,
+                        ));
+                    }
+                };
+                let content = match &args.insert_snippet {
+                    Some(name) => {
+                        let notebook = args.notebook.as_deref();
+                        match snippets::insert(&self.nb, &content, name, notebook).await {
+                            Ok(content) => content,
+                            Err(err) => return Ok(Err(err)),
+                        }
+                    }
+                    None => content,
+                };
+                let (folder, tags) = self.with_defaults(args.folder, args.tags, args.no_defaults);
+                let generated_title = match args.title {
+                    None if self.auto_title => notes::derive_title(&content),
+                    _ => None,
+                };
                 self.nb
-                    .tasks(args.folder.as_deref(), args.notebook.as_deref())
+                    .add(
+                        args.title.as_deref().or(generated_title.as_deref()),
+                        &content,
+                        &tags,
+                        folder.as_deref(),
+                        args.notebook.as_deref(),
+                    )
                     .await
+                    .map(|output| match generated_title {
+                        Some(title) => format!("{}\nGenerated title: {}", output.trim_end(), title),
+                        None => output,
+                    })
             }
-            "bookmark" => {
-                let args: BookmarkArgs = parse_args(call.args)?;
+            "edit" => {
+                let args: EditArgs = parse_args(args)?;
+                let notebook = args.notebook.as_deref();
+                let content = match &args.insert_snippet {
+                    Some(name) => {
+                        match snippets::insert(&self.nb, &args.content, name, notebook).await {
+                            Ok(content) => content,
+                            Err(err) => return Ok(Err(err)),
+                        }
+                    }
+                    None => args.content,
+                };
+                self.nb.edit(&args.id, &content, notebook).await
+            }
+            "delete" => {
+                let args: DeleteArgs = parse_args(args)?;
+                if !args.confirm {
+                    return Err(McpError::invalid_params(
+                        "delete requires confirm: true",
+                        Some(serde_json::json!({
+                            "hint": "Set confirm: true to delete the note.",
+                            "id": args.id,
+                        })),
+                    ));
+                }
+                self.nb.delete(&args.id, args.notebook.as_deref()).await
+            }
+            "move" => {
+                let args: MoveArgs = parse_args(args)?;
+                self.nb
+                    .move_note(&args.id, &args.destination, args.notebook.as_deref())
+                    .await
+            }
+            "archive_note" => {
+                let args: ArchiveNoteArgs = parse_args(args)?;
+                archive::archive_note(&self.nb, &args.id, args.notebook.as_deref())
+                    .await
+                    .map(|archived| to_json_text(&archived))
+            }
+            "link" => {
+                let args: LinkArgs = parse_args(args)?;
+                links::link(
+                    &self.nb,
+                    self.link_style,
+                    &args.from_id,
+                    &args.to_id,
+                    args.anchor_text.as_deref(),
+                    args.notebook.as_deref(),
+                )
+                .await
+                .map(|linked| to_json_text(&linked))
+            }
+            "show_many" => {
+                let args: ShowManyArgs = parse_args(args)?;
+                if args.ids.is_empty() && args.glob.is_none() {
+                    return Err(McpError::invalid_params(
+                        "show_many requires ids or glob",
+                        None,
+                    ));
+                }
+                if let Some(glob) = &args.glob {
+                    show_many::split_glob(glob)
+                        .map_err(|message| McpError::invalid_params(message, None))?;
+                }
+                self.show_many(args).await
+            }
+            "show_folder" => {
+                let args: ShowFolderArgs = parse_args(args)?;
+                let budget = args
+                    .max_bytes
+                    .unwrap_or(self.max_output_bytes)
+                    .min(self.max_output_bytes);
+                show_folder::show_folder(&self.nb, &args, budget).await
+            }
+            "resolve" => {
+                let args: ResolveArgs = parse_args(args)?;
+                resolve::resolve(&self.nb, &args)
+                    .await
+                    .map(|resolution| to_json_text(&resolution))
+            }
+            "copy_to" => {
+                let args: CopyToArgs = parse_args(args)?;
                 self.nb
-                    .bookmark(
-                        &args.url,
-                        args.title.as_deref(),
-                        &args.tags,
-                        args.comment.as_deref(),
+                    .copy_to(
+                        &args.id,
+                        &args.target_notebook,
                         args.folder.as_deref(),
                         args.notebook.as_deref(),
                     )
                     .await
             }
+            "list" => {
+                let args: ListArgs = parse_args(args)?;
+                let result = self.list(&args).await;
+                let source = format!(
+                    "Notes listed by nb.list in {}.",
+                    args.folder.as_deref().unwrap_or("the notebook root")
+                );
+                self.save_output_note(
+                    result,
+                    args.output_note.as_deref(),
+                    args.notebook.as_deref(),
+                    |text| output_note::items(&source, text),
+                )
+                .await
+            }
+            "search" => {
+                let args: SearchArgs = parse_args(args)?;
+                let result = self.search(&args).await;
+                let source = format!("Results of nb.search for `{}`.", args.query);
+                self.save_output_note(
+                    result,
+                    args.output_note.as_deref(),
+                    args.notebook.as_deref(),
+                    |text| output_note::items(&source, text),
+                )
+                .await
+            }
+            "todo" => {
+                let args: TodoArgs = parse_args(args)?;
+                let (folder, tags) = self.with_defaults(args.folder, args.tags, args.no_defaults);
+                match &args.due {
+                    Some(due) => {
+                        self.nb
+                            .todo_with_due(
+                                &args.description,
+                                &tags,
+                                folder.as_deref(),
+                                due,
+                                args.notebook.as_deref(),
+                            )
+                            .await
+                    }
+                    None => {
+                        self.nb
+                            .todo(
+                                &args.description,
+                                &tags,
+                                folder.as_deref(),
+                                args.notebook.as_deref(),
+                            )
+                            .await
+                    }
+                }
+            }
+            "do" => {
+                let args: TaskIdArgs = parse_args(args)?;
+                task_log::complete(&self.nb, &args.id, args.notebook.as_deref()).await
+            }
+            "undo" => {
+                let args: TaskIdArgs = parse_args(args)?;
+                task_log::reopen(&self.nb, &args.id, args.notebook.as_deref()).await
+            }
+            "task_metrics" => {
+                let args: TaskMetricsArgs = parse_args(args)?;
+                task_log::metrics(&self.nb, args.weeks, args.notebook.as_deref())
+                    .await
+                    .map(|metrics| to_json_text(&metrics))
+            }
+            "todotxt_import" => {
+                let args: TodotxtImportArgs = parse_args(args)?;
+                if args.content.is_none() && args.path.is_none() {
+                    return Err(McpError::invalid_params(
+                        "todotxt_import needs content or path",
+                        None,
+                    ));
+                }
+                let path = match self
+                    .file_path(args.path.as_deref(), args.notebook.as_deref())
+                    .await?
+                {
+                    Ok(path) => path,
+                    Err(err) => return Ok(Err(err)),
+                };
+                todotxt::import(&self.nb, &args, path.as_deref())
+                    .await
+                    .map(|summary| to_json_text(&summary))
+            }
+            "todotxt_export" => {
+                let args: TodotxtExportArgs = parse_args(args)?;
+                let path = match self
+                    .file_path(args.path.as_deref(), args.notebook.as_deref())
+                    .await?
+                {
+                    Ok(path) => path,
+                    Err(err) => return Ok(Err(err)),
+                };
+                todotxt::export(&self.nb, &args, path.as_deref()).await
+            }
+            "board" => {
+                let args: BoardArgs = parse_args(args)?;
+                board::board(
+                    &self.nb,
+                    &self.board_columns,
+                    args.folder.as_deref(),
+                    args.include_archived,
+                    args.notebook.as_deref(),
+                )
+                .await
+                .map(|board| to_json_text(&board))
+            }
+            "board_move" => {
+                let args: BoardMoveArgs = parse_args(args)?;
+                board::move_card(
+                    &self.nb,
+                    &self.board_columns,
+                    &args.id,
+                    &args.column,
+                    args.notebook.as_deref(),
+                )
+                .await
+                .map(|moved| to_json_text(&moved))
+            }
+            "tasks_ical" => {
+                let args: TasksIcalArgs = parse_args(args)?;
+                let path = match self
+                    .file_path(args.path.as_deref(), args.notebook.as_deref())
+                    .await?
+                {
+                    Ok(path) => path,
+                    Err(err) => return Ok(Err(err)),
+                };
+                tasks_ical::tasks_ical(&self.nb, &args, path.as_deref()).await
+            }
+            "tasks" => {
+                let args: TasksArgs = parse_args(args)?;
+                self.nb
+                    .tasks(args.folder.as_deref(), args.notebook.as_deref())
+                    .await
+            }
+            "bookmark" => {
+                let args: BookmarkArgs = parse_args(args)?;
+                match &args.content {
+                    _ if args.enrich => bookmark::add_enriched(&self.nb, &args).await,
+                    // Pre-fetched content is written as the bookmark file,
+                    // so nb never requests the page.
+                    Some(content) => {
+                        let note = bookmark::render(
+                            &args.url,
+                            args.title.as_deref(),
+                            args.comment.as_deref(),
+                            &args.tags,
+                            content,
+                        );
+                        self.nb
+                            .add_at_path(
+                                &bookmark::path(args.folder.as_deref()),
+                                &note,
+                                args.notebook.as_deref(),
+                            )
+                            .await
+                    }
+                    None => {
+                        self.nb
+                            .bookmark(
+                                &args.url,
+                                args.title.as_deref(),
+                                &args.tags,
+                                args.comment.as_deref(),
+                                args.folder.as_deref(),
+                                args.notebook.as_deref(),
+                            )
+                            .await
+                    }
+                }
+            }
             "folders" => {
-                let args: FoldersArgs = parse_args(call.args)?;
+                let args: FoldersArgs = parse_args(args)?;
                 self.nb
                     .folders(args.parent.as_deref(), args.notebook.as_deref())
                     .await
             }
+            "pinned" => {
+                let args: PinnedArgs = parse_args(args)?;
+                self.nb
+                    .pinned(args.folder.as_deref(), args.notebook.as_deref())
+                    .await
+            }
+            "preview_url" => {
+                let args: PreviewUrlArgs = parse_args(args)?;
+                match &self.preview {
+                    Some(preview) => self
+                        .nb
+                        .resolve_notebook(args.notebook.as_deref())
+                        .await
+                        .map(|notebook| preview.url(&notebook, &args.id)),
+                    None => Err(NbError::CommandFailed(
+                        "preview server is not running; start the server with --preview-port"
+                            .to_string(),
+                    )),
+                }
+            }
             "mkdir" => {
-                let args: MkdirArgs = parse_args(call.args)?;
+                let args: MkdirArgs = parse_args(args)?;
                 self.nb.mkdir(&args.path, args.notebook.as_deref()).await
             }
             "import" => {
-                let args: ImportArgs = parse_args(call.args)?;
-                self.nb
-                    .import(
+                let args: ImportArgs = parse_args(args)?;
+                match args.format.as_deref() {
+                    None => {
+                        self.nb
+                            .import(
+                                &args.source,
+                                args.folder.as_deref(),
+                                args.filename.as_deref(),
+                                args.convert,
+                                args.notebook.as_deref(),
+                            )
+                            .await
+                    }
+                    Some("enex") => enex::import(
+                        &self.nb,
+                        &args.source,
+                        args.folder.as_deref(),
+                        args.notebook.as_deref(),
+                    )
+                    .await
+                    .map(|summary| to_json_text(&summary)),
+                    Some("notion") => notion::import(
+                        &self.nb,
+                        &args.source,
+                        args.folder.as_deref(),
+                        args.notebook.as_deref(),
+                    )
+                    .await
+                    .map(|summary| to_json_text(&summary)),
+                    Some(format) if pandoc::IMPORT_FORMATS.contains(&format) => pandoc::import(
+                        &self.nb,
                         &args.source,
+                        format,
                         args.folder.as_deref(),
                         args.filename.as_deref(),
-                        args.convert,
                         args.notebook.as_deref(),
                     )
                     .await
+                    .map(|summary| to_json_text(&summary)),
+                    Some(other) => {
+                        return Err(McpError::invalid_params(
+                            "unsupported import format",
+                            Some(serde_json::json!({
+                                "format": other,
+                                "hint": "Omit format, or use \"enex\", \"notion\", or (with pandoc) \"docx\", \"odt\", \"org\", or \"rst\".",
+                            })),
+                        ));
+                    }
+                }
+            }
+            "export" => {
+                let args: ExportArgs = parse_args(args)?;
+                let path = match self
+                    .file_path(args.path.as_deref(), args.notebook.as_deref())
+                    .await?
+                {
+                    Ok(path) => path,
+                    Err(err) => return Ok(Err(err)),
+                };
+                pandoc::export(
+                    &self.nb,
+                    &args.id,
+                    &args.format,
+                    path.as_deref(),
+                    args.notebook.as_deref(),
+                )
+                .await
+                .map(|export| to_json_text(&export))
+            }
+            "dump" => {
+                let args: DumpArgs = parse_args(args)?;
+                dump::dump(&self.nb, args.folder.as_deref(), args.notebook.as_deref())
+                    .await
+                    .map(|dump| to_json_text(&dump))
+            }
+            "load" => {
+                let args: LoadArgs = parse_args(args)?;
+                let dump: dump::NotebookDump = parse_value(args.dump)?;
+                dump::load(
+                    &self.nb,
+                    &dump,
+                    args.folder.as_deref(),
+                    args.notebook.as_deref(),
+                )
+                .await
+                .map(|summary| to_json_text(&summary))
+            }
+            "push" => {
+                let args: PushArgs = parse_args(args)?;
+                git_sync::push(
+                    &self.nb,
+                    args.remote.as_deref(),
+                    args.branch.as_deref(),
+                    args.notebook.as_deref(),
+                )
+                .await
+                .map(|result| to_json_text(&result))
+            }
+            "pull" => {
+                let args: PullArgs = parse_args(args)?;
+                git_sync::pull(
+                    &self.nb,
+                    args.remote.as_deref(),
+                    args.branch.as_deref(),
+                    args.ff_only,
+                    args.notebook.as_deref(),
+                )
+                .await
+                .map(|result| to_json_text(&result))
+            }
+            "blame" => {
+                let args: BlameArgs = parse_args(args)?;
+                blame::blame(
+                    &self.nb,
+                    &args.id,
+                    args.paragraphs,
+                    args.notebook.as_deref(),
+                )
+                .await
+                .map(|blame| to_json_text(&blame))
+            }
+            "changes" => {
+                let args: ChangesArgs = parse_args(args)?;
+                changes::since(&self.nb, &args.since, args.notebook.as_deref())
+                    .await
+                    .map(|changes| to_json_text(&changes))
+            }
+            "git_status" => {
+                let args: GitStatusArgs = parse_args(args)?;
+                git_sync::status(&self.nb, args.fetch, args.notebook.as_deref())
+                    .await
+                    .map(|status| to_json_text(&status))
+            }
+            "summarize" => {
+                let args: SummarizeArgs = parse_args(args)?;
+                sampling::summarize(&self.nb, peer, &args)
+                    .await
+                    .map(|summary| to_json_text(&summary))
+            }
+            "suggest_tags" => {
+                let args: SuggestTagsArgs = parse_args(args)?;
+                sampling::suggest_tags(&self.nb, peer, &args)
+                    .await
+                    .map(|suggestion| to_json_text(&suggestion))
+            }
+            "tag_graph" => {
+                let args: TagGraphArgs = parse_args(args)?;
+                tag_graph::tag_graph(&self.nb, &args)
+                    .await
+                    .map(|graph| to_json_text(&graph))
+            }
+            "inbox_report" => {
+                let args: InboxReportArgs = parse_args(args)?;
+                let result = inbox_report::inbox_report(&self.nb, &args)
+                    .await
+                    .map(|report| to_json_text(&report));
+                self.save_output_note(
+                    result,
+                    args.output_note.as_deref(),
+                    args.notebook.as_deref(),
+                    |text| {
+                        output_note::json(
+                            "Untagged and unfiled notes found by nb.inbox_report.",
+                            text,
+                        )
+                    },
+                )
+                .await
+            }
+            "stale" => {
+                let args: StaleArgs = parse_args(args)?;
+                stale::stale(&self.nb, &args)
+                    .await
+                    .map(|report| to_json_text(&report))
+            }
+            "dedupe" => {
+                let args: DedupeArgs = parse_args(args)?;
+                if args.merge && !args.confirm {
+                    return Err(McpError::invalid_params(
+                        "dedupe with merge requires confirm: true",
+                        Some(serde_json::json!({
+                            "hint": "Run without merge to review clusters, then set merge and confirm to true.",
+                        })),
+                    ));
+                }
+                dedupe::dedupe(&self.nb, &args)
+                    .await
+                    .map(|report| to_json_text(&report))
+            }
+            "merge" => {
+                let args: MergeArgs = parse_args(args)?;
+                if args.delete_sources && !args.confirm {
+                    return Err(McpError::invalid_params(
+                        "merge with delete_sources requires confirm: true",
+                        Some(serde_json::json!({
+                            "hint": "Set confirm: true to delete the source notes after merging.",
+                            "sources": args.sources,
+                        })),
+                    ));
+                }
+                merge::merge(&self.nb, &args)
+                    .await
+                    .map(|result| to_json_text(&result))
+            }
+            "split" => {
+                let args: SplitArgs = parse_args(args)?;
+                let level = args.level.unwrap_or(split::DEFAULT_LEVEL);
+                if !(1..=6).contains(&level) {
+                    return Err(McpError::invalid_params(
+                        "split level must be between 1 and 6",
+                        None,
+                    ));
+                }
+                split::split(
+                    &self.nb,
+                    self.link_style,
+                    &args.id,
+                    level,
+                    args.folder.as_deref(),
+                    args.notebook.as_deref(),
+                )
+                .await
+                .map(|split| to_json_text(&split))
+            }
+            "outline" => {
+                let args: OutlineArgs = parse_args(args)?;
+                self.nb
+                    .show(&args.id, args.notebook.as_deref())
+                    .await
+                    .map(|content| {
+                        to_json_text(&outline::Outline {
+                            id: args.id,
+                            lines: content.lines().count(),
+                            headings: outline::headings(&content),
+                        })
+                    })
+            }
+            "note_stats" => {
+                let args: NoteStatsArgs = parse_args(args)?;
+                note_stats::note_stats(&self.nb, &args)
+                    .await
+                    .map(|report| to_json_text(&report))
+            }
+            "references" => {
+                let args: ReferencesArgs = parse_args(args)?;
+                references::references(&self.nb, &args)
+                    .await
+                    .map(|list| to_json_text(&list))
+            }
+            "journal_stats" => {
+                let args: JournalStatsArgs = parse_args(args)?;
+                let parse_day = |name: &str, value: &Option<String>| {
+                    value
+                        .as_deref()
+                        .map(|value| {
+                            chrono::NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").map_err(
+                                |_| {
+                                    McpError::invalid_params(
+                                        format!("{name} must be a YYYY-MM-DD date, got {value:?}"),
+                                        None,
+                                    )
+                                },
+                            )
+                        })
+                        .transpose()
+                };
+                let until = parse_day("until", &args.until)?
+                    .unwrap_or_else(|| chrono::Local::now().date_naive());
+                let since = parse_day("since", &args.since)?
+                    .unwrap_or_else(|| until - chrono::Days::new(journal_stats::DEFAULT_DAYS - 1));
+                if since > until {
+                    return Err(McpError::invalid_params(
+                        "journal_stats since must not be after until",
+                        None,
+                    ));
+                }
+                let folder = args
+                    .folder
+                    .as_deref()
+                    .map(|folder| folder.trim_matches('/'))
+                    .filter(|folder| !folder.is_empty())
+                    .unwrap_or(journal_stats::DEFAULT_FOLDER);
+                if folder.split('/').any(|part| part == "..") {
+                    return Err(McpError::invalid_params(
+                        "journal_stats folder must be inside the notebook",
+                        None,
+                    ));
+                }
+                journal_stats::journal_stats(
+                    &self.nb,
+                    folder,
+                    since,
+                    until,
+                    args.notebook.as_deref(),
+                )
+                .await
+                .map(|stats| to_json_text(&stats))
+            }
+            "extract_todos" => {
+                let args: ExtractTodosArgs = parse_args(args)?;
+                let (folder, tags) = self.with_defaults(
+                    args.todo_folder.clone(),
+                    args.tags.clone(),
+                    args.no_defaults,
+                );
+                checklists::extract_todos(&self.nb, &args, &tags, folder.as_deref())
+                    .await
+                    .map(|summary| to_json_text(&summary))
+            }
+            "toc" => {
+                let args: TocArgs = parse_args(args)?;
+                toc::toc(
+                    &self.nb,
+                    &args.id,
+                    args.max_level.unwrap_or(toc::DEFAULT_MAX_LEVEL),
+                    args.insert,
+                    args.notebook.as_deref(),
+                )
+                .await
+                .map(|toc| to_json_text(&toc))
+            }
+            "toggle_checkbox" => {
+                let args: ToggleCheckboxArgs = parse_args(args)?;
+                checklists::toggle_checkbox(&self.nb, &args)
+                    .await
+                    .map(|toggled| to_json_text(&toggled))
+            }
+            "view" => {
+                let args: ViewArgs = parse_args(args)?;
+                self.view(args).await
+            }
+            "save_view" => {
+                let args: SaveViewArgs = parse_args(args)?;
+                views::save(&self.nb, &args.name, args.view, args.notebook.as_deref()).await
+            }
+            "snippet" => {
+                let args: SnippetArgs = parse_args(args)?;
+                match &args.name {
+                    Some(name) => snippets::get(&self.nb, name, args.notebook.as_deref()).await,
+                    None => snippets::list(&self.nb, args.notebook.as_deref())
+                        .await
+                        .map(|list| to_json_text(&list)),
+                }
+            }
+            "save_snippet" => {
+                let args: SaveSnippetArgs = parse_args(args)?;
+                snippets::save(&self.nb, &args.name, &args.text, args.notebook.as_deref()).await
+            }
+            "use_folder" => {
+                let args: UseFolderArgs = parse_args(args)?;
+                let folder = session_folder::normalize(args.folder.as_deref())
+                    .map_err(|message| McpError::invalid_params(message, None))?;
+                *self
+                    .session_folder
+                    .write()
+                    .expect("session folder lock poisoned") = folder.clone();
+                Ok(to_json_text(&serde_json::json!({"folder": folder})))
+            }
+            "use_notebook" => {
+                let args: UseNotebookArgs = parse_args(args)?;
+                let notebook = args
+                    .notebook
+                    .map(|notebook| notebook.trim().to_string())
+                    .filter(|notebook| !notebook.is_empty());
+                if let Some(notebook) = &notebook {
+                    notebook_scope::check_served(&self.served_notebooks, notebook)
+                        .map_err(|message| McpError::invalid_params(message, None))?;
+                    if let Err(err) = self.nb.notebook_path(Some(notebook)).await {
+                        return Ok(Err(err));
+                    }
+                }
+                *self
+                    .session_notebook
+                    .write()
+                    .expect("session notebook lock poisoned") = notebook.clone();
+                Ok(to_json_text(&serde_json::json!({"notebook": notebook})))
             }
             _ => {
                 return Err(McpError::invalid_params(
@@ -431,15 +2474,271 @@ impl McpServer {
                 ));
             }
         };
+        Ok(result)
+    }
+
+    /// Locks every notebook a mutating call writes to.
+    ///
+    /// Notebooks that do not exist yet (or are not git repositories) are not
+    /// locked; the call itself reports or creates them. Locks are taken in
+    /// path order, so two instances writing the same notebooks cannot each
+    /// hold one the other waits for.
+    async fn lock_notebooks(&self, args: &serde_json::Value) -> Result<Vec<NotebookLock>, NbError> {
+        let mut roots = Vec::new();
+        for notebook in self.written_notebook_selectors(args) {
+            if let Ok(root) = self.nb.notebook_path(notebook.as_deref()).await {
+                roots.push(root);
+            }
+        }
+        roots.sort();
+        roots.dedup();
+        let mut locks = Vec::new();
+        for root in roots {
+            locks.extend(notebook_lock::acquire(&root, notebook_lock::WAIT).await?);
+        }
+        Ok(locks)
+    }
+
+    /// Runs a mutating subcommand and rewrites the message of the commit it creates.
+    async fn run_with_commit_message(
+        &self,
+        command: &str,
+        subcommand: &str,
+        args: serde_json::Value,
+        template: &CommitMessageTemplate,
+        peer: Option<&Peer<RoleServer>>,
+        progress_token: Option<&ProgressToken>,
+    ) -> Result<Result<String, NbError>, McpError> {
+        // Clients may send the arguments as a JSON string.
+        let args = decode_string_args(args);
+        let title = commit_message::subject_from_args(&args);
+        // `copy_to` commits to its target notebook.
+        let notebook = args
+            .get("target_notebook")
+            .or_else(|| args.get("notebook"))
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        let repository = match self.nb.notebook_path(notebook.as_deref()).await {
+            Ok(path) => path,
+            Err(err) => return Ok(Err(err)),
+        };
+        let before = match commit_message::head(&self.nb, &repository).await {
+            Ok(head) => head,
+            Err(err) => return Ok(Err(err)),
+        };
+        let result = self
+            .run_command(command, subcommand, args, peer, progress_token)
+            .await?;
+        if result.is_ok() {
+            let notebook = self
+                .nb
+                .resolve_notebook(notebook.as_deref())
+                .await
+                .unwrap_or_default();
+            let vars = [
+                ("command", subcommand),
+                ("title", title.as_str()),
+                ("notebook", notebook.as_str()),
+            ];
+            if let Err(err) = commit_message::amend_if_new(
+                &self.nb,
+                &repository,
+                before.as_deref(),
+                template,
+                &vars,
+            )
+            .await
+            {
+                warn!(error = %err, command = subcommand, "commit message template not applied");
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Names the command an alias ran as, in a trailing text block of results
+/// and as `command` in error data.
+fn with_canonical_command(
+    result: Result<CallToolResult, McpError>,
+    alias: &str,
+    canonical: &str,
+) -> Result<CallToolResult, McpError> {
+    match result {
+        Ok(mut result) => {
+            result.content.push(Content::text(format!(
+                "Ran nb.{canonical}; `{alias}` is an alias for it."
+            )));
+            Ok(result)
+        }
+        Err(mut error) => {
+            let mut data = match error.data.take() {
+                Some(serde_json::Value::Object(data)) => data,
+                Some(other) => serde_json::Map::from_iter([("details".to_string(), other)]),
+                None => serde_json::Map::new(),
+            };
+            data.insert("command".to_string(), format!("nb.{canonical}").into());
+            error.data = Some(serde_json::Value::Object(data));
+            Err(error)
+        }
+    }
+}
+
+/// Adds `request_id` to the payload of a failed call, so clients can quote
+/// it when reporting the failure.
+fn with_request_id(
+    result: Result<CallToolResult, McpError>,
+    request_id: &str,
+) -> Result<CallToolResult, McpError> {
+    match result {
+        Ok(mut result) if result.is_error == Some(true) => {
+            let payload = result.structured_content.take().unwrap_or_else(|| {
+                let message = result
+                    .content
+                    .first()
+                    .and_then(|content| content.as_text())
+                    .map(|text| text.text.clone())
+                    .unwrap_or_default();
+                serde_json::json!({"error": message})
+            });
+            result.structured_content = Some(insert_request_id(payload, request_id));
+            Ok(result)
+        }
+        Err(mut error) => {
+            let data = error.data.take().unwrap_or_else(|| serde_json::json!({}));
+            error.data = Some(insert_request_id(data, request_id));
+            Err(error)
+        }
+        other => other,
+    }
+}
+
+fn insert_request_id(payload: serde_json::Value, request_id: &str) -> serde_json::Value {
+    match payload {
+        serde_json::Value::Object(mut object) => {
+            object.insert("request_id".to_string(), request_id.into());
+            serde_json::Value::Object(object)
+        }
+        other => serde_json::json!({"details": other, "request_id": request_id}),
+    }
+}
+
+/// Schema for a yes/no confirmation elicitation.
+fn confirmation_schema() -> serde_json::Map<String, serde_json::Value> {
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "confirm": {
+                "type": "boolean",
+                "title": "Confirm",
+                "description": "Check to proceed.",
+                "default": false,
+            },
+        },
+        "required": ["confirm"],
+    });
+    match schema {
+        serde_json::Value::Object(schema) => schema,
+        _ => unreachable!("schema literal is an object"),
+    }
+}
+
+/// Converts a `file://` root URI to a local path.
+fn root_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    // Accept `file://localhost/path` as well as `file:///path`.
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    path.starts_with('/')
+        .then(|| PathBuf::from(notes::percent_decode(path)))
+}
+
+/// Returns a note's text, followed by its content hash as a JSON block.
+fn show_result(text: String, hash: Option<String>) -> CallToolResult {
+    let mut result = CallToolResult::success(vec![Content::text(text)]);
+    if let Some(hash) = hash {
+        let payload = serde_json::json!({ "hash": hash });
+        result.content.push(Content::text(payload.to_string()));
+        result.structured_content = Some(payload);
+    }
+    result
+}
 
-        match result {
-            Ok(output) => Ok(CallToolResult::success(vec![Content::text(output)])),
-            Err(err) => Ok(CallToolResult::error(vec![Content::text(err.to_string())])),
+/// Reports a refused edit with the note's current hash and content.
+fn conflict_result(conflict: Conflict) -> CallToolResult {
+    let message = format!(
+        "note {} changed since it was read (expected hash {}, now {}); merge the current content and retry with the new hash",
+        conflict.id, conflict.expected_hash, conflict.current_hash
+    );
+    let payload = serde_json::json!({
+        "error": message,
+        "conflict": conflict,
+    });
+    let mut result = CallToolResult::error(vec![
+        Content::text(message),
+        Content::text(payload.to_string()),
+    ]);
+    result.structured_content = Some(payload);
+    result
+}
+
+fn to_json_text<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|err| format!("{{\"error\": \"{err}\"}}"))
+}
+
+/// Error for an `output_note` requested in read-only filesystem mode.
+fn fallback_output_note() -> NbError {
+    NbError::CommandFailed(
+        "output_note is unavailable: nb is not installed and the server is in read-only \
+         filesystem mode"
+            .to_string(),
+    )
+}
+
+/// Decodes args that a client sent as a JSON string, so they can be
+/// inspected before parsing; other values are returned unchanged.
+fn decode_string_args(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(raw) => {
+            serde_json::from_str(&raw).unwrap_or(serde_json::Value::String(raw))
+        }
+        other => other,
+    }
+}
+
+/// Removes a per-call boolean override (e.g., `allow_create`) from `args`.
+fn take_bool_arg(args: &mut serde_json::Value, name: &str) -> Result<Option<bool>, McpError> {
+    let Some(value) = args.as_object_mut().and_then(|args| args.remove(name)) else {
+        return Ok(None);
+    };
+    value
+        .as_bool()
+        .map(Some)
+        .ok_or_else(|| McpError::invalid_params(format!("{name} must be a boolean"), None))
+}
+
+/// Parses command arguments, rejecting any the command does not define.
+fn parse_args<T: serde::de::DeserializeOwned + Default + JsonSchema>(
+    value: serde_json::Value,
+) -> Result<T, McpError> {
+    if let Some(args) = value.as_object() {
+        let schema = json_schema_for::<T>();
+        let unknown = unknown_args::find(args, &schema);
+        if !unknown.is_empty() {
+            return Err(McpError::invalid_params(
+                unknown_args::message(&unknown),
+                Some(serde_json::json!({
+                    "unknown": unknown,
+                    "allowed": schema["properties"]
+                        .as_object()
+                        .map(|properties| properties.keys().collect::<Vec<_>>()),
+                    "hint": "Check the argument names using the help tool.",
+                })),
+            ));
         }
     }
+    parse_value(value)
 }
 
-fn parse_args<T: serde::de::DeserializeOwned + Default>(
+fn parse_value<T: serde::de::DeserializeOwned + Default>(
     value: serde_json::Value,
 ) -> Result<T, McpError> {
     // Handle empty/null args by using defaults
@@ -472,30 +2771,126 @@ fn parse_args<T: serde::de::DeserializeOwned + Default>(
     })
 }
 
-fn help_tool(params: HelpParams) -> Result<CallToolResult, McpError> {
+/// Returns help for `params.query`, with descriptions from `overrides`
+/// (keyed by subcommand) replacing the built-in ones. Queries that name no
+/// command are answered with the commands matching their keywords.
+fn help_tool(
+    params: HelpParams,
+    overrides: &BTreeMap<String, String>,
+) -> Result<CallToolResult, McpError> {
     let query = params.query.trim();
+    let response = match help_response(query) {
+        Some(mut response) => {
+            override_help(&mut response, overrides);
+            response
+        }
+        None => search_help(query, overrides).ok_or_else(|| {
+            McpError::invalid_params(
+                "unknown query; try 'nb' for command list",
+                Some(serde_json::json!({"query": query})),
+            )
+        })?,
+    };
+
+    Ok(CallToolResult::success(vec![Content::json(response)?]))
+}
+
+/// Ranks the commands against the keywords of a free-text query, or returns
+/// `None` if nothing matches.
+fn search_help(query: &str, overrides: &BTreeMap<String, String>) -> Option<serde_json::Value> {
+    let mut listing = help_response("nb")?;
+    override_help(&mut listing, overrides);
+    let entries = listing["commands"]
+        .as_array()?
+        .iter()
+        .filter_map(|entry| {
+            let command = entry["command"].as_str()?;
+            let help = help_response(command)?;
+            Some(help_search::Entry::new(
+                command,
+                entry["description"].as_str().unwrap_or_default(),
+                &help["args_schema"],
+            ))
+        })
+        .collect::<Vec<_>>();
+    let matches = help_search::search(query, &entries);
+    if matches.is_empty() {
+        return None;
+    }
+    Some(serde_json::json!({
+        "query": query,
+        "matches": matches,
+        "hint": "Call help with one of these commands for its arguments and examples.",
+    }))
+}
 
+/// Returns the built-in help for `query` (`nb` or `nb.<command>`).
+fn help_response(query: &str) -> Option<serde_json::Value> {
     let response = match query {
         "nb" => serde_json::json!({
             "namespace": "nb",
             "commands": [
                 {"command": "nb.status", "description": "Show current notebook and stats"},
                 {"command": "nb.notebooks", "description": "List available notebooks"},
+                {"command": "nb.notebook_clone", "description": "Add a notebook by cloning a remote git repository"},
                 {"command": "nb.add", "description": "Create a new note"},
-                {"command": "nb.show", "description": "Read a note's content"},
+                {"command": "nb.show", "description": "Read a note's content (use range for large notes)"},
                 {"command": "nb.edit", "description": "Update a note's content"},
                 {"command": "nb.delete", "description": "Delete a note (requires confirm: true)"},
                 {"command": "nb.move", "description": "Move or rename a note"},
+                {"command": "nb.show_many", "description": "Read several notes by ids or folder glob in one call"},
+                {"command": "nb.show_folder", "description": "Read every note of a folder in one call, in name or date order"},
+                {"command": "nb.resolve", "description": "Find note ids by title, filename, or URL, with confidence"},
+                {"command": "nb.copy_to", "description": "Copy a note into another notebook"},
+                {"command": "nb.archive_note", "description": "Move a note into archive/ and tag it #archived"},
+                {"command": "nb.link", "description": "Add a link from one note to another"},
                 {"command": "nb.list", "description": "List notes with optional filtering"},
                 {"command": "nb.search", "description": "Full-text search notes"},
                 {"command": "nb.todo", "description": "Create a todo item"},
                 {"command": "nb.do", "description": "Mark a todo as complete"},
                 {"command": "nb.undo", "description": "Reopen a completed todo"},
                 {"command": "nb.tasks", "description": "List todo items"},
+                {"command": "nb.task_metrics", "description": "Report todo completions and average age by week"},
+                {"command": "nb.tasks_ical", "description": "Export open todos with due dates as iCalendar"},
+                {"command": "nb.todotxt_import", "description": "Create todos from a todo.txt list"},
+                {"command": "nb.todotxt_export", "description": "Export todos as a todo.txt list"},
+                {"command": "nb.board", "description": "Group todos into columns by status tag"},
+                {"command": "nb.board_move", "description": "Retag a todo into another board column"},
                 {"command": "nb.bookmark", "description": "Save a URL as a bookmark"},
                 {"command": "nb.folders", "description": "List folders in notebook"},
+                {"command": "nb.pinned", "description": "List pinned items"},
+                {"command": "nb.preview_url", "description": "Link to a note rendered as HTML"},
                 {"command": "nb.mkdir", "description": "Create a folder"},
                 {"command": "nb.import", "description": "Import a file or URL into notebook"},
+                {"command": "nb.export", "description": "Convert a note to docx, odt, pdf, org, or rst (needs pandoc)"},
+                {"command": "nb.dump", "description": "Export notes as a JSON document"},
+                {"command": "nb.load", "description": "Recreate notes from a JSON dump"},
+                {"command": "nb.push", "description": "Push notebook commits to its git remote"},
+                {"command": "nb.pull", "description": "Fetch and merge notebook changes from its git remote"},
+                {"command": "nb.blame", "description": "Show when each line or paragraph of a note last changed"},
+                {"command": "nb.changes", "description": "List notes changed since a commit or timestamp"},
+                {"command": "nb.git_status", "description": "Show uncommitted files and ahead/behind counts for the notebook repo"},
+                {"command": "nb.summarize", "description": "Summarize a note with the client's model (sampling)"},
+                {"command": "nb.suggest_tags", "description": "Suggest tags from the notebook's vocabulary (sampling)"},
+                {"command": "nb.tag_graph", "description": "Report tag usage, co-occurring tags, and tags that may be merged"},
+                {"command": "nb.inbox_report", "description": "List untagged notes and notes outside any folder"},
+                {"command": "nb.stale", "description": "List notes past their expires or review_by date, or untouched for N days"},
+                {"command": "nb.dedupe", "description": "Find duplicate or near-duplicate notes; optionally merge them"},
+                {"command": "nb.merge", "description": "Append several notes to a target note in one commit"},
+                {"command": "nb.split", "description": "Move a note's sections into child notes, leaving links"},
+                {"command": "nb.outline", "description": "List a note's headings with line numbers and byte ranges"},
+                {"command": "nb.toc", "description": "Generate a note's table of contents, optionally inserting it"},
+                {"command": "nb.note_stats", "description": "Count words, characters, headings, and links, with reading time"},
+                {"command": "nb.journal_stats", "description": "Report journal entry days, streaks, and average length"},
+                {"command": "nb.references", "description": "Collect the URLs, DOIs, and files cited by notes"},
+                {"command": "nb.extract_todos", "description": "Create todos from unchecked - [ ] items in notes"},
+                {"command": "nb.toggle_checkbox", "description": "Check or uncheck one - [ ] item inside any note"},
+                {"command": "nb.view", "description": "Run a saved view (named list or search), or list views"},
+                {"command": "nb.save_view", "description": "Save a named list or search query in the notebook"},
+                {"command": "nb.snippet", "description": "Recall a saved text snippet, or list snippets"},
+                {"command": "nb.save_snippet", "description": "Save a reusable text snippet in the notebook"},
+                {"command": "nb.use_folder", "description": "Set the folder later calls of this session act in"},
+                {"command": "nb.use_notebook", "description": "Set the notebook later calls of this session act in"},
             ],
             "invoke": {
                 "tool": "nb",
@@ -510,7 +2905,7 @@ fn help_tool(params: HelpParams) -> Result<CallToolResult, McpError> {
         "nb.add" => command_help("nb.add", "Create a new note", json_schema_for::<AddArgs>()),
         "nb.show" => command_help(
             "nb.show",
-            "Read a note's content",
+            "Read a note's content (use range for large notes)",
             json_schema_for::<ShowArgs>(),
         ),
         "nb.edit" => command_help(
@@ -528,6 +2923,47 @@ fn help_tool(params: HelpParams) -> Result<CallToolResult, McpError> {
             "Move or rename a note. Can move between folders or rename the file.",
             json_schema_for::<MoveArgs>(),
         ),
+        "nb.show_folder" => command_help(
+            "nb.show_folder",
+            "Read all notes of a folder (with recursive, also its subfolders) in name, created, or \
+             modified order, as a JSON list of path, content, and hash, or with concatenate as one \
+             Markdown text. Content shares one size cap (max_bytes); later notes are listed as \
+             omitted.",
+            json_schema_for::<ShowFolderArgs>(),
+        ),
+        "nb.show_many" => command_help(
+            "nb.show_many",
+            "Read several notes in one call, by ids or by a folder glob such as \
+             'clients/*.md'. Returns each note's content and hash keyed by id. All content \
+             shares one size cap; notes past it are listed under omitted.",
+            json_schema_for::<ShowManyArgs>(),
+        ),
+        "nb.resolve" => command_help(
+            "nb.resolve",
+            "Map a title, partial filename, or bookmarked URL to note ids with a confidence \
+             from 0 to 1, most likely first. Use it before show, edit, or delete when the \
+             selector is uncertain.",
+            json_schema_for::<ResolveArgs>(),
+        ),
+        "nb.copy_to" => command_help(
+            "nb.copy_to",
+            "Copy a note into another notebook, e.g. to promote a scratch note into a shared \
+             notebook. The copy is committed to the target notebook.",
+            json_schema_for::<CopyToArgs>(),
+        ),
+        "nb.archive_note" => command_help(
+            "nb.archive_note",
+            "Archive a note: move it into the archive/ folder, keeping its folder path, and tag \
+             it #archived. list and search hide archived notes unless include_archived is true.",
+            json_schema_for::<ArchiveNoteArgs>(),
+        ),
+        "nb.link" => command_help(
+            "nb.link",
+            "Add a link to another note under the note's ## Links section, creating it if \
+             needed, and commit. Links use relative Markdown paths, or [[wiki-links]] when \
+             link_style is \"wiki\" in .nb-mcp.toml. Existing links are not duplicated.",
+            json_schema_for::<LinkArgs>(),
+        ),
         "nb.list" => command_help(
             "nb.list",
             "List notes with optional filtering",
@@ -558,9 +2994,42 @@ fn help_tool(params: HelpParams) -> Result<CallToolResult, McpError> {
             "List todo items",
             json_schema_for::<TasksArgs>(),
         ),
+        "nb.task_metrics" => command_help(
+            "nb.task_metrics",
+            "Report todos completed per ISO week and their average age, from the completion \
+             log that nb.do and nb.undo keep in .nb-mcp-tasks.jsonl",
+            json_schema_for::<TaskMetricsArgs>(),
+        ),
+        "nb.tasks_ical" => command_help(
+            "nb.tasks_ical",
+            "Export open todos with due dates as an iCalendar feed",
+            json_schema_for::<TasksIcalArgs>(),
+        ),
+        "nb.todotxt_import" => command_help(
+            "nb.todotxt_import",
+            "Create todos from a todo.txt list, committed at once",
+            json_schema_for::<TodotxtImportArgs>(),
+        ),
+        "nb.todotxt_export" => command_help(
+            "nb.todotxt_export",
+            "Export todos as a todo.txt list",
+            json_schema_for::<TodotxtExportArgs>(),
+        ),
+        "nb.board" => command_help(
+            "nb.board",
+            "Group todos into columns by status tag (#todo, #doing, #done unless \
+             board_columns is set in .nb-mcp.toml), ordered by priority and due date",
+            json_schema_for::<BoardArgs>(),
+        ),
+        "nb.board_move" => command_help(
+            "nb.board_move",
+            "Move a todo to another board column by replacing its status tag",
+            json_schema_for::<BoardMoveArgs>(),
+        ),
         "nb.bookmark" => command_help(
             "nb.bookmark",
-            "Save a URL as a bookmark",
+            "Save a URL as a bookmark. With enrich, the server fetches the page and stores \
+             its title, description, canonical URL, and og:image in the frontmatter.",
             json_schema_for::<BookmarkArgs>(),
         ),
         "nb.folders" => command_help(
@@ -568,6 +3037,18 @@ fn help_tool(params: HelpParams) -> Result<CallToolResult, McpError> {
             "List folders in notebook",
             json_schema_for::<FoldersArgs>(),
         ),
+        "nb.pinned" => command_help(
+            "nb.pinned",
+            "List pinned items (pin them with `nb pin`), e.g. to surface key notes \
+             at the start of a session",
+            json_schema_for::<PinnedArgs>(),
+        ),
+        "nb.preview_url" => command_help(
+            "nb.preview_url",
+            "Return a localhost link to the note rendered as HTML, for showing as a \
+             clickable preview (needs --preview-port)",
+            json_schema_for::<PreviewUrlArgs>(),
+        ),
         "nb.mkdir" => command_help(
             "nb.mkdir",
             "Create a folder",
@@ -575,37 +3056,1598 @@ fn help_tool(params: HelpParams) -> Result<CallToolResult, McpError> {
         ),
         "nb.import" => command_help(
             "nb.import",
-            "Import a file or URL into notebook",
+            "Import a file or URL into notebook. Set format \"enex\" or \"notion\" to \
+             import an Evernote or Notion export as Markdown notes in one commit. With pandoc \
+             installed, format \"docx\", \"odt\", \"org\", or \"rst\" converts a document \
+             to a Markdown note.",
             json_schema_for::<ImportArgs>(),
         ),
+        "nb.export" => command_help(
+            "nb.export",
+            "Convert a note with pandoc to docx, odt, pdf (needs a LaTeX engine), org, or rst. \
+             Binary formats are written to path; org and rst are returned as text without one. \
+             server_info reports whether pandoc is installed.",
+            json_schema_for::<ExportArgs>(),
+        ),
+        "nb.dump" => command_help(
+            "nb.dump",
+            "Export notes (path, frontmatter, body, tags) as a single JSON document",
+            json_schema_for::<DumpArgs>(),
+        ),
+        "nb.load" => command_help(
+            "nb.load",
+            "Recreate notes from a JSON document produced by nb.dump",
+            json_schema_for::<LoadArgs>(),
+        ),
+        "nb.push" => command_help(
+            "nb.push",
+            "Push notebook commits to its git remote. Reports ahead/behind counts and \
+             rejection reasons.",
+            json_schema_for::<PushArgs>(),
+        ),
+        "nb.pull" => command_help(
+            "nb.pull",
+            "Fetch and merge notebook changes from its git remote. Reports fast-forward, \
+             merge, divergence, or aborted conflicts.",
+            json_schema_for::<PullArgs>(),
+        ),
+        "nb.blame" => command_help(
+            "nb.blame",
+            "Show when each line of a note last changed: commit, date, author, and commit \
+             summary from git blame. With paragraphs, report each paragraph's most recent \
+             change instead.",
+            json_schema_for::<BlameArgs>(),
+        ),
+        "nb.changes" => command_help(
+            "nb.changes",
+            "List notes added, modified, deleted, or renamed since a commit or timestamp, \
+             from the notebook's git history. Pass the returned head as since on the next \
+             call to pick up where you left off.",
+            json_schema_for::<ChangesArgs>(),
+        ),
+        "nb.git_status" => command_help(
+            "nb.git_status",
+            "Report whether the notebook repository is clean, which files are staged, \
+             modified, untracked, or conflicted, and how far it is ahead of or behind \
+             its remote.",
+            json_schema_for::<GitStatusArgs>(),
+        ),
+        "nb.summarize" => command_help(
+            "nb.summarize",
+            "Summarize a note using client sampling; optionally store it in frontmatter",
+            json_schema_for::<SummarizeArgs>(),
+        ),
+        "nb.suggest_tags" => command_help(
+            "nb.suggest_tags",
+            "Suggest tags for a note using client sampling and the notebook's existing tags",
+            json_schema_for::<SuggestTagsArgs>(),
+        ),
+        "nb.tag_graph" => command_help(
+            "nb.tag_graph",
+            "Report how often each tag is used, which tags appear together, and tags that look \
+             like variants of each other (case, separators, plurals, abbreviations such as #mtg \
+             for #meeting), to plan taxonomy cleanups.",
+            json_schema_for::<TagGraphArgs>(),
+        ),
+        "nb.inbox_report" => command_help(
+            "nb.inbox_report",
+            "List notes without tags (hashtags or frontmatter tags) and notes at the notebook \
+             root, outside any folder, for triage in a weekly review.",
+            json_schema_for::<InboxReportArgs>(),
+        ),
+        "nb.stale" => command_help(
+            "nb.stale",
+            "List notes whose expires or review_by frontmatter date has passed and, with \
+             days, notes not modified for more than that many days, most overdue first. \
+             Notes with a review date still ahead are not reported as untouched.",
+            json_schema_for::<StaleArgs>(),
+        ),
+        "nb.dedupe" => command_help(
+            "nb.dedupe",
+            "Find clusters of identical or highly similar notes (hashing and shingling). \
+             With merge and confirm, append each cluster's content to its first note and \
+             delete the rest in one commit.",
+            json_schema_for::<DedupeArgs>(),
+        ),
+        "nb.merge" => command_help(
+            "nb.merge",
+            "Append the bodies of source notes to a target note, each under a header \
+             naming its source, optionally deleting the sources, in one commit.",
+            json_schema_for::<MergeArgs>(),
+        ),
+        "nb.split" => command_help(
+            "nb.split",
+            "Move each section at a heading level (default 2) of a note into its own note in \
+             a folder (default: one named after the note, beside it), with the section heading \
+             as its title, and replace the sections with links to the new notes, in one commit.",
+            json_schema_for::<SplitArgs>(),
+        ),
+        "nb.outline" => command_help(
+            "nb.outline",
+            "List a note's headings (level, text, line) with the byte offset and length \
+             of each section, for reading one section at a time with nb.show range.",
+            json_schema_for::<OutlineArgs>(),
+        ),
+        "nb.toc" => command_help(
+            "nb.toc",
+            "Generate a Markdown table of contents linking to a note's headings. With insert, \
+             write it between <!-- toc --> and <!-- tocstop --> markers (added after the title \
+             if missing) and commit; an unchanged table is not rewritten.",
+            json_schema_for::<TocArgs>(),
+        ),
+        "nb.note_stats" => command_help(
+            "nb.note_stats",
+            "Count words, characters, headings, and links, and estimate reading time (200 words \
+             per minute), for one note or every note of a folder, with totals.",
+            json_schema_for::<NoteStatsArgs>(),
+        ),
+        "nb.journal_stats" => command_help(
+            "nb.journal_stats",
+            "Report which days from since to until (default: the last 30 days) have entries in \
+             the journal folder, dated by the file paths (2025-06-01.md, 2025/06/01.md, or nb \
+             timestamps), with the current and longest streaks and average words per entry.",
+            json_schema_for::<JournalStatsArgs>(),
+        ),
+        "nb.references" => command_help(
+            "nb.references",
+            "List the URLs, DOIs (bare, doi:, or doi.org links), and linked local files other \
+             than notes cited by one note or every note of a folder, deduplicated, each with \
+             the notes citing it.",
+            json_schema_for::<ReferencesArgs>(),
+        ),
+        "nb.extract_todos" => command_help(
+            "nb.extract_todos",
+            "Create a todo for each unchecked `- [ ]` item in a note or folder, linked \
+             back to its source note. Items matching an existing todo are skipped.",
+            json_schema_for::<ExtractTodosArgs>(),
+        ),
+        "nb.toggle_checkbox" => command_help(
+            "nb.toggle_checkbox",
+            "Flip (or set with checked) one Markdown checkbox in any note, chosen by \
+             1-based index or a unique text match, and commit the change.",
+            json_schema_for::<ToggleCheckboxArgs>(),
+        ),
+        "nb.view" => command_help(
+            "nb.view",
+            "Run a saved view by name, or list the available views when no name is given. \
+             Views come from .nb-mcp.toml and the notebook's .nb-mcp-views.toml.",
+            json_schema_for::<ViewArgs>(),
+        ),
+        "nb.save_view" => command_help(
+            "nb.save_view",
+            "Save a named query (query, mode, tags, folder, filter, sort, order, limit) in the notebook's \
+             .nb-mcp-views.toml and commit it.",
+            json_schema_for::<SaveViewArgs>(),
+        ),
+        "nb.snippet" => command_help(
+            "nb.snippet",
+            "Return the text of a saved snippet, or list snippet names with their first lines when \
+             no name is given. add and edit take insert_snippet to append one to the content.",
+            json_schema_for::<SnippetArgs>(),
+        ),
+        "nb.save_snippet" => command_help(
+            "nb.save_snippet",
+            "Save reusable boilerplate (e.g., a meeting header) as .snippets/<name>.md in the \
+             notebook and commit it.",
+            json_schema_for::<SaveSnippetArgs>(),
+        ),
+        "nb.use_folder" => command_help(
+            "nb.use_folder",
+            "Set a working folder for the rest of the session: add, todo, bookmark, and import \
+             create notes there, and list, search, tasks, board, and the reports are scoped to it, \
+             unless a call passes its own folder or no_defaults: true. Omit folder to clear it.",
+            json_schema_for::<UseFolderArgs>(),
+        ),
+        "nb.use_notebook" => command_help(
+            "nb.use_notebook",
+            "Set the default notebook for the rest of the session, replacing the server's default \
+             (or the one requested at initialize); calls passing notebook still use theirs. When \
+             the server serves several notebooks it must be one of them. Omit notebook to clear it.",
+            json_schema_for::<UseNotebookArgs>(),
+        ),
+        "nb.notebook_clone" => command_help(
+            "nb.notebook_clone",
+            "Add a notebook by cloning a remote git repository (nb notebooks add <name> <url>), \
+             then verify the clone. Reports progress when the request carries a progress token.",
+            json_schema_for::<NotebookCloneArgs>(),
+        ),
         "nb.notebooks" => command_help(
             "nb.notebooks",
             "List available notebooks",
             serde_json::json!({"type": "object", "properties": {}}),
         ),
-        _ => {
-            return Err(McpError::invalid_params(
-                "unknown query; try 'nb' for command list",
-                Some(serde_json::json!({"query": query})),
-            ));
-        }
+        _ => return None,
     };
+    Some(response)
+}
 
-    Ok(CallToolResult::success(vec![Content::json(response)?]))
+/// Replaces the descriptions of the commands in a help response that the
+/// project configuration describes.
+fn override_help(response: &mut serde_json::Value, overrides: &BTreeMap<String, String>) {
+    let entries = match response.get_mut("commands").and_then(|c| c.as_array_mut()) {
+        Some(commands) => commands.iter_mut().collect::<Vec<_>>(),
+        None => vec![response],
+    };
+    for entry in entries {
+        let Some(command) = entry.get("command").and_then(|c| c.as_str()) else {
+            continue;
+        };
+        let subcommand = command.strip_prefix("nb.").unwrap_or(command);
+        let description = overrides
+            .iter()
+            .find(|(name, _)| name.strip_prefix("nb.").unwrap_or(name) == subcommand)
+            .map(|(_, description)| description.clone());
+        if let Some(description) = description {
+            entry["description"] = serde_json::Value::String(description);
+        }
+    }
 }
 
 fn command_help(command: &str, description: &str, schema: serde_json::Value) -> serde_json::Value {
-    serde_json::json!({
+    let examples = help_examples::examples(command, &schema);
+    let mut help = serde_json::json!({
         "command": command,
         "description": description,
         "args_schema": schema,
+        "examples": examples,
         "invoke": {
             "tool": "nb",
             "params": {"command": command, "args": {}},
         },
-    })
+    });
+    // The `nb` tool serves every subcommand, so per-command output schemas
+    // are published here rather than as the tool's outputSchema.
+    if let Some(output_schema) = command
+        .strip_prefix("nb.")
+        .and_then(structured::output_schema)
+    {
+        help["output_schema"] = output_schema;
+    }
+    help
 }
 
 fn json_schema_for<T: schemars::JsonSchema>() -> serde_json::Value {
     serde_json::to_value(schemars::schema_for!(T)).unwrap_or(serde_json::Value::Null)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::Config;
+    use crate::executor::{EnvValue, FakeExecutor};
+    use crate::nb::NbClient;
+    use crate::project_config::Permission;
+
+    use super::{McpServer, NbCall};
+
+    fn server(fake: Arc<FakeExecutor>) -> McpServer {
+        let nb = NbClient::with_executor(fake, Some("work".to_string()), false);
+        McpServer::with_client(nb, &Config::default())
+    }
+
+    fn call(command: &str, args: serde_json::Value) -> NbCall {
+        NbCall {
+            command: command.to_string(),
+            args,
+            progress_token: None,
+        }
+    }
+
+    fn text(result: &rmcp::model::CallToolResult) -> &str {
+        result.content[0]
+            .as_text()
+            .map(|t| t.text.as_str())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn dispatch_add_qualifies_notebook_and_tags() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["work:add"], "Added: [1] plan.md"),
+        );
+        let result = server(fake.clone())
+            .dispatch_nb(
+                call(
+                    "nb.add",
+                    serde_json::json!({"title": "Plan", "content": "`x`", "tags": ["a"]}),
+                ),
+                None,
+            )
+            .await
+            .unwrap();
+        let saved: serde_json::Value = serde_json::from_str(text(&result)).unwrap();
+        assert_eq!(saved["id"], "1");
+        assert_eq!(saved["path"], "/notes/work/plan.md");
+        assert_eq!(result.structured_content, Some(saved));
+        assert_eq!(
+            fake.calls()
+                .iter()
+                .rfind(|args| args[0] == "work:add")
+                .unwrap(),
+            &[
+                "work:add",
+                "--title",
+                "Plan",
+                "--content",
+                "`x`",
+                "--tags",
+                "#a"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn dispatch_reports_nb_failures_as_tool_errors() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .fail(&["show"], "Not found: 99"),
+        );
+        let result = server(fake)
+            .dispatch_nb(call("show", serde_json::json!({"id": "99"})), None)
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(text(&result).contains("Not found: 99"));
+        let request_id = &result.structured_content.unwrap()["request_id"];
+        assert!(request_id.as_str().is_some_and(|id| id.contains('-')));
+    }
+
+    #[tokio::test]
+    async fn dispatch_suggests_candidates_for_unknown_selectors() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .fail(&["show"], "Not found: plan")
+                .respond(&["list"], "[3] Quarterly plan\n[2] Notes\n"),
+        );
+        let result = server(fake)
+            .dispatch_nb(call("show", serde_json::json!({"id": "plan"})), None)
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        let remediation = &result.structured_content.unwrap()["remediation"];
+        assert_eq!(remediation["action"], "resolve_selector");
+        assert_eq!(
+            remediation["candidates"],
+            serde_json::json!([{"id": "3", "title": "Quarterly plan"}])
+        );
+    }
+
+    #[tokio::test]
+    async fn dispatch_rejects_delete_without_confirmation() {
+        let fake = Arc::new(FakeExecutor::new().notebook("work", "/notes/work"));
+        let error = server(fake.clone())
+            .dispatch_nb(call("delete", serde_json::json!({"id": "3"})), None)
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("confirm"));
+        // Only the notebook lock's path lookup runs.
+        assert!(fake.calls().iter().all(|args| args[0] == "notebooks"));
+    }
+
+    #[tokio::test]
+    async fn dispatch_enforces_project_permissions() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["list"], "[1] Plan\n"),
+        );
+        let mut config = Config::default();
+        config
+            .permissions
+            .insert("delete".to_string(), Permission::Deny);
+        config
+            .permissions
+            .insert("nb.list".to_string(), Permission::Confirm);
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), false);
+        let server = McpServer::with_client(nb, &config);
+
+        let error = server
+            .dispatch_nb(
+                call("delete", serde_json::json!({"id": "3", "confirm": true})),
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("denied"));
+        let error = server
+            .dispatch_nb(call("list", serde_json::json!({})), None)
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("confirm"));
+        assert!(fake.calls().is_empty());
+
+        let result = server
+            .dispatch_nb(call("list", serde_json::json!({"confirm": true})), None)
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(false));
+        let description = server.tools().map["nb"].attr.description.clone().unwrap();
+        assert!(!description.contains(" delete,"));
+    }
+
+    #[tokio::test]
+    async fn dispatch_dates_listed_items() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-list-dates-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(".index"), "plan.md\n").unwrap();
+        std::fs::write(dir.join("plan.md"), "# Plan\n").unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", dir.to_str().unwrap())
+                .respond(&["list"], "[1] Plan\n[2] Missing\n"),
+        );
+        let result = server(fake)
+            .dispatch_nb(call("list", serde_json::json!({})), None)
+            .await
+            .unwrap();
+        let items = &result.structured_content.unwrap()["items"];
+        assert!(items[0]["modified"].as_str().unwrap().ends_with('Z'));
+        assert!(items[0]["created"].is_string());
+        assert!(items[1].get("modified").is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn project_overrides_descriptions_and_help() {
+        let config = Config {
+            instructions: Some("Team notes.".to_string()),
+            tool_description: Some("Team notebook.".to_string()),
+            help: [("add".to_string(), "Create a design note.".to_string())].into(),
+            ..Config::default()
+        };
+        let nb = NbClient::with_executor(Arc::new(FakeExecutor::new()), None, false);
+        let server = McpServer::with_client(nb, &config);
+
+        let info = rmcp::ServerHandler::get_info(&server);
+        assert_eq!(info.instructions.as_deref(), Some("Team notes."));
+        let tools = server.tools();
+        assert_eq!(
+            tools.map["nb"].attr.description.as_deref(),
+            Some("Team notebook.")
+        );
+        let help = |query: &str| {
+            let params = super::HelpParams {
+                query: query.to_string(),
+            };
+            let result = super::help_tool(params, &server.help).unwrap();
+            serde_json::from_str::<serde_json::Value>(text(&result)).unwrap()
+        };
+        assert_eq!(help("nb.add")["description"], "Create a design note.");
+        let listing = help("nb");
+        let add = listing["commands"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry["command"] == "nb.add")
+            .unwrap();
+        assert_eq!(add["description"], "Create a design note.");
+        assert_eq!(help("nb.show")["command"], "nb.show");
+    }
+
+    #[test]
+    fn help_examples_match_command_schemas() {
+        let help = |query: &str| {
+            let params = super::HelpParams {
+                query: query.to_string(),
+            };
+            let result = super::help_tool(params, &Default::default()).unwrap();
+            serde_json::from_str::<serde_json::Value>(text(&result)).unwrap()
+        };
+        for entry in help("nb")["commands"].as_array().unwrap() {
+            let command = entry["command"].as_str().unwrap();
+            let help = help(command);
+            let schema = &help["args_schema"];
+            let examples = help["examples"].as_array().unwrap();
+            assert!(!examples.is_empty(), "{command} has no examples");
+            for example in examples {
+                assert_eq!(example["command"], command);
+                let args = example["args"].as_object().unwrap();
+                for name in args.keys() {
+                    assert!(
+                        schema["properties"].get(name).is_some(),
+                        "{command} example uses unknown argument {name}"
+                    );
+                }
+                for name in schema["required"].as_array().into_iter().flatten() {
+                    assert!(
+                        args.contains_key(name.as_str().unwrap()),
+                        "{command} example lacks {name}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn help_searches_commands_by_keyword() {
+        let help = |query: &str| {
+            let params = super::HelpParams {
+                query: query.to_string(),
+            };
+            super::help_tool(params, &Default::default())
+        };
+        let result = help("how do I mark a todo as complete").unwrap();
+        let response: serde_json::Value = serde_json::from_str(text(&result)).unwrap();
+        assert_eq!(response["matches"][0]["command"], "nb.do");
+        assert!(help("zzyzx").is_err());
+    }
+
+    #[tokio::test]
+    async fn dispatch_rejects_unknown_arguments_with_suggestion() {
+        let fake = Arc::new(FakeExecutor::new().notebook("work", "/notes/work"));
+        let error = server(fake.clone())
+            .dispatch_nb(
+                call(
+                    "add",
+                    serde_json::json!({"content": "x", "foldr": "clients", "allow_create": true}),
+                ),
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.message,
+            "unknown argument `foldr` (did you mean `folder`?)"
+        );
+        assert!(fake.calls().iter().all(|args| args[0] == "notebooks"));
+    }
+
+    #[tokio::test]
+    async fn dispatch_runs_aliases_as_canonical_commands() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["list"], "[1] Plan\n"),
+        );
+        let result = server(fake.clone())
+            .dispatch_nb(call("nb.ls", serde_json::json!({})), None)
+            .await
+            .unwrap();
+        assert_eq!(text(&result), "[1] Plan\n");
+        let note = result.content.last().unwrap().as_text().unwrap();
+        assert_eq!(note.text, "Ran nb.list; `ls` is an alias for it.");
+
+        let error = server(fake)
+            .dispatch_nb(call("rm", serde_json::json!({"id": "3"})), None)
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("confirm"));
+        assert_eq!(error.data.unwrap()["command"], "nb.delete");
+    }
+
+    #[tokio::test]
+    async fn dispatch_rejects_unknown_subcommands() {
+        let fake = Arc::new(FakeExecutor::new());
+        let error = server(fake)
+            .dispatch_nb(call("nb.frobnicate", serde_json::Value::Null), None)
+            .await
+            .unwrap_err();
+        assert_eq!(error.message, "unknown subcommand");
+    }
+
+    #[tokio::test]
+    async fn dispatch_honors_per_call_allow_create() {
+        let fake = Arc::new(FakeExecutor::new().respond(&["notebooks", "add"], "Added"));
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), true);
+        let server = McpServer::with_client(nb, &Config::default());
+        let probe = server
+            .dispatch_nb(
+                call("status", serde_json::json!({"allow_create": false})),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(probe.is_error, Some(true));
+        assert!(
+            !fake
+                .calls()
+                .iter()
+                .any(|args| args[..2] == ["notebooks", "add"])
+        );
+
+        let strict = NbClient::with_executor(fake, Some("work".to_string()), true)
+            .with_strict_notebooks(true);
+        let error = McpServer::with_client(strict, &Config::default())
+            .dispatch_nb(
+                call("status", serde_json::json!({"allow_create": true})),
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("strict"));
+    }
+
+    #[tokio::test]
+    async fn dispatch_honors_per_call_auto_sync() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/tmp/work")
+                .respond(&["work:add"], "Added [1]"),
+        );
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), false);
+        let server = McpServer::with_client(nb, &Config::default());
+        let args = serde_json::json!({"content": "Plan", "auto_sync": true});
+        let result = server.dispatch_nb(call("add", args), None).await.unwrap();
+        assert_ne!(result.is_error, Some(true));
+        let environments = fake.environments();
+        let calls = fake.calls();
+        let add = calls
+            .iter()
+            .position(|args| args[0] == "work:add")
+            .expect("add was not run");
+        assert_eq!(
+            environments[add],
+            vec![("NB_AUTO_SYNC".to_string(), EnvValue::Set("1".to_string()))]
+        );
+
+        let args = serde_json::json!({"content": "Plan", "auto_sync": "yes"});
+        let error = server
+            .dispatch_nb(call("add", args), None)
+            .await
+            .unwrap_err();
+        assert_eq!(error.message, "auto_sync must be a boolean");
+    }
+
+    #[tokio::test]
+    async fn dispatch_adds_notes_from_templates() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-templates-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(".templates")).unwrap();
+        std::fs::write(
+            dir.join(".templates/decision.md"),
+            "---\nvariables:\n  - name: topic\n    description: What was decided\n---\n\
+             # Decision: {{topic}}\n",
+        )
+        .unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", dir.to_str().unwrap())
+                .respond(&["work:add"], "Added [1]"),
+        );
+        let server = server(fake.clone());
+
+        let error = server
+            .dispatch_nb(
+                call("add", serde_json::json!({"template": "decision"})),
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("`topic` (What was decided)"));
+        let result = server
+            .dispatch_nb(
+                call("add", serde_json::json!({"template": "missing"})),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(text(&result).contains("available: decision"));
+
+        let args = serde_json::json!({"template": "decision", "vars": {"topic": "Use SQLite"}});
+        server.dispatch_nb(call("add", args), None).await.unwrap();
+        let add = fake
+            .calls()
+            .into_iter()
+            .find(|args| args[0] == "work:add")
+            .expect("add was not run");
+        let content = add.iter().position(|arg| arg == "--content").unwrap() + 1;
+        assert_eq!(add[content], "# Decision: Use SQLite\n");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_maintains_backlinks_after_writes() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-backlinks-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.md"), "# A\n\nSee [B](b.md).\n").unwrap();
+        std::fs::write(dir.join("b.md"), "# B\n").unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", dir.to_str().unwrap())
+                .respond(&["edit"], "Updated [1] a.md")
+                .respond(&["work:git", "checkpoint"], ""),
+        );
+        let config = Config {
+            backlinks: true,
+            ..Config::default()
+        };
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), false);
+        let server = McpServer::with_client(nb, &config);
+        let args = serde_json::json!({"id": "a.md", "content": "# A\n\nSee [B](b.md).\n"});
+        server.dispatch_nb(call("edit", args), None).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("b.md")).unwrap(),
+            "# B\n\n## Backlinks\n\n- [A](a.md)\n"
+        );
+        assert!(
+            fake.calls()
+                .iter()
+                .any(|args| args[..2] == ["work:git", "checkpoint"])
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_writes_exports_inside_the_notebook() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-export-path-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("1.todo.md"), "# [ ] Call Mom\n").unwrap();
+        let fake = FakeExecutor::new().notebook("work", dir.to_str().unwrap());
+        let server = server(Arc::new(fake));
+
+        let result = server
+            .dispatch_nb(
+                call("todotxt_export", serde_json::json!({"path": "./todo.txt"})),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_ne!(result.is_error, Some(true));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("todo.txt")).unwrap(),
+            "Call Mom\n"
+        );
+
+        for path in ["../todo.txt", "/tmp/todo.txt"] {
+            let error = server
+                .dispatch_nb(
+                    call("todotxt_export", serde_json::json!({"path": path})),
+                    None,
+                )
+                .await
+                .unwrap_err();
+            assert!(error.message.contains(path));
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_refuses_mutations_beyond_the_budget() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/tmp/work")
+                .respond(&["work:add"], "Added [1] idea.md")
+                .respond(&["list"], "[1] idea.md"),
+        );
+        let config = Config {
+            max_mutations: Some(1),
+            ..Config::default()
+        };
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), false);
+        let server = McpServer::with_client(nb, &config);
+        // A rejected mutation leaves the budget as it was.
+        let invalid = serde_json::json!({"content": 1});
+        assert!(
+            server
+                .dispatch_nb(call("add", invalid), None)
+                .await
+                .is_err()
+        );
+        let args = serde_json::json!({"content": "Idea"});
+        let result = server
+            .dispatch_nb(call("add", args.clone()), None)
+            .await
+            .unwrap();
+        assert_ne!(result.is_error, Some(true));
+        let result = server.dispatch_nb(call("add", args), None).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(text(&result).starts_with("rate limited"));
+        let limited = result.structured_content.unwrap();
+        assert_eq!(limited["limit"], "mutations");
+        assert_eq!(limited["max"], 1);
+        let result = server
+            .dispatch_nb(call("list", serde_json::json!({})), None)
+            .await
+            .unwrap();
+        assert_ne!(result.is_error, Some(true));
+        let adds = fake
+            .calls()
+            .iter()
+            .filter(|args| args[0] == "work:add")
+            .count();
+        assert_eq!(adds, 1);
+    }
+
+    #[tokio::test]
+    async fn dispatch_saves_and_inserts_snippets() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-snippets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", dir.to_str().unwrap())
+                .respond(&["work:git", "checkpoint"], "")
+                .respond(&["edit"], "Updated [1] sync.md"),
+        );
+        let server = server(fake.clone());
+        let args = serde_json::json!({"name": "actions", "text": "## Actions"});
+        let result = server
+            .dispatch_nb(call("save_snippet", args), None)
+            .await
+            .unwrap();
+        assert_eq!(text(&result), "Saved snippet: actions");
+        let result = server
+            .dispatch_nb(call("snippet", serde_json::json!({})), None)
+            .await
+            .unwrap();
+        assert!(text(&result).contains("## Actions"));
+
+        let args =
+            serde_json::json!({"id": "1", "content": "# Sync\n", "insert_snippet": "actions"});
+        server.dispatch_nb(call("edit", args), None).await.unwrap();
+        let edit = fake
+            .calls()
+            .into_iter()
+            .find(|args| args[0] == "edit")
+            .expect("edit was not run");
+        assert_eq!(edit[3], "# Sync\n\n## Actions\n");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_show_truncates_and_serves_ranges() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["show", "work:big.md"], "0123456789"),
+        );
+        let nb = NbClient::with_executor(fake, Some("work".to_string()), false);
+        let config = Config {
+            max_output_bytes: 4,
+            ..Config::default()
+        };
+        let server = McpServer::with_client(nb, &config);
+        let result = server
+            .dispatch_nb(call("show", serde_json::json!({"id": "big.md"})), None)
+            .await
+            .unwrap();
+        assert!(text(&result).starts_with("0123\n"));
+        assert!(text(&result).contains("\"total_bytes\":10"));
+        let result = server
+            .dispatch_nb(
+                call(
+                    "show",
+                    serde_json::json!({"id": "big.md", "range": {"offset": 8}}),
+                ),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(text(&result), "89");
+    }
+
+    #[tokio::test]
+    async fn dispatch_show_folder_reads_notes_within_the_budget() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-show-folder-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("acme/sub")).unwrap();
+        std::fs::write(dir.join("acme/b.md"), "# B\n").unwrap();
+        std::fs::write(dir.join("acme/a.md"), "# A\n").unwrap();
+        std::fs::write(dir.join("acme/sub/c.md"), "# C\n").unwrap();
+        let fake = Arc::new(FakeExecutor::new().notebook("work", dir.to_str().unwrap()));
+        let server = server(fake);
+        let read = |args: serde_json::Value| {
+            let server = server.clone();
+            async move {
+                let result = server
+                    .dispatch_nb(call("show_folder", args), None)
+                    .await
+                    .unwrap();
+                serde_json::from_str::<serde_json::Value>(text(&result)).unwrap()
+            }
+        };
+        let shown = read(serde_json::json!({"folder": "acme"})).await;
+        let paths = shown["notes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|note| note["path"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["acme/a.md", "acme/b.md"]);
+        assert_eq!(shown["notes"][0]["content"], "# A\n");
+
+        let shown =
+            read(serde_json::json!({"folder": "acme", "recursive": true, "max_bytes": 4})).await;
+        assert_eq!(shown["notes"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            shown["omitted"],
+            serde_json::json!(["acme/b.md", "acme/sub/c.md"])
+        );
+        assert_eq!(shown["truncated"], true);
+
+        let error = server
+            .dispatch_nb(
+                call("show_folder", serde_json::json!({"folder": "acme/../.."})),
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("outside the notebook"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_show_many_shares_one_budget() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["show", "work:1"], "first")
+                .respond(&["show", "work:2"], "second")
+                .fail(&["show", "work:9"], "Not found: 9"),
+        );
+        let server = server(fake);
+        let args = serde_json::json!({"ids": ["1", "9", "2", "3"], "max_bytes": 8});
+        let result = server
+            .dispatch_nb(call("show_many", args), None)
+            .await
+            .unwrap();
+        let shown: serde_json::Value = serde_json::from_str(text(&result)).unwrap();
+        assert_eq!(shown["notes"]["1"]["content"], "first");
+        assert!(
+            shown["notes"]["9"]["error"]
+                .as_str()
+                .unwrap()
+                .contains("Not found")
+        );
+        assert!(
+            shown["notes"]["2"]["content"]
+                .as_str()
+                .unwrap()
+                .starts_with("sec\n")
+        );
+        assert_eq!(shown["omitted"], serde_json::json!(["3"]));
+        assert_eq!(shown["truncated"], true);
+    }
+
+    #[tokio::test]
+    async fn dispatch_show_reports_binary_files_as_metadata() {
+        let path = std::env::temp_dir().join(format!("nb-mcp-show-{}.png", std::process::id()));
+        std::fs::write(&path, [0x89, b'P', b'N', b'G', 0x00]).unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["show", "work:logo.png", "--path"], path.to_str().unwrap()),
+        );
+        let server = server(fake);
+        let result = server
+            .dispatch_nb(call("show", serde_json::json!({"id": "logo.png"})), None)
+            .await
+            .unwrap();
+        let metadata: serde_json::Value = serde_json::from_str(text(&result)).unwrap();
+        assert_eq!(metadata["binary"], true);
+        assert_eq!(metadata["type"], "image/png");
+        assert_eq!(metadata["size"], 5);
+        assert!(metadata.get("content_base64").is_none());
+        let result = server
+            .dispatch_nb(
+                call(
+                    "show",
+                    serde_json::json!({"id": "logo.png", "raw_base64": true}),
+                ),
+                None,
+            )
+            .await
+            .unwrap();
+        let metadata: serde_json::Value = serde_json::from_str(text(&result)).unwrap();
+        assert_eq!(metadata["content_base64"], "iVBORwA=");
+
+        // Content over the size limit is left out rather than cut.
+        let mut large = vec![0x89, b'P', b'N', b'G', 0x00];
+        large.resize(4096, 0);
+        std::fs::write(&path, large).unwrap();
+        let fake = FakeExecutor::new()
+            .notebook("work", "/notes/work")
+            .respond(&["show", "work:logo.png", "--path"], path.to_str().unwrap());
+        let nb = NbClient::with_executor(Arc::new(fake), Some("work".to_string()), false);
+        let config = Config {
+            max_output_bytes: 1024,
+            ..Config::default()
+        };
+        let server = McpServer::with_client(nb, &config);
+        let args =
+            serde_json::json!({"id": "logo.png", "raw_base64": true, "range": {"offset": 10}});
+        let result = server.dispatch_nb(call("show", args), None).await.unwrap();
+        let metadata: serde_json::Value = serde_json::from_str(text(&result)).unwrap();
+        assert_eq!(metadata["too_large"], true);
+        assert_eq!(metadata["size"], 4096);
+        assert!(metadata.get("content_base64").is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_show_serves_unchanged_notes_from_cache() {
+        let path = std::env::temp_dir().join(format!("nb-mcp-cached-{}.md", std::process::id()));
+        std::fs::write(&path, "# Plan\n").unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["show", "work:plan.md"], "# Plan\n")
+                .respond(&["show", "work:plan.md", "--path"], path.to_str().unwrap())
+                .respond(&["edit"], "Updated [1] plan.md"),
+        );
+        let server = server(fake.clone());
+        let shows = || fake.calls().iter().filter(|args| args[0] == "show").count();
+        let show = || call("show", serde_json::json!({"id": "plan.md"}));
+        let result = server.dispatch_nb(show(), None).await.unwrap();
+        assert_eq!(text(&result), "# Plan\n");
+        let before = shows();
+        let result = server.dispatch_nb(show(), None).await.unwrap();
+        assert_eq!(text(&result), "# Plan\n");
+        assert_eq!(shows(), before);
+
+        let args = serde_json::json!({"id": "plan.md", "content": "# Plan\n"});
+        server.dispatch_nb(call("edit", args), None).await.unwrap();
+        let before = shows();
+        server.dispatch_nb(show(), None).await.unwrap();
+        assert!(shows() > before);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_list_refreshes_cached_listings_after_writes() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-listing-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", dir.to_str().unwrap())
+                .respond(&["list"], "[1] a.md")
+                .respond(&["work:add"], "Added [2] b.md"),
+        );
+        let server = server(fake.clone());
+        let lists = || fake.calls().iter().filter(|args| args[0] == "list").count();
+        let list = || call("list", serde_json::json!({}));
+        server.dispatch_nb(list(), None).await.unwrap();
+        let result = server.dispatch_nb(list(), None).await.unwrap();
+        assert_eq!(text(&result), "[1] a.md");
+        assert_eq!(lists(), 1);
+
+        let args = serde_json::json!({"content": "B"});
+        server.dispatch_nb(call("add", args), None).await.unwrap();
+        server.listings_refreshed().await;
+        assert_eq!(lists(), 2);
+        server.dispatch_nb(list(), None).await.unwrap();
+        assert_eq!(lists(), 2);
+
+        // Writes to another notebook leave the listing cached.
+        let args = serde_json::json!({"content": "C", "notebook": "home"});
+        server.dispatch_nb(call("add", args), None).await.unwrap();
+        server.listings_refreshed().await;
+        server.dispatch_nb(list(), None).await.unwrap();
+        assert_eq!(lists(), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_list_refreshes_the_session_notebook_after_writes() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-session-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .notebook("acme", dir.to_str().unwrap())
+                .respond(&["list"], "[1] a.md")
+                .respond(&["edit"], "Updated [1] a.md"),
+        );
+        let server = server(fake.clone());
+        let lists = || fake.calls().iter().filter(|args| args[0] == "list").count();
+        let args = serde_json::json!({"notebook": "acme"});
+        server
+            .dispatch_nb(call("use_notebook", args), None)
+            .await
+            .unwrap();
+        server
+            .dispatch_nb(call("list", serde_json::json!({})), None)
+            .await
+            .unwrap();
+        assert_eq!(lists(), 1);
+
+        let args = serde_json::json!({"id": "1", "content": "# Renamed\n"});
+        server.dispatch_nb(call("edit", args), None).await.unwrap();
+        server.listings_refreshed().await;
+        assert_eq!(lists(), 2);
+        let list = fake
+            .calls()
+            .into_iter()
+            .rfind(|args| args[0] == "list")
+            .unwrap();
+        assert_eq!(list[1], "acme:");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_edit_rejects_stale_expected_hash() {
+        let path = std::env::temp_dir().join(format!("nb-mcp-etag-{}.md", std::process::id()));
+        std::fs::write(&path, "# Plan\n").unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["show", "work:3", "--path"], path.to_str().unwrap())
+                .respond(&["show", "work:3", "--no-color"], "# Plan\n"),
+        );
+        let server = server(fake.clone());
+        let result = server
+            .dispatch_nb(call("show", serde_json::json!({"id": "3"})), None)
+            .await
+            .unwrap();
+        let hash = result.structured_content.unwrap()["hash"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(hash, crate::content_hash::of(b"# Plan\n"));
+
+        // The user edits the note after the agent read it.
+        std::fs::write(&path, "# Plan\n\nShip Friday.\n").unwrap();
+        let args = serde_json::json!({"id": "3", "content": "# Plan v2\n", "expected_hash": hash});
+        let result = server.dispatch_nb(call("edit", args), None).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+        let conflict = &result.structured_content.unwrap()["conflict"];
+        assert_eq!(conflict["current_content"], "# Plan\n\nShip Friday.\n");
+        assert!(!fake.calls().iter().any(|args| args[0] == "edit"));
+
+        let args = serde_json::json!({
+            "id": "3",
+            "content": "# Plan v2\n",
+            "expected_hash": conflict["current_hash"],
+        });
+        server.dispatch_nb(call("edit", args), None).await.unwrap();
+        assert!(fake.calls().iter().any(|args| args[0] == "edit"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_bookmark_stores_prefetched_content_without_fetching() {
+        let fake = Arc::new(FakeExecutor::new().notebook("work", "/notes/work"));
+        let server = server(fake.clone());
+        let args = serde_json::json!({
+            "url": "https://example.com/post",
+            "content": "# Post\n\nBody.",
+            "folder": "reading",
+        });
+        let _ = server.dispatch_nb(call("bookmark", args), None).await;
+        let calls = fake.calls();
+        assert!(!calls.iter().any(|args| args[0].ends_with("bookmark")));
+        let add = calls.iter().find(|args| args[0] == "add").unwrap();
+        assert!(add[1].starts_with("work:reading/") && add[1].ends_with(".bookmark.md"));
+        assert!(add[3].ends_with("## Content\n\n# Post\n\nBody.\n"));
+    }
+
+    #[tokio::test]
+    async fn dispatch_todo_applies_default_folder_and_tags() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["work:todo", "add"], "Added: [1] todo.todo.md"),
+        );
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), false);
+        let config = Config {
+            default_folder: Some("inbox".to_string()),
+            default_tags: vec!["ai".to_string()],
+            ..Config::default()
+        };
+        let server = McpServer::with_client(nb, &config);
+        server
+            .dispatch_nb(
+                call(
+                    "todo",
+                    serde_json::json!({"description": "Ship", "tags": ["ai", "x"]}),
+                ),
+                None,
+            )
+            .await
+            .unwrap();
+        let args = fake.calls().last().unwrap().clone();
+        assert_eq!(args[2], "inbox/");
+        assert_eq!(args.iter().filter(|a| *a == "#ai").count(), 1);
+        assert!(args.contains(&"#x".to_string()));
+        server
+            .dispatch_nb(
+                call(
+                    "todo",
+                    serde_json::json!({"description": "Ship", "no_defaults": true}),
+                ),
+                None,
+            )
+            .await
+            .unwrap();
+        let args = fake.calls().last().unwrap().clone();
+        assert!(!args.iter().any(|a| a.contains("inbox") || a == "#ai"));
+    }
+
+    #[tokio::test]
+    async fn lock_notebooks_covers_qualified_ids() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-locks-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for notebook in ["work", "home"] {
+            std::fs::create_dir_all(dir.join(notebook).join(".git")).unwrap();
+        }
+        let fake = FakeExecutor::new()
+            .notebook("work", dir.join("work").to_str().unwrap())
+            .notebook("home", dir.join("home").to_str().unwrap());
+        let server = server(Arc::new(fake));
+        let lock_file = |notebook: &str| {
+            dir.join(notebook)
+                .join(".git")
+                .join(crate::notebook_lock::FILE_NAME)
+        };
+
+        let locks = server
+            .lock_notebooks(&serde_json::json!({"id": "home:12"}))
+            .await
+            .unwrap();
+        assert_eq!(locks.len(), 2);
+        assert!(lock_file("home").exists() && lock_file("work").exists());
+        drop(locks);
+        assert!(!lock_file("home").exists());
+
+        let locks = server
+            .lock_notebooks(&serde_json::json!({"notebook": "home", "id": "home:12"}))
+            .await
+            .unwrap();
+        assert_eq!(locks.len(), 1);
+        drop(locks);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_use_notebook_sets_the_session_default() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .notebook("acme", "/notes/acme")
+                .respond(&["list"], "[1] plan.md"),
+        );
+        let server = server(fake.clone());
+        let result = server
+            .dispatch_nb(
+                call("use_notebook", serde_json::json!({"notebook": "acme"})),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(text(&result).contains(r#""notebook": "acme""#));
+        server
+            .dispatch_nb(call("list", serde_json::json!({})), None)
+            .await
+            .unwrap();
+        let list = fake
+            .calls()
+            .into_iter()
+            .rfind(|args| args[0] == "list")
+            .unwrap();
+        assert_eq!(list[1], "acme:");
+
+        let result = server
+            .dispatch_nb(
+                call("use_notebook", serde_json::json!({"notebook": "missing"})),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        server
+            .dispatch_nb(call("list", serde_json::json!({})), None)
+            .await
+            .unwrap();
+        let list = fake
+            .calls()
+            .into_iter()
+            .rfind(|args| args[0] == "list")
+            .unwrap();
+        assert_eq!(list[1], "acme:");
+    }
+
+    #[tokio::test]
+    async fn dispatch_use_folder_scopes_later_calls() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["work:todo", "add"], "Added: [1] todo.todo.md"),
+        );
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), false);
+        let config = Config {
+            default_folder: Some("inbox".to_string()),
+            ..Config::default()
+        };
+        let server = McpServer::with_client(nb, &config);
+        let result = server
+            .dispatch_nb(
+                call(
+                    "use_folder",
+                    serde_json::json!({"folder": "/projects/acme/"}),
+                ),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(text(&result).contains(r#""folder": "projects/acme""#));
+        let todo = serde_json::json!({"description": "Ship"});
+        server
+            .dispatch_nb(call("todo", todo.clone()), None)
+            .await
+            .unwrap();
+        assert_eq!(fake.calls().last().unwrap()[2], "projects/acme/");
+
+        server
+            .dispatch_nb(call("use_folder", serde_json::json!({})), None)
+            .await
+            .unwrap();
+        server.dispatch_nb(call("todo", todo), None).await.unwrap();
+        assert_eq!(fake.calls().last().unwrap()[2], "inbox/");
+
+        let error = server
+            .dispatch_nb(
+                call("use_folder", serde_json::json!({"folder": "../x"})),
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("outside the notebook"));
+    }
+
+    #[tokio::test]
+    async fn dispatch_output_note_saves_results_as_a_note() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["list"], "[1] Plan\n[2] Retro")
+                .respond(&["work:add"], "Added: [3] digest.md \"Digest\""),
+        );
+        let server = server(fake.clone());
+        let result = server
+            .dispatch_nb(
+                call(
+                    "list",
+                    serde_json::json!({"folder": "acme", "output_note": "Digest {date}"}),
+                ),
+                None,
+            )
+            .await
+            .unwrap();
+        let output = text(&result);
+        assert!(output.starts_with("[1] Plan\n[2] Retro\n"));
+        assert!(output.contains(r#"[nb-mcp] {"output_note":"Added: [3] digest.md \"Digest\""}"#));
+        let add = fake
+            .calls()
+            .into_iter()
+            .find(|args| args[0] == "work:add")
+            .unwrap();
+        let today = chrono::Local::now().date_naive();
+        assert_eq!(add[2], format!("Digest {}", today.format("%Y-%m-%d")));
+        assert_eq!(
+            add[4],
+            "Notes listed by nb.list in acme.\n\n- [1] Plan\n- [2] Retro\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn dispatch_output_note_needs_add_permission() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["list"], "[1] Plan"),
+        );
+        let mut config = Config::default();
+        config
+            .permissions
+            .insert("add".to_string(), Permission::Deny);
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), false);
+        let server = McpServer::with_client(nb, &config);
+        let error = server
+            .dispatch_nb(
+                call("list", serde_json::json!({"output_note": "Digest"})),
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("denied"));
+        assert!(fake.calls().iter().all(|args| args[0] != "work:add"));
+        server
+            .dispatch_nb(call("list", serde_json::json!({})), None)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn root_path_decodes_file_uris() {
+        assert_eq!(
+            super::root_path("file:///home/me/My%20Project"),
+            Some("/home/me/My Project".into())
+        );
+        assert_eq!(
+            super::root_path("file://localhost/srv/app"),
+            Some("/srv/app".into())
+        );
+        assert_eq!(super::root_path("https://example.com/app"), None);
+    }
+
+    #[tokio::test]
+    async fn dispatch_summarize_requires_sampling_client() {
+        let fake = Arc::new(FakeExecutor::new().notebook("work", "/notes/work"));
+        let result = server(fake.clone())
+            .dispatch_nb(call("summarize", serde_json::json!({"id": "1"})), None)
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(text(&result).contains("does not support sampling"));
+        assert!(fake.calls().iter().all(|args| args[0] == "notebooks"));
+    }
+
+    #[tokio::test]
+    async fn dispatch_add_generates_missing_title() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["work:add"], "Added: [2] sync-notes.md"),
+        );
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), false);
+        let config = Config {
+            auto_title: true,
+            ..Config::default()
+        };
+        let result = McpServer::with_client(nb, &config)
+            .dispatch_nb(
+                call(
+                    "add",
+                    serde_json::json!({"content": "# Sync notes\n\nBody"}),
+                ),
+                None,
+            )
+            .await
+            .unwrap();
+        let saved = result.structured_content.unwrap();
+        assert!(
+            saved["message"]
+                .as_str()
+                .unwrap()
+                .ends_with("Generated title: Sync notes")
+        );
+        let calls = fake.calls();
+        let add = calls.iter().rfind(|args| args[0] == "work:add").unwrap();
+        assert_eq!(add[1..3], ["--title", "Sync notes"]);
+    }
+
+    #[tokio::test]
+    async fn server_info_counts_calls() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .respond(&["--version"], "7.12.1\n")
+                .notebook("work", "/notes/work")
+                .respond(&["work:", "status"], "work"),
+        );
+        let server = server(fake);
+        server
+            .dispatch_nb(call("nb.status", serde_json::json!({})), None)
+            .await
+            .unwrap();
+        let _ = server
+            .dispatch_nb(call("missing", serde_json::json!({})), None)
+            .await;
+        let report = crate::server_info::report(&server.nb, &server.stats, None).await;
+        assert_eq!(report.nb_version.as_deref(), Some("7.12.1"));
+        assert_eq!(report.default_notebook.as_deref(), Some("work"));
+        assert_eq!(report.notebook_path.as_deref(), Some("/notes/work"));
+        assert_eq!(report.calls.total, 2);
+        assert_eq!(report.calls.failed, 1);
+        assert_eq!(report.calls.by_command["status"], 1);
+    }
+
+    #[tokio::test]
+    async fn dispatch_merge_appends_sources_and_commits_once() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-merge-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (target, source) = (dir.join("1.md"), dir.join("2.md"));
+        std::fs::write(dir.join(".index"), "1.md\n2.md\n").unwrap();
+        std::fs::write(&target, "# Plan\n\nFirst.\n").unwrap();
+        std::fs::write(&source, "---\ntitle: Extra\n---\nSecond.\n").unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["show", "work:1", "--path"], target.to_str().unwrap())
+                .respond(&["show", "work:2", "--path"], source.to_str().unwrap())
+                .respond(&["show", "work:2", "--title"], "Extra")
+                .respond(&["work:git", "checkpoint"], ""),
+        );
+        let server = server(fake.clone());
+        let args =
+            serde_json::json!({"target": "1", "sources": ["2", "2"], "delete_sources": true});
+        assert!(
+            server
+                .dispatch_nb(call("merge", args.clone()), None)
+                .await
+                .is_err()
+        );
+        let mut args = args;
+        args["confirm"] = serde_json::Value::Bool(true);
+        server.dispatch_nb(call("merge", args), None).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&target).unwrap(),
+            "# Plan\n\nFirst.\n\n## From Extra\n\nSecond.\n"
+        );
+        let count = |command: &str| {
+            fake.calls()
+                .iter()
+                .filter(|call| call[0] == command)
+                .count()
+        };
+        assert_eq!(count("work:git"), 1);
+        assert_eq!(count("delete"), 0);
+        assert!(!source.exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join(".index")).unwrap(),
+            "1.md\n\n"
+        );
+
+        std::fs::write(&source, "Second.\n").unwrap();
+        let args = serde_json::json!({"target": "1", "sources": ["2", "1"]});
+        let result = server.dispatch_nb(call("merge", args), None).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_dedupe_deletes_duplicates_through_nb() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-dedupe-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.md"), "# Plan\n\nShip it.\n").unwrap();
+        std::fs::write(dir.join("b.md"), "# plan\nship   it.\n").unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", dir.to_str().unwrap())
+                .respond(&["work:git", "checkpoint"], "")
+                .respond(&["delete", "work:b.md", "--force"], "Deleted: [2] b.md"),
+        );
+        let server = server(fake.clone());
+        let args = serde_json::json!({"merge": true, "confirm": true});
+        let result = server
+            .dispatch_nb(call("dedupe", args), None)
+            .await
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_str(text(&result)).unwrap();
+        assert_eq!(report["removed"], 1);
+        let calls = fake.calls();
+        let checkpoint = calls.iter().position(|call| call[0] == "work:git");
+        let delete = calls.iter().position(|call| call[0] == "delete");
+        assert!(checkpoint.unwrap() < delete.unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatch_split_moves_sections_into_child_notes() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-split-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("projects")).unwrap();
+        let note = dir.join("projects/launch.md");
+        std::fs::write(
+            &note,
+            "# Launch\n\n## Scope\n\nSmall.\n\n## Risks\n\nLate.\n",
+        )
+        .unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", dir.to_str().unwrap())
+                .respond(&["show", "work:4", "--path"], note.to_str().unwrap())
+                .respond(&["work:git", "checkpoint"], ""),
+        );
+        let server = server(fake.clone());
+        let result = server
+            .dispatch_nb(call("split", serde_json::json!({"id": "4"})), None)
+            .await
+            .unwrap();
+        assert!(text(&result).contains("projects/launch/risks.md"));
+        assert_eq!(
+            std::fs::read_to_string(&note).unwrap(),
+            "# Launch\n\n- [Scope](launch/scope.md)\n- [Risks](launch/risks.md)\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("projects/launch/scope.md")).unwrap(),
+            "# Scope\n\nSmall.\n"
+        );
+        assert!(
+            fake.calls()
+                .iter()
+                .any(|call| call[..2] == ["work:git", "checkpoint"])
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
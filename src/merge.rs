@@ -0,0 +1,100 @@
+//! Merging several notes into one.
+//!
+//! After a messy capture session, related fragments end up in separate
+//! notes. `nb.merge` appends the bodies of the source notes to a target note,
+//! each under a header naming its source, and records the merge as one git
+//! checkpoint. Every note is read before anything is written. With
+//! `delete_sources`, the source files and their `.index` entries are removed
+//! before the checkpoint, so the merge and the deletions land in one commit.
+
+use serde::Serialize;
+
+use crate::args::MergeArgs;
+use crate::nb::{NbClient, NbError};
+use crate::nb_index;
+use crate::notes;
+
+/// Result of `nb.merge`.
+#[derive(Debug, Serialize)]
+pub struct MergeResult {
+    pub target: String,
+    /// Source notes appended, in order.
+    pub merged: Vec<String>,
+    /// Whether the source notes were deleted.
+    pub deleted_sources: bool,
+}
+
+/// Appends the source notes to the target note and commits once.
+///
+/// Callers must obtain confirmation before passing `delete_sources: true`.
+pub async fn merge(nb: &NbClient, args: &MergeArgs) -> Result<MergeResult, NbError> {
+    let notebook = args.notebook.as_deref();
+    if args.sources.is_empty() {
+        return Err(NbError::CommandFailed(
+            "merge needs at least one source note".to_string(),
+        ));
+    }
+    let target_path = nb.show_path(&args.target, notebook).await?;
+    let mut merged = read_text(&target_path, &args.target).await?;
+    // Sources named twice (possibly by different selectors) merge once.
+    let mut sources: Vec<(String, std::path::PathBuf)> = Vec::with_capacity(args.sources.len());
+    for id in &args.sources {
+        let path = nb.show_path(id, notebook).await?;
+        if path == target_path {
+            return Err(NbError::CommandFailed(format!(
+                "note {id} is the merge target; it cannot also be a source"
+            )));
+        }
+        if sources.iter().all(|(_, seen)| *seen != path) {
+            sources.push((id.clone(), path));
+        }
+    }
+    for (id, path) in &sources {
+        let content = read_text(path, id).await?;
+        let title = nb.title(id, notebook).await.unwrap_or_default();
+        let label = if title.is_empty() {
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| id.clone())
+        } else {
+            title
+        };
+        let (_, body) = notes::split_frontmatter(&content);
+        merged = notes::append_section(&merged, &format!("From {label}"), body);
+    }
+
+    tokio::fs::write(&target_path, merged).await?;
+    if args.delete_sources {
+        let paths = sources
+            .iter()
+            .map(|(_, path)| path.clone())
+            .collect::<Vec<_>>();
+        tokio::task::spawn_blocking(move || {
+            paths
+                .iter()
+                .try_for_each(|path| nb_index::remove_file(path))
+        })
+        .await
+        .map_err(|err| NbError::CommandFailed(format!("merge task failed: {err}")))??;
+    }
+    nb.checkpoint(
+        &format!(
+            "[nb-mcp] Merge {} notes into {}",
+            sources.len(),
+            args.target
+        ),
+        notebook,
+    )
+    .await?;
+    Ok(MergeResult {
+        target: args.target.clone(),
+        merged: sources.into_iter().map(|(id, _)| id).collect(),
+        deleted_sources: args.delete_sources,
+    })
+}
+
+/// Reads a note file as text, rejecting binary files.
+async fn read_text(path: &std::path::Path, id: &str) -> Result<String, NbError> {
+    String::from_utf8(tokio::fs::read(path).await?)
+        .map_err(|_| NbError::CommandFailed(format!("note {id} is not a text file")))
+}
@@ -0,0 +1,383 @@
+//! Typed notebook items and parsers for nb's text output.
+//!
+//! nb prints human-oriented listings. These types give the rest of the
+//! server, and embedders, one typed reading of them: [`NbClient`] exposes
+//! typed variants of its listing methods, and tool results carry the same
+//! values as `structuredContent`.
+//!
+//! [`NbClient`]: crate::NbClient
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::output;
+
+/// Matches an item line: `[id] rest`.
+static ITEM_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[([^\]]+)\]\s+(.*)$").unwrap());
+
+/// Matches a search match line: `line:text`.
+static MATCH_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(\d+):(.*)$").unwrap());
+
+/// Matches the site nb prints after a bookmark title: `Title (example.com)`.
+static SITE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.*?)\s*\(([^()\s]+\.[^()\s]+)\)$").unwrap());
+
+/// Leading indicators nb prints before titles, and the entry kinds they mark.
+const INDICATORS: &[(&str, Kind)] = &[
+    ("📂", Kind::Folder),
+    ("🔖", Kind::Bookmark),
+    ("✔️", Kind::Todo),
+    ("✅", Kind::Todo),
+    ("🌄", Kind::Image),
+    ("📄", Kind::Document),
+    ("🔉", Kind::Audio),
+    ("📹", Kind::Video),
+    ("📖", Kind::Ebook),
+];
+
+/// Indicators that decorate an item without changing its kind.
+const DECORATIONS: &[&str] = &["📌", "🔒"];
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Note,
+    Folder,
+    Bookmark,
+    Todo,
+    Image,
+    Document,
+    Audio,
+    Video,
+    Ebook,
+}
+
+/// A note, or a file nb lists like one.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Note {
+    /// Selector for the note (e.g., "12" or "docs/3").
+    pub id: String,
+    pub title: String,
+    pub pinned: bool,
+}
+
+/// A folder in a notebook.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Folder {
+    /// Selector for the folder (e.g., "2").
+    pub id: String,
+    /// Folder name.
+    pub title: String,
+    pub pinned: bool,
+}
+
+/// A bookmarked web page.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Bookmark {
+    /// Selector for the bookmark.
+    pub id: String,
+    pub title: String,
+    /// Domain nb shows after the title, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site: Option<String>,
+    pub pinned: bool,
+}
+
+/// An entry in a notebook listing, tagged by `kind`.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Entry {
+    Note(Note),
+    Todo(Note),
+    Image(Note),
+    Document(Note),
+    Audio(Note),
+    Video(Note),
+    Ebook(Note),
+    Folder(Folder),
+    Bookmark(Bookmark),
+}
+
+impl Entry {
+    /// Returns the entry's selector.
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Folder(folder) => &folder.id,
+            Self::Bookmark(bookmark) => &bookmark.id,
+            Self::Note(note)
+            | Self::Todo(note)
+            | Self::Image(note)
+            | Self::Document(note)
+            | Self::Audio(note)
+            | Self::Video(note)
+            | Self::Ebook(note) => &note.id,
+        }
+    }
+
+    /// Returns whether the entry is pinned to the top of its folder.
+    pub fn pinned(&self) -> bool {
+        match self {
+            Self::Folder(folder) => folder.pinned,
+            Self::Bookmark(bookmark) => bookmark.pinned,
+            Self::Note(note)
+            | Self::Todo(note)
+            | Self::Image(note)
+            | Self::Document(note)
+            | Self::Audio(note)
+            | Self::Video(note)
+            | Self::Ebook(note) => note.pinned,
+        }
+    }
+
+    /// Returns the entry's title, or a folder's name.
+    pub fn title(&self) -> &str {
+        match self {
+            Self::Folder(folder) => &folder.title,
+            Self::Bookmark(bookmark) => &bookmark.title,
+            Self::Note(note)
+            | Self::Todo(note)
+            | Self::Image(note)
+            | Self::Document(note)
+            | Self::Audio(note)
+            | Self::Video(note)
+            | Self::Ebook(note) => &note.title,
+        }
+    }
+}
+
+/// A matching line within a search result.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct LineMatch {
+    pub line: u32,
+    pub text: String,
+}
+
+/// A note matching a search.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct SearchHit {
+    pub id: String,
+    pub title: String,
+    pub matches: Vec<LineMatch>,
+}
+
+/// A todo or a task inside a note.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Task {
+    /// Selector for the task (e.g., "3" for a todo, "2 1" for a task in a note).
+    pub id: String,
+    pub description: String,
+    pub done: bool,
+}
+
+/// A notebook known to nb.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Notebook {
+    pub name: String,
+    pub archived: bool,
+}
+
+/// Yields output lines, skipping blanks and truncation markers.
+pub(crate) fn lines(output: &str) -> impl Iterator<Item = &str> {
+    output
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty() && !output::is_marker(line))
+}
+
+/// Parses `nb list` output.
+///
+/// Lines that are not items, such as nb's "N more" footer, are skipped.
+pub fn parse_entries(output: &str) -> Vec<Entry> {
+    lines(output)
+        .filter_map(|line| ITEM_REGEX.captures(line))
+        .map(|captures| {
+            let mut rest = captures[2].trim();
+            let mut kind = Kind::Note;
+            let mut pinned = false;
+            loop {
+                if let Some((indicator, item_kind)) = INDICATORS
+                    .iter()
+                    .find(|(indicator, _)| rest.starts_with(indicator))
+                {
+                    kind = *item_kind;
+                    rest = rest[indicator.len()..].trim_start();
+                } else if let Some(decoration) = DECORATIONS
+                    .iter()
+                    .find(|decoration| rest.starts_with(*decoration))
+                {
+                    pinned |= *decoration == "📌";
+                    rest = rest[decoration.len()..].trim_start();
+                } else {
+                    break;
+                }
+            }
+            let id = captures[1].to_string();
+            let note = |id: String| Note {
+                id,
+                title: rest.to_string(),
+                pinned,
+            };
+            match kind {
+                Kind::Note => Entry::Note(note(id)),
+                Kind::Todo => Entry::Todo(note(id)),
+                Kind::Image => Entry::Image(note(id)),
+                Kind::Document => Entry::Document(note(id)),
+                Kind::Audio => Entry::Audio(note(id)),
+                Kind::Video => Entry::Video(note(id)),
+                Kind::Ebook => Entry::Ebook(note(id)),
+                Kind::Folder => Entry::Folder(Folder {
+                    id,
+                    title: rest.to_string(),
+                    pinned,
+                }),
+                Kind::Bookmark => {
+                    let (title, site) = match SITE_REGEX.captures(rest) {
+                        Some(site) if !site[1].is_empty() => {
+                            (site[1].to_string(), Some(site[2].to_string()))
+                        }
+                        _ => (rest.to_string(), None),
+                    };
+                    Entry::Bookmark(Bookmark {
+                        id,
+                        title,
+                        site,
+                        pinned,
+                    })
+                }
+            }
+        })
+        .collect()
+}
+
+/// Parses `nb search --list`-style output, grouping match lines under notes.
+pub fn parse_search(output: &str) -> Vec<SearchHit> {
+    let mut hits: Vec<SearchHit> = Vec::new();
+    for line in lines(output) {
+        if let Some(captures) = ITEM_REGEX.captures(line) {
+            hits.push(SearchHit {
+                id: captures[1].to_string(),
+                title: captures[2].trim().to_string(),
+                matches: Vec::new(),
+            });
+        } else if let Some(captures) = MATCH_REGEX.captures(line) {
+            if let (Some(hit), Ok(number)) = (hits.last_mut(), captures[1].parse()) {
+                hit.matches.push(LineMatch {
+                    line: number,
+                    text: captures[2].to_string(),
+                });
+            }
+        }
+    }
+    hits
+}
+
+/// Parses `nb tasks` output.
+pub fn parse_tasks(output: &str) -> Vec<Task> {
+    lines(output)
+        .filter_map(|line| ITEM_REGEX.captures(line))
+        .filter_map(|captures| {
+            let rest = captures[2].trim();
+            let rest = ["✔️", "✅"]
+                .iter()
+                .find_map(|marker| rest.strip_prefix(marker))
+                .unwrap_or(rest)
+                .trim_start();
+            let (done, description) = if let Some(description) = rest.strip_prefix("[ ]") {
+                (false, description)
+            } else if let Some(description) = rest
+                .strip_prefix("[x]")
+                .or_else(|| rest.strip_prefix("[X]"))
+            {
+                (true, description)
+            } else {
+                return None;
+            };
+            Some(Task {
+                id: captures[1].to_string(),
+                description: description.trim().to_string(),
+                done,
+            })
+        })
+        .collect()
+}
+
+/// Parses `nb notebooks` output, one notebook per line.
+pub fn parse_notebooks(output: &str) -> Vec<Notebook> {
+    lines(output)
+        .map(str::trim)
+        .map(|line| match line.strip_suffix("(archived)") {
+            Some(name) => Notebook {
+                name: name.trim().to_string(),
+                archived: true,
+            },
+            None => Notebook {
+                name: line.to_string(),
+                archived: false,
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, parse_entries, parse_notebooks, parse_search, parse_tasks};
+
+    #[test]
+    fn parse_entries_reads_kinds_and_pins() {
+        let output = "[3] 📌 Plan\n[2] 📂 docs\n[1] 🔖 Example (example.com)\n0 more\n";
+        let entries = parse_entries(output);
+        assert_eq!(entries.len(), 3);
+        let Entry::Note(plan) = &entries[0] else {
+            panic!("expected a note: {:?}", entries[0]);
+        };
+        assert_eq!((plan.title.as_str(), plan.pinned), ("Plan", true));
+        assert!(matches!(&entries[1], Entry::Folder(folder) if folder.title == "docs"));
+        let Entry::Bookmark(bookmark) = &entries[2] else {
+            panic!("expected a bookmark: {:?}", entries[2]);
+        };
+        assert_eq!(bookmark.title, "Example");
+        assert_eq!(bookmark.site.as_deref(), Some("example.com"));
+    }
+
+    #[test]
+    fn entries_serialize_with_kind_tag() {
+        let entries = parse_entries("[2] 📂 docs\n");
+        assert_eq!(
+            serde_json::to_value(&entries[0]).unwrap(),
+            serde_json::json!({"kind": "folder", "id": "2", "title": "docs", "pinned": false})
+        );
+    }
+
+    #[test]
+    fn parse_search_groups_matches_under_notes() {
+        let output = "[4] API notes\n------------\n3:Use `GET /items`\n7:items page\n[9] Other\n";
+        let hits = parse_search(output);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].matches.len(), 2);
+        assert_eq!(hits[0].matches[1].line, 7);
+        assert!(hits[1].matches.is_empty());
+    }
+
+    #[test]
+    fn parse_tasks_reads_completion() {
+        let output = "[5] ✔️ [ ] Ship release\n[2 1] [x] Write notes\n";
+        let tasks = parse_tasks(output);
+        assert_eq!(tasks.len(), 2);
+        assert!(!tasks[0].done);
+        assert_eq!(tasks[1].id, "2 1");
+        assert_eq!(tasks[1].description, "Write notes");
+        assert!(tasks[1].done);
+    }
+
+    #[test]
+    fn parse_notebooks_marks_archived() {
+        let notebooks = parse_notebooks("home\nold (archived)\n");
+        assert_eq!(notebooks[0].name, "home");
+        assert!(!notebooks[0].archived);
+        assert_eq!(notebooks[1].name, "old");
+        assert!(notebooks[1].archived);
+    }
+}
@@ -2,24 +2,21 @@
 //!
 //! Handles notebook qualification, escaping, and output parsing.
 
-use std::{path::PathBuf, process::Stdio, sync::LazyLock};
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
 
-use regex::Regex;
 use tokio::process::Command;
+use tokio::sync::{Semaphore, SemaphorePermit};
 
-/// Regex to match ANSI/ISO 2022 escape sequences.
-///
-/// Covers:
-/// - Fe sequences: `ESC [@-Z\-_]` (single byte after ESC)
-/// - CSI sequences: `ESC [ ... m` (SGR colors, cursor control, etc.)
-/// - nF sequences: `ESC [ -/]* [0-~]` (character set designation like `ESC ( B`)
-static ANSI_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"\x1B(?:[@-Z\\-_]|\[[0-?]*[ -/]*[@-~]|[ -/]*[0-~])").unwrap());
-
-/// Strip ANSI escape sequences from text.
-fn strip_ansi(text: &str) -> String {
-    ANSI_REGEX.replace_all(text, "").into_owned()
-}
+use crate::executor::{EnvValue, NbExecutor, ProcessExecutor};
+use crate::model::{self, Entry, Folder, Notebook, SearchHit, Task};
+use crate::password::{self, PasswordCommand};
+use crate::paths;
+use crate::scaffold::Scaffold;
 
 /// Errors from nb CLI invocation.
 #[derive(Debug, thiserror::Error)]
@@ -36,58 +33,296 @@ pub enum NbError {
     Io(#[from] std::io::Error),
 }
 
+/// Output of a git invocation in a notebook repository.
+#[derive(Debug)]
+pub struct GitOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
 /// Client for invoking nb commands.
 #[derive(Clone)]
 pub struct NbClient {
-    /// Default notebook to use if not specified per-command.
-    default_notebook: Option<String>,
+    /// Runs the prepared nb invocations.
+    executor: Arc<dyn NbExecutor>,
+    /// Configured notebook chain (CLI or NB_MCP_NOTEBOOK), if any.
+    notebook_chain: Option<String>,
+    /// How to derive a notebook name from a directory.
+    derivation: NotebookDerivation,
+    /// Default notebooks to use if not specified per-command, in preference
+    /// order. The first existing one wins. Shared between clones so a
+    /// workspace root learned later applies everywhere.
+    default_notebooks: Arc<RwLock<Vec<String>>>,
     /// Automatically create missing notebooks.
     create_notebook: bool,
-    /// Disable Git commit and tag signing for `nb` subprocesses.
+    /// Never create notebooks, even when a call asks for it.
+    strict_notebooks: bool,
+    /// Structure written into notebooks this client creates.
+    scaffold: Option<Arc<Scaffold>>,
+    /// Disable Git commit and tag signing for `nb` and `git` subprocesses.
     disable_git_signing: bool,
+    /// Whether nb syncs with the remote after each write, if set; otherwise
+    /// the executor's environment decides.
+    auto_sync: Option<bool>,
+    /// nb and git invocations taking longer are logged as warnings.
+    slow_call_threshold: Option<Duration>,
+    /// Prints the password nb asks for when reading encrypted notes.
+    password_command: Option<PasswordCommand>,
+    /// Slots for nb and git subprocesses, shared between clones; unlimited
+    /// if `None`.
+    subprocesses: Option<Arc<Semaphore>>,
 }
 
 impl NbClient {
     /// Creates a new nb client.
     ///
     /// CLI notebook argument takes precedence over NB_MCP_NOTEBOOK env var.
-    /// Falls back to a Git-derived notebook name when available.
+    /// Either may be a comma-separated fallback chain (see
+    /// [`parse_notebook_chain`]). Falls back to a notebook name derived from
+    /// the current directory, following `derivation`, when neither is set.
     pub fn new(
         cli_notebook: Option<&str>,
+        derivation: NotebookDerivation,
         create_notebook: bool,
         disable_git_signing: bool,
     ) -> anyhow::Result<Self> {
-        let default_notebook = cli_notebook
+        let notebook_chain = cli_notebook
             .map(String::from)
-            .or_else(|| std::env::var("NB_MCP_NOTEBOOK").ok())
-            .or_else(derive_git_notebook_name);
+            .or_else(|| std::env::var("NB_MCP_NOTEBOOK").ok());
+        let current_dir = std::env::current_dir().ok();
+        let default_notebooks = default_notebooks_in(
+            notebook_chain.as_deref(),
+            derivation,
+            current_dir.as_deref(),
+        );
         Ok(Self {
-            default_notebook,
+            executor: Arc::new(ProcessExecutor::new(disable_git_signing)),
+            notebook_chain,
+            derivation,
+            default_notebooks: Arc::new(RwLock::new(default_notebooks)),
             create_notebook,
+            strict_notebooks: false,
+            scaffold: None,
             disable_git_signing,
+            auto_sync: None,
+            slow_call_threshold: None,
+            password_command: None,
+            subprocesses: None,
         })
     }
 
-    /// Resolves the notebook to use for a command.
-    fn resolve_notebook_name(&self, notebook: Option<&str>) -> Result<String, NbError> {
+    /// Creates a client that runs nb through a custom executor.
+    ///
+    /// The default notebook may be a comma-separated fallback chain; no
+    /// environment or Git derivation is performed.
+    pub fn with_executor(
+        executor: Arc<dyn NbExecutor>,
+        default_notebook: Option<String>,
+        create_notebook: bool,
+    ) -> Self {
+        let default_notebooks = default_notebooks_in(
+            default_notebook.as_deref(),
+            NotebookDerivation::default(),
+            None,
+        );
+        Self {
+            executor,
+            notebook_chain: default_notebook,
+            derivation: NotebookDerivation::default(),
+            default_notebooks: Arc::new(RwLock::new(default_notebooks)),
+            create_notebook,
+            strict_notebooks: false,
+            scaffold: None,
+            disable_git_signing: false,
+            auto_sync: None,
+            slow_call_threshold: None,
+            password_command: None,
+            subprocesses: None,
+        }
+    }
+
+    /// Runs nb with `overrides` applied over the default subprocess
+    /// environment (see [`ProcessExecutor::with_env_overrides`]).
+    ///
+    /// This replaces the executor, so it applies to clients created with
+    /// [`NbClient::new`].
+    pub fn with_nb_env(mut self, overrides: Vec<(String, EnvValue)>) -> Self {
+        self.executor =
+            Arc::new(ProcessExecutor::new(self.disable_git_signing).with_env_overrides(overrides));
+        self
+    }
+
+    /// Sets whether nb syncs with the notebook's remote after each write
+    /// (`NB_AUTO_SYNC`). Without auto-sync, writes stay local until pushed.
+    pub fn with_auto_sync(mut self, auto_sync: bool) -> Self {
+        self.auto_sync = Some(auto_sync);
+        self
+    }
+
+    /// Logs nb and git invocations slower than `threshold` as warnings, with
+    /// their arguments (note content redacted, see [`redacted_args`]).
+    pub fn with_slow_call_threshold(mut self, threshold: Option<Duration>) -> Self {
+        self.slow_call_threshold = threshold;
+        self
+    }
+
+    /// Runs `command` through `sh` for the password of encrypted notes, with
+    /// `{notebook}` replaced by the notebook name (see [`crate::password`]).
+    pub fn with_password_command(mut self, command: Option<String>) -> Self {
+        self.password_command = command.map(PasswordCommand::new);
+        self
+    }
+
+    /// Runs at most `limit` nb and git subprocesses at once; later calls wait
+    /// for a slot. A burst of parallel tool calls would otherwise start
+    /// dozens of shells contending for the notebook's git repository.
+    pub fn with_max_subprocesses(mut self, limit: Option<usize>) -> Self {
+        self.subprocesses = limit
+            .filter(|limit| *limit > 0)
+            .map(|limit| Arc::new(Semaphore::new(limit)));
+        self
+    }
+
+    /// Waits for a subprocess slot, if the number of subprocesses is limited.
+    async fn subprocess_slot(&self) -> Option<SemaphorePermit<'_>> {
+        let semaphore = self.subprocesses.as_ref()?;
+        if semaphore.available_permits() == 0 {
+            tracing::debug!("waiting for a subprocess slot");
+        }
+        // The semaphore is never closed.
+        semaphore.acquire().await.ok()
+    }
+
+    /// Returns the slow-call warning threshold, if enabled.
+    pub fn slow_call_threshold(&self) -> Option<Duration> {
+        self.slow_call_threshold
+    }
+
+    /// Warns when an invocation took longer than the slow-call threshold.
+    fn note_duration(&self, program: &str, args: &[String], started: Instant) {
+        let elapsed = started.elapsed();
+        if self
+            .slow_call_threshold
+            .is_some_and(|threshold| elapsed > threshold)
+        {
+            tracing::warn!(
+                program,
+                ?args,
+                elapsed_ms = elapsed.as_millis() as u64,
+                "slow command"
+            );
+        }
+    }
+
+    /// Forbids creating notebooks, including on calls that request it.
+    pub fn with_strict_notebooks(mut self, strict: bool) -> Self {
+        self.strict_notebooks = strict;
+        self.create_notebook &= !strict;
+        self
+    }
+
+    /// Returns a client that creates missing notebooks only if `allow` is set.
+    ///
+    /// Used for calls that override the configured creation policy. Fails if
+    /// `allow` is requested in strict mode.
+    pub fn with_notebook_creation(&self, allow: bool) -> Result<Self, NbError> {
+        if allow && self.strict_notebooks {
+            return Err(NbError::CommandFailed(
+                "notebook creation is disabled by --strict-notebooks".to_string(),
+            ));
+        }
+        Ok(Self {
+            create_notebook: allow,
+            ..self.clone()
+        })
+    }
+
+    /// Seeds notebooks this client creates with `scaffold`.
+    pub fn with_scaffold(mut self, scaffold: Option<Scaffold>) -> Self {
+        self.scaffold = scaffold.map(Arc::new);
+        self
+    }
+
+    /// Returns the most preferred configured default notebook, if any.
+    pub fn default_notebook(&self) -> Option<String> {
+        self.default_notebook_chain().into_iter().next()
+    }
+
+    /// Re-derives the default notebooks from a workspace root directory.
+    ///
+    /// Used when the MCP client reports its workspace roots, since the server
+    /// process may have been started in an unrelated directory. Keeps the
+    /// current defaults if nothing can be derived from `root`. Returns the
+    /// defaults in effect afterwards.
+    pub fn set_workspace_root(&self, root: &Path) -> Vec<String> {
+        let derived =
+            default_notebooks_in(self.notebook_chain.as_deref(), self.derivation, Some(root));
+        let mut defaults = self
+            .default_notebooks
+            .write()
+            .expect("default notebooks lock poisoned");
+        if !derived.is_empty() {
+            *defaults = derived;
+        }
+        defaults.clone()
+    }
+
+    fn default_notebook_chain(&self) -> Vec<String> {
+        self.default_notebooks
+            .read()
+            .expect("default notebooks lock poisoned")
+            .clone()
+    }
+
+    /// Resolves the notebook to use for a command, creating it if needed.
+    ///
+    /// Without a per-command notebook, the first existing default notebook
+    /// wins; if none exists, the most preferred one is created.
+    pub async fn resolve_notebook(&self, notebook: Option<&str>) -> Result<String, NbError> {
         if let Some(name) = notebook {
+            self.ensure_notebook(name).await?;
             return Ok(name.to_string());
         }
-        if let Some(name) = self.default_notebook.as_deref() {
-            return Ok(name.to_string());
+        let defaults = self.default_notebook_chain();
+        let Some(preferred) = defaults.first() else {
+            return Err(NbError::CommandFailed(
+                "notebook not configured; set --notebook or NB_MCP_NOTEBOOK".to_string(),
+            ));
+        };
+        for name in &defaults {
+            if self.notebook_exists(name).await? {
+                return Ok(name.clone());
+            }
+        }
+        self.create_missing_notebook(preferred).await?;
+        Ok(preferred.clone())
+    }
+
+    /// Returns the first existing default notebook without creating one.
+    pub async fn existing_default_notebook(&self) -> Result<Option<String>, NbError> {
+        for name in self.default_notebook_chain() {
+            if self.notebook_exists(&name).await? {
+                return Ok(Some(name));
+            }
         }
-        Err(NbError::CommandFailed(
-            "notebook not configured; set --notebook or NB_MCP_NOTEBOOK".to_string(),
-        ))
+        Ok(None)
     }
 
-    async fn resolve_notebook(&self, notebook: Option<&str>) -> Result<String, NbError> {
-        let name = self.resolve_notebook_name(notebook)?;
-        self.ensure_notebook(&name).await?;
-        Ok(name)
+    /// Returns whether Git signing is disabled for nb and git subprocesses.
+    pub fn commit_signing_disabled(&self) -> bool {
+        self.disable_git_signing
     }
 
     async fn ensure_notebook(&self, notebook: &str) -> Result<(), NbError> {
+        if self.notebook_exists(notebook).await? {
+            return Ok(());
+        }
+        self.create_missing_notebook(notebook).await
+    }
+
+    /// Returns whether nb knows the notebook.
+    pub async fn notebook_exists(&self, notebook: &str) -> Result<bool, NbError> {
         let show_result = self
             .exec_vec(vec![
                 "notebooks".to_string(),
@@ -103,71 +338,157 @@ impl NbClient {
                         "nb notebooks path output was empty".to_string(),
                     ));
                 }
-                Ok(())
-            }
-            Err(_) => {
-                if !self.create_notebook {
-                    return Err(NbError::CommandFailed(format!(
-                        "notebook not found; run `nb notebooks add {}` or remove \
-                         --no-create-notebook",
-                        notebook
-                    )));
-                }
-                self.exec_vec(vec![
-                    "notebooks".to_string(),
-                    "add".to_string(),
-                    notebook.to_string(),
-                ])
-                .await?;
-                Ok(())
+                Ok(true)
             }
+            Err(_) => Ok(false),
+        }
+    }
+
+    async fn create_missing_notebook(&self, notebook: &str) -> Result<(), NbError> {
+        if self.strict_notebooks {
+            return Err(NbError::CommandFailed(format!(
+                "notebook not found; run `nb notebooks add {}`",
+                notebook
+            )));
+        }
+        if !self.create_notebook {
+            return Err(NbError::CommandFailed(format!(
+                "notebook not found; run `nb notebooks add {}`, pass allow_create: true, \
+                 or remove --no-create-notebook",
+                notebook
+            )));
         }
+        self.exec_vec(vec![
+            "notebooks".to_string(),
+            "add".to_string(),
+            notebook.to_string(),
+        ])
+        .await?;
+        if let Some(scaffold) = &self.scaffold {
+            self.write_scaffold(notebook, scaffold).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes `scaffold` into a newly created notebook and commits it.
+    async fn write_scaffold(&self, notebook: &str, scaffold: &Scaffold) -> Result<(), NbError> {
+        let output = self
+            .exec_vec(vec![
+                "notebooks".to_string(),
+                "show".to_string(),
+                notebook.to_string(),
+                "--path".to_string(),
+            ])
+            .await?;
+        scaffold.write(Path::new(output.trim())).await?;
+        self.exec_vec(vec![
+            format!("{}:git", notebook),
+            "checkpoint".to_string(),
+            "[nb-mcp] Scaffold notebook".to_string(),
+        ])
+        .await?;
+        Ok(())
     }
 
     /// Executes an nb command and returns stdout.
     async fn exec(&self, args: &[&str]) -> Result<String, NbError> {
-        tracing::debug!(?args, "executing nb command");
-        let mut command = Command::new("nb");
+        self.exec_vec(args.iter().map(|a| a.to_string()).collect())
+            .await
+    }
+
+    /// Executes an nb command with dynamic arguments.
+    async fn exec_vec(&self, args: Vec<String>) -> Result<String, NbError> {
+        self.exec_with_input(args, None).await
+    }
+
+    /// Executes an nb command, writing `input` to its standard input.
+    async fn exec_with_input(
+        &self,
+        args: Vec<String>,
+        input: Option<Vec<u8>>,
+    ) -> Result<String, NbError> {
+        // Queueing does not count toward the slow-call threshold.
+        let _slot = self.subprocess_slot().await;
+        let started = Instant::now();
+        let logged = self.slow_call_threshold.map(|_| redacted_args(&args));
+        let env = match self.auto_sync {
+            Some(auto_sync) => {
+                let value = if auto_sync { "1" } else { "0" };
+                vec![("NB_AUTO_SYNC".to_string(), EnvValue::Set(value.to_string()))]
+            }
+            None => Vec::new(),
+        };
+        let result = match (input, env.is_empty()) {
+            (Some(input), _) => self.executor.exec_with_input(args, env, input).await,
+            (None, false) => self.executor.exec_with_env(args, env).await,
+            (None, true) => self.executor.exec(args).await,
+        };
+        if let Some(args) = logged {
+            self.note_duration("nb", &args, started);
+        }
+        result
+    }
+
+    /// Runs git in a notebook repository.
+    ///
+    /// Unlike nb commands, a non-zero exit is not an error: callers inspect
+    /// [`GitOutput::success`] and parse the output (e.g., push rejections).
+    pub async fn git(&self, repository: &Path, args: &[&str]) -> Result<GitOutput, NbError> {
+        tracing::debug!(
+            args = ?redacted_args(args),
+            repository = %repository.display(),
+            "executing git command"
+        );
+        let _slot = self.subprocess_slot().await;
+        let started = Instant::now();
+        let mut command = Command::new("git");
         command
+            .arg("-C")
+            .arg(repository)
             .args(args)
-            .stdin(Stdio::null()) // Prevent TTY hangs
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::null())
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
         if self.disable_git_signing {
             apply_git_signing_env(&mut command);
         }
-        let output = command
-            .spawn()
-            .map_err(|e| {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    NbError::NotFound
-                } else {
-                    NbError::Io(e)
-                }
-            })?
-            .wait_with_output()
-            .await?;
-
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            Ok(strip_ansi(&stdout))
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            // nb sometimes writes errors to stdout
-            let msg = if stderr.is_empty() {
-                strip_ansi(&stdout)
+        let output = command.output().await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                NbError::CommandFailed("git not found in PATH".to_string())
             } else {
-                strip_ansi(&stderr)
-            };
-            Err(NbError::CommandFailed(msg))
+                NbError::Io(e)
+            }
+        })?;
+        tracing::debug!(
+            args = ?redacted_args(args),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            status = ?output.status.code(),
+            "git command finished"
+        );
+        if self.slow_call_threshold.is_some() {
+            self.note_duration("git", &redacted_args(args), started);
         }
+        Ok(GitOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
     }
 
-    /// Executes an nb command with dynamic arguments.
-    async fn exec_vec(&self, args: Vec<String>) -> Result<String, NbError> {
-        let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-        self.exec(&args_ref).await
+    /// Returns the installed nb version (e.g., "7.12.1").
+    pub async fn version(&self) -> Result<String, NbError> {
+        Ok(self.exec(&["--version"]).await?.trim().to_string())
+    }
+
+    /// Returns the installed git version string.
+    pub async fn git_version(&self) -> Result<String, NbError> {
+        let output = self.git(Path::new("."), &["--version"]).await?;
+        if !output.success {
+            return Err(NbError::CommandFailed(output.stderr.trim().to_string()));
+        }
+        Ok(output.stdout.trim().to_string())
     }
 
     /// Returns status information about the resolved notebook.
@@ -183,6 +504,33 @@ impl NbClient {
         self.exec(&["notebooks", "--no-color"]).await
     }
 
+    /// Returns nb's home directory (`NB_DIR`), where it looks for notebooks.
+    pub async fn nb_dir(&self) -> Result<PathBuf, NbError> {
+        let output = self.exec(&["env"]).await?;
+        output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("NB_DIR="))
+            .map(|dir| PathBuf::from(dir.trim_matches('"')))
+            .ok_or_else(|| NbError::CommandFailed("nb env did not report NB_DIR".to_string()))
+    }
+
+    /// Adds a notebook by cloning a remote Git repository.
+    pub async fn clone_notebook(
+        &self,
+        name: &str,
+        url: &str,
+        branch: Option<&str>,
+    ) -> Result<String, NbError> {
+        let mut args = vec![
+            "notebooks".to_string(),
+            "add".to_string(),
+            name.to_string(),
+            url.to_string(),
+        ];
+        args.extend(branch.map(String::from));
+        self.exec_vec(args).await
+    }
+
     /// Returns the path for a notebook.
     pub async fn notebook_path(&self, notebook: Option<&str>) -> Result<PathBuf, NbError> {
         let notebook = self.resolve_notebook(notebook).await?;
@@ -248,12 +596,91 @@ impl NbClient {
         self.exec_vec(args).await
     }
 
+    /// Creates a note at an explicit path (folder and filename) with raw content.
+    pub async fn add_at_path(
+        &self,
+        path: &str,
+        content: &str,
+        notebook: Option<&str>,
+    ) -> Result<String, NbError> {
+        let notebook = self.resolve_notebook(notebook).await?;
+        let selector = format!("{}:{}", notebook, path);
+        self.exec_vec(vec![
+            "add".to_string(),
+            selector,
+            "--content".to_string(),
+            content.to_string(),
+        ])
+        .await
+    }
+
+    /// Commits all pending changes in the notebook repository.
+    ///
+    /// Used after writing files directly into the notebook directory.
+    pub async fn checkpoint(
+        &self,
+        message: &str,
+        notebook: Option<&str>,
+    ) -> Result<String, NbError> {
+        let notebook = self.resolve_notebook(notebook).await?;
+        self.exec_vec(vec![
+            format!("{}:git", notebook),
+            "checkpoint".to_string(),
+            message.to_string(),
+        ])
+        .await
+    }
+
     /// Shows a note's content.
     pub async fn show(&self, id: &str, notebook: Option<&str>) -> Result<String, NbError> {
         let notebook = self.resolve_notebook(notebook).await?;
+        let password = self.password_input(id, &notebook).await?;
         let selector = format!("{}:{}", notebook, id);
-        self.exec_vec(vec!["show".to_string(), selector, "--no-color".to_string()])
-            .await
+        self.exec_with_input(
+            vec!["show".to_string(), selector, "--no-color".to_string()],
+            password,
+        )
+        .await
+    }
+
+    /// Returns the password input for nb when note `id` is encrypted and a
+    /// password command is configured.
+    async fn password_input(&self, id: &str, notebook: &str) -> Result<Option<Vec<u8>>, NbError> {
+        let Some(command) = &self.password_command else {
+            return Ok(None);
+        };
+        let encrypted = password::is_encrypted(id)
+            || password::is_encrypted(&self.show_path(id, Some(notebook)).await?.to_string_lossy());
+        if !encrypted {
+            return Ok(None);
+        }
+        command.input(notebook).await.map(Some)
+    }
+
+    /// Returns a note's title.
+    pub async fn title(&self, id: &str, notebook: Option<&str>) -> Result<String, NbError> {
+        let notebook = self.resolve_notebook(notebook).await?;
+        let selector = format!("{}:{}", notebook, id);
+        let output = self
+            .exec_vec(vec!["show".to_string(), selector, "--title".to_string()])
+            .await?;
+        Ok(output.trim().to_string())
+    }
+
+    /// Returns the filesystem path of a note.
+    pub async fn show_path(&self, id: &str, notebook: Option<&str>) -> Result<PathBuf, NbError> {
+        let notebook = self.resolve_notebook(notebook).await?;
+        let selector = format!("{}:{}", notebook, id);
+        let output = self
+            .exec_vec(vec!["show".to_string(), selector, "--path".to_string()])
+            .await?;
+        let path = output.trim();
+        if path.is_empty() {
+            return Err(NbError::CommandFailed(
+                "nb show path output was empty".to_string(),
+            ));
+        }
+        Ok(PathBuf::from(path))
     }
 
     /// Lists notes in a notebook or folder.
@@ -343,13 +770,17 @@ impl NbClient {
         notebook: Option<&str>,
     ) -> Result<String, NbError> {
         let notebook = self.resolve_notebook(notebook).await?;
+        let password = self.password_input(id, &notebook).await?;
         let selector = format!("{}:{}", notebook, id);
-        self.exec_vec(vec![
-            "edit".to_string(),
-            selector,
-            "--content".to_string(),
-            content.to_string(),
-        ])
+        self.exec_with_input(
+            vec![
+                "edit".to_string(),
+                selector,
+                "--content".to_string(),
+                content.to_string(),
+            ],
+            password,
+        )
         .await
     }
 
@@ -379,6 +810,28 @@ impl NbClient {
         .await
     }
 
+    /// Copies a note into `target_notebook`, keeping its file name.
+    ///
+    /// The copy is imported, so nb commits it to the target notebook; the
+    /// source notebook is left unchanged.
+    pub async fn copy_to(
+        &self,
+        id: &str,
+        target_notebook: &str,
+        folder: Option<&str>,
+        notebook: Option<&str>,
+    ) -> Result<String, NbError> {
+        let path = self.show_path(id, notebook).await?;
+        self.import(
+            &path.to_string_lossy(),
+            folder,
+            None,
+            false,
+            Some(target_notebook),
+        )
+        .await
+    }
+
     /// Creates a todo item.
     pub async fn todo(
         &self,
@@ -386,6 +839,45 @@ impl NbClient {
         tags: &[String],
         folder: Option<&str>,
         notebook: Option<&str>,
+    ) -> Result<String, NbError> {
+        self.todo_with_related(description, tags, folder, &[], notebook)
+            .await
+    }
+
+    /// Creates a todo due on `due` (nb's `--due`, e.g. `2024-05-01`).
+    pub async fn todo_with_due(
+        &self,
+        description: &str,
+        tags: &[String],
+        folder: Option<&str>,
+        due: &str,
+        notebook: Option<&str>,
+    ) -> Result<String, NbError> {
+        self.todo_add(description, tags, folder, &[], Some(due), notebook)
+            .await
+    }
+
+    /// Creates a todo linked to related notes or URLs (`--related`).
+    pub async fn todo_with_related(
+        &self,
+        description: &str,
+        tags: &[String],
+        folder: Option<&str>,
+        related: &[String],
+        notebook: Option<&str>,
+    ) -> Result<String, NbError> {
+        self.todo_add(description, tags, folder, related, None, notebook)
+            .await
+    }
+
+    async fn todo_add(
+        &self,
+        description: &str,
+        tags: &[String],
+        folder: Option<&str>,
+        related: &[String],
+        due: Option<&str>,
+        notebook: Option<&str>,
     ) -> Result<String, NbError> {
         let mut args = Vec::new();
 
@@ -416,6 +908,16 @@ impl NbClient {
             args.push(tag_str);
         }
 
+        for item in related {
+            args.push("--related".to_string());
+            args.push(item.clone());
+        }
+
+        if let Some(due) = due {
+            args.push("--due".to_string());
+            args.push(due.to_string());
+        }
+
         self.exec_vec(args).await
     }
 
@@ -522,6 +1024,93 @@ impl NbClient {
         self.exec_vec(args).await
     }
 
+    /// Lists the pinned items of a folder, in `nb list` format.
+    ///
+    /// nb marks items pinned with `nb pin` with 📌; the other lines are
+    /// dropped.
+    pub async fn pinned(
+        &self,
+        folder: Option<&str>,
+        notebook: Option<&str>,
+    ) -> Result<String, NbError> {
+        let output = self.list(folder, &[], None, notebook).await?;
+        let pinned = model::lines(&output)
+            .filter(|line| {
+                model::parse_entries(line)
+                    .first()
+                    .is_some_and(Entry::pinned)
+            })
+            .collect::<Vec<_>>();
+        Ok(pinned.join("\n"))
+    }
+
+    /// Lists the pinned items of a folder as typed values.
+    pub async fn pinned_entries(
+        &self,
+        folder: Option<&str>,
+        notebook: Option<&str>,
+    ) -> Result<Vec<Entry>, NbError> {
+        let output = self.pinned(folder, notebook).await?;
+        Ok(model::parse_entries(&output))
+    }
+
+    /// Lists notes and other entries as typed values.
+    pub async fn list_entries(
+        &self,
+        folder: Option<&str>,
+        tags: &[String],
+        limit: Option<u32>,
+        notebook: Option<&str>,
+    ) -> Result<Vec<Entry>, NbError> {
+        let output = self.list(folder, tags, limit, notebook).await?;
+        Ok(model::parse_entries(&output))
+    }
+
+    /// Searches notes, returning each matching note with its matching lines.
+    ///
+    /// `query` is passed to nb as is; see [`search_pattern`].
+    pub async fn search_hits(
+        &self,
+        query: &str,
+        tags: &[String],
+        folder: Option<&str>,
+        notebook: Option<&str>,
+    ) -> Result<Vec<SearchHit>, NbError> {
+        let output = self.search(query, tags, folder, notebook).await?;
+        Ok(model::parse_search(&output))
+    }
+
+    /// Lists todos and tasks as typed values.
+    pub async fn task_list(
+        &self,
+        folder: Option<&str>,
+        notebook: Option<&str>,
+    ) -> Result<Vec<Task>, NbError> {
+        let output = self.tasks(folder, notebook).await?;
+        Ok(model::parse_tasks(&output))
+    }
+
+    /// Lists the folders in a notebook or folder as typed values.
+    pub async fn folder_list(
+        &self,
+        parent: Option<&str>,
+        notebook: Option<&str>,
+    ) -> Result<Vec<Folder>, NbError> {
+        let output = self.folders(parent, notebook).await?;
+        Ok(model::parse_entries(&output)
+            .into_iter()
+            .filter_map(|entry| match entry {
+                Entry::Folder(folder) => Some(folder),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Lists available notebooks as typed values.
+    pub async fn notebook_list(&self) -> Result<Vec<Notebook>, NbError> {
+        Ok(model::parse_notebooks(&self.notebooks().await?))
+    }
+
     /// Creates a folder.
     pub async fn mkdir(&self, path: &str, notebook: Option<&str>) -> Result<String, NbError> {
         let notebook = self.resolve_notebook(notebook).await?;
@@ -569,41 +1158,106 @@ impl NbClient {
     }
 }
 
-fn derive_git_notebook_name() -> Option<String> {
-    let current_root = git_rev_parse(&["--show-toplevel"])?;
-    let git_common_dir = git_rev_parse(&["--git-common-dir"])?;
-    let git_common_dir = if git_common_dir.is_relative() {
-        current_root.join(&git_common_dir)
-    } else {
-        git_common_dir
-    };
-    let git_common_dir = git_common_dir.canonicalize().ok()?;
-    let master_root = if git_common_dir.file_name().is_some_and(|n| n == ".git") {
-        git_common_dir.parent()?.to_path_buf()
-    } else {
-        return None;
-    };
-    master_root
-        .file_name()
-        .and_then(|name| name.to_str())
-        .map(|name| name.to_string())
+/// Characters with special meaning in extended regular expressions.
+const REGEX_METACHARACTERS: &str = r".^$*+?()[]{}|\";
+
+/// Returns the pattern passed to `nb search` for `query` in the given mode.
+///
+/// nb always treats the query as an extended regular expression. In the
+/// default `literal` mode its metacharacters are escaped so the text is
+/// matched as typed.
+pub fn search_pattern(query: &str, mode: Option<&str>) -> Result<String, NbError> {
+    match mode {
+        None | Some("literal") => {
+            Ok(query
+                .chars()
+                .fold(String::with_capacity(query.len()), |mut pattern, c| {
+                    if REGEX_METACHARACTERS.contains(c) {
+                        pattern.push('\\');
+                    }
+                    pattern.push(c);
+                    pattern
+                }))
+        }
+        Some("regex") => Ok(query.to_string()),
+        Some(other) => Err(NbError::CommandFailed(format!(
+            "unknown search mode {other:?}; expected literal or regex"
+        ))),
+    }
+}
+
+/// Placeholder in a notebook chain for the Git-derived notebook name.
+const GIT_NOTEBOOK_PLACEHOLDER: &str = "@git";
+
+/// Splits a comma-separated notebook chain into names, in preference order.
+///
+/// The `@git` entry expands to the Git-derived notebook name and is dropped
+/// when none can be derived. Empty and duplicate entries are ignored.
+pub fn parse_notebook_chain(chain: &str, git_name: impl Fn() -> Option<String>) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for entry in chain.split(',').map(str::trim) {
+        let name = match entry {
+            "" => continue,
+            GIT_NOTEBOOK_PLACEHOLDER => match git_name() {
+                Some(name) => name,
+                None => continue,
+            },
+            other => other.to_string(),
+        };
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Expands a configured chain, or derives a single name, relative to `dir`.
+fn default_notebooks_in(
+    chain: Option<&str>,
+    derivation: NotebookDerivation,
+    dir: Option<&Path>,
+) -> Vec<String> {
+    let derive = || dir.and_then(|dir| derive_notebook_name(dir, derivation));
+    match chain {
+        Some(chain) => parse_notebook_chain(chain, derive),
+        None => derive().into_iter().collect(),
+    }
+}
+
+/// How the default notebook name is derived when none is configured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NotebookDerivation {
+    /// Name notebooks `{project}--{worktree}` (as log files are named) so
+    /// each Git worktree gets its own notebook. By default all worktrees of
+    /// a repository share the `{project}` notebook.
+    pub per_worktree: bool,
+    /// Outside a Git repository, name the notebook after the directory
+    /// instead of failing to derive one.
+    pub directory_fallback: bool,
+}
+
+fn derive_notebook_name(dir: &Path, derivation: NotebookDerivation) -> Option<String> {
+    derive_git_notebook_name(dir, derivation).or_else(|| {
+        if derivation.directory_fallback {
+            derive_directory_notebook_name(dir)
+        } else {
+            None
+        }
+    })
+}
+
+fn derive_directory_notebook_name(dir: &Path) -> Option<String> {
+    let name = dir.file_name()?.to_str()?;
+    Some(paths::sanitize_name(name))
 }
 
-fn git_rev_parse(args: &[&str]) -> Option<PathBuf> {
-    let output = std::process::Command::new("git")
-        .args(["rev-parse"])
-        .args(args)
-        .output()
-        .ok()?;
-    if !output.status.success() {
-        return None;
-    }
-    let stdout = String::from_utf8(output.stdout).ok()?;
-    let value = stdout.trim();
-    if value.is_empty() {
-        return None;
-    }
-    Some(PathBuf::from(value))
+fn derive_git_notebook_name(dir: &Path, derivation: NotebookDerivation) -> Option<String> {
+    let checkout = paths::git_checkout(dir)?;
+    if !derivation.per_worktree {
+        return Some(checkout.project);
+    }
+    let worktree = checkout.worktree_root.file_name()?.to_str()?;
+    Some(paths::worktree_name(&checkout.project, worktree))
 }
 
 const GIT_SIGNING_OVERRIDES: [(&str, &str); 2] =
@@ -626,7 +1280,34 @@ fn git_signing_env_vars(start_index: usize) -> Vec<(String, String)> {
     env_vars
 }
 
-fn apply_git_signing_env(command: &mut Command) {
+/// Options whose values may hold note content.
+const CONTENT_OPTIONS: &[&str] = &["--content", "--title", "--comment", "--message", "-m"];
+
+/// Returns `args` for logging, with the values of content options replaced
+/// by their length. Input piped to nb is never logged.
+pub(crate) fn redacted_args<S: AsRef<str>>(args: &[S]) -> Vec<String> {
+    let redact = |value: &str| format!("<{} bytes redacted>", value.len());
+    let mut follows_option = false;
+    args.iter()
+        .map(|arg| {
+            let arg = arg.as_ref();
+            let logged = if follows_option {
+                redact(arg)
+            } else {
+                match arg.split_once('=') {
+                    Some((option, value)) if CONTENT_OPTIONS.contains(&option) => {
+                        format!("{option}={}", redact(value))
+                    }
+                    _ => arg.to_string(),
+                }
+            };
+            follows_option = !follows_option && CONTENT_OPTIONS.contains(&arg);
+            logged
+        })
+        .collect()
+}
+
+pub(crate) fn apply_git_signing_env(command: &mut Command) {
     let start_index = git_config_count(std::env::var("GIT_CONFIG_COUNT").ok().as_deref());
     for (name, value) in git_signing_env_vars(start_index) {
         command.env(name, value);
@@ -637,7 +1318,194 @@ fn apply_git_signing_env(command: &mut Command) {
 mod tests {
     use std::collections::BTreeMap;
 
-    use super::{git_config_count, git_signing_env_vars};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::{
+        NbClient, git_config_count, git_signing_env_vars, parse_notebook_chain, redacted_args,
+        search_pattern,
+    };
+    use crate::executor::{ExecFuture, FakeExecutor, NbExecutor};
+
+    #[test]
+    fn redacted_args_hide_note_content() {
+        assert_eq!(
+            redacted_args(&[
+                "add",
+                "work:",
+                "--title",
+                "Plan",
+                "--content=secret",
+                "--tags",
+                "a"
+            ]),
+            [
+                "add",
+                "work:",
+                "--title",
+                "<4 bytes redacted>",
+                "--content=<6 bytes redacted>",
+                "--tags",
+                "a"
+            ]
+        );
+        assert_eq!(
+            redacted_args(&["commit", "-m", "-m"]),
+            ["commit", "-m", "<2 bytes redacted>"]
+        );
+    }
+
+    #[test]
+    fn search_pattern_escapes_literal_queries() {
+        assert_eq!(
+            search_pattern("fn main() + a?", None).unwrap(),
+            r"fn main\(\) \+ a\?"
+        );
+        assert_eq!(search_pattern("a.*b", Some("regex")).unwrap(), "a.*b");
+        assert!(search_pattern("a", Some("glob")).is_err());
+    }
+
+    #[test]
+    fn parse_notebook_chain_expands_git_placeholder() {
+        let git = || Some("proj".to_string());
+        assert_eq!(
+            parse_notebook_chain("@git, inbox,,proj", git),
+            vec!["proj", "inbox"]
+        );
+        assert_eq!(parse_notebook_chain("@git,inbox", || None), vec!["inbox"]);
+    }
+
+    #[tokio::test]
+    async fn resolve_notebook_prefers_first_existing_in_chain() {
+        let fake = Arc::new(FakeExecutor::new().notebook("inbox", "/notes/inbox"));
+        let nb = NbClient::with_executor(fake, Some("proj,inbox".to_string()), true);
+        assert_eq!(nb.resolve_notebook(None).await.unwrap(), "inbox");
+    }
+
+    #[test]
+    fn set_workspace_root_keeps_defaults_when_nothing_derives() {
+        let nb = NbClient::with_executor(Arc::new(FakeExecutor::new()), Some("inbox".into()), true);
+        let root = std::env::temp_dir();
+        assert_eq!(nb.set_workspace_root(&root), vec!["inbox"]);
+        assert_eq!(nb.default_notebook().as_deref(), Some("inbox"));
+    }
+
+    #[tokio::test]
+    async fn resolve_notebook_creates_preferred_when_none_exist() {
+        let fake = Arc::new(FakeExecutor::new().respond(&["notebooks", "add"], "Added"));
+        let nb = NbClient::with_executor(fake.clone(), Some("proj,inbox".to_string()), true);
+        assert_eq!(nb.resolve_notebook(None).await.unwrap(), "proj");
+        assert_eq!(fake.calls().last().unwrap(), &["notebooks", "add", "proj"]);
+    }
+
+    #[tokio::test]
+    async fn show_pipes_the_password_only_for_encrypted_notes() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["show"], "content")
+                .respond(&["show", "work:3", "--path"], "/notes/work/keys.md.enc\n")
+                .respond(&["show", "work:4", "--path"], "/notes/work/plan.md\n"),
+        );
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), false)
+            .with_password_command(Some("echo pw-{notebook}".to_string()));
+        nb.show("3", None).await.unwrap();
+        nb.show("4", None).await.unwrap();
+        let inputs = fake
+            .calls()
+            .into_iter()
+            .zip(fake.inputs())
+            .filter(|(call, _)| call.last().map(String::as_str) == Some("--no-color"))
+            .map(|(_, input)| input)
+            .collect::<Vec<_>>();
+        assert_eq!(inputs, [Some("pw-work\n".to_string()), None]);
+    }
+
+    #[tokio::test]
+    async fn copy_to_imports_the_note_file_into_the_target() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("scratch", "/notes/scratch")
+                .notebook("team", "/notes/team")
+                .respond(&["show", "scratch:3", "--path"], "/notes/scratch/idea.md\n")
+                .respond(&["team:import"], "Imported"),
+        );
+        let nb = NbClient::with_executor(fake.clone(), Some("scratch".to_string()), false);
+        nb.copy_to("3", "team", Some("ideas"), None).await.unwrap();
+        assert_eq!(
+            fake.calls().last().unwrap(),
+            &["team:import", "/notes/scratch/idea.md", "ideas/"]
+        );
+    }
+
+    #[tokio::test]
+    async fn folder_list_keeps_only_folders() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("inbox", "/notes/inbox")
+                .respond(&["list", "inbox:"], "[2] 📂 docs\n[1] Plan\n"),
+        );
+        let nb = NbClient::with_executor(fake, Some("inbox".to_string()), true);
+        let folders = nb.folder_list(None, None).await.unwrap();
+        assert_eq!(folders.len(), 1);
+        assert_eq!(
+            (folders[0].id.as_str(), folders[0].title.as_str()),
+            ("2", "docs")
+        );
+    }
+
+    #[tokio::test]
+    async fn pinned_keeps_pinned_items() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("inbox", "/notes/inbox")
+                .respond(
+                    &["list", "inbox:docs/"],
+                    "[docs/3] 📌 Roadmap\n[docs/2] Draft\n[docs/1] 📌 🔖 Spec (example.com)\n",
+                ),
+        );
+        let nb = NbClient::with_executor(fake, Some("inbox".to_string()), true);
+        assert_eq!(
+            nb.pinned(Some("docs"), None).await.unwrap(),
+            "[docs/3] 📌 Roadmap\n[docs/1] 📌 🔖 Spec (example.com)"
+        );
+        let entries = nb.pinned_entries(Some("docs"), None).await.unwrap();
+        assert!(matches!(&entries[1], crate::model::Entry::Bookmark(bookmark) if bookmark.pinned));
+    }
+
+    /// Executor recording the most invocations running at once.
+    #[derive(Default)]
+    struct ConcurrencyProbe {
+        running: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl NbExecutor for ConcurrencyProbe {
+        fn exec(&self, _args: Vec<String>) -> ExecFuture<'_> {
+            Box::pin(async move {
+                let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(running, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                self.running.fetch_sub(1, Ordering::SeqCst);
+                Ok(String::new())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn max_subprocesses_queues_excess_calls() {
+        let probe = Arc::new(ConcurrencyProbe::default());
+        let nb = NbClient::with_executor(probe.clone(), None, false).with_max_subprocesses(Some(2));
+        let mut calls = tokio::task::JoinSet::new();
+        for _ in 0..6 {
+            let nb = nb.clone();
+            calls.spawn(async move { nb.notebooks().await });
+        }
+        while let Some(result) = calls.join_next().await {
+            result.unwrap().unwrap();
+        }
+        assert_eq!(probe.peak.load(Ordering::SeqCst), 2);
+    }
 
     #[test]
     fn git_config_count_defaults_to_zero() {
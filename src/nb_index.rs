@@ -0,0 +1,125 @@
+//! Mapping nb item ids to files.
+//!
+//! nb numbers the items of each folder by line in the folder's `.index` file:
+//! line N names the file of item N. Commands that post-process `nb list` or
+//! `nb search` output use this to reach the file behind an `[id]`, and
+//! commands that remove files themselves update the index as nb would.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Resolves item ids printed by nb to file paths, caching `.index` reads.
+pub struct ItemResolver {
+    root: PathBuf,
+    folder_dir: PathBuf,
+    indexes: HashMap<PathBuf, Vec<String>>,
+}
+
+impl ItemResolver {
+    /// Creates a resolver for ids printed while listing `folder` of the
+    /// notebook at `root`.
+    pub fn new(root: &Path, folder: Option<&str>) -> Self {
+        let folder_dir = match folder
+            .map(|f| f.trim_matches('/'))
+            .filter(|f| !f.is_empty())
+        {
+            Some(folder) => root.join(folder),
+            None => root.to_path_buf(),
+        };
+        Self {
+            root: root.to_path_buf(),
+            folder_dir,
+            indexes: HashMap::new(),
+        }
+    }
+
+    /// Returns the file of an item id such as `3`, `docs/3`, or `work:docs/3`.
+    ///
+    /// Ids with a folder part are relative to the notebook root; bare numbers
+    /// are relative to the listed folder.
+    pub fn resolve(&mut self, id: &str) -> Option<PathBuf> {
+        let id = id.split_once(':').map_or(id, |(_, rest)| rest);
+        let (dir, number) = match id.rsplit_once('/') {
+            Some((folder, number)) => (self.root.join(folder), number),
+            None => (self.folder_dir.clone(), id),
+        };
+        let number: usize = number.trim().parse().ok()?;
+        let names = self.indexes.entry(dir.clone()).or_insert_with(|| {
+            std::fs::read_to_string(dir.join(".index"))
+                .unwrap_or_default()
+                .lines()
+                .map(String::from)
+                .collect()
+        });
+        let name = names.get(number.checked_sub(1)?)?;
+        Some(dir.join(name))
+    }
+}
+
+/// Splits an nb item line (`[id] rest`) into its id and remainder.
+pub fn split_item(line: &str) -> Option<(&str, &str)> {
+    let (id, rest) = line.strip_prefix('[')?.split_once(']')?;
+    Some((id, rest.trim()))
+}
+
+/// Removes the file `path` and its entry in its folder's `.index`.
+///
+/// Like `nb delete`, the entry's line is left blank, so the items after it
+/// keep their ids.
+pub fn remove_file(path: &Path) -> std::io::Result<()> {
+    std::fs::remove_file(path)?;
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Ok(());
+    };
+    let index_path = dir.join(".index");
+    let index = match std::fs::read_to_string(&index_path) {
+        Ok(index) => index,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    let name = name.to_string_lossy();
+    let updated = index
+        .lines()
+        .map(|line| if line == name { "" } else { line })
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+    std::fs::write(index_path, updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ItemResolver, remove_file};
+
+    #[test]
+    fn resolve_reads_folder_indexes() {
+        let root = std::env::temp_dir().join(format!("nb-mcp-index-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join(".index"), "a.md\nb.md\n").unwrap();
+        std::fs::write(root.join("docs/.index"), "c.md\n").unwrap();
+        let mut resolver = ItemResolver::new(&root, None);
+        assert_eq!(resolver.resolve("2"), Some(root.join("b.md")));
+        assert_eq!(
+            resolver.resolve("work:docs/1"),
+            Some(root.join("docs/c.md"))
+        );
+        assert_eq!(resolver.resolve("3"), None);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn remove_file_blanks_its_index_line() {
+        let root = std::env::temp_dir().join(format!("nb-mcp-unindex-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join(".index"), "a.md\nb.md\nc.md\n").unwrap();
+        std::fs::write(root.join("b.md"), "# B\n").unwrap();
+        remove_file(&root.join("b.md")).unwrap();
+        assert!(!root.join("b.md").exists());
+        assert_eq!(
+            std::fs::read_to_string(root.join(".index")).unwrap(),
+            "a.md\n\nc.md\n"
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+//! nb version compatibility.
+//!
+//! Older nb releases lack some subcommands and options the server relies on,
+//! and fail with terse "unknown option" errors when given them. The server
+//! probes `nb --version` at startup, warns when the installed release is
+//! older than required, and rejects the affected commands with a clear error.
+
+use std::fmt;
+
+use serde::Serialize;
+
+/// A parsed `major.minor.patch` nb version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NbVersion(pub u32, pub u32, pub u32);
+
+impl fmt::Display for NbVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+impl Serialize for NbVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl NbVersion {
+    /// Parses the first version number in `nb --version` output.
+    ///
+    /// Missing minor or patch components count as zero.
+    pub fn parse(output: &str) -> Option<Self> {
+        let token = output
+            .split_whitespace()
+            .map(|token| token.trim_start_matches('v'))
+            .find(|token| token.starts_with(|c: char| c.is_ascii_digit()))?;
+        let mut parts = token
+            .split(|c: char| !c.is_ascii_digit())
+            .take(3)
+            .map(|part| part.parse::<u32>().ok());
+        let major = parts.next()??;
+        let minor = parts.next().flatten().unwrap_or(0);
+        let patch = parts.next().flatten().unwrap_or(0);
+        Some(Self(major, minor, patch))
+    }
+}
+
+/// Oldest nb release supporting every command the server offers.
+pub const MINIMUM_NB_VERSION: NbVersion = NbVersion(6, 0, 0);
+
+/// Commands wrapping the todo subcommands nb added in the minimum release;
+/// older releases fail them with "unknown option" errors.
+const TODO_COMMANDS: &[&str] = &["todo", "do", "undo", "tasks"];
+
+/// Result of checking the installed nb against the server's requirements.
+#[derive(Debug, Clone, Serialize)]
+pub struct Compatibility {
+    /// Installed version, if it could be parsed.
+    pub version: Option<NbVersion>,
+    pub minimum: NbVersion,
+    /// Whether every command is available.
+    pub supported: bool,
+    /// Commands the installed nb is too old for.
+    pub unavailable_commands: Vec<&'static str>,
+}
+
+impl Compatibility {
+    /// Checks `nb --version` output.
+    ///
+    /// An unparseable version is assumed to be new enough, since nb
+    /// development builds may not report a plain version number.
+    pub fn check(version_output: &str) -> Self {
+        let version = NbVersion::parse(version_output);
+        let supported = version.is_none_or(|version| version >= MINIMUM_NB_VERSION);
+        Self {
+            version,
+            minimum: MINIMUM_NB_VERSION,
+            supported,
+            unavailable_commands: if supported {
+                Vec::new()
+            } else {
+                TODO_COMMANDS.to_vec()
+            },
+        }
+    }
+
+    /// Returns an error message if `command` needs a newer nb.
+    pub fn unavailable(&self, command: &str) -> Option<String> {
+        if !self.unavailable_commands.contains(&command) {
+            return None;
+        }
+        let version = self.version?;
+        Some(format!(
+            "nb.{command} requires nb {} or newer, but nb {version} is installed; \
+             upgrade nb to use it",
+            self.minimum
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Compatibility, NbVersion};
+
+    #[test]
+    fn parse_accepts_common_formats() {
+        assert_eq!(NbVersion::parse("7.12.1\n"), Some(NbVersion(7, 12, 1)));
+        assert_eq!(NbVersion::parse("nb v6.3"), Some(NbVersion(6, 3, 0)));
+        assert_eq!(NbVersion::parse("unknown"), None);
+    }
+
+    #[test]
+    fn check_lists_commands_needing_newer_nb() {
+        let old = Compatibility::check("5.7.8");
+        assert!(!old.supported);
+        assert!(old.unavailable_commands.contains(&"todo"));
+        assert!(
+            old.unavailable("tasks")
+                .unwrap()
+                .contains("requires nb 6.0.0")
+        );
+        let current = Compatibility::check("7.12.1");
+        assert!(current.supported);
+        assert!(current.unavailable("tasks").is_none());
+    }
+}
@@ -0,0 +1,166 @@
+//! In-memory cache of note content.
+//!
+//! Agents re-read the same notes constantly, and each `nb.show` costs two nb
+//! invocations (the path, then the text). Shown notes are kept here, keyed by
+//! notebook and id, with the file's modification time and size. A later show
+//! only checks those on disk and answers from memory when neither changed.
+//! The whole cache is dropped after every call that writes to a notebook, so
+//! ids that now name another note are not served stale; changes made outside
+//! the server are caught by the modification time. The least recently used
+//! notes are evicted beyond a fixed number of entries, and large notes are
+//! not kept at all.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Notes kept at most.
+const CAPACITY: usize = 128;
+
+/// Notes larger than this are not kept.
+const MAX_NOTE_BYTES: usize = 1024 * 1024;
+
+/// A shown note.
+#[derive(Debug, Clone)]
+pub struct CachedNote {
+    /// Text as `nb show` printed it.
+    pub text: String,
+    /// Content hash of the note file.
+    pub hash: String,
+}
+
+#[derive(Debug)]
+struct Entry {
+    notebook: String,
+    id: String,
+    path: PathBuf,
+    modified: SystemTime,
+    len: u64,
+    note: CachedNote,
+}
+
+/// Shown notes, most recently used first.
+#[derive(Debug, Default)]
+pub struct NoteCache {
+    entries: Mutex<VecDeque<Entry>>,
+}
+
+impl NoteCache {
+    /// Returns note `id` of `notebook` if it is cached and its file is
+    /// unchanged.
+    pub async fn get(&self, notebook: &str, id: &str) -> Option<CachedNote> {
+        let path = {
+            let entries = self.entries.lock().expect("note cache lock poisoned");
+            entries
+                .iter()
+                .find(|entry| entry.notebook == notebook && entry.id == id)?
+                .path
+                .clone()
+        };
+        let metadata = tokio::fs::metadata(&path).await.ok();
+        let mut entries = self.entries.lock().expect("note cache lock poisoned");
+        let index = entries
+            .iter()
+            .position(|entry| entry.notebook == notebook && entry.id == id)?;
+        let current = metadata.is_some_and(|metadata| {
+            metadata.modified().ok() == Some(entries[index].modified)
+                && metadata.len() == entries[index].len
+        });
+        let entry = entries.remove(index)?;
+        if !current {
+            return None;
+        }
+        let note = entry.note.clone();
+        entries.push_front(entry);
+        Some(note)
+    }
+
+    /// Caches note `id` of `notebook`, read from `path` when its file had
+    /// `modified` time and `len` bytes.
+    pub fn insert(
+        &self,
+        notebook: &str,
+        id: &str,
+        path: &Path,
+        modified: SystemTime,
+        len: u64,
+        note: CachedNote,
+    ) {
+        if note.text.len() > MAX_NOTE_BYTES {
+            return;
+        }
+        let mut entries = self.entries.lock().expect("note cache lock poisoned");
+        entries.retain(|entry| !(entry.notebook == notebook && entry.id == id));
+        entries.push_front(Entry {
+            notebook: notebook.to_string(),
+            id: id.to_string(),
+            path: path.to_path_buf(),
+            modified,
+            len,
+            note,
+        });
+        entries.truncate(CAPACITY);
+    }
+
+    /// Drops every cached note, e.g. after a write.
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .expect("note cache lock poisoned")
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CAPACITY, CachedNote, NoteCache};
+
+    fn note(text: &str) -> CachedNote {
+        CachedNote {
+            text: text.to_string(),
+            hash: "hash".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn get_serves_unchanged_files_only() {
+        let path = std::env::temp_dir().join(format!("nb-mcp-cache-{}.md", std::process::id()));
+        std::fs::write(&path, "# Plan\n").unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+        let cache = NoteCache::default();
+        cache.insert(
+            "work",
+            "1",
+            &path,
+            metadata.modified().unwrap(),
+            metadata.len(),
+            note("# Plan\n"),
+        );
+        assert_eq!(cache.get("work", "1").await.unwrap().text, "# Plan\n");
+        assert!(cache.get("home", "1").await.is_none());
+
+        std::fs::write(&path, "# Plan\n\nMore.\n").unwrap();
+        assert!(cache.get("work", "1").await.is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn insert_evicts_the_least_recently_used() {
+        let path = std::env::temp_dir().join(format!("nb-mcp-cache-lru-{}.md", std::process::id()));
+        std::fs::write(&path, "x").unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+        let modified = metadata.modified().unwrap();
+        let cache = NoteCache::default();
+        for id in 0..CAPACITY {
+            cache.insert("work", &id.to_string(), &path, modified, 1, note("x"));
+        }
+        assert!(cache.get("work", "0").await.is_some());
+        cache.insert("work", "new", &path, modified, 1, note("x"));
+        assert!(cache.get("work", "0").await.is_some());
+        assert!(cache.get("work", "1").await.is_none());
+        cache.clear();
+        assert!(cache.get("work", "0").await.is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+}
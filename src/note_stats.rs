@@ -0,0 +1,153 @@
+//! Size and reading-time statistics for notes.
+//!
+//! `nb.note_stats` measures one note, or every note of a folder or notebook,
+//! from the files themselves: words, characters, headings, links, and an
+//! estimated reading time. Frontmatter is not counted, and Markdown syntax
+//! is not counted as words.
+
+use std::sync::LazyLock;
+
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::archive;
+use crate::args::NoteStatsArgs;
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// Reading speed used for `reading_minutes`.
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Matches nb's `[[wiki-style]]` links, which Markdown parsers do not see.
+static WIKI_LINK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[\[[^\[\]\n]+\]\]").unwrap());
+
+/// Statistics of a note or of several notes combined.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct Stats {
+    pub words: usize,
+    pub characters: usize,
+    pub headings: usize,
+    /// Markdown, autolinks, and `[[wiki]]` links; images are not counted.
+    pub links: usize,
+    /// Estimated at 200 words per minute, rounded up.
+    pub reading_minutes: usize,
+}
+
+/// Statistics of one note.
+#[derive(Debug, Serialize)]
+pub struct NoteStats {
+    /// Path relative to the notebook root, or the `id` for a single note.
+    pub note: String,
+    #[serde(flatten)]
+    pub stats: Stats,
+}
+
+/// Result of `nb.note_stats`.
+#[derive(Debug, Serialize)]
+pub struct StatsReport {
+    pub notes: Vec<NoteStats>,
+    /// Sums over `notes`.
+    pub total: Stats,
+}
+
+/// Measures note `args.id`, or all notes of `args.folder` (the whole
+/// notebook without either).
+pub async fn note_stats(nb: &NbClient, args: &NoteStatsArgs) -> Result<StatsReport, NbError> {
+    let notebook = args.notebook.as_deref();
+    let notes = match &args.id {
+        Some(id) => {
+            let content = nb.show(id, notebook).await?;
+            vec![NoteStats {
+                note: id.clone(),
+                stats: stats(&content),
+            }]
+        }
+        None => {
+            let root = nb.notebook_path(notebook).await?;
+            let folder = args.folder.clone();
+            let hide_archived = archive::hides(&root, folder.as_deref(), args.include_archived);
+            let files =
+                tokio::task::spawn_blocking(move || notes::read_notes(&root, folder.as_deref()))
+                    .await
+                    .map_err(|err| {
+                        NbError::CommandFailed(format!("note stats task failed: {err}"))
+                    })??;
+            files
+                .into_iter()
+                .filter(|note| {
+                    !(hide_archived && note.path.split('/').next() == Some(archive::FOLDER))
+                })
+                .map(|note| NoteStats {
+                    stats: stats(&note.content),
+                    note: note.path,
+                })
+                .collect()
+        }
+    };
+    let mut total = Stats::default();
+    for note in &notes {
+        total.words += note.stats.words;
+        total.characters += note.stats.characters;
+        total.headings += note.stats.headings;
+        total.links += note.stats.links;
+    }
+    total.reading_minutes = reading_minutes(total.words);
+    Ok(StatsReport { notes, total })
+}
+
+/// Measures Markdown `content`.
+pub fn stats(content: &str) -> Stats {
+    let (_, body) = notes::split_frontmatter(content);
+    let mut stats = Stats {
+        characters: body.chars().count(),
+        links: WIKI_LINK_REGEX.find_iter(body).count(),
+        ..Stats::default()
+    };
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    // Text arrives in fragments that may split words, so it is joined
+    // first, with block boundaries and line breaks as spaces.
+    let mut text = String::new();
+    for event in Parser::new_ext(body, options) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => stats.headings += 1,
+            Event::Start(Tag::Link { .. }) => stats.links += 1,
+            Event::Text(fragment) | Event::Code(fragment) => text.push_str(&fragment),
+            Event::End(_) | Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    stats.words = text
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count();
+    stats.reading_minutes = reading_minutes(stats.words);
+    stats
+}
+
+fn reading_minutes(words: usize) -> usize {
+    words.div_ceil(WORDS_PER_MINUTE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stats;
+
+    #[test]
+    fn stats_counts_words_headings_and_links() {
+        let note = "---\ntitle: Plan\n---\n# Plan\n\nShip **v2** by [Friday](https://example.com).\n\n\
+                    ## Notes\n\nSee [[ideas]] and <https://nb.example>. ![chart](chart.png)\n";
+        let stats = stats(note);
+        assert_eq!(stats.headings, 2);
+        assert_eq!(stats.links, 3);
+        // Plan / Ship v2 by Friday. / Notes / See [[ideas]] and https://nb.example. chart
+        assert_eq!(stats.words, 1 + 4 + 1 + 5);
+        assert_eq!(stats.reading_minutes, 1);
+        assert_eq!(
+            stats.characters,
+            note.chars().count() - "---\ntitle: Plan\n---\n".len()
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Attaching an existing notes repository as a notebook.
+//!
+//! `nb.notebook_clone` runs `nb notebooks add <name> <url>`, then checks that
+//! the clone is a usable Git repository before reporting it, so an agent can
+//! start working in the notebook right away. Clients that send a progress
+//! token receive a notification as each step starts, and a last one with
+//! `progress` equal to `total` when the clone ends, failed or not.
+
+use rmcp::{
+    Peer, RoleServer,
+    model::{ProgressNotificationParam, ProgressToken},
+};
+use serde::Serialize;
+
+use crate::args::NotebookCloneArgs;
+use crate::nb::{NbClient, NbError};
+
+/// Number of progress steps reported for a clone.
+const STEPS: f64 = 2.0;
+
+/// Result of cloning a notebook.
+#[derive(Debug, Serialize)]
+pub struct ClonedNotebook {
+    pub notebook: String,
+    pub path: String,
+    pub url: String,
+    /// Checked-out branch.
+    pub branch: String,
+    /// Commit at the head of the branch.
+    pub head: String,
+    /// Number of files tracked in the repository.
+    pub files: usize,
+}
+
+/// Sends progress notifications for one request, if the client asked for them.
+struct Progress<'a> {
+    peer: Option<&'a Peer<RoleServer>>,
+    token: Option<&'a ProgressToken>,
+}
+
+impl Progress<'_> {
+    async fn step(&self, step: f64, message: String) {
+        let (Some(peer), Some(token)) = (self.peer, self.token) else {
+            return;
+        };
+        let notification = ProgressNotificationParam {
+            progress_token: token.clone(),
+            progress: step,
+            total: Some(STEPS),
+            message: Some(message),
+        };
+        if let Err(err) = peer.notify_progress(notification).await {
+            tracing::debug!(error = %err, "failed to send progress notification");
+        }
+    }
+}
+
+/// Clones a remote repository as a new notebook and verifies it.
+pub async fn clone(
+    nb: &NbClient,
+    peer: Option<&Peer<RoleServer>>,
+    progress_token: Option<&ProgressToken>,
+    args: &NotebookCloneArgs,
+) -> Result<ClonedNotebook, NbError> {
+    let progress = Progress {
+        peer,
+        token: progress_token,
+    };
+    let result = clone_and_verify(nb, &progress, args).await;
+    let message = match &result {
+        Ok(cloned) => format!("Notebook {} is ready", cloned.notebook),
+        Err(err) => format!("Clone failed: {err}"),
+    };
+    progress.step(STEPS, message).await;
+    result
+}
+
+async fn clone_and_verify(
+    nb: &NbClient,
+    progress: &Progress<'_>,
+    args: &NotebookCloneArgs,
+) -> Result<ClonedNotebook, NbError> {
+    let url = args.url.trim();
+    let name = match args.name.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => name_from_url(url).ok_or_else(|| {
+            NbError::CommandFailed(format!(
+                "cannot derive a notebook name from {url:?}; pass name"
+            ))
+        })?,
+    };
+    if nb.notebook_exists(&name).await? {
+        return Err(NbError::CommandFailed(format!(
+            "notebook {name} already exists"
+        )));
+    }
+
+    progress
+        .step(0.0, format!("Cloning {url} into {name}"))
+        .await;
+    nb.clone_notebook(&name, url, args.branch.as_deref())
+        .await?;
+
+    progress.step(1.0, format!("Verifying {name}")).await;
+    let path = nb.notebook_path(Some(&name)).await?;
+    let head = nb.git(&path, &["rev-parse", "HEAD"]).await?;
+    if !head.success {
+        return Err(NbError::CommandFailed(format!(
+            "cloned notebook {name} has no commits: {}",
+            head.stderr.trim()
+        )));
+    }
+    let branch = nb.git(&path, &["branch", "--show-current"]).await?;
+    let files = nb.git(&path, &["ls-files"]).await?;
+    Ok(ClonedNotebook {
+        notebook: name,
+        path: path.display().to_string(),
+        url: url.to_string(),
+        branch: branch.stdout.trim().to_string(),
+        head: head.stdout.trim().to_string(),
+        files: files.stdout.lines().count(),
+    })
+}
+
+/// Derives a notebook name from the last path segment of a Git URL.
+///
+/// Handles `https://host/user/notes.git`, `git@host:user/notes.git`, and
+/// local paths.
+fn name_from_url(url: &str) -> Option<String> {
+    let last = url
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()?
+        .trim_end_matches(".git");
+    let name: String = last
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    (!name.trim_matches('-').is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::name_from_url;
+
+    #[test]
+    fn name_from_url_uses_repository_name() {
+        assert_eq!(
+            name_from_url("https://github.com/me/notes.git").as_deref(),
+            Some("notes")
+        );
+        assert_eq!(
+            name_from_url("git@github.com:me/work.notes.git").as_deref(),
+            Some("work-notes")
+        );
+        assert_eq!(
+            name_from_url("/srv/git/journal/").as_deref(),
+            Some("journal")
+        );
+        assert_eq!(name_from_url("https://host/.git"), None);
+    }
+}
@@ -0,0 +1,223 @@
+//! Serving an existing directory of notes as a notebook.
+//!
+//! nb only knows notebooks under its home directory (`NB_DIR`). With
+//! `--notebook-path <DIR>`, the server reuses the notebook whose path is the
+//! directory, or registers one: it makes the directory a Git repository if
+//! it is not one yet and links it into `NB_DIR` under the directory's name.
+//! The notes stay where they are.
+
+use std::path::Path;
+
+use anyhow::Context;
+use tracing::{info, warn};
+
+use crate::Config;
+use crate::nb::{NbClient, NbError};
+
+/// Registers `config.notebook_path`, if set, and makes it the default
+/// notebook.
+///
+/// Without nb, the path is left unregistered when a filesystem-only fallback
+/// is configured, so the fallback can take over.
+pub async fn apply(config: &mut Config) -> anyhow::Result<()> {
+    let Some(dir) = config.notebook_path.clone() else {
+        return Ok(());
+    };
+    let nb = NbClient::new(
+        None,
+        config.notebook_derivation,
+        false,
+        config.commit_signing_disabled,
+    )?
+    .with_nb_env(config.nb_env.clone());
+    match register(&nb, &dir).await {
+        Ok(name) => {
+            if let Some(notebook) = &config.notebook {
+                warn!(%notebook, path = %dir.display(), "--notebook-path replaces the configured notebook");
+            }
+            config.notebook = Some(name);
+            Ok(())
+        }
+        Err(NbError::NotFound) if config.nb_fallback_dir.is_some() => Ok(()),
+        Err(err) => {
+            Err(err).with_context(|| format!("failed to serve {} as a notebook", dir.display()))
+        }
+    }
+}
+
+/// Returns the name of the notebook serving `dir`, registering one if needed.
+pub async fn register(nb: &NbClient, dir: &Path) -> Result<String, NbError> {
+    let dir = dir
+        .canonicalize()
+        .map_err(|err| NbError::CommandFailed(format!("notebook path {}: {err}", dir.display())))?;
+    if !dir.is_dir() {
+        return Err(NbError::CommandFailed(format!(
+            "notebook path {} is not a directory",
+            dir.display()
+        )));
+    }
+    for notebook in nb.notebook_list().await? {
+        let path = nb.notebook_path(Some(&notebook.name)).await?;
+        if path.canonicalize().is_ok_and(|path| path == dir) {
+            return Ok(notebook.name);
+        }
+    }
+
+    let name = notebook_name(&dir)?;
+    if nb.notebook_exists(&name).await? {
+        return Err(NbError::CommandFailed(format!(
+            "notebook {name} already exists at another path; rename {} or the notebook",
+            dir.display()
+        )));
+    }
+    if !dir.join(".git").exists() {
+        init_repository(nb, &dir).await?;
+    }
+    let link = nb.nb_dir().await?.join(&name);
+    link_notebook(&dir, &link)?;
+    if !nb.notebook_exists(&name).await? {
+        return Err(NbError::CommandFailed(format!(
+            "nb does not list {} as notebook {name}",
+            link.display()
+        )));
+    }
+    info!(notebook = %name, path = %dir.display(), "registered notebook directory");
+    Ok(name)
+}
+
+/// Names the notebook after the directory, replacing characters nb rejects.
+fn notebook_name(dir: &Path) -> Result<String, NbError> {
+    let name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    let name = name.trim_matches(['-', '.']);
+    if name.is_empty() {
+        return Err(NbError::CommandFailed(format!(
+            "cannot name a notebook after {}",
+            dir.display()
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// Makes `dir` a Git repository with its current files committed.
+async fn init_repository(nb: &NbClient, dir: &Path) -> Result<(), NbError> {
+    let steps: [&[&str]; 3] = [
+        &["init", "--quiet"],
+        &["add", "--all"],
+        &[
+            "commit",
+            "--quiet",
+            "--allow-empty",
+            "--message",
+            "[nb-mcp] Initialize notebook",
+        ],
+    ];
+    for args in steps {
+        let output = nb.git(dir, args).await?;
+        if !output.success {
+            return Err(NbError::CommandFailed(format!(
+                "git {} failed in {}: {}",
+                args[0],
+                dir.display(),
+                output.stderr.trim()
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn link_notebook(dir: &Path, link: &Path) -> Result<(), NbError> {
+    std::os::unix::fs::symlink(dir, link)
+        .map_err(|err| NbError::CommandFailed(format!("failed to link {}: {err}", link.display())))
+}
+
+#[cfg(not(unix))]
+fn link_notebook(_dir: &Path, link: &Path) -> Result<(), NbError> {
+    Err(NbError::CommandFailed(format!(
+        "cannot link {}: notebook paths need symbolic links",
+        link.display()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{notebook_name, register};
+    use crate::{FakeExecutor, NbClient};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-dir-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    #[tokio::test]
+    async fn register_reuses_notebook_at_path() {
+        let notes = temp_dir("reuse");
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .respond(&["notebooks", "--no-color"], "home\nnotes\n")
+                .notebook("home", "/nonexistent/home")
+                .notebook("notes", notes.to_str().unwrap()),
+        );
+        let nb = NbClient::with_executor(fake, None, false);
+        assert_eq!(register(&nb, &notes).await.unwrap(), "notes");
+        std::fs::remove_dir_all(&notes).unwrap();
+    }
+
+    #[tokio::test]
+    async fn register_links_repository_into_nb_dir() {
+        let root = temp_dir("link");
+        let notes = root.join("My Notes");
+        std::fs::create_dir_all(notes.join(".git")).unwrap();
+        let nb_dir = root.join("nb");
+        std::fs::create_dir_all(&nb_dir).unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .respond(&["notebooks", "--no-color"], "home\n")
+                .notebook("home", "/nonexistent/home")
+                .respond(&["env"], &format!("NB_DIR={}\n", nb_dir.display())),
+        );
+        let nb = NbClient::with_executor(fake, None, false);
+        // The link is only listed once created.
+        let error = register(&nb, &notes).await.unwrap_err();
+        assert!(error.to_string().contains("does not list"));
+        let link = nb_dir.join("My-Notes");
+        assert_eq!(std::fs::read_link(&link).unwrap(), notes);
+
+        std::fs::remove_file(&link).unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .respond(&["notebooks", "--no-color"], "home\n")
+                .notebook("home", "/nonexistent/home")
+                .respond(&["env"], &format!("NB_DIR={}\n", nb_dir.display()))
+                .notebook("My-Notes", link.to_str().unwrap()),
+        );
+        let nb = NbClient::with_executor(fake, None, false);
+        // An existing notebook of the same name at another path is refused.
+        let error = register(&nb, &notes).await.unwrap_err();
+        assert!(error.to_string().contains("already exists"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn notebook_name_replaces_invalid_characters() {
+        let name = notebook_name(std::path::Path::new("/home/me/Work notes:2024")).unwrap();
+        assert_eq!(name, "Work-notes-2024");
+        assert!(notebook_name(std::path::Path::new("/")).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+//! Advisory lock serializing writes to a notebook across processes.
+//!
+//! Several nb-mcp instances (one per IDE window or worktree) may serve the
+//! same notebook, and concurrent nb commits can corrupt its git index. Each
+//! mutating call therefore holds `nb-mcp.lock` in the notebook's git
+//! directory, created exclusively and removed when the call ends. The holder
+//! touches the file while it holds the lock, however long the call runs. A
+//! lock is taken over when its holder is no longer running (checked on
+//! Linux) or it was not touched for [`STALE_AFTER`], so a crashed instance
+//! cannot block writes forever. Takeover renames the stale file away, so of
+//! several waiters only one takes it over, and a holder only ever removes a
+//! lock file carrying its own token.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use chrono::Local;
+use tracing::warn;
+
+use crate::nb::NbError;
+
+/// Name of the lock file inside the git directory.
+pub const FILE_NAME: &str = "nb-mcp.lock";
+
+/// Time without a touch after which a lock is considered abandoned.
+pub const STALE_AFTER: Duration = Duration::from_secs(600);
+
+/// How often a holder touches its lock file.
+const TOUCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a call waits for another instance to finish.
+pub const WAIT: Duration = Duration::from_secs(30);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Lock tokens handed out by this process.
+static TOKENS: AtomicU64 = AtomicU64::new(0);
+
+/// A held lock, released on drop.
+#[derive(Debug)]
+pub struct NotebookLock {
+    path: PathBuf,
+    /// Identifies this holder's lock file.
+    token: String,
+    toucher: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for NotebookLock {
+    fn drop(&mut self) {
+        self.toucher.abort();
+        // A lock taken over from us belongs to its new holder.
+        if !holds(&self.path, &self.token) {
+            warn!(path = %self.path.display(), "notebook lock was taken over");
+            return;
+        }
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            warn!(path = %self.path.display(), error = %err, "failed to release notebook lock");
+        }
+    }
+}
+
+/// Locks the notebook at `root`, waiting up to `wait` for another holder.
+///
+/// Returns `None` when the notebook is not a git repository.
+pub async fn acquire(root: &Path, wait: Duration) -> Result<Option<NotebookLock>, NbError> {
+    let Some(git_dir) = git_dir(root) else {
+        return Ok(None);
+    };
+    let path = git_dir.join(FILE_NAME);
+    let deadline = tokio::time::Instant::now() + wait;
+    let token = format!(
+        "{}-{}-{}",
+        std::process::id(),
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+        TOKENS.fetch_add(1, Ordering::Relaxed)
+    );
+    loop {
+        match try_create(&path, &token) {
+            Ok(()) => {
+                let toucher = tokio::spawn(touch(path.clone(), token.clone()));
+                return Ok(Some(NotebookLock {
+                    path,
+                    token,
+                    toucher,
+                }));
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(err.into()),
+        }
+        let holder = std::fs::read_to_string(&path).unwrap_or_default();
+        if is_stale(&path, &holder) {
+            warn!(path = %path.display(), holder = holder.trim(), "taking over stale notebook lock");
+            take_over(&path, &holder, &token);
+            continue;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(NbError::CommandFailed(format!(
+                "notebook is locked by another nb-mcp instance ({}); retry when it finishes, \
+                 or remove {} if no instance is running",
+                holder.trim().replace('\n', ", "),
+                path.display()
+            )));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn try_create(path: &Path, token: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    write!(
+        file,
+        "pid {}\ntoken {token}\nacquired {}\n",
+        std::process::id(),
+        Local::now().to_rfc3339()
+    )
+}
+
+/// Returns true if the lock file at `path` carries `token`.
+fn holds(path: &Path, token: &str) -> bool {
+    std::fs::read_to_string(path)
+        .is_ok_and(|holder| holder.lines().any(|line| line == format!("token {token}")))
+}
+
+/// Touches the lock file while it is ours, so it never looks abandoned.
+async fn touch(path: PathBuf, token: String) {
+    loop {
+        tokio::time::sleep(TOUCH_INTERVAL).await;
+        if !holds(&path, &token) {
+            return;
+        }
+        let touched = std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+        if let Err(err) = touched {
+            warn!(path = %path.display(), error = %err, "failed to touch notebook lock");
+        }
+    }
+}
+
+/// Moves the stale lock `holder` out of the way.
+///
+/// Renaming is atomic, so when several waiters find the same stale lock only
+/// one moves it. A waiter that finds it moved a fresh lock instead (taken
+/// after another waiter's takeover) puts that lock back.
+fn take_over(path: &Path, holder: &str, token: &str) {
+    let moved = path.with_file_name(format!("{FILE_NAME}.stale-{token}"));
+    if std::fs::rename(path, &moved).is_err() {
+        return;
+    }
+    if std::fs::read_to_string(&moved).is_ok_and(|moved| moved != holder) {
+        // Fails, keeping the newer lock in place, if yet another waiter
+        // already created one.
+        let _ = std::fs::hard_link(&moved, path);
+    }
+    let _ = std::fs::remove_file(&moved);
+}
+
+/// Returns the git directory of a repository, following `.git` files.
+fn git_dir(root: &Path) -> Option<PathBuf> {
+    let dot_git = root.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let text = std::fs::read_to_string(&dot_git).ok()?;
+    let target = text.trim().strip_prefix("gitdir:")?.trim();
+    Some(root.join(target)).filter(|dir| dir.is_dir())
+}
+
+fn is_stale(path: &Path, holder: &str) -> bool {
+    let age = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    if age.is_some_and(|age| age > STALE_AFTER) {
+        return true;
+    }
+    let pid = holder
+        .lines()
+        .find_map(|line| line.strip_prefix("pid "))
+        .and_then(|pid| pid.trim().parse::<u32>().ok());
+    pid.is_some_and(|pid| !is_running(pid))
+}
+
+#[cfg(target_os = "linux")]
+fn is_running(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{FILE_NAME, STALE_AFTER, acquire, take_over};
+
+    #[tokio::test]
+    async fn acquire_waits_for_holder_and_takes_over_stale_locks() {
+        let root = std::env::temp_dir().join(format!("nb-mcp-lock-{}", std::process::id()));
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        let path = root.join(".git").join(FILE_NAME);
+
+        let lock = acquire(&root, Duration::ZERO).await.unwrap().unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with(&format!("pid {}\n", std::process::id())));
+        let error = acquire(&root, Duration::from_millis(150))
+            .await
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("locked by another nb-mcp instance")
+        );
+        drop(lock);
+        assert!(!path.exists());
+
+        std::fs::write(&path, "pid 1\n").unwrap();
+        let old = SystemTime::now() - STALE_AFTER - Duration::from_secs(1);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let lock = acquire(&root, Duration::ZERO).await.unwrap();
+        assert!(lock.is_some());
+        drop(lock);
+
+        assert!(
+            acquire(&root.join("missing"), Duration::ZERO)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn locks_taken_over_are_left_to_their_new_holder() {
+        let root = std::env::temp_dir().join(format!("nb-mcp-lock-over-{}", std::process::id()));
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        let path = root.join(".git").join(FILE_NAME);
+
+        let lock = acquire(&root, Duration::ZERO).await.unwrap().unwrap();
+        std::fs::write(&path, "pid 1\ntoken newer\n").unwrap();
+        drop(lock);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "pid 1\ntoken newer\n"
+        );
+
+        // A waiter that judged an older lock stale keeps the fresh one.
+        take_over(&path, "pid 1\ntoken older\n", "waiter");
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "pid 1\ntoken newer\n"
+        );
+        take_over(&path, "pid 1\ntoken newer\n", "waiter");
+        assert!(!path.exists());
+        assert_eq!(std::fs::read_dir(root.join(".git")).unwrap().count(), 0);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
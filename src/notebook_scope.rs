@@ -0,0 +1,132 @@
+//! Serving several notebooks from one process.
+//!
+//! By default a server is tied to one default notebook, so each project needs
+//! its own process. With a set of served notebooks configured, every call
+//! must name one of them in its `notebook` argument, unless the client chose
+//! a session default when it initialized:
+//!
+//! ```json
+//! {"capabilities": {"experimental": {"nb-mcp": {"notebook": "work"}}}}
+//! ```
+//!
+//! Calls naming a notebook outside the set are rejected, so one client
+//! cannot reach another project's notes through the shared server.
+//!
+//! Any server also accepts a session notebook, from that capability or from
+//! `nb.use_notebook` later on, which calls naming no notebook then use
+//! instead of the process-wide default. Over stdio the session is the server
+//! process.
+
+use rmcp::model::ClientCapabilities;
+
+/// Experimental capability key under which clients pass session settings.
+pub const CAPABILITY_KEY: &str = "nb-mcp";
+
+/// Subcommands that do not act on a single notebook.
+const UNSCOPED_COMMANDS: &[&str] = &["notebooks", "notebook_clone", "use_folder", "use_notebook"];
+
+/// Returns the session notebook a client requested at initialize, if any.
+pub fn requested_notebook(capabilities: &ClientCapabilities) -> Option<String> {
+    capabilities
+        .experimental
+        .as_ref()?
+        .get(CAPABILITY_KEY)?
+        .get("notebook")?
+        .as_str()
+        .map(String::from)
+}
+
+/// Returns why `notebook` may not be used, if the server is scoped to a set
+/// of `served` notebooks that does not include it.
+pub fn check_served(served: &[String], notebook: &str) -> Result<(), String> {
+    if served.is_empty() || served.iter().any(|served| served == notebook) {
+        return Ok(());
+    }
+    Err(format!(
+        "notebook {notebook} is not served here; use one of: {}",
+        served.join(", ")
+    ))
+}
+
+/// Checks a call's notebook against the served set, if any, filling in the
+/// session default when the call names none.
+///
+/// Returns the arguments to run the call with, or why the call is refused.
+pub fn scope_args(
+    served: &[String],
+    session_default: Option<&str>,
+    subcommand: &str,
+    mut args: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    if UNSCOPED_COMMANDS.contains(&subcommand) {
+        return Ok(args);
+    }
+    let requested = args.get("notebook").and_then(|value| value.as_str());
+    let notebook = match requested.or(session_default) {
+        Some(notebook) => notebook.to_string(),
+        None if served.is_empty() => return Ok(args),
+        None => {
+            return Err(format!(
+                "this server serves several notebooks; pass notebook (one of: {})",
+                served.join(", ")
+            ));
+        }
+    };
+    if let Some(target) = args.get("target_notebook").and_then(|value| value.as_str()) {
+        check_served(served, target)?;
+    }
+    check_served(served, &notebook)?;
+    if requested.is_none() {
+        if args.is_null() {
+            args = serde_json::json!({});
+        }
+        if let Some(object) = args.as_object_mut() {
+            object.insert("notebook".to_string(), notebook.into());
+        }
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::scope_args;
+
+    #[test]
+    fn scope_args_requires_a_served_notebook() {
+        let served = vec!["work".to_string(), "home".to_string()];
+        assert_eq!(
+            scope_args(
+                &served,
+                None,
+                "show",
+                json!({"id": "1", "notebook": "home"})
+            )
+            .unwrap(),
+            json!({"id": "1", "notebook": "home"})
+        );
+        assert_eq!(
+            scope_args(&served, Some("work"), "list", serde_json::Value::Null).unwrap(),
+            json!({"notebook": "work"})
+        );
+        assert!(scope_args(&served, None, "list", json!({})).is_err());
+        assert!(scope_args(&served, Some("work"), "list", json!({"notebook": "other"})).is_err());
+        assert!(scope_args(&served, None, "notebooks", json!({})).is_ok());
+        let copy = json!({"id": "1", "notebook": "work", "target_notebook": "other"});
+        assert!(scope_args(&served, None, "copy_to", copy).is_err());
+    }
+
+    #[test]
+    fn scope_args_fills_in_the_session_notebook_when_unscoped() {
+        assert_eq!(
+            scope_args(&[], Some("acme"), "list", json!({})).unwrap(),
+            json!({"notebook": "acme"})
+        );
+        assert_eq!(
+            scope_args(&[], Some("acme"), "show", json!({"notebook": "home"})).unwrap(),
+            json!({"notebook": "home"})
+        );
+        assert_eq!(scope_args(&[], None, "list", json!({})).unwrap(), json!({}));
+    }
+}
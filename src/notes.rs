@@ -0,0 +1,625 @@
+//! Helpers for reading note files directly from a notebook directory.
+//!
+//! Used by commands that operate on many notes at once, where invoking
+//! `nb show` per note would be prohibitively slow.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use regex::Regex;
+
+/// Regex to match inline hashtags (`#tag`, `#multi-word-tag`).
+///
+/// The tag must be preceded by start-of-line or whitespace so that Markdown
+/// headings (`# Heading`) and URL fragments are not treated as tags.
+static HASHTAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)(?:^|\s)#([A-Za-z0-9][A-Za-z0-9_/-]*)").unwrap());
+
+/// A note file read from the notebook directory.
+#[derive(Debug, Clone)]
+pub struct NoteFile {
+    /// Path relative to the notebook root, using `/` separators.
+    pub path: String,
+    /// Raw file content.
+    pub content: String,
+}
+
+/// Splits a note into its YAML frontmatter (without delimiters) and body.
+///
+/// Returns `None` for the frontmatter if the note does not begin with a
+/// `---` delimited block.
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let frontmatter = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return (Some(frontmatter.trim_end_matches(['\n', '\r'])), body);
+        }
+        offset += line.len();
+    }
+    (None, content)
+}
+
+/// Reassembles a note from optional frontmatter and body.
+pub fn join_frontmatter(frontmatter: Option<&str>, body: &str) -> String {
+    match frontmatter {
+        Some(frontmatter) => format!("---\n{}\n---\n{}", frontmatter, body),
+        None => body.to_string(),
+    }
+}
+
+/// Quotes a string for YAML using JSON syntax, which YAML accepts.
+pub fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+/// Sets a top-level frontmatter field, replacing any existing value.
+///
+/// `value` must already be valid YAML (see [`yaml_string`]). Indented
+/// continuation lines of a replaced value are removed. A frontmatter block
+/// is created if the note has none.
+pub fn set_frontmatter_field(content: &str, key: &str, value: &str) -> String {
+    let (frontmatter, body) = split_frontmatter(content);
+    let field = format!("{}: {}", key, value);
+    let mut lines = Vec::new();
+    let mut replaced = false;
+    let mut skipping = false;
+    for line in frontmatter.unwrap_or_default().lines() {
+        if skipping && line.starts_with([' ', '\t']) {
+            continue;
+        }
+        skipping = false;
+        let is_key = line
+            .strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with(':'));
+        if is_key {
+            if !replaced {
+                lines.push(field.clone());
+                replaced = true;
+            }
+            skipping = true;
+            continue;
+        }
+        lines.push(line.to_string());
+    }
+    if !replaced {
+        lines.push(field);
+    }
+    join_frontmatter(Some(&lines.join("\n")), body)
+}
+
+/// Appends a `## heading` section with `body` to the end of a note.
+pub fn append_section(content: &str, heading: &str, body: &str) -> String {
+    format!(
+        "{}\n\n## {}\n\n{}\n",
+        content.trim_end(),
+        heading,
+        body.trim()
+    )
+}
+
+/// Extracts hashtags from note content, without the `#` prefix.
+///
+/// Tags are returned in order of first appearance, without duplicates.
+pub fn extract_tags(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for capture in HASHTAG_REGEX.captures_iter(content) {
+        let tag = capture[1].to_string();
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Extracts the `tags` frontmatter field, without `#` prefixes.
+///
+/// Accepts a flow list (`tags: [a, b]`), a comma-separated value
+/// (`tags: a, b`), or a block list of `- a` lines.
+pub fn frontmatter_tags(content: &str) -> Vec<String> {
+    let Some(frontmatter) = split_frontmatter(content).0 else {
+        return Vec::new();
+    };
+    let mut values = Vec::new();
+    let mut in_block = false;
+    for line in frontmatter.lines() {
+        if in_block {
+            match line.trim_start().strip_prefix("- ") {
+                Some(item) if line.starts_with([' ', '\t', '-']) => {
+                    values.push(item.to_string());
+                    continue;
+                }
+                _ => in_block = false,
+            }
+        }
+        let Some(value) = line
+            .strip_prefix("tags")
+            .and_then(|rest| rest.trim_start().strip_prefix(':'))
+        else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            in_block = true;
+            continue;
+        }
+        let value = value
+            .strip_prefix('[')
+            .and_then(|value| value.strip_suffix(']'))
+            .unwrap_or(value);
+        values.extend(value.split(',').map(String::from));
+    }
+    let mut tags: Vec<String> = Vec::new();
+    for value in values {
+        let tag = value
+            .trim()
+            .trim_matches(['"', '\''])
+            .trim_start_matches('#')
+            .to_string();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Returns the scalar value of top-level frontmatter field `key`, unquoted.
+pub fn frontmatter_field(content: &str, key: &str) -> Option<String> {
+    let frontmatter = split_frontmatter(content).0?;
+    frontmatter.lines().find_map(|line| {
+        let value = line
+            .strip_prefix(key)?
+            .trim_start()
+            .strip_prefix(':')?
+            .trim();
+        let value = value.trim_matches(['"', '\'']);
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// Adds hashtags to a note, skipping tags it already has.
+///
+/// Tags are appended to the first line made up only of hashtags. If there is
+/// none, a new hashtag line is inserted after a leading `# Heading` (or at
+/// the top of the body).
+pub fn add_hashtags(content: &str, tags: &[String]) -> String {
+    let existing = extract_tags(content);
+    let new_tags = tags
+        .iter()
+        .map(|tag| {
+            tag.trim_start_matches('#')
+                .replace(char::is_whitespace, "-")
+        })
+        .filter(|tag| !tag.is_empty() && !existing.contains(tag))
+        .map(|tag| format!("#{}", tag))
+        .collect::<Vec<_>>();
+    if new_tags.is_empty() {
+        return content.to_string();
+    }
+    let hashtags = new_tags.join(" ");
+    let (frontmatter, body) = split_frontmatter(content);
+    let mut lines = body.lines().map(String::from).collect::<Vec<_>>();
+    match lines.iter().position(|line| is_hashtag_line(line)) {
+        Some(index) => {
+            lines[index] = format!("{} {}", lines[index].trim_end(), hashtags);
+        }
+        None => {
+            let (index, inserted) = match lines.first() {
+                Some(first) if first.starts_with("# ") => {
+                    if lines.get(1).is_some_and(|line| line.trim().is_empty()) {
+                        (2, vec![hashtags, String::new()])
+                    } else {
+                        (1, vec![String::new(), hashtags, String::new()])
+                    }
+                }
+                _ => (0, vec![hashtags, String::new()]),
+            };
+            lines.splice(index..index, inserted);
+        }
+    }
+    let mut body = lines.join("\n");
+    if content.ends_with('\n') {
+        body.push('\n');
+    }
+    join_frontmatter(frontmatter, &body)
+}
+
+/// Replaces each `#from` hashtag with `#to`, or removes it when `to` is
+/// `None` (along with one adjoining space).
+pub fn replace_hashtag(content: &str, from: &str, to: Option<&str>) -> String {
+    let mut replaced = String::with_capacity(content.len());
+    let mut last = 0;
+    for capture in HASHTAG_REGEX.captures_iter(content) {
+        let tag = capture.get(1).expect("hashtag group always matches");
+        if tag.as_str() != from {
+            continue;
+        }
+        let start = tag.start() - 1;
+        let before = &content[last..start];
+        last = tag.end();
+        match to {
+            Some(to) => {
+                replaced.push_str(before);
+                replaced.push('#');
+                replaced.push_str(to.trim_start_matches('#'));
+            }
+            None => match before.strip_suffix([' ', '\t']) {
+                Some(before) => replaced.push_str(before),
+                None => {
+                    replaced.push_str(before);
+                    if content[last..].starts_with(' ') {
+                        last += 1;
+                    }
+                }
+            },
+        }
+    }
+    replaced.push_str(&content[last..]);
+    replaced
+}
+
+/// Maximum length of a derived title, in characters.
+const DERIVED_TITLE_MAX_CHARS: usize = 60;
+
+/// Derives a title from a note's first heading or first sentence.
+///
+/// Frontmatter, hashtag lines, and code fences are skipped; Markdown list and
+/// quote markers are stripped. Long titles are cut at a word boundary.
+/// Returns `None` if the content has no usable text.
+pub fn derive_title(content: &str) -> Option<String> {
+    let (_, body) = split_frontmatter(content);
+    let line = body
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with("```") && !is_hashtag_line(line))?;
+    let text = match line.trim_start_matches('#') {
+        heading if heading.len() < line.len() && heading.starts_with(' ') => heading.trim(),
+        _ => first_sentence(line.trim_start_matches(['-', '*', '+', '>', ' '])),
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    if text.chars().count() <= DERIVED_TITLE_MAX_CHARS {
+        return Some(text);
+    }
+    let mut title = String::new();
+    for word in text.split(' ') {
+        if title.chars().count() + word.chars().count() + 1 > DERIVED_TITLE_MAX_CHARS {
+            break;
+        }
+        if !title.is_empty() {
+            title.push(' ');
+        }
+        title.push_str(word);
+    }
+    if title.is_empty() {
+        title = text.chars().take(DERIVED_TITLE_MAX_CHARS).collect();
+    }
+    Some(title)
+}
+
+/// Returns text up to the first sentence-ending punctuation mark.
+fn first_sentence(text: &str) -> &str {
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let at_boundary = chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+        if matches!(c, '.' | '?' | '!') && at_boundary {
+            return &text[..index];
+        }
+    }
+    text
+}
+
+/// Returns true if the line consists only of hashtags (`#a #b`).
+fn is_hashtag_line(line: &str) -> bool {
+    !line.trim().is_empty()
+        && line
+            .split_whitespace()
+            .all(|word| word.len() > 1 && word.starts_with('#') && !word.starts_with("##"))
+}
+
+/// Converts a title into a lowercase, hyphenated file stem.
+///
+/// Returns `"untitled"` when the title has no alphanumeric characters.
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in title.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "untitled".to_string()
+    } else {
+        slug.chars()
+            .take(80)
+            .collect::<String>()
+            .trim_end_matches('-')
+            .to_string()
+    }
+}
+
+/// Returns true if the file name looks like a text note nb can show.
+pub fn is_note_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("md" | "markdown" | "txt" | "org" | "rst" | "adoc" | "latex" | "tex")
+    )
+}
+
+/// Recursively reads note files beneath `root`, optionally scoped to a folder.
+///
+/// Hidden entries (such as `.git` and nb's `.index` files) are skipped, as are
+/// files that are not valid UTF-8. Results are sorted by path. A folder
+/// leaving the notebook through `..` is refused as invalid input.
+pub fn read_notes(root: &Path, folder: Option<&str>) -> std::io::Result<Vec<NoteFile>> {
+    let start = match folder.map(|f| f.trim_matches('/')) {
+        Some(f) if f.split('/').any(|part| part == "..") => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("folder {f} is outside the notebook"),
+            ));
+        }
+        Some(f) => root.join(f),
+        None => root.to_path_buf(),
+    };
+    let mut notes = Vec::new();
+    let mut pending: Vec<PathBuf> = vec![start];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if hidden {
+                continue;
+            }
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            if !file_type.is_file() || !is_note_file(&path) {
+                continue;
+            }
+            let content = match std::fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(err) if err.kind() == std::io::ErrorKind::InvalidData => continue,
+                Err(err) => return Err(err),
+            };
+            notes.push(NoteFile {
+                path: relative_path(root, &path),
+                content,
+            });
+        }
+    }
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(notes)
+}
+
+/// Renders `path` relative to `root` with `/` separators.
+pub fn relative_path(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Resolves `.` and `..` in a `/`-separated path, rejecting paths that leave
+/// the notebook.
+pub fn normalize_path(path: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Checks a client-supplied file path that must stay inside a directory,
+/// returning it without `.` parts.
+///
+/// Absolute paths, home-relative paths, and paths with `..` are refused.
+pub fn relative_file_path(path: &str) -> Result<String, String> {
+    let trimmed = path.trim();
+    if Path::new(trimmed).is_absolute() || trimmed.starts_with(['/', '\\', '~']) {
+        return Err(format!("path {trimmed} must be relative"));
+    }
+    let parts = trimmed
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>();
+    if parts.contains(&"..") {
+        return Err(format!("path {trimmed} must not contain .."));
+    }
+    if parts.is_empty() {
+        return Err("path must name a file".to_string());
+    }
+    Ok(parts.join("/"))
+}
+
+/// Decodes `%XX` escapes, leaving malformed escapes as-is.
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%' && index + 2 < bytes.len())
+            .then(|| std::str::from_utf8(&bytes[index + 1..index + 3]).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(byte) = escaped {
+            out.push(byte);
+            index += 3;
+            continue;
+        }
+        out.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        add_hashtags, derive_title, extract_tags, frontmatter_field, frontmatter_tags,
+        join_frontmatter, relative_file_path, replace_hashtag, set_frontmatter_field, slugify,
+        split_frontmatter,
+    };
+
+    #[test]
+    fn derive_title_prefers_heading_then_first_sentence() {
+        assert_eq!(
+            derive_title("---\ntags: [a]\n---\n#a #b\n\n## Release v1.2 plan\n").as_deref(),
+            Some("Release v1.2 plan")
+        );
+        assert_eq!(
+            derive_title("- Call Sam about v1.2. Then email.").as_deref(),
+            Some("Call Sam about v1.2")
+        );
+        assert_eq!(derive_title("\n```\n"), None);
+        let long = "word ".repeat(30);
+        assert!(derive_title(&long).unwrap().chars().count() <= 60);
+    }
+
+    #[test]
+    fn add_hashtags_extends_or_inserts_tag_line() {
+        let tags = vec!["api".to_string(), "design".to_string()];
+        assert_eq!(
+            add_hashtags("# Plan\n\n#design\n\nBody\n", &tags),
+            "# Plan\n\n#design #api\n\nBody\n"
+        );
+        assert_eq!(
+            add_hashtags("# Plan\n\nBody\n", &tags),
+            "# Plan\n\n#api #design\n\nBody\n"
+        );
+    }
+
+    #[test]
+    fn replace_hashtag_matches_whole_tags() {
+        let note = "# Task\n\n#doing #doing-later #urgent\n";
+        assert_eq!(
+            replace_hashtag(note, "doing", Some("done")),
+            "# Task\n\n#done #doing-later #urgent\n"
+        );
+        assert_eq!(
+            replace_hashtag(note, "urgent", None),
+            "# Task\n\n#doing #doing-later\n"
+        );
+        assert_eq!(
+            replace_hashtag(note, "doing", None),
+            "# Task\n\n#doing-later #urgent\n"
+        );
+    }
+
+    #[test]
+    fn frontmatter_tags_reads_flow_and_block_lists() {
+        assert_eq!(
+            frontmatter_tags("---\ntags: [a, \"#b\"]\n---\nBody #c\n"),
+            vec!["a", "b"]
+        );
+        assert_eq!(
+            frontmatter_tags("---\ntitle: T\ntags:\n  - a\n  - b\nsummary: s\n---\n"),
+            vec!["a", "b"]
+        );
+        assert!(frontmatter_tags("tags: a\n").is_empty());
+    }
+
+    #[test]
+    fn frontmatter_field_reads_scalars() {
+        let note = "---\ntitle: Plan\nreview_by: \"2024-06-01\"\n---\nreview_by: no\n";
+        assert_eq!(
+            frontmatter_field(note, "review_by").as_deref(),
+            Some("2024-06-01")
+        );
+        assert_eq!(frontmatter_field(note, "expires"), None);
+        assert_eq!(frontmatter_field("review_by: 2024\n", "review_by"), None);
+    }
+
+    #[test]
+    fn set_frontmatter_field_replaces_or_appends() {
+        let note = "---\ntitle: Plan\nsummary: |\n  old\n  text\ntags: [a]\n---\nBody\n";
+        assert_eq!(
+            set_frontmatter_field(note, "summary", "\"new\""),
+            "---\ntitle: Plan\nsummary: \"new\"\ntags: [a]\n---\nBody\n"
+        );
+        assert_eq!(
+            set_frontmatter_field("Body\n", "summary", "\"new\""),
+            "---\nsummary: \"new\"\n---\nBody\n"
+        );
+    }
+
+    #[test]
+    fn split_frontmatter_without_block_returns_body() {
+        let (frontmatter, body) = split_frontmatter("# Title\n\nBody\n");
+        assert_eq!(frontmatter, None);
+        assert_eq!(body, "# Title\n\nBody\n");
+    }
+
+    #[test]
+    fn split_frontmatter_extracts_block() {
+        let (frontmatter, body) = split_frontmatter("---\ntitle: Example\n---\n# Example\n");
+        assert_eq!(frontmatter, Some("title: Example"));
+        assert_eq!(body, "# Example\n");
+    }
+
+    #[test]
+    fn split_frontmatter_ignores_unterminated_block() {
+        let (frontmatter, body) = split_frontmatter("---\ntitle: Example\n");
+        assert_eq!(frontmatter, None);
+        assert_eq!(body, "---\ntitle: Example\n");
+    }
+
+    #[test]
+    fn join_frontmatter_round_trips() {
+        let original = "---\ntitle: Example\n---\n# Example\n";
+        let (frontmatter, body) = split_frontmatter(original);
+        assert_eq!(join_frontmatter(frontmatter, body), original);
+    }
+
+    #[test]
+    fn extract_tags_skips_headings_and_duplicates() {
+        let tags = extract_tags("# Heading\n\n#design #api text #design\nsee url#fragment");
+        assert_eq!(tags, vec!["design".to_string(), "api".to_string()]);
+    }
+
+    #[test]
+    fn slugify_normalizes_titles() {
+        assert_eq!(
+            slugify("Quarterly Plan: 2024 (Draft)"),
+            "quarterly-plan-2024-draft"
+        );
+        assert_eq!(slugify("  ***  "), "untitled");
+    }
+
+    #[test]
+    fn relative_file_path_stays_inside_its_base() {
+        assert_eq!(
+            relative_file_path("./exports//plan.pdf").as_deref(),
+            Ok("exports/plan.pdf")
+        );
+        assert!(relative_file_path("/etc/passwd").is_err());
+        assert!(relative_file_path("~/.bashrc").is_err());
+        assert!(relative_file_path("exports/../../x").is_err());
+        assert!(relative_file_path(" ./ ").is_err());
+    }
+}
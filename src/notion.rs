@@ -0,0 +1,326 @@
+//! Notion export import.
+//!
+//! Notion exports pages as a zip of Markdown (or HTML) files whose names carry
+//! a 32-character hex page ID (`Roadmap 0f1e2d...c3b4.md`), with child pages in
+//! a sibling directory of the same name. This module unpacks such an archive,
+//! strips the IDs from every path, converts HTML pages to Markdown, rewrites
+//! internal links to the cleaned paths, and writes the result in one commit.
+
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read, Seek},
+    sync::LazyLock,
+};
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::batch::{self, BatchSummary, PendingNote};
+use crate::html;
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// Matches the trailing Notion page ID in a file stem or directory name.
+static NOTION_ID_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.*?)\s*\b[0-9a-f]{32}$").unwrap());
+
+/// Matches inline Markdown link and image targets: `](target)`.
+static LINK_TARGET_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\]\(([^)\s]+)\)").unwrap());
+
+/// Summary of a Notion import.
+#[derive(Debug, Serialize)]
+pub struct NotionSummary {
+    #[serde(flatten)]
+    pub batch: BatchSummary,
+    /// Number of internal links rewritten to cleaned paths.
+    pub links_rewritten: usize,
+    /// Archive entries that were not imported.
+    pub skipped: Vec<String>,
+}
+
+/// A file extracted from the export archive.
+struct ExportFile {
+    path: String,
+    data: Vec<u8>,
+}
+
+/// Imports a Notion export zip into the notebook.
+pub async fn import(
+    nb: &NbClient,
+    source: &str,
+    folder: Option<&str>,
+    notebook: Option<&str>,
+) -> Result<NotionSummary, NbError> {
+    let source = source.to_string();
+    let (files, mut skipped) = tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::open(&source)?;
+        read_archive(file)
+    })
+    .await
+    .map_err(|err| NbError::CommandFailed(format!("notion import task failed: {err}")))??;
+
+    let (pending, links_rewritten, unconverted) = convert(files);
+    skipped.extend(unconverted);
+    let message = format!("[nb] Import {} files from Notion", pending.len());
+    let batch = batch::write_notes(nb, pending, folder, &message, notebook).await?;
+    Ok(NotionSummary {
+        batch,
+        links_rewritten,
+        skipped,
+    })
+}
+
+/// Reads every file from a zip archive, descending into nested zips.
+///
+/// Notion splits large exports into an outer zip containing part zips.
+fn read_archive<R: Read + Seek>(reader: R) -> Result<(Vec<ExportFile>, Vec<String>), NbError> {
+    let mut archive = zip::ZipArchive::new(reader)
+        .map_err(|err| NbError::CommandFailed(format!("invalid Notion export zip: {err}")))?;
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|err| NbError::CommandFailed(format!("invalid Notion export zip: {err}")))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(path) = entry.enclosed_name() else {
+            skipped.push(entry.name().to_string());
+            continue;
+        };
+        let path = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        if path.to_lowercase().ends_with(".zip") {
+            let (nested, nested_skipped) = read_archive(Cursor::new(data))?;
+            files.extend(nested);
+            skipped.extend(nested_skipped);
+        } else {
+            files.push(ExportFile { path, data });
+        }
+    }
+    Ok((files, skipped))
+}
+
+/// Cleans paths, converts HTML, and rewrites links for a set of export files.
+///
+/// Returns the notes to write, the number of links rewritten, and the paths
+/// of files that could not be converted.
+fn convert(files: Vec<ExportFile>) -> (Vec<PendingNote>, usize, Vec<String>) {
+    let renames = files
+        .iter()
+        .map(|file| (file.path.clone(), clean_path(&file.path)))
+        .collect::<HashMap<_, _>>();
+
+    let mut pending = Vec::with_capacity(files.len());
+    let mut links_rewritten = 0;
+    let mut skipped = Vec::new();
+    for file in files {
+        let target = renames[&file.path].clone();
+        let is_page = is_page(&file.path);
+        if !is_page {
+            pending.push(PendingNote {
+                path: target,
+                content: file.data,
+                title: None,
+            });
+            continue;
+        }
+        let Ok(text) = String::from_utf8(file.data) else {
+            skipped.push(file.path);
+            continue;
+        };
+        let markdown = if file.path.to_lowercase().ends_with(".html") {
+            html::to_markdown(&text)
+        } else {
+            text
+        };
+        let (markdown, rewritten) = rewrite_links(&markdown, &file.path, &target, &renames);
+        links_rewritten += rewritten;
+        pending.push(PendingNote {
+            title: markdown
+                .lines()
+                .find_map(|line| line.strip_prefix("# "))
+                .map(|title| title.trim().to_string()),
+            path: target,
+            content: markdown.into_bytes(),
+        });
+    }
+    (pending, links_rewritten, skipped)
+}
+
+fn is_page(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    lower.ends_with(".md") || lower.ends_with(".html")
+}
+
+/// Strips Notion IDs from each path component; HTML pages become `.md`.
+fn clean_path(path: &str) -> String {
+    let mut components = path.split('/').map(str::to_string).collect::<Vec<_>>();
+    let last = components.len() - 1;
+    for (index, component) in components.iter_mut().enumerate() {
+        let (stem, extension) = match component.rfind('.') {
+            Some(dot) if index == last && dot > 0 => {
+                (component[..dot].to_string(), component[dot..].to_string())
+            }
+            _ => (component.clone(), String::new()),
+        };
+        let stem = match NOTION_ID_REGEX.captures(&stem) {
+            Some(captures) if !captures[1].is_empty() => captures[1].to_string(),
+            _ => stem,
+        };
+        let extension = if extension.eq_ignore_ascii_case(".html") {
+            ".md".to_string()
+        } else {
+            extension
+        };
+        *component = format!("{}{}", stem, extension);
+    }
+    components.join("/")
+}
+
+/// Rewrites relative link targets in `markdown` from original to cleaned paths.
+///
+/// `original` and `cleaned` are the paths of the note being rewritten, used to
+/// resolve and re-relativize targets. Returns the new text and rewrite count.
+fn rewrite_links(
+    markdown: &str,
+    original: &str,
+    cleaned: &str,
+    renames: &HashMap<String, String>,
+) -> (String, usize) {
+    let original_dir = parent(original);
+    let cleaned_dir = parent(cleaned);
+    let mut rewritten = 0;
+    let text = LINK_TARGET_REGEX.replace_all(markdown, |captures: &regex::Captures<'_>| {
+        let target = &captures[1];
+        if target.contains("://") || target.starts_with('#') || target.starts_with("mailto:") {
+            return captures[0].to_string();
+        }
+        let (target_path, fragment) = match target.split_once('#') {
+            Some((path, fragment)) => (path, format!("#{}", fragment)),
+            None => (target, String::new()),
+        };
+        let resolved = normalize(&join(original_dir, &notes::percent_decode(target_path)));
+        match renames.get(&resolved) {
+            Some(new_path) => {
+                rewritten += 1;
+                format!(
+                    "]({}{})",
+                    percent_encode(&relative_to(cleaned_dir, new_path)),
+                    fragment
+                )
+            }
+            None => captures[0].to_string(),
+        }
+    });
+    (text.into_owned(), rewritten)
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+}
+
+fn join(dir: &str, path: &str) -> String {
+    if dir.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}/{}", dir, path)
+    }
+}
+
+/// Resolves `.` and `..` components in a `/`-separated relative path.
+fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            other => parts.push(other),
+        }
+    }
+    parts.join("/")
+}
+
+/// Expresses `target` relative to the directory `from`.
+fn relative_to(from: &str, target: &str) -> String {
+    let from_parts = from
+        .split('/')
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>();
+    let target_parts = target.split('/').collect::<Vec<_>>();
+    let common = from_parts
+        .iter()
+        .zip(&target_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut parts = vec![".."; from_parts.len() - common];
+    parts.extend(&target_parts[common..]);
+    parts.join("/")
+}
+
+/// Percent-encodes characters that would break a Markdown link target.
+fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            ' ' => out.push_str("%20"),
+            '(' => out.push_str("%28"),
+            ')' => out.push_str("%29"),
+            '%' => out.push_str("%25"),
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{clean_path, relative_to, rewrite_links};
+
+    const PAGE_ID: &str = "0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn clean_path_strips_notion_ids() {
+        assert_eq!(
+            clean_path(&format!("Roadmap {PAGE_ID}/Q3 Plan {PAGE_ID}.html")),
+            "Roadmap/Q3 Plan.md"
+        );
+        assert_eq!(clean_path("image.png"), "image.png");
+    }
+
+    #[test]
+    fn relative_to_walks_up_and_down() {
+        assert_eq!(relative_to("a/b", "a/c/d.md"), "../c/d.md");
+        assert_eq!(relative_to("", "a/d.md"), "a/d.md");
+    }
+
+    #[test]
+    fn rewrite_links_maps_original_targets() {
+        let original = format!("Roadmap {PAGE_ID}.md");
+        let child = format!("Roadmap {PAGE_ID}/Q3 Plan {PAGE_ID}.md");
+        let renames = HashMap::from([
+            (original.clone(), clean_path(&original)),
+            (child.clone(), clean_path(&child)),
+        ]);
+        let markdown = format!(
+            "See [Q3](Roadmap%20{PAGE_ID}/Q3%20Plan%20{PAGE_ID}.md#goals) and [web](https://x.io)"
+        );
+        let (text, count) = rewrite_links(&markdown, &original, "Roadmap.md", &renames);
+        assert_eq!(
+            text,
+            "See [Q3](Roadmap/Q3%20Plan.md#goals) and [web](https://x.io)"
+        );
+        assert_eq!(count, 1);
+    }
+}
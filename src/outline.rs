@@ -0,0 +1,112 @@
+//! Heading outlines of notes.
+//!
+//! `nb.outline` lists a note's Markdown headings with their line numbers and
+//! byte ranges, so an agent can read a long note one section at a time
+//! through `nb.show` with `range`.
+
+use serde::Serialize;
+
+/// A heading and the section it introduces.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Heading {
+    /// Heading level, 1 for `#` through 6 for `######`.
+    pub level: usize,
+    pub text: String,
+    /// 1-based line number of the heading.
+    pub line: usize,
+    /// Byte offset of the heading, usable as `range.offset` in `nb.show`.
+    pub offset: usize,
+    /// Byte length of the section up to the next heading of the same or a
+    /// higher level, usable as `range.length`.
+    pub length: usize,
+}
+
+/// Result of `nb.outline`.
+#[derive(Debug, Serialize)]
+pub struct Outline {
+    pub id: String,
+    /// Number of lines in the note.
+    pub lines: usize,
+    pub headings: Vec<Heading>,
+}
+
+/// Extracts ATX headings (`# Heading`) from Markdown.
+///
+/// Headings inside fenced code blocks and frontmatter are ignored.
+pub fn headings(content: &str) -> Vec<Heading> {
+    let mut headings: Vec<Heading> = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut in_frontmatter = false;
+    let mut offset = 0;
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim_end();
+        if index == 0 && trimmed == "---" {
+            in_frontmatter = true;
+            continue;
+        }
+        if in_frontmatter {
+            in_frontmatter = trimmed != "---";
+            continue;
+        }
+        let indented = trimmed.trim_start();
+        if let Some(marker) = fence {
+            if indented.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| indented.starts_with(marker))
+        {
+            fence = Some(marker);
+            continue;
+        }
+        // ATX headings allow at most three spaces of indentation.
+        if trimmed.len() - indented.len() > 3 {
+            continue;
+        }
+        let level = indented.chars().take_while(|&c| c == '#').count();
+        let rest = &indented[level..];
+        if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+            continue;
+        }
+        let text = rest.trim().trim_end_matches('#').trim_end();
+        headings.push(Heading {
+            level,
+            text: text.to_string(),
+            line: index + 1,
+            offset: start,
+            length: 0,
+        });
+    }
+    for i in 0..headings.len() {
+        let end = headings[i + 1..]
+            .iter()
+            .find(|next| next.level <= headings[i].level)
+            .map_or(content.len(), |next| next.offset);
+        headings[i].length = end - headings[i].offset;
+    }
+    headings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::headings;
+
+    #[test]
+    fn headings_skip_code_and_frontmatter() {
+        let content = "---\ntitle: x\n---\n# Top\nintro\n```\n# not a heading\n```\n## Sub ##\ntext\n# Next\n";
+        let found = headings(content);
+        let summary: Vec<_> = found
+            .iter()
+            .map(|h| (h.level, h.text.as_str(), h.line))
+            .collect();
+        assert_eq!(summary, vec![(1, "Top", 4), (2, "Sub", 9), (1, "Next", 11)]);
+        let top = &found[0];
+        assert!(content[top.offset..top.offset + top.length].ends_with("text\n"));
+        assert_eq!(top.offset + top.length, found[2].offset);
+    }
+}
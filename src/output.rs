@@ -0,0 +1,123 @@
+//! Response size limits.
+//!
+//! A `show` of a large imported file (or a broad `list`) can produce more text
+//! than an MCP client can reasonably accept. Outputs beyond the configured
+//! limit are cut at a UTF-8 boundary and followed by a one-line JSON marker
+//! describing what was omitted and how to fetch the rest.
+
+use serde::Serialize;
+
+/// Default maximum response size, in bytes.
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// Prefix of the line carrying the truncation marker.
+const MARKER_PREFIX: &str = "[nb-mcp]";
+
+/// Describes an output that was cut short.
+#[derive(Debug, Serialize)]
+struct TruncationMarker<'a> {
+    truncated: bool,
+    /// Size of the full output in bytes.
+    total_bytes: usize,
+    /// Byte offset of the first returned byte.
+    offset: usize,
+    /// Number of bytes returned.
+    returned_bytes: usize,
+    /// Offset to request next to continue reading.
+    next_offset: usize,
+    hint: &'a str,
+}
+
+/// Returns `output` unchanged, or its first `max` bytes plus a marker.
+pub fn limit(output: String, max: usize, hint: &str) -> String {
+    if output.len() <= max {
+        return output;
+    }
+    window(&output, 0, None, max, hint)
+}
+
+/// Returns the bytes of `output` starting at `offset`.
+///
+/// At most `length` bytes (or all remaining) are returned, capped at `max`.
+/// Boundaries are moved back to the nearest character boundary. When `max`
+/// cuts the requested range short, a truncation marker is appended.
+pub fn window(
+    output: &str,
+    offset: usize,
+    length: Option<usize>,
+    max: usize,
+    hint: &str,
+) -> String {
+    let total = output.len();
+    let start = floor_char_boundary(output, offset.min(total));
+    let requested_end = floor_char_boundary(
+        output,
+        length.map_or(total, |length| start.saturating_add(length).min(total)),
+    );
+    let end = floor_char_boundary(output, requested_end.min(start.saturating_add(max)));
+    let mut text = output[start..end].to_string();
+    if end < requested_end {
+        let marker = TruncationMarker {
+            truncated: true,
+            total_bytes: total,
+            offset: start,
+            returned_bytes: end - start,
+            next_offset: end,
+            hint,
+        };
+        let marker = serde_json::to_string(&marker).unwrap_or_default();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&format!("{} {}\n", MARKER_PREFIX, marker));
+    }
+    text
+}
+
+/// Returns whether `line` is a truncation marker line.
+pub fn is_marker(line: &str) -> bool {
+    line.starts_with(MARKER_PREFIX)
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{limit, window};
+
+    #[test]
+    fn limit_leaves_small_output_alone() {
+        assert_eq!(limit("short".to_string(), 10, "hint"), "short");
+    }
+
+    #[test]
+    fn limit_appends_marker_with_sizes() {
+        let text = limit("abcdefghij".to_string(), 4, "use range");
+        let (head, marker) = text.split_once('\n').unwrap();
+        assert_eq!(head, "abcd");
+        let marker: serde_json::Value =
+            serde_json::from_str(marker.trim().trim_start_matches("[nb-mcp] ")).unwrap();
+        assert_eq!(marker["truncated"], true);
+        assert_eq!(marker["total_bytes"], 10);
+        assert_eq!(marker["next_offset"], 4);
+        assert_eq!(marker["hint"], "use range");
+    }
+
+    #[test]
+    fn window_respects_char_boundaries() {
+        // "é" is two bytes; a cut in the middle moves back to its start.
+        assert_eq!(window("aébc", 0, Some(2), 100, ""), "a");
+        assert_eq!(window("aébc", 1, Some(2), 100, ""), "é");
+    }
+
+    #[test]
+    fn window_marks_only_limit_truncation() {
+        assert_eq!(window("abcdef", 2, Some(2), 100, ""), "cd");
+        assert!(window("abcdef", 2, Some(4), 2, "").contains("\"next_offset\":4"));
+    }
+}
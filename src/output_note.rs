@@ -0,0 +1,114 @@
+//! Saving query results as a note.
+//!
+//! Research workflows search, then write a note collecting what was found.
+//! `nb.list`, `nb.search`, and `nb.inbox_report` take an `output_note` title
+//! (`{date}` is replaced by today's date) and, besides returning their
+//! results, add them as a new note in Markdown: item lines become a bullet
+//! list with their match lines nested below, and structured results a fenced
+//! JSON block. The note is added like any `nb.add`, in the session or default
+//! folder with the default tags, and committed; the call's output ends with
+//! a `[nb-mcp]` marker line naming the added note.
+
+use chrono::NaiveDate;
+
+use crate::nb_index;
+
+/// Commands taking an `output_note` argument.
+pub const COMMANDS: &[&str] = &["list", "search", "inbox_report"];
+
+/// Returns true if a call of `command` with `args` saves its results.
+pub fn requested(command: &str, args: &serde_json::Value) -> bool {
+    COMMANDS.contains(&command)
+        && args
+            .get("output_note")
+            .is_some_and(|title| !title.is_null())
+}
+
+/// Expands `{date}` in an `output_note` title.
+pub fn title(template: &str, today: NaiveDate) -> String {
+    template
+        .replace("{date}", &today.format("%Y-%m-%d").to_string())
+        .trim()
+        .to_string()
+}
+
+/// Formats nb's item output as a Markdown list under a line naming `source`;
+/// `count_only` results are kept as JSON.
+pub fn items(source: &str, output: &str) -> String {
+    if output.starts_with('{') {
+        return json(source, output);
+    }
+    let mut content = format!("{source}\n\n");
+    let mut listed = false;
+    for line in output.lines().map(str::trim_end) {
+        // Blank lines and nb's rules under titles would break the list.
+        if line.trim().is_empty() || line.trim().chars().all(|c| c == '-') {
+            continue;
+        }
+        match nb_index::split_item(line.trim_start()) {
+            Some((id, rest)) => {
+                content.push_str(&format!("- [{id}] {rest}\n"));
+                listed = true;
+            }
+            None if listed => content.push_str(&format!("  {}\n", line.trim_start())),
+            None => content.push_str(&format!("{line}\n")),
+        }
+    }
+    if !listed && output.trim().is_empty() {
+        content.push_str("No results.\n");
+    }
+    content
+}
+
+/// Formats structured output as a fenced JSON block under a line naming
+/// `source`.
+pub fn json(source: &str, output: &str) -> String {
+    format!("{source}\n\n```json\n{}\n```\n", output.trim_end())
+}
+
+/// Marker line reporting the note the results were saved to.
+pub fn marker(added: &str) -> String {
+    format!(
+        "\n[nb-mcp] {}",
+        serde_json::json!({"output_note": added.trim()})
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{items, requested, title};
+
+    #[test]
+    fn title_expands_the_date() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        assert_eq!(
+            title("Research digest {date}", today),
+            "Research digest 2024-06-01"
+        );
+    }
+
+    #[test]
+    fn items_nests_match_lines_under_their_note() {
+        assert_eq!(
+            items(
+                "Search for `retry`.",
+                "[3] Backoff notes\n------------------\n4: retry with jitter\n\n[7] Outage review\n"
+            ),
+            "Search for `retry`.\n\n- [3] Backoff notes\n  4: retry with jitter\n- [7] Outage review\n"
+        );
+        assert_eq!(items("Notes.", ""), "Notes.\n\nNo results.\n");
+    }
+
+    #[test]
+    fn requested_needs_an_output_note_on_a_query() {
+        let args = serde_json::json!({"output_note": "Digest"});
+        assert!(requested("search", &args));
+        assert!(!requested("show", &args));
+        assert!(!requested(
+            "list",
+            &serde_json::json!({"output_note": null})
+        ));
+    }
+}
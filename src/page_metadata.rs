@@ -0,0 +1,308 @@
+//! Page metadata for enriched bookmarks.
+//!
+//! nb keeps little more than a bookmarked page's title and text. With
+//! `enrich`, `nb.bookmark` fetches the page itself through curl and reads the
+//! metadata sites publish for link previews: the title and description
+//! (Open Graph first, then the plain HTML tags), the canonical URL, and the
+//! `og:image` preview. They are stored in the bookmark's frontmatter, with
+//! relative URLs resolved against the page. Like pandoc, curl is optional:
+//! enriched bookmarks fail with an error naming it when it is missing.
+
+use std::process::Stdio;
+use std::sync::LazyLock;
+
+use quick_xml::escape::{resolve_html5_entity, unescape_with};
+use regex::Regex;
+use tokio::process::Command;
+
+use crate::html;
+use crate::nb::NbError;
+use crate::notes;
+
+/// Seconds a page fetch may take.
+const FETCH_TIMEOUT_SECONDS: &str = "20";
+
+/// Largest page fetched, in bytes.
+const MAX_PAGE_BYTES: &str = "5242880";
+
+/// Matches `<meta>` and `<link>` tags, capturing the tag name and attributes.
+static TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<(meta|link)\b([^>]*)>").unwrap());
+
+/// Matches one attribute, quoted or not.
+static ATTRIBUTE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?s)([A-Za-z_:.-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap()
+});
+
+static TITLE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<title\b[^>]*>(.*?)</title>").unwrap());
+
+/// Matches elements whose text is not page content.
+static HIDDEN_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?is)<head\b.*?</head>|<script\b.*?</script>|<style\b.*?</style>|<noscript\b.*?</noscript>|<svg\b.*?</svg>|<template\b.*?</template>",
+    )
+    .unwrap()
+});
+
+static BODY_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<body\b[^>]*>(.*?)(?:</body>|$)").unwrap());
+
+/// Metadata a page publishes about itself.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub canonical_url: Option<String>,
+    pub image: Option<String>,
+}
+
+impl PageMetadata {
+    /// Reads the metadata of `html`, fetched from `url`.
+    pub fn parse(html: &str, url: &str) -> Self {
+        let mut properties: Vec<(String, String)> = Vec::new();
+        let mut canonical = None;
+        for tag in TAG_REGEX.captures_iter(html) {
+            let attributes = attributes(&tag[2]);
+            let attribute = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.as_str())
+            };
+            if tag[1].eq_ignore_ascii_case("link") {
+                let rel = attribute("rel").unwrap_or_default();
+                if canonical.is_none()
+                    && rel
+                        .split_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("canonical"))
+                {
+                    canonical = attribute("href").map(String::from);
+                }
+                continue;
+            }
+            let Some(content) = attribute("content").filter(|content| !content.is_empty()) else {
+                continue;
+            };
+            if let Some(key) = attribute("property").or(attribute("name")) {
+                properties.push((key.to_lowercase(), content.to_string()));
+            }
+        }
+        let property = |keys: &[&str]| {
+            keys.iter().find_map(|key| {
+                properties
+                    .iter()
+                    .find(|(name, _)| name == key)
+                    .map(|(_, value)| value.clone())
+            })
+        };
+        let title = property(&["og:title", "twitter:title"]).or_else(|| {
+            TITLE_REGEX
+                .captures(html)
+                .map(|title| decode(&title[1]))
+                .filter(|title| !title.is_empty())
+        });
+        let canonical_url = canonical
+            .filter(|href| !href.is_empty())
+            .or_else(|| property(&["og:url"]));
+        let image = property(&[
+            "og:image",
+            "og:image:url",
+            "og:image:secure_url",
+            "twitter:image",
+        ]);
+        Self {
+            title,
+            description: property(&["og:description", "description", "twitter:description"]),
+            canonical_url: canonical_url.map(|href| resolve(url, &href)),
+            image: image.map(|href| resolve(url, &href)),
+        }
+    }
+
+    /// Sets the metadata found as frontmatter fields of `note`.
+    pub fn add_frontmatter(&self, note: &str) -> String {
+        let mut note = note.to_string();
+        for (key, value) in [
+            ("title", &self.title),
+            ("description", &self.description),
+            ("canonical_url", &self.canonical_url),
+            ("image", &self.image),
+        ] {
+            if let Some(value) = value {
+                note = notes::set_frontmatter_field(&note, key, &notes::yaml_string(value));
+            }
+        }
+        note
+    }
+}
+
+/// Fetches the HTML of `url` with curl.
+pub async fn fetch(url: &str) -> Result<String, NbError> {
+    let scheme = url
+        .split_once("://")
+        .map(|(scheme, _)| scheme.to_lowercase());
+    if !matches!(scheme.as_deref(), Some("http" | "https")) {
+        return Err(NbError::CommandFailed(format!(
+            "cannot enrich {url}: only http and https pages are fetched"
+        )));
+    }
+    tracing::debug!(url, "fetching page metadata");
+    let output = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--location",
+            "--max-redirs",
+            "5",
+            "--max-time",
+            FETCH_TIMEOUT_SECONDS,
+            "--max-filesize",
+            MAX_PAGE_BYTES,
+            "--proto",
+            "=http,https",
+            "--proto-redir",
+            "=http,https",
+            "--user-agent",
+            concat!("nb-mcp-server/", env!("CARGO_PKG_VERSION")),
+            "--url",
+            url,
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|err| {
+            if err.kind() == std::io::ErrorKind::NotFound {
+                NbError::CommandFailed(
+                    "curl not found in PATH; install curl to enrich bookmarks".to_string(),
+                )
+            } else {
+                NbError::Io(err)
+            }
+        })?;
+    if !output.status.success() {
+        return Err(NbError::CommandFailed(format!(
+            "fetching {url} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Converts the visible content of a fetched page to Markdown.
+pub fn text(html: &str) -> String {
+    let body = BODY_REGEX
+        .captures(html)
+        .map_or(html, |body| body.get(1).map_or("", |body| body.as_str()));
+    html::to_markdown(&HIDDEN_REGEX.replace_all(body, ""))
+}
+
+/// Returns the attributes of a tag, names lowercased and values decoded.
+fn attributes(tag: &str) -> Vec<(String, String)> {
+    ATTRIBUTE_REGEX
+        .captures_iter(tag)
+        .map(|attribute| {
+            let value = attribute
+                .get(2)
+                .or(attribute.get(3))
+                .or(attribute.get(4))
+                .map_or("", |value| value.as_str());
+            (attribute[1].to_lowercase(), decode(value))
+        })
+        .collect()
+}
+
+/// Decodes character references and collapses whitespace.
+fn decode(text: &str) -> String {
+    let decoded = unescape_with(text, resolve_html5_entity)
+        .map(|text| text.into_owned())
+        .unwrap_or_else(|_| text.to_string());
+    decoded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Resolves `href` against the page `base` URL.
+fn resolve(base: &str, href: &str) -> String {
+    if href.contains("://") {
+        return href.to_string();
+    }
+    let Some((scheme, rest)) = base.split_once("://") else {
+        return href.to_string();
+    };
+    if let Some(network_path) = href.strip_prefix("//") {
+        return format!("{scheme}://{network_path}");
+    }
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let origin = format!("{scheme}://{}", &rest[..authority_end]);
+    if href.starts_with('/') {
+        return format!("{origin}{href}");
+    }
+    let path = rest[authority_end..]
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default();
+    let directory = path.rfind('/').map_or("/", |end| &path[..=end]);
+    format!("{origin}{directory}{href}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PageMetadata, resolve, text};
+
+    #[test]
+    fn parse_prefers_open_graph_and_resolves_urls() {
+        let html = r#"<html><head>
+            <title>Fallback &amp; title</title>
+            <meta name="description" content="Plain description">
+            <meta property="og:description" content="Rich &quot;description&quot;" />
+            <link rel="canonical" href="/posts/retry">
+            <meta property='og:image' content='img/cover.png'>
+            </head><body><p>Hi</p></body></html>"#;
+        assert_eq!(
+            PageMetadata::parse(html, "https://example.com/blog/post?id=3"),
+            PageMetadata {
+                title: Some("Fallback & title".to_string()),
+                description: Some("Rich \"description\"".to_string()),
+                canonical_url: Some("https://example.com/posts/retry".to_string()),
+                image: Some("https://example.com/blog/img/cover.png".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn add_frontmatter_sets_found_fields() {
+        let metadata = PageMetadata {
+            title: Some("Post".to_string()),
+            image: Some("https://example.com/a.png".to_string()),
+            ..PageMetadata::default()
+        };
+        assert_eq!(
+            metadata.add_frontmatter("# Post\n"),
+            "---\ntitle: \"Post\"\nimage: \"https://example.com/a.png\"\n---\n# Post\n"
+        );
+    }
+
+    #[test]
+    fn resolve_handles_relative_forms() {
+        let base = "https://example.com/a/b.html";
+        assert_eq!(
+            resolve(base, "//cdn.example.com/x.png"),
+            "https://cdn.example.com/x.png"
+        );
+        assert_eq!(resolve(base, "c.png"), "https://example.com/a/c.png");
+        assert_eq!(
+            resolve("https://example.com", "c.png"),
+            "https://example.com/c.png"
+        );
+        assert_eq!(resolve(base, "http://other.org/"), "http://other.org/");
+    }
+
+    #[test]
+    fn text_skips_scripts_and_head() {
+        let html = "<html><head><title>T</title></head><body><script>var a = 1 < 2;</script>\
+                    <p>Visible</p></body></html>";
+        assert_eq!(text(html).trim(), "Visible");
+    }
+}
@@ -0,0 +1,188 @@
+//! Document conversion through pandoc.
+//!
+//! When pandoc is installed, `nb.import` accepts Word, OpenDocument, Org,
+//! and reStructuredText files, converting them to Markdown notes, and
+//! `nb.export` converts a note to those formats or to PDF. pandoc is
+//! optional: `server_info` reports whether it was found, and the formats
+//! fail with an error naming it when it is not.
+
+use std::path::Path;
+use std::process::Stdio;
+
+use serde::Serialize;
+use tokio::process::Command;
+
+use crate::batch::{self, BatchSummary, PendingNote};
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// Formats `nb.import` converts from.
+pub const IMPORT_FORMATS: &[&str] = &["docx", "odt", "org", "rst"];
+
+/// Formats `nb.export` converts to.
+pub const EXPORT_FORMATS: &[&str] = &["docx", "odt", "pdf", "org", "rst"];
+
+/// Markdown flavor pandoc reads and writes.
+const MARKDOWN: &str = "gfm";
+
+/// Result of `nb.export`.
+#[derive(Debug, Serialize)]
+pub struct Export {
+    pub id: String,
+    pub format: String,
+    /// File written, when `path` was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Converted text, for text formats exported without `path`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+/// Returns the installed pandoc version, if pandoc can be run.
+pub async fn version() -> Option<String> {
+    let output = run(&["--version".to_string()]).await.ok()?;
+    let line = output.lines().next()?;
+    Some(line.trim_start_matches("pandoc").trim().to_string())
+}
+
+/// Imports a document as a Markdown note, named after the source unless
+/// `filename` is given, in one commit.
+pub async fn import(
+    nb: &NbClient,
+    source: &str,
+    format: &str,
+    folder: Option<&str>,
+    filename: Option<&str>,
+    notebook: Option<&str>,
+) -> Result<BatchSummary, NbError> {
+    let markdown = run(&convert_args(source, format, MARKDOWN, None)).await?;
+    let path = match filename {
+        Some(filename) => filename.to_string(),
+        None => {
+            let stem = Path::new(source)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            format!("{}.md", notes::slugify(&stem))
+        }
+    };
+    let note = PendingNote {
+        path,
+        title: notes::derive_title(&markdown),
+        content: markdown.into_bytes(),
+    };
+    let message = format!("[nb] Import {source} from {format}");
+    batch::write_notes(nb, vec![note], folder, &message, notebook).await
+}
+
+/// Converts note `id` to `format`, writing it to `path` if given.
+///
+/// Binary formats (docx, odt, pdf) need a `path`.
+pub async fn export(
+    nb: &NbClient,
+    id: &str,
+    format: &str,
+    path: Option<&Path>,
+    notebook: Option<&str>,
+) -> Result<Export, NbError> {
+    if !EXPORT_FORMATS.contains(&format) {
+        return Err(NbError::CommandFailed(format!(
+            "unsupported export format {format:?}; use one of: {}",
+            EXPORT_FORMATS.join(", ")
+        )));
+    }
+    if path.is_none() && !is_text_format(format) {
+        return Err(NbError::CommandFailed(format!(
+            "exporting to {format} needs a path to write the file to"
+        )));
+    }
+    let source = nb.show_path(id, notebook).await?;
+    let output = run(&convert_args(
+        &source.to_string_lossy(),
+        MARKDOWN,
+        format,
+        path,
+    ))
+    .await?;
+    Ok(Export {
+        id: id.to_string(),
+        format: format.to_string(),
+        path: path.map(|path| path.display().to_string()),
+        content: path.is_none().then_some(output),
+    })
+}
+
+fn is_text_format(format: &str) -> bool {
+    matches!(format, "org" | "rst")
+}
+
+/// Builds the pandoc arguments converting `input` from `from` to `to`,
+/// writing to `output` or to stdout.
+fn convert_args(input: &str, from: &str, to: &str, output: Option<&Path>) -> Vec<String> {
+    let mut args = vec!["--from".to_string(), from.to_string()];
+    // pandoc picks the PDF engine from the output file name.
+    if to != "pdf" {
+        args.extend(["--to".to_string(), to.to_string()]);
+    }
+    if let Some(output) = output {
+        args.extend(["--output".to_string(), output.display().to_string()]);
+    }
+    args.extend(["--".to_string(), input.to_string()]);
+    args
+}
+
+async fn run(args: &[String]) -> Result<String, NbError> {
+    tracing::debug!(?args, "executing pandoc");
+    let output = Command::new("pandoc")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|err| {
+            if err.kind() == std::io::ErrorKind::NotFound {
+                NbError::CommandFailed(
+                    "pandoc not found in PATH; install pandoc to convert documents \
+                     (https://pandoc.org/installing.html)"
+                        .to_string(),
+                )
+            } else {
+                NbError::Io(err)
+            }
+        })?;
+    if !output.status.success() {
+        return Err(NbError::CommandFailed(format!(
+            "pandoc failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::convert_args;
+
+    #[test]
+    fn convert_args_name_formats_and_output() {
+        assert_eq!(
+            convert_args("notes/-a.docx", "docx", "gfm", None),
+            ["--from", "docx", "--to", "gfm", "--", "notes/-a.docx"]
+        );
+        assert_eq!(
+            convert_args("plan.md", "gfm", "pdf", Some(Path::new("/tmp/plan.pdf"))),
+            [
+                "--from",
+                "gfm",
+                "--output",
+                "/tmp/plan.pdf",
+                "--",
+                "plan.md"
+            ]
+        );
+    }
+}
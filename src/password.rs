@@ -0,0 +1,105 @@
+//! Passwords for encrypted notes from a password manager.
+//!
+//! nb encrypts notes it saves with `--encrypt` (as `.enc` files) and asks
+//! for the password on standard input whenever one is read or edited, which
+//! an MCP client cannot answer. With `--password-command` (or
+//! `NB_MCP_PASSWORD_COMMAND`), the server runs that command through `sh`,
+//! with `{notebook}` replaced by the notebook name, each time nb needs the
+//! password for an encrypted note, and writes the first line of its output
+//! to nb's standard input. The password is not kept between calls, never
+//! passed on a command line, and never logged; buffers holding it are
+//! zeroed once nb has read it.
+
+use std::process::Stdio;
+
+use tokio::process::Command;
+
+use crate::nb::NbError;
+
+/// Extension nb gives encrypted notes.
+const ENCRYPTED_EXTENSION: &str = ".enc";
+
+/// Shell command printing the password of a notebook.
+#[derive(Debug, Clone)]
+pub struct PasswordCommand(String);
+
+impl PasswordCommand {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self(command.into())
+    }
+
+    /// Runs the command for `notebook` and returns the password as input for
+    /// nb's prompt: the first line of the output, newline-terminated.
+    pub async fn input(&self, notebook: &str) -> Result<Vec<u8>, NbError> {
+        let command = self.0.replace("{notebook}", &shell_quote(notebook));
+        tracing::debug!(notebook, "running password command");
+        let mut output = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output()
+            .await?;
+        let result = if !output.status.success() {
+            let status = output
+                .status
+                .code()
+                .map_or_else(|| "a signal".to_string(), |code| format!("status {code}"));
+            Err(NbError::CommandFailed(format!(
+                "password command for notebook {notebook} exited with {status}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+        } else {
+            let line = output
+                .stdout
+                .split(|byte| *byte == b'\n')
+                .next()
+                .unwrap_or_default();
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if line.is_empty() {
+                Err(NbError::CommandFailed(format!(
+                    "password command for notebook {notebook} printed no password"
+                )))
+            } else {
+                let mut input = Vec::with_capacity(line.len() + 1);
+                input.extend_from_slice(line);
+                input.push(b'\n');
+                Ok(input)
+            }
+        };
+        output.stdout.fill(0);
+        result
+    }
+}
+
+/// Returns whether the note at `path` is encrypted by nb.
+pub fn is_encrypted(path: &str) -> bool {
+    path.trim_end().ends_with(ENCRYPTED_EXTENSION)
+}
+
+/// Quotes `value` as a single shell word.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PasswordCommand, is_encrypted};
+
+    #[tokio::test]
+    async fn input_is_the_first_line_for_the_notebook() {
+        let command = PasswordCommand::new("printf 'secret-%s\\nextra\\n' {notebook}");
+        assert_eq!(command.input("work").await.unwrap(), b"secret-work\n");
+        let failing = PasswordCommand::new("echo 'not in store' >&2; exit 1");
+        let message = failing.input("work").await.unwrap_err().to_string();
+        assert!(message.contains("status 1: not in store"));
+    }
+
+    #[test]
+    fn is_encrypted_checks_the_extension() {
+        assert!(is_encrypted("/notes/work/secrets.md.enc\n"));
+        assert!(!is_encrypted("/notes/work/plan.md"));
+    }
+}
@@ -4,7 +4,10 @@
 //! When running inside a Git repository, logs are named after the project and worktree
 //! to avoid collisions between multiple MCP server instances.
 
-use std::{path::PathBuf, process::Command, sync::OnceLock};
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 
 /// Cached log path (computed once per process).
 static LOG_PATH: OnceLock<PathBuf> = OnceLock::new();
@@ -18,97 +21,111 @@ pub fn get_log_path() -> PathBuf {
     LOG_PATH.get_or_init(compute_log_path).clone()
 }
 
+/// Get the file persisting `server_usage` counts, next to the log file.
+///
+/// Format: `{XDG_STATE_HOME}/nb-mcp/{project}--{worktree}.usage.json`
+pub fn get_usage_path() -> PathBuf {
+    get_log_path().with_extension("usage.json")
+}
+
 /// Compute the log path based on Git repository detection.
 fn compute_log_path() -> PathBuf {
     let state_dir = xdg_state_home().join("nb-mcp");
 
     // Try to get a unique name from Git info
     let log_name = match detect_git_info() {
-        Some((project, worktree)) => {
-            if project == worktree {
-                format!("{}.log", project)
-            } else {
-                format!("{}--{}.log", project, worktree)
-            }
-        }
+        Some((project, worktree)) => format!("{}.log", worktree_name(&project, &worktree)),
         None => "nb-mcp.log".to_string(),
     };
 
     state_dir.join(log_name)
 }
 
+/// Name for a project worktree: `{project}--{worktree}`, or just `{project}`
+/// for the master worktree.
+///
+/// Both parts are sanitized. Used for log files and per-worktree notebooks.
+pub fn worktree_name(project: &str, worktree: &str) -> String {
+    let project = sanitize_name(project);
+    let worktree = sanitize_name(worktree);
+    if project == worktree {
+        project
+    } else {
+        format!("{}--{}", project, worktree)
+    }
+}
+
 /// Detect Git project name and worktree basename.
 ///
 /// Returns `(project_name, worktree_basename)` where:
-/// - `project_name` is derived from the master repo directory name
+/// - `project_name` is derived from the main repository (see [`git_checkout`])
 /// - `worktree_basename` is the current worktree directory name
 ///
 /// These are the same for non-worktree repos.
 fn detect_git_info() -> Option<(String, String)> {
-    // Get current worktree root
-    let current_root = git_rev_parse(&["--show-toplevel"])?;
-
-    // Get common git directory (may be relative)
-    let git_common_dir = git_rev_parse(&["--git-common-dir"])?;
+    let checkout = git_checkout(Path::new("."))?;
+    let worktree_name = checkout.worktree_root.file_name()?.to_str()?;
+    Some((
+        sanitize_name(&checkout.project),
+        sanitize_name(worktree_name),
+    ))
+}
 
-    // Resolve git_common_dir relative to current_root if it's relative
-    let git_common_dir = if git_common_dir.is_relative() {
-        current_root.join(&git_common_dir)
-    } else {
-        git_common_dir
-    };
+/// Returns the root of the current Git worktree, if any.
+pub fn git_toplevel() -> Option<PathBuf> {
+    git_checkout(Path::new(".")).map(|checkout| checkout.worktree_root)
+}
 
-    // Canonicalize to resolve any .. or symlinks
-    let git_common_dir = git_common_dir.canonicalize().ok()?;
+/// A Git worktree and the project it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitCheckout {
+    /// Name of the project: the main repository's directory, without a
+    /// `.git` suffix for bare repositories.
+    pub project: String,
+    /// Canonical root of the worktree containing the directory.
+    pub worktree_root: PathBuf,
+}
 
-    // Master root is parent of git_common_dir when it ends with .git
-    let master_root = if git_common_dir.file_name().is_some_and(|n| n == ".git") {
-        git_common_dir.parent()?.to_path_buf()
-    } else {
-        // Bare repo or unusual structure - fall back to current
-        current_root.clone()
+/// Finds the Git worktree containing `dir`, reading the repository directly.
+///
+/// Linked worktrees belong to their main repository, whether that has a
+/// worktree of its own or is bare (`project.git`, or `project/.bare`).
+/// Submodule checkouts are projects of their own. Bare repositories have no
+/// worktree, so `None` is returned for them, as outside any repository.
+pub fn git_checkout(dir: &Path) -> Option<GitCheckout> {
+    let repository = gix::discover_with_environment_overrides(dir).ok()?;
+    let worktree_root = repository.workdir()?.canonicalize().ok()?;
+    let project = match repository.kind() {
+        gix::repository::Kind::WorkTree { is_linked: true } => {
+            main_repository_name(&repository.common_dir().canonicalize().ok()?)?
+        }
+        gix::repository::Kind::WorkTree { is_linked: false } | gix::repository::Kind::Submodule => {
+            worktree_root.file_name()?.to_str()?.to_string()
+        }
+        gix::repository::Kind::Bare => return None,
     };
-
-    // Extract directory names
-    let project_name = master_root
-        .file_name()
-        .and_then(|n| n.to_str())
-        .map(sanitize_name)?;
-
-    let worktree_name = current_root
-        .file_name()
-        .and_then(|n| n.to_str())
-        .map(sanitize_name)?;
-
-    Some((project_name, worktree_name))
+    Some(GitCheckout {
+        project,
+        worktree_root,
+    })
 }
 
-/// Run `git rev-parse` with the given arguments and return the output as a path.
-fn git_rev_parse(args: &[&str]) -> Option<PathBuf> {
-    let output = Command::new("git")
-        .args(["rev-parse"])
-        .args(args)
-        .output()
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
-
-    let stdout = String::from_utf8(output.stdout).ok()?;
-    let path = stdout.trim();
-
-    if path.is_empty() {
-        return None;
-    }
-
-    Some(PathBuf::from(path))
+/// Names the project owning a linked worktree from its common Git directory.
+fn main_repository_name(common_dir: &Path) -> Option<String> {
+    let name = common_dir.file_name()?.to_str()?;
+    let name = match name.strip_suffix(".git") {
+        Some(stem) if !stem.is_empty() => stem,
+        // `.git` of a main worktree, or a hidden bare directory like `.bare`.
+        _ if name.starts_with('.') => common_dir.parent()?.file_name()?.to_str()?,
+        _ => name,
+    };
+    Some(name.to_string())
 }
 
 /// Sanitize a name for use in a filename.
 ///
 /// Replaces problematic characters with dashes.
-fn sanitize_name(name: &str) -> String {
+pub fn sanitize_name(name: &str) -> String {
     name.chars()
         .map(|c| {
             if c.is_alphanumeric() || c == '-' || c == '_' {
@@ -154,6 +171,54 @@ mod tests {
         assert_eq!(sanitize_name("my/project"), "my-project");
     }
 
+    #[test]
+    fn test_worktree_name() {
+        assert_eq!(worktree_name("nb-mcp", "nb-mcp"), "nb-mcp");
+        assert_eq!(worktree_name("nb-mcp", "fix bug"), "nb-mcp--fix-bug");
+    }
+
+    #[test]
+    fn test_main_repository_name() {
+        let name = |path: &str| main_repository_name(Path::new(path));
+        assert_eq!(name("/src/nb-mcp/.git").as_deref(), Some("nb-mcp"));
+        assert_eq!(name("/src/nb-mcp.git").as_deref(), Some("nb-mcp"));
+        assert_eq!(name("/src/nb-mcp/.bare").as_deref(), Some("nb-mcp"));
+        assert_eq!(name("/srv/repos/nb-mcp").as_deref(), Some("nb-mcp"));
+    }
+
+    #[test]
+    fn test_git_checkout_of_linked_worktree() {
+        let dir = std::env::temp_dir().join(format!("nb-mcp-checkout-{}", std::process::id()));
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .current_dir(&dir)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
+        std::fs::create_dir_all(&dir).unwrap();
+        git(&["init", "-q", "main"]);
+        git(&["-C", "main", "commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&[
+            "-C",
+            "main",
+            "worktree",
+            "add",
+            "-q",
+            "-b",
+            "fix",
+            "../fix bug",
+        ]);
+        let checkout = git_checkout(&dir.join("fix bug")).unwrap();
+        assert_eq!(checkout.project, "main");
+        assert!(checkout.worktree_root.ends_with("fix bug"));
+        assert_eq!(git_checkout(&dir.join("main")).unwrap().project, "main");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_log_path_has_expected_structure() {
         let path = get_log_path();
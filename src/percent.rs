@@ -0,0 +1,48 @@
+//! Percent-encoding for note paths in URLs and URIs.
+//!
+//! Selectors and file paths are placed in preview URLs and `nb://` resource
+//! URIs segment by segment, so `/` is kept while every other reserved or
+//! non-ASCII byte is escaped.
+
+/// Percent-encodes a path, keeping `/` between segments.
+pub fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Decodes a percent-encoded path; `None` if it is malformed or not UTF-8.
+pub fn decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = text.get(index + 1..index + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+
+    #[test]
+    fn encode_round_trips_paths() {
+        let encoded = encode("docs/My note #2.md");
+        assert_eq!(encoded, "docs/My%20note%20%232.md");
+        assert_eq!(decode(&encoded).as_deref(), Some("docs/My note #2.md"));
+        assert!(decode("%G1").is_none());
+    }
+}
@@ -0,0 +1,132 @@
+//! Startup checks run before the server accepts a client.
+//!
+//! Without them a missing nb or an unresolvable notebook only shows up on the
+//! first tool call, which many clients render as an opaque failure. The
+//! server instead verifies its environment up front, logs a one-line summary,
+//! and exits with every problem listed when something is wrong.
+
+use std::fmt;
+
+use tracing::{info, warn};
+
+use crate::nb::{NbClient, NbError};
+
+/// Problems found by a failed preflight.
+#[derive(Debug)]
+pub struct PreflightError {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for PreflightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "startup checks failed")?;
+        for problem in &self.problems {
+            write!(f, "\n  - {problem}")?;
+        }
+        write!(f, "\n(pass --no-preflight to start anyway)")
+    }
+}
+
+impl std::error::Error for PreflightError {}
+
+/// Checks that nb and git run and that the notebooks to serve resolve.
+///
+/// Missing notebooks are created when the client's creation policy allows
+/// it. Without served notebooks, only the default notebook is checked; a
+/// missing default is not a problem, since calls may name their notebook.
+/// Returns the `nb --version` output.
+pub async fn run(nb: &NbClient, served_notebooks: &[String]) -> Result<String, PreflightError> {
+    let mut problems = Vec::new();
+    let nb_version = match nb.version().await {
+        Ok(version) => Some(version),
+        Err(err @ NbError::NotFound) => {
+            problems.push(err.to_string());
+            None
+        }
+        Err(err) => {
+            problems.push(format!("nb is installed but could not run: {err}"));
+            None
+        }
+    };
+    let git_version = nb
+        .git_version()
+        .await
+        .map_err(|err| problems.push(format!("git could not run: {err}")))
+        .ok();
+    let mut notebook = None;
+    // Notebook checks need a working nb.
+    if nb_version.is_some() {
+        if served_notebooks.is_empty() {
+            if nb.default_notebook().is_some() {
+                match nb.resolve_notebook(None).await {
+                    Ok(name) => notebook = Some(name),
+                    Err(err) => problems.push(format!("default notebook: {err}")),
+                }
+            } else {
+                warn!("no default notebook configured; calls must pass a notebook");
+            }
+        }
+        for name in served_notebooks {
+            if let Err(err) = nb.resolve_notebook(Some(name)).await {
+                problems.push(format!("served notebook {name}: {err}"));
+            }
+        }
+    }
+    match (nb_version, git_version) {
+        (Some(nb_version), Some(git_version)) if problems.is_empty() => {
+            info!(
+                nb = %nb_version,
+                git = %git_version,
+                notebook = notebook.as_deref().unwrap_or("(none)"),
+                served = served_notebooks.len(),
+                "preflight passed"
+            );
+            Ok(nb_version)
+        }
+        _ => Err(PreflightError { problems }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::run;
+    use crate::{FakeExecutor, NbClient};
+
+    #[tokio::test]
+    async fn run_creates_missing_default_notebook() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .respond(&["--version"], "7.12.1")
+                .respond(&["notebooks", "add"], "Added"),
+        );
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), true);
+        assert_eq!(run(&nb, &[]).await.unwrap(), "7.12.1");
+        assert!(
+            fake.calls()
+                .iter()
+                .any(|args| args.starts_with(&["notebooks".to_string(), "add".to_string()]))
+        );
+    }
+
+    #[tokio::test]
+    async fn run_lists_every_problem() {
+        let fake = Arc::new(FakeExecutor::new().respond(&["--version"], "7.12.1"));
+        let nb = NbClient::with_executor(fake, None, false);
+        let served = ["work".to_string(), "home".to_string()];
+        let error = run(&nb, &served).await.unwrap_err();
+        assert_eq!(error.problems.len(), 2);
+        assert!(error.to_string().contains("served notebook home"));
+    }
+
+    #[tokio::test]
+    async fn run_skips_notebooks_without_nb() {
+        let fake = Arc::new(FakeExecutor::new().fail(&["--version"], "bad interpreter"));
+        let nb = NbClient::with_executor(fake.clone(), Some("work".to_string()), true);
+        let error = run(&nb, &[]).await.unwrap_err();
+        assert_eq!(error.problems.len(), 1);
+        assert!(error.problems[0].contains("could not run"));
+        assert_eq!(fake.calls().len(), 1);
+    }
+}
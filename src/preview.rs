@@ -0,0 +1,300 @@
+//! Local HTTP server rendering notes as HTML.
+//!
+//! With `--preview-port <PORT>`, the server listens on `127.0.0.1` and
+//! renders notes for browsers; `nb.preview_url` returns a note's link so chat
+//! clients can show it. URLs carry a random token so other local users and
+//! web pages cannot guess them, and a content security policy keeps scripts
+//! embedded in notes from running. Rendering a note is a `show`: when the
+//! project configuration does not allow `show`, or asks for confirmation,
+//! previews are refused with 403.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use pulldown_cmark::{Options, Parser, html};
+use quick_xml::escape::escape;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, warn};
+
+use crate::nb::NbClient;
+use crate::notes;
+use crate::percent::{decode, encode};
+use crate::reload::Policy;
+
+/// Largest request head read; the rest is ignored.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Allows images and inline styles, and nothing else.
+const CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; img-src * data:; style-src 'unsafe-inline'";
+
+const STYLE: &str = "body{max-width:46rem;margin:2rem auto;padding:0 1rem;\
+font-family:system-ui,sans-serif;line-height:1.5}\
+pre,code{background:#f4f4f4}pre{overflow-x:auto;padding:.75rem}img{max-width:100%}\
+table{border-collapse:collapse}td,th{border:1px solid #ccc;padding:.25rem .5rem}";
+
+/// A running preview server.
+#[derive(Debug)]
+pub struct Preview {
+    /// `http://127.0.0.1:<port>/<token>`
+    base_url: String,
+}
+
+impl Preview {
+    /// Returns the URL prefix of every preview link.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns the URL rendering note `id` of `notebook`.
+    pub fn url(&self, notebook: &str, id: &str) -> String {
+        format!(
+            "{}/{}/{}",
+            self.base_url,
+            encode(notebook),
+            encode(id.trim_matches('/'))
+        )
+    }
+}
+
+/// Starts the preview server on `port` (0 picks a free port).
+///
+/// With `served_notebooks` set, notes of other notebooks are not served;
+/// `policy` is read on every request, so reloads apply at once.
+pub(crate) async fn spawn(
+    nb: NbClient,
+    port: u16,
+    served_notebooks: Vec<String>,
+    policy: Arc<RwLock<Policy>>,
+) -> std::io::Result<Arc<Preview>> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    let port = listener.local_addr()?.port();
+    let token = new_token();
+    let preview = Arc::new(Preview {
+        base_url: format!("http://127.0.0.1:{port}/{token}"),
+    });
+    let served = Arc::new(served_notebooks);
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    warn!(error = %err, "preview connection failed");
+                    continue;
+                }
+            };
+            let (nb, token, served, policy) =
+                (nb.clone(), token.clone(), served.clone(), policy.clone());
+            tokio::spawn(async move {
+                if let Err(err) = handle(stream, &nb, &token, &served, &policy).await {
+                    debug!(error = %err, "preview request failed");
+                }
+            });
+        }
+    });
+    Ok(preview)
+}
+
+fn new_token() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    hasher.write_u128(nanos);
+    hasher.write_u32(std::process::id());
+    let first = hasher.finish();
+    hasher.write_u64(first);
+    format!("{first:016x}{:016x}", hasher.finish())
+}
+
+async fn handle(
+    mut stream: TcpStream,
+    nb: &NbClient,
+    token: &str,
+    served: &[String],
+    policy: &RwLock<Policy>,
+) -> std::io::Result<()> {
+    let mut head = Vec::new();
+    let mut chunk = [0; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 || head.len() + read > MAX_REQUEST_BYTES {
+            break;
+        }
+        head.extend_from_slice(&chunk[..read]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let target = head
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("GET "))
+        .and_then(|rest| rest.split_whitespace().next());
+    let (status, body) = match target {
+        Some(target) => respond(nb, token, served, policy, target).await,
+        None => (
+            "405 Method Not Allowed",
+            page("Method not allowed", "<p>Only GET is supported.</p>"),
+        ),
+    };
+    let header = format!(
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: text/html; charset=utf-8\r\n\
+         Content-Security-Policy: {CONTENT_SECURITY_POLICY}\r\n\
+         X-Content-Type-Options: nosniff\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Renders the note named by `target`, a `/<token>/<notebook>/<id>` path.
+async fn respond(
+    nb: &NbClient,
+    token: &str,
+    served: &[String],
+    policy: &RwLock<Policy>,
+    target: &str,
+) -> (&'static str, String) {
+    let not_found = || ("404 Not Found", page("Not found", "<p>No such note.</p>"));
+    let Some((notebook, id)) = target
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.strip_prefix('/'))
+        .and_then(|path| path.strip_prefix(token))
+        .and_then(|path| path.strip_prefix('/'))
+        .and_then(|path| path.split_once('/'))
+    else {
+        return not_found();
+    };
+    let (Some(notebook), Some(id)) = (decode(notebook), decode(id)) else {
+        return not_found();
+    };
+    if !policy.read().expect("policy lock poisoned").permits("show") {
+        return (
+            "403 Forbidden",
+            page(
+                "Forbidden",
+                "<p>Showing notes is not allowed by the project configuration.</p>",
+            ),
+        );
+    }
+    if id.is_empty() || (!served.is_empty() && !served.contains(&notebook)) {
+        return not_found();
+    }
+    // Never let a request create a notebook.
+    if !nb.notebook_exists(&notebook).await.unwrap_or(false) {
+        return not_found();
+    }
+    match nb.show(&id, Some(&notebook)).await {
+        Ok(content) => ("200 OK", render(&content, &id)),
+        Err(err) => {
+            debug!(%notebook, %id, error = %err, "preview note not shown");
+            not_found()
+        }
+    }
+}
+
+/// Renders a Markdown note as a standalone HTML page.
+fn render(markdown: &str, id: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut body = String::new();
+    html::push_html(&mut body, Parser::new_ext(markdown, options));
+    let title = notes::derive_title(markdown).unwrap_or_else(|| id.to_string());
+    page(&title, &body)
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title>\
+         <style>{STYLE}</style></head>\n<body>\n{body}</body></html>\n",
+        escape(title)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, RwLock};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    use super::{render, spawn};
+    use crate::project_config::Permission;
+    use crate::reload::Policy;
+    use crate::{FakeExecutor, NbClient};
+
+    async fn get(url: &str) -> String {
+        let rest = url.strip_prefix("http://").unwrap();
+        let (host, path) = rest.split_at(rest.find('/').unwrap());
+        let mut stream = TcpStream::connect(host).await.unwrap();
+        let request = format!("GET {path} HTTP/1.1\r\nHost: {host}\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn render_escapes_title() {
+        let html = render("# A <b> plan\n\n| a |\n|---|\n| 1 |\n", "3");
+        assert!(html.contains("<title>A &lt;b&gt; plan</title>"));
+        assert!(html.contains("<table>"));
+    }
+
+    #[tokio::test]
+    async fn serves_notes_under_token() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["show", "work:docs/3"], "# Plan\n\nShip *v2*.\n"),
+        );
+        let nb = NbClient::with_executor(fake, None, false);
+        let policy = Arc::new(RwLock::new(Policy::default()));
+        let preview = spawn(nb, 0, Vec::new(), policy).await.unwrap();
+        let url = preview.url("work", "docs/3");
+        let response = get(&url).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.contains("<em>v2</em>"));
+
+        let token = url.split('/').nth(3).unwrap();
+        let guessed = url.replace(token, &"0".repeat(32));
+        assert!(get(&guessed).await.starts_with("HTTP/1.1 404"));
+        let other = preview.url("home", "docs/3");
+        assert!(get(&other).await.starts_with("HTTP/1.1 404"));
+    }
+
+    #[tokio::test]
+    async fn refuses_notes_the_policy_does_not_show() {
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", "/notes/work")
+                .respond(&["show", "work:3"], "# Plan\n"),
+        );
+        let nb = NbClient::with_executor(fake.clone(), None, false);
+        let policy = Arc::new(RwLock::new(Policy {
+            allowed_commands: Some(vec!["list".to_string()]),
+            ..Policy::default()
+        }));
+        let preview = spawn(nb, 0, Vec::new(), policy.clone()).await.unwrap();
+        let url = preview.url("work", "3");
+        assert!(get(&url).await.starts_with("HTTP/1.1 403"));
+
+        *policy.write().unwrap() = Policy {
+            permissions: BTreeMap::from([("show".to_string(), Permission::Confirm)]),
+            ..Policy::default()
+        };
+        assert!(get(&url).await.starts_with("HTTP/1.1 403"));
+        assert!(fake.calls().iter().all(|call| call[0] != "show"));
+
+        *policy.write().unwrap() = Policy::default();
+        assert!(get(&url).await.starts_with("HTTP/1.1 200 OK"));
+    }
+}
@@ -0,0 +1,327 @@
+//! Per-project configuration file.
+//!
+//! When the server starts inside a Git repository, `.nb-mcp.toml` at the
+//! repository root is merged over the environment and built-in defaults, so
+//! teams can commit their notebook policy alongside their code. Command-line
+//! flags still take precedence over the file.
+//!
+//! ```toml
+//! notebook = "myproject"
+//! worktree_notebooks = false
+//! default_folder = "inbox"
+//! default_tags = ["ai"]
+//! allowed_commands = ["status", "add", "show", "list", "search"]
+//! board_columns = ["backlog", "doing", "review", "done"]
+//! link_style = "wiki"
+//! backlinks = true
+//!
+//! scaffold = true
+//!
+//! instructions = "Notes for this repository. Tag design notes #design."
+//!
+//! [help]
+//! add = "Create a note. Put design notes in the design/ folder."
+//!
+//! [permissions]
+//! delete = "deny"
+//! board_move = "confirm"
+//!
+//! [views.open-issues]
+//! tags = ["client", "issue"]
+//! sort = "modified"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::Config;
+use crate::args::SavedView;
+use crate::backup::BackupConfig;
+use crate::links::LinkStyle;
+use crate::paths;
+use crate::scaffold::Scaffold;
+
+/// Name of the project configuration file at the repository root.
+pub const FILE_NAME: &str = ".nb-mcp.toml";
+
+/// Settings read from a project configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Default notebook for the project, or a fallback chain of notebooks.
+    pub notebook: Option<NotebookSetting>,
+    /// Derive a separate notebook per Git worktree.
+    pub worktree_notebooks: Option<bool>,
+    /// Never create notebooks, even for calls that ask to.
+    pub strict_notebooks: Option<bool>,
+    /// Folder for notes and todos created without one.
+    pub default_folder: Option<String>,
+    /// Tags added to every note and todo.
+    pub default_tags: Option<Vec<String>>,
+    /// Notebooks served to clients, each call targeting one of them.
+    pub serve_notebooks: Option<Vec<String>>,
+    /// Derive titles for untitled notes from their content.
+    pub auto_title: Option<bool>,
+    /// Sync with the notebook remote after every write.
+    pub auto_sync: Option<bool>,
+    /// Subcommands clients may call; all others are rejected.
+    pub allowed_commands: Option<Vec<String>>,
+    /// Commit message template for mutations.
+    pub commit_message: Option<String>,
+    /// `nb` calls admitted per minute.
+    pub max_calls_per_minute: Option<u32>,
+    /// Mutating `nb` calls admitted per session.
+    pub max_mutations: Option<u32>,
+    /// Saved views, by name.
+    #[serde(default)]
+    pub views: BTreeMap<String, SavedView>,
+    /// Seed new notebooks with the standard structure, or a custom one.
+    pub scaffold: Option<ScaffoldSetting>,
+    /// Scheduled pushes or archives of the notebook.
+    pub backup: Option<BackupConfig>,
+    /// Status tags naming the board columns, in order.
+    pub board_columns: Option<Vec<String>>,
+    /// Syntax of links written between notes: "markdown" or "wiki".
+    pub link_style: Option<LinkStyle>,
+    /// Maintain `## Backlinks` sections after every write.
+    pub backlinks: Option<bool>,
+    /// Replacement for the server instructions sent at initialize.
+    pub instructions: Option<String>,
+    /// Replacement for the `nb` tool description.
+    pub tool_description: Option<String>,
+    /// Replacement help descriptions, by subcommand.
+    #[serde(default)]
+    pub help: BTreeMap<String, String>,
+    /// Per-subcommand permission; subcommands not listed are allowed.
+    #[serde(default)]
+    pub permissions: BTreeMap<String, Permission>,
+}
+
+/// What the server does when a client calls a subcommand.
+///
+/// Ordered from least to most restrictive.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, serde::Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    /// Run the call.
+    #[default]
+    Allow,
+    /// Run the call only after the user confirms it.
+    Confirm,
+    /// Reject the call.
+    Deny,
+}
+
+/// Returns the permission for `subcommand` (with or without `nb.`).
+pub fn permission(permissions: &BTreeMap<String, Permission>, subcommand: &str) -> Permission {
+    let subcommand = subcommand.strip_prefix("nb.").unwrap_or(subcommand);
+    permissions
+        .iter()
+        .find(|(name, _)| name.strip_prefix("nb.").unwrap_or(name) == subcommand)
+        .map(|(_, permission)| *permission)
+        .unwrap_or_default()
+}
+
+/// A single notebook name or an ordered fallback chain.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum NotebookSetting {
+    Name(String),
+    Chain(Vec<String>),
+}
+
+/// Whether to scaffold new notebooks, or the structure to use.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ScaffoldSetting {
+    Enabled(bool),
+    Custom(Scaffold),
+}
+
+impl NotebookSetting {
+    /// Returns the setting in `--notebook` syntax (comma-separated).
+    pub fn to_chain(&self) -> String {
+        match self {
+            Self::Name(name) => name.clone(),
+            Self::Chain(names) => names.join(","),
+        }
+    }
+}
+
+impl ProjectConfig {
+    /// Fills settings in `config` that were not given on the command line.
+    ///
+    /// `config` must hold only command-line values at this point; environment
+    /// fallbacks are applied afterwards so the file wins over them.
+    pub fn merge_into(self, config: &mut Config) {
+        if config.notebook.is_none() {
+            config.notebook = self.notebook.as_ref().map(NotebookSetting::to_chain);
+        }
+        if let Some(per_worktree) = self.worktree_notebooks {
+            config.notebook_derivation.per_worktree |= per_worktree;
+        }
+        if let Some(strict) = self.strict_notebooks {
+            config.strict_notebooks |= strict;
+        }
+        if let Some(auto_title) = self.auto_title {
+            config.auto_title |= auto_title;
+        }
+        if let Some(auto_sync) = self.auto_sync {
+            config.auto_sync |= auto_sync;
+        }
+        if config.default_folder.is_none() {
+            config.default_folder = self.default_folder;
+        }
+        if config.default_tags.is_empty() {
+            config.default_tags = self.default_tags.unwrap_or_default();
+        }
+        if config.served_notebooks.is_empty() {
+            config.served_notebooks = self.serve_notebooks.unwrap_or_default();
+        }
+        if config.commit_message_template.is_none() {
+            config.commit_message_template = self.commit_message;
+        }
+        if config.max_calls_per_minute.is_none() {
+            config.max_calls_per_minute = self.max_calls_per_minute;
+        }
+        if config.max_mutations.is_none() {
+            config.max_mutations = self.max_mutations;
+        }
+        if config.allowed_commands.is_none() {
+            config.allowed_commands = self.allowed_commands;
+        }
+        config.views.extend(self.views);
+        config.permissions.extend(self.permissions);
+        if self.instructions.is_some() {
+            config.instructions = self.instructions;
+        }
+        if self.tool_description.is_some() {
+            config.tool_description = self.tool_description;
+        }
+        config.help.extend(self.help);
+        match self.scaffold {
+            Some(ScaffoldSetting::Custom(scaffold)) => config.scaffold = Some(scaffold),
+            Some(ScaffoldSetting::Enabled(true)) => {
+                config.scaffold.get_or_insert_with(Scaffold::default);
+            }
+            Some(ScaffoldSetting::Enabled(false)) | None => {}
+        }
+        if self.backup.is_some() {
+            config.backup = self.backup;
+        }
+        if let Some(columns) = self.board_columns {
+            config.board_columns = columns;
+        }
+        if let Some(style) = self.link_style {
+            config.link_style = style;
+        }
+        if let Some(backlinks) = self.backlinks {
+            config.backlinks |= backlinks;
+        }
+    }
+}
+
+/// Returns the project configuration file for the current repository, if any.
+pub fn discover() -> Option<PathBuf> {
+    let path = paths::git_toplevel()?.join(FILE_NAME);
+    path.is_file().then_some(path)
+}
+
+/// Reads and parses a project configuration file.
+pub fn load(path: &Path) -> Result<ProjectConfig> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    parse(&text).with_context(|| format!("invalid project configuration {}", path.display()))
+}
+
+fn parse(text: &str) -> Result<ProjectConfig> {
+    Ok(toml::from_str(text)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use crate::Config;
+
+    #[test]
+    fn merge_keeps_command_line_values() {
+        let project = parse(
+            r#"
+            notebook = "team"
+            default_folder = "inbox"
+            allowed_commands = ["show"]
+            "#,
+        )
+        .unwrap();
+        let mut config = Config {
+            notebook: Some("mine".to_string()),
+            ..Config::default()
+        };
+        project.merge_into(&mut config);
+        assert_eq!(config.notebook.as_deref(), Some("mine"));
+        assert_eq!(config.default_folder.as_deref(), Some("inbox"));
+        assert_eq!(config.allowed_commands, Some(vec!["show".to_string()]));
+    }
+
+    #[test]
+    fn notebook_accepts_fallback_chain() {
+        let project = parse(r#"notebook = ["@git", "inbox"]"#).unwrap();
+        let mut config = Config::default();
+        project.merge_into(&mut config);
+        assert_eq!(config.notebook.as_deref(), Some("@git,inbox"));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_keys() {
+        assert!(parse("notebok = \"typo\"").is_err());
+    }
+
+    #[test]
+    fn views_are_merged_by_name() {
+        let project = parse(
+            r#"
+            [views.open-issues]
+            tags = ["client", "issue"]
+            sort = "modified"
+            "#,
+        )
+        .unwrap();
+        let mut config = Config::default();
+        project.merge_into(&mut config);
+        let view = &config.views["open-issues"];
+        assert_eq!(view.tags, vec!["client", "issue"]);
+        assert_eq!(view.sort.as_deref(), Some("modified"));
+        assert!(view.query.is_none());
+    }
+
+    #[test]
+    fn backup_schedule_is_validated() {
+        let project = parse("[backup]\nschedule = \"0 3 * * *\"\nkeep = 7").unwrap();
+        assert_eq!(project.backup.unwrap().keep, Some(7));
+        assert!(parse("[backup]\nschedule = \"daily\"").is_err());
+    }
+
+    #[test]
+    fn scaffold_accepts_flag_or_definition() {
+        let mut config = Config::default();
+        parse("scaffold = true").unwrap().merge_into(&mut config);
+        assert!(
+            config
+                .scaffold
+                .unwrap()
+                .folders
+                .contains(&"inbox".to_string())
+        );
+
+        let mut config = Config::default();
+        parse("[scaffold]\nfolders = [\"notes\"]")
+            .unwrap()
+            .merge_into(&mut config);
+        assert_eq!(config.scaffold.unwrap().folders, vec!["notes"]);
+    }
+}
@@ -0,0 +1,262 @@
+//! Boolean filter expressions for list and search.
+//!
+//! nb filters by a single tag set and one search pattern. A `filter` such as
+//! `tag:client AND (tag:urgent OR title:"weekly sync") AND NOT text:draft`
+//! is parsed here and evaluated against each item nb returned, using the
+//! item's file for tags and text.
+//!
+//! Terms are `tag:<name>`, `title:<text>`, `text:<text>`, or a bare word
+//! (same as `text:`). Values may be double-quoted. `AND`, `OR`, and `NOT`
+//! must be uppercase; adjacent terms are joined by `AND`. Matching is
+//! case-insensitive; `title:` and `text:` match substrings.
+
+use crate::nb::NbError;
+use crate::nb_index::{self, ItemResolver};
+use crate::notes;
+
+/// A parsed filter expression.
+#[derive(Debug, PartialEq)]
+pub enum Expr {
+    Tag(String),
+    Title(String),
+    Text(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+/// What an expression is evaluated against.
+pub struct Item<'a> {
+    pub title: &'a str,
+    pub tags: &'a [String],
+    pub content: &'a str,
+}
+
+impl Expr {
+    pub fn matches(&self, item: &Item) -> bool {
+        match self {
+            Self::Tag(tag) => item.tags.iter().any(|t| t.to_lowercase() == *tag),
+            Self::Title(text) => item.title.to_lowercase().contains(text),
+            Self::Text(text) => item.content.to_lowercase().contains(text),
+            Self::Not(inner) => !inner.matches(item),
+            Self::And(a, b) => a.matches(item) && b.matches(item),
+            Self::Or(a, b) => a.matches(item) || b.matches(item),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(Expr),
+}
+
+/// Parses a filter expression.
+pub fn parse(input: &str) -> Result<Expr, NbError> {
+    let tokens = tokenize(input).map_err(invalid)?;
+    let mut parser = Parser {
+        tokens: tokens.into_iter().peekable(),
+    };
+    let expr = parser.or().map_err(invalid)?;
+    if parser.tokens.peek().is_some() {
+        return Err(invalid("unexpected ')'".to_string()));
+    }
+    Ok(expr)
+}
+
+fn invalid(message: String) -> NbError {
+    NbError::CommandFailed(format!("invalid filter: {message}"))
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                let mut word = String::new();
+                let mut quoted = false;
+                while let Some(&c) = chars.peek() {
+                    if c == '"' {
+                        chars.next();
+                        quoted = true;
+                        let mut closed = false;
+                        for c in chars.by_ref() {
+                            if c == '"' {
+                                closed = true;
+                                break;
+                            }
+                            word.push(c);
+                        }
+                        if !closed {
+                            return Err("unterminated quote".to_string());
+                        }
+                    } else if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    } else {
+                        word.push(c);
+                        chars.next();
+                    }
+                }
+                tokens.push(match word.as_str() {
+                    "AND" if !quoted => Token::And,
+                    "OR" if !quoted => Token::Or,
+                    "NOT" if !quoted => Token::Not,
+                    _ => Token::Term(term(&word)?),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn term(word: &str) -> Result<Expr, String> {
+    let (field, value) = word.split_once(':').unwrap_or(("text", word));
+    let value = value.to_lowercase();
+    if value.is_empty() {
+        return Err(format!("{field}: needs a value"));
+    }
+    match field {
+        "tag" => Ok(Expr::Tag(value.trim_start_matches('#').to_string())),
+        "title" => Ok(Expr::Title(value)),
+        "text" => Ok(Expr::Text(value)),
+        // Not a field (e.g., a URL): search for the whole word.
+        _ => Ok(Expr::Text(word.to_lowercase())),
+    }
+}
+
+/// Recursive-descent parser; `OR` binds looser than `AND`, `NOT` tightest.
+struct Parser {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.tokens.next_if_eq(&Token::Or).is_some() {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        loop {
+            match self.tokens.peek() {
+                Some(Token::And) => {
+                    self.tokens.next();
+                }
+                Some(Token::Term(_) | Token::Not | Token::Open) => {}
+                _ => return Ok(expr),
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        let Some(token) = self.tokens.next() else {
+            return Err("unexpected end of filter".to_string());
+        };
+        match token {
+            Token::Not => Ok(Expr::Not(Box::new(self.unary()?))),
+            Token::Open => {
+                let expr = self.or()?;
+                if self.tokens.next_if_eq(&Token::Close).is_none() {
+                    return Err("missing ')'".to_string());
+                }
+                Ok(expr)
+            }
+            Token::Term(expr) => Ok(expr),
+            Token::Close => Err("unexpected ')'".to_string()),
+            Token::And | Token::Or => Err("operator without a left-hand term".to_string()),
+        }
+    }
+}
+
+/// Keeps the items of nb list or search output that match `expr`.
+///
+/// Search match lines (`N:text`) and header underlines stay with their item;
+/// other lines are dropped. Items whose file cannot be read do not match.
+pub fn filter_output(output: &str, resolver: &mut ItemResolver, expr: &Expr) -> String {
+    let mut kept = String::new();
+    let mut keeping = false;
+    for line in output.lines() {
+        if let Some((id, title)) = nb_index::split_item(line) {
+            let content = resolver
+                .resolve(id)
+                .and_then(|path| std::fs::read_to_string(path).ok());
+            keeping = content.is_some_and(|content| {
+                let tags = notes::extract_tags(&content);
+                expr.matches(&Item {
+                    title,
+                    tags: &tags,
+                    content: &content,
+                })
+            });
+        } else {
+            let attached = line.chars().all(|c| c == '-')
+                || line
+                    .split_once(':')
+                    .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+            if !attached {
+                continue;
+            }
+        }
+        if keeping {
+            kept.push_str(line);
+            kept.push('\n');
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Expr, Item, parse};
+
+    #[test]
+    fn parse_respects_precedence_and_grouping() {
+        let expr = parse(r#"tag:foo AND (tag:bar OR title:"Weekly Sync") NOT draft"#).unwrap();
+        let tags = vec!["foo".to_string(), "baz".to_string()];
+        let item = |title, content| Item {
+            title,
+            tags: &tags,
+            content,
+        };
+        assert!(expr.matches(&item("Weekly sync notes", "body")));
+        assert!(!expr.matches(&item("Weekly sync notes", "a Draft")));
+        assert!(!expr.matches(&item("Other", "body")));
+        assert_eq!(
+            parse("a OR b c").unwrap(),
+            Expr::Or(
+                Box::new(Expr::Text("a".to_string())),
+                Box::new(Expr::And(
+                    Box::new(Expr::Text("b".to_string())),
+                    Box::new(Expr::Text("c".to_string()))
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn parse_reports_malformed_filters() {
+        for filter in ["(tag:a", "tag:a)", "AND tag:a", "title:", "\"open"] {
+            assert!(parse(filter).is_err(), "{filter}");
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Call rate and mutation budget limits.
+//!
+//! A runaway agent loop can flood a notebook with notes and commits. With
+//! `--max-calls-per-minute` or `--max-mutations` (or their environment
+//! variables and `.nb-mcp.toml` keys), `nb` calls beyond the limit are
+//! refused with a structured error instead of running: calls over a sliding
+//! one-minute window say when to retry, and mutations beyond the session's
+//! budget are refused until the server restarts. A limit of 0 is no limit.
+//! Refused calls do not count toward either limit, and a mutation that fails
+//! (including one rejected by the project policy or its arguments) is
+//! refunded to the budget.
+
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+/// Window the call rate is measured over.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Commands that spend the mutation budget without committing notes
+/// themselves: pulls merge others' commits and clones add notebooks.
+const BUDGETED_COMMANDS: &[&str] = &["pull", "notebook_clone"];
+
+/// Returns true if a call of `command` spends the mutation budget, given
+/// whether it is expected to commit.
+pub fn is_budgeted(command: &str, mutation: bool) -> bool {
+    mutation || BUDGETED_COMMANDS.contains(&command)
+}
+
+/// Limits on the calls of one session.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    calls_per_minute: Option<u32>,
+    max_mutations: Option<u32>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Start times of admitted calls within the window, oldest first.
+    recent: VecDeque<Instant>,
+    /// Mutations admitted this session.
+    mutations: u32,
+}
+
+/// Why a call was refused, returned to the client as structured content.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Limited {
+    pub error: &'static str,
+    /// `calls_per_minute` or `mutations`.
+    pub limit: &'static str,
+    /// The configured maximum.
+    pub max: u32,
+    /// Seconds until a call would be admitted; absent when the session's
+    /// mutation budget is spent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_seconds: Option<u64>,
+}
+
+impl Limited {
+    /// Describes the refusal for the text content of the error.
+    pub fn message(&self) -> String {
+        match self.retry_after_seconds {
+            Some(seconds) => format!(
+                "rate limited: more than {} calls per minute; retry after {seconds} s",
+                self.max
+            ),
+            None => format!(
+                "rate limited: the session's budget of {} mutations is spent; \
+                 restart the server to write again",
+                self.max
+            ),
+        }
+    }
+}
+
+impl RateLimiter {
+    pub fn new(calls_per_minute: Option<u32>, max_mutations: Option<u32>) -> Self {
+        Self {
+            calls_per_minute: calls_per_minute.filter(|limit| *limit > 0),
+            max_mutations: max_mutations.filter(|limit| *limit > 0),
+            state: Mutex::default(),
+        }
+    }
+
+    /// Admits a call starting `now`, counting it, or reports why not.
+    pub fn admit(&self, mutation: bool, now: Instant) -> Result<(), Limited> {
+        let mut state = self.state.lock().expect("rate limiter lock poisoned");
+        while state
+            .recent
+            .front()
+            .is_some_and(|start| now.duration_since(*start) >= WINDOW)
+        {
+            state.recent.pop_front();
+        }
+        if let Some(max) = self.max_mutations {
+            if mutation && state.mutations >= max {
+                return Err(Limited {
+                    error: "rate limited",
+                    limit: "mutations",
+                    max,
+                    retry_after_seconds: None,
+                });
+            }
+        }
+        if let Some(max) = self.calls_per_minute {
+            if state.recent.len() >= max as usize {
+                let oldest = state.recent[state.recent.len() - max as usize];
+                let wait = WINDOW.saturating_sub(now.duration_since(oldest));
+                return Err(Limited {
+                    error: "rate limited",
+                    limit: "calls_per_minute",
+                    max,
+                    retry_after_seconds: Some(wait.as_secs_f64().ceil().max(1.0) as u64),
+                });
+            }
+        }
+        if self.calls_per_minute.is_some() {
+            state.recent.push_back(now);
+        }
+        if mutation {
+            state.mutations += 1;
+        }
+        Ok(())
+    }
+
+    /// Returns an admitted mutation that failed to the budget.
+    pub fn refund(&self) {
+        let mut state = self.state.lock().expect("rate limiter lock poisoned");
+        state.mutations = state.mutations.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::RateLimiter;
+
+    #[test]
+    fn admit_limits_calls_over_a_sliding_minute() {
+        let limiter = RateLimiter::new(Some(2), None);
+        let start = Instant::now();
+        assert!(limiter.admit(false, start).is_ok());
+        assert!(
+            limiter
+                .admit(false, start + Duration::from_secs(20))
+                .is_ok()
+        );
+        let limited = limiter
+            .admit(false, start + Duration::from_secs(30))
+            .unwrap_err();
+        assert_eq!(limited.limit, "calls_per_minute");
+        assert_eq!(limited.retry_after_seconds, Some(30));
+        assert!(
+            limiter
+                .admit(false, start + Duration::from_secs(60))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn admit_spends_the_mutation_budget_once() {
+        let limiter = RateLimiter::new(None, Some(1));
+        let now = Instant::now();
+        assert!(limiter.admit(true, now).is_ok());
+        assert!(limiter.admit(false, now).is_ok());
+        let limited = limiter.admit(true, now).unwrap_err();
+        assert_eq!(limited.limit, "mutations");
+        assert_eq!(limited.retry_after_seconds, None);
+        limiter.refund();
+        assert!(limiter.admit(true, now).is_ok());
+    }
+
+    #[test]
+    fn zero_limits_are_unlimited() {
+        let limiter = RateLimiter::new(Some(0), Some(0));
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.admit(true, now).is_ok());
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! External references cited in notes.
+//!
+//! `nb.references` collects the URLs, DOIs, and file references of a note or
+//! folder into one deduplicated list, each with the notes citing it, as the
+//! raw material for a bibliography. DOIs are recognized bare, after `doi:`,
+//! and as `doi.org` URLs. File references are `file://` URLs and Markdown
+//! links or link definitions pointing at local files other than notes,
+//! resolved against the citing note's folder.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::archive;
+use crate::args::ReferencesArgs;
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// Matches a URL up to whitespace, quotes, or brackets.
+static URL_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\b(?:https?|ftp|file)://[^\s<>"'`()\[\]{}|\\^]+"#).unwrap());
+
+/// Matches a DOI (`10.<registrant>/<suffix>`).
+static DOI_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\b10\.\d{4,9}/[^\s<>"'`()\[\]{}]+"#).unwrap());
+
+/// Matches a `doi.org` URL, capturing the DOI.
+static DOI_URL_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^https?://(?:dx\.)?doi\.org/(10\..+)$").unwrap());
+
+/// Matches a Markdown link or image target: `[text](target)` or
+/// `[text](<target>)`.
+static LINK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[[^\]]*\]\((?:<([^>]+)>|([^)\s]+))[^)]*\)").unwrap());
+
+/// Matches a link reference definition: `[label]: target`.
+static DEFINITION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^ {0,3}\[[^\]]+\]:\s*(?:<([^>]+)>|(\S+))").unwrap());
+
+/// Kind of a reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceKind {
+    Url,
+    Doi,
+    File,
+}
+
+/// A reference and the notes citing it.
+#[derive(Debug, Serialize)]
+pub struct Reference {
+    pub kind: ReferenceKind,
+    /// The URL, the DOI (lowercase), or the file path (relative to the
+    /// notebook root when inside it).
+    pub value: String,
+    /// Paths of the citing notes, or the `id` for a single note.
+    pub notes: Vec<String>,
+}
+
+/// Result of `nb.references`.
+#[derive(Debug, Serialize)]
+pub struct ReferenceList {
+    /// Number of notes scanned.
+    pub scanned: usize,
+    /// References ordered by kind, then value.
+    pub references: Vec<Reference>,
+}
+
+/// Collects the references of note `args.id`, or of all notes of
+/// `args.folder` (the whole notebook without either).
+pub async fn references(nb: &NbClient, args: &ReferencesArgs) -> Result<ReferenceList, NbError> {
+    let notebook = args.notebook.as_deref();
+    let root = nb.notebook_path(notebook).await?;
+    // (label, path relative to the notebook root, content)
+    let sources = match &args.id {
+        Some(id) => {
+            let path = nb.show_path(id, notebook).await?;
+            let content = tokio::fs::read_to_string(&path).await?;
+            vec![(id.clone(), notes::relative_path(&root, &path), content)]
+        }
+        None => {
+            let folder = args.folder.clone();
+            let hide_archived = archive::hides(&root, folder.as_deref(), args.include_archived);
+            let files =
+                tokio::task::spawn_blocking(move || notes::read_notes(&root, folder.as_deref()))
+                    .await
+                    .map_err(|err| {
+                        NbError::CommandFailed(format!("references task failed: {err}"))
+                    })??;
+            files
+                .into_iter()
+                .filter(|note| {
+                    !(hide_archived && note.path.split('/').next() == Some(archive::FOLDER))
+                })
+                .map(|note| (note.path.clone(), note.path, note.content))
+                .collect()
+        }
+    };
+    let mut cited: BTreeMap<(ReferenceKind, String), BTreeSet<String>> = BTreeMap::new();
+    for (label, path, content) in &sources {
+        for reference in extract(path, content) {
+            cited.entry(reference).or_default().insert(label.clone());
+        }
+    }
+    Ok(ReferenceList {
+        scanned: sources.len(),
+        references: cited
+            .into_iter()
+            .map(|((kind, value), notes)| Reference {
+                kind,
+                value,
+                notes: notes.into_iter().collect(),
+            })
+            .collect(),
+    })
+}
+
+/// Extracts the references of the note at `path` (relative to the notebook
+/// root) with `content`.
+fn extract(path: &str, content: &str) -> BTreeSet<(ReferenceKind, String)> {
+    let mut found = BTreeSet::new();
+    let mut rest = content.to_string();
+    for url in URL_REGEX.find_iter(content) {
+        // Blank the URL so DOIs inside it are not found again.
+        rest.replace_range(url.range(), &" ".repeat(url.len()));
+        let value = trim_trailing(url.as_str());
+        if let Some(captures) = DOI_URL_REGEX.captures(value) {
+            found.insert((
+                ReferenceKind::Doi,
+                doi(&notes::percent_decode(&captures[1])),
+            ));
+        } else if value.starts_with("file://") {
+            found.insert((ReferenceKind::File, value.to_string()));
+        } else {
+            found.insert((ReferenceKind::Url, value.to_string()));
+        }
+    }
+    for matched in DOI_REGEX.find_iter(&rest) {
+        found.insert((ReferenceKind::Doi, doi(trim_trailing(matched.as_str()))));
+    }
+    let folder = path.rsplit_once('/').map_or("", |(folder, _)| folder);
+    let targets = LINK_REGEX
+        .captures_iter(content)
+        .chain(DEFINITION_REGEX.captures_iter(content))
+        .filter_map(|captures| captures.get(1).or_else(|| captures.get(2)));
+    for target in targets {
+        if let Some(file) = local_file(folder, target.as_str()) {
+            found.insert((ReferenceKind::File, file));
+        }
+    }
+    found
+}
+
+/// Returns the file a local link `target` in a note in `folder` points at,
+/// unless it is a note, an anchor, or a URL.
+fn local_file(folder: &str, target: &str) -> Option<String> {
+    if target.starts_with('#') || target.contains(':') {
+        return None;
+    }
+    let target = target.split(['#', '?']).next().unwrap_or_default();
+    let target = notes::percent_decode(target);
+    if target.is_empty() || target.ends_with('/') || notes::is_note_file(Path::new(&target)) {
+        return None;
+    }
+    if target.starts_with('/') {
+        return Some(target);
+    }
+    let joined = if folder.is_empty() {
+        target.clone()
+    } else {
+        format!("{folder}/{target}")
+    };
+    Some(notes::normalize_path(&joined).unwrap_or(target))
+}
+
+/// Normalizes a DOI for comparison; DOIs are case-insensitive.
+fn doi(value: &str) -> String {
+    value.to_lowercase()
+}
+
+/// Drops sentence punctuation that follows a URL or DOI in prose.
+fn trim_trailing(value: &str) -> &str {
+    value.trim_end_matches(['.', ',', ';', ':', '!', '?', '*', '_'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReferenceKind, extract};
+
+    #[test]
+    fn extract_finds_urls_dois_and_files() {
+        let content = "# Reading\n\nSee https://example.com/paper, and \
+                       [the DOI](https://doi.org/10.1000/ABC.1).\n\
+                       Also doi:10.5555/xyz.2 and [data](../data/results.csv).\n\
+                       ![chart](chart.png) links [[other]] and [a note](plan.md#goals).\n\n\
+                       [1]: <../papers/smith 2020.pdf>\n";
+        let found = extract("research/reading.md", content)
+            .into_iter()
+            .collect::<Vec<_>>();
+        let expected = [
+            (ReferenceKind::Url, "https://example.com/paper"),
+            (ReferenceKind::Doi, "10.1000/abc.1"),
+            (ReferenceKind::Doi, "10.5555/xyz.2"),
+            (ReferenceKind::File, "data/results.csv"),
+            (ReferenceKind::File, "papers/smith 2020.pdf"),
+            (ReferenceKind::File, "research/chart.png"),
+        ]
+        .map(|(kind, value)| (kind, value.to_string()));
+        assert_eq!(found, expected);
+    }
+}
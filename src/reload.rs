@@ -0,0 +1,161 @@
+//! Reloading the project configuration without a restart.
+//!
+//! Long-lived clients keep one server for hours. On SIGHUP, or when a client
+//! calls the `server_reload` tool, `.nb-mcp.toml` is read again and its
+//! `allowed_commands`, permissions, and views replace the running ones.
+//! Because the `nb` tool's description lists the allowed commands, clients
+//! are sent `notifications/tools/list_changed` when the allowlist or the
+//! denied commands change. Other
+//! settings still take effect only at startup.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::Config;
+use crate::args::SavedView;
+use crate::project_config::{self, Permission};
+
+/// Settings that can change while the server runs.
+#[derive(Debug, Default)]
+pub struct Policy {
+    /// Subcommands clients may call; `None` allows all.
+    pub allowed_commands: Option<Vec<String>>,
+    /// Per-subcommand permissions; unlisted subcommands are allowed.
+    pub permissions: BTreeMap<String, Permission>,
+    /// Saved views from the project configuration.
+    pub views: BTreeMap<String, SavedView>,
+}
+
+impl Policy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            allowed_commands: config.allowed_commands.clone(),
+            permissions: config.permissions.clone(),
+            views: config.views.clone(),
+        }
+    }
+
+    /// Returns the permission for `subcommand`.
+    pub fn permission(&self, subcommand: &str) -> Permission {
+        project_config::permission(&self.permissions, subcommand)
+    }
+
+    /// Returns true if `allowed_commands` lists `subcommand` or is unset.
+    pub fn allows(&self, subcommand: &str) -> bool {
+        self.allowed_commands.as_ref().is_none_or(|allowed| {
+            allowed
+                .iter()
+                .any(|name| name.strip_prefix("nb.").unwrap_or(name) == subcommand)
+        })
+    }
+
+    /// Returns true if `subcommand` is allowed and runs without asking the
+    /// user, as callers that cannot confirm need.
+    pub fn permits(&self, subcommand: &str) -> bool {
+        self.allows(subcommand) && self.permission(subcommand) == Permission::Allow
+    }
+
+    /// Returns the subcommands the permissions deny.
+    pub fn denied(&self) -> Vec<String> {
+        self.permissions
+            .iter()
+            .filter(|(_, permission)| **permission == Permission::Deny)
+            .map(|(name, _)| name.strip_prefix("nb.").unwrap_or(name).to_string())
+            .collect()
+    }
+}
+
+/// Result of the `server_reload` tool.
+#[derive(Debug, Serialize)]
+pub struct ReloadReport {
+    /// Configuration file that was read, if one was found.
+    pub config_file: Option<String>,
+    pub allowed_commands: Option<Vec<String>>,
+    pub permissions: BTreeMap<String, Permission>,
+    /// Names of the views from the configuration file.
+    pub views: Vec<String>,
+    /// Whether clients were told the tool list changed.
+    pub tools_changed: bool,
+}
+
+/// Reads the reloadable settings from the project configuration file.
+///
+/// Without a file, the allowlist, permissions, and views are cleared, as at
+/// startup.
+pub fn read() -> anyhow::Result<(Option<String>, Policy)> {
+    let Some(path) = project_config::discover() else {
+        return Ok((None, Policy::default()));
+    };
+    let project = project_config::load(&path)?;
+    let policy = Policy {
+        allowed_commands: project.allowed_commands,
+        permissions: project.permissions,
+        views: project.views,
+    };
+    Ok((Some(path.display().to_string()), policy))
+}
+
+/// Rewrites the command list of the `nb` tool description to `allowed`.
+pub fn restrict_description(description: &str, allowed: &[String]) -> String {
+    let Some((head, rest)) = description.split_once("Commands: ") else {
+        return description.to_string();
+    };
+    let Some((_, tail)) = rest.split_once(". ") else {
+        return description.to_string();
+    };
+    let commands = allowed
+        .iter()
+        .map(|name| name.strip_prefix("nb.").unwrap_or(name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{head}Commands: {commands}. {tail}")
+}
+
+/// Drops `denied` from the command list of the `nb` tool description.
+pub fn remove_from_description(description: &str, denied: &[String]) -> String {
+    let Some((_, rest)) = description.split_once("Commands: ") else {
+        return description.to_string();
+    };
+    let Some((commands, _)) = rest.split_once(". ") else {
+        return description.to_string();
+    };
+    let remaining = commands
+        .split(", ")
+        .filter(|name| !denied.iter().any(|denied| denied == name))
+        .map(String::from)
+        .collect::<Vec<_>>();
+    restrict_description(description, &remaining)
+}
+
+/// Returns a stream of SIGHUP deliveries, or `None` if it cannot be set up.
+#[cfg(unix)]
+pub fn hangups() -> Option<tokio::signal::unix::Signal> {
+    use tokio::signal::unix::{SignalKind, signal};
+    signal(SignalKind::hangup())
+        .map_err(|err| tracing::warn!(error = %err, "failed to listen for SIGHUP"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{remove_from_description, restrict_description};
+
+    #[test]
+    fn restrict_description_lists_only_allowed_commands() {
+        let description = "nb tool. Commands: status, add, show. Use `help` for schemas.";
+        assert_eq!(
+            restrict_description(description, &["nb.show".to_string(), "add".to_string()]),
+            "nb tool. Commands: show, add. Use `help` for schemas."
+        );
+    }
+
+    #[test]
+    fn remove_from_description_drops_denied_commands() {
+        let description = "nb tool. Commands: status, add, show. Use `help` for schemas.";
+        assert_eq!(
+            remove_from_description(description, &["add".to_string()]),
+            "nb tool. Commands: status, show. Use `help` for schemas."
+        );
+    }
+}
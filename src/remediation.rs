@@ -0,0 +1,151 @@
+//! Machine-readable recovery hints for failed calls.
+//!
+//! Tool error text is written for people. When a failure is recognized, the
+//! result also carries a `remediation` object naming the action that would
+//! fix it, so agents can recover on their own instead of asking the user.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::model::Entry;
+
+/// Maximum number of candidates suggested for an unknown selector.
+const MAX_CANDIDATES: usize = 5;
+
+/// Where to find nb installation instructions.
+const INSTALL_URL: &str = "https://github.com/xwmx/nb#installation";
+
+/// Matches the server's missing-notebook errors.
+static MISSING_NOTEBOOK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"notebook not found; run `nb notebooks add ([^`]+)`(, pass allow_create)?").unwrap()
+});
+
+/// Matches version requirement errors.
+static UPGRADE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"requires nb (\S+) or newer").unwrap());
+
+/// An action that would let a failed call succeed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Remediation {
+    /// nb is not installed.
+    InstallNb { url: &'static str },
+    /// The installed nb is too old for the command.
+    UpgradeNb { minimum: String },
+    /// The notebook does not exist.
+    CreateNotebook {
+        notebook: String,
+        /// Whether retrying with `allow_create: true` creates it.
+        allow_create: bool,
+    },
+    /// No notebook is configured; pass `notebook` or configure a default.
+    SetNotebook,
+    /// No item matches the selector.
+    ResolveSelector {
+        selector: String,
+        /// Items whose title contains the selector.
+        candidates: Vec<Candidate>,
+    },
+}
+
+/// An item that may be the one a selector meant.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Candidate {
+    pub id: String,
+    pub title: String,
+}
+
+/// Recognizes the failure described by `message`.
+///
+/// `selector` is the `id` the call passed, if any. Unknown-selector hints
+/// are returned without candidates; see [`candidates`].
+pub fn classify(message: &str, selector: Option<&str>) -> Option<Remediation> {
+    if message.contains("nb not found in PATH") || message.contains("nb is not installed") {
+        return Some(Remediation::InstallNb { url: INSTALL_URL });
+    }
+    if let Some(captures) = MISSING_NOTEBOOK_REGEX.captures(message) {
+        return Some(Remediation::CreateNotebook {
+            notebook: captures[1].to_string(),
+            allow_create: captures.get(2).is_some(),
+        });
+    }
+    if message.contains("notebook not configured") {
+        return Some(Remediation::SetNotebook);
+    }
+    if let Some(captures) = UPGRADE_REGEX.captures(message) {
+        return Some(Remediation::UpgradeNb {
+            minimum: captures[1].to_string(),
+        });
+    }
+    match selector {
+        Some(selector) if message.to_lowercase().contains("not found") => {
+            Some(Remediation::ResolveSelector {
+                selector: selector.to_string(),
+                candidates: Vec::new(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Picks the entries whose title contains `selector`, ignoring case.
+pub fn candidates(entries: &[Entry], selector: &str) -> Vec<Candidate> {
+    let needle = selector.to_lowercase();
+    entries
+        .iter()
+        .filter(|entry| entry.title().to_lowercase().contains(&needle))
+        .take(MAX_CANDIDATES)
+        .map(|entry| Candidate {
+            id: entry.id().to_string(),
+            title: entry.title().to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Remediation, candidates, classify};
+    use crate::model::parse_entries;
+
+    #[test]
+    fn classify_reads_missing_notebooks() {
+        let message = "nb command failed: notebook not found; run `nb notebooks add foo`, \
+                       pass allow_create: true, or remove --no-create-notebook";
+        assert_eq!(
+            classify(message, None),
+            Some(Remediation::CreateNotebook {
+                notebook: "foo".to_string(),
+                allow_create: true,
+            })
+        );
+        let strict = "nb command failed: notebook not found; run `nb notebooks add foo`";
+        assert!(matches!(
+            classify(strict, None),
+            Some(Remediation::CreateNotebook {
+                allow_create: false,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn classify_needs_a_selector_for_unknown_items() {
+        assert!(classify("nb command failed: Not found: 99", None).is_none());
+        assert!(matches!(
+            classify("nb command failed: Not found: plan", Some("plan")),
+            Some(Remediation::ResolveSelector { .. })
+        ));
+    }
+
+    #[test]
+    fn candidates_match_titles() {
+        let entries = parse_entries("[3] Quarterly plan\n[2] Notes\n[1] 📂 Plans\n");
+        let found = candidates(&entries, "PLAN");
+        assert_eq!(
+            found.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(),
+            vec!["3", "1"]
+        );
+    }
+}
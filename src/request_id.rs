@@ -0,0 +1,45 @@
+//! Correlation IDs for tool calls.
+//!
+//! Every `nb` tool call runs in a tracing span carrying a request ID, which
+//! is also returned in error payloads, so a failure an agent reports can be
+//! found in the server log. IDs are `{instance}-{sequence}` in hex; the
+//! instance part distinguishes servers writing to the same log directory.
+
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static INSTANCE: LazyLock<u32> = LazyLock::new(|| {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+    (nanos ^ std::process::id().rotate_left(16)) & 0xffff
+});
+
+static SEQUENCE: AtomicU64 = AtomicU64::new(1);
+
+/// Returns a new request ID, e.g. `3fa2-000017`.
+pub fn next() -> String {
+    format!(
+        "{:04x}-{:06x}",
+        *INSTANCE,
+        SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::next;
+
+    #[test]
+    fn next_shares_instance_and_increments() {
+        let first = next();
+        let second = next();
+        let (instance, sequence) = first.split_once('-').unwrap();
+        assert_eq!(instance.len(), 4);
+        assert!(second.starts_with(instance));
+        let sequence = u64::from_str_radix(sequence, 16).unwrap();
+        let next_sequence = u64::from_str_radix(second.split_once('-').unwrap().1, 16).unwrap();
+        assert!(next_sequence > sequence);
+    }
+}
@@ -0,0 +1,280 @@
+//! Mapping loose selectors to note ids.
+//!
+//! Agents often know a note by its title, part of its filename, or the URL
+//! it bookmarks, while `show`, `edit`, and `delete` want an id. `nb.resolve`
+//! lists the candidates in a folder with a confidence for each, so an agent
+//! can pick one explicitly instead of letting nb guess.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::args::ResolveArgs;
+use crate::model::Entry;
+use crate::nb::{NbClient, NbError};
+use crate::nb_index::ItemResolver;
+
+/// Maximum candidates returned.
+const MAX_MATCHES: usize = 10;
+
+/// What a query is matched against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Title,
+    Filename,
+    Url,
+}
+
+/// Result of `nb.resolve`.
+#[derive(Debug, Serialize)]
+pub struct Resolution {
+    pub query: String,
+    /// Candidates, most likely first.
+    pub matches: Vec<Match>,
+}
+
+/// A note the query may refer to.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Match {
+    pub id: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// What the query matched.
+    pub matched: Kind,
+    /// From 0 (weak) to 1 (exact).
+    pub confidence: f64,
+}
+
+/// Lists the items of the folder that `args.query` may refer to.
+pub async fn resolve(nb: &NbClient, args: &ResolveArgs) -> Result<Resolution, NbError> {
+    let kinds = kinds(args.kind.as_deref(), &args.query)?;
+    let notebook = args.notebook.as_deref();
+    let folder = args.folder.as_deref();
+    let entries = nb.list_entries(folder, &[], None, notebook).await?;
+    let root = nb.notebook_path(notebook).await?;
+    let mut resolver = ItemResolver::new(&root, folder);
+    let mut matches = entries
+        .iter()
+        .filter(|entry| !matches!(entry, Entry::Folder(_)))
+        .filter_map(|entry| {
+            let path = resolver.resolve(entry.id());
+            let filename = path
+                .as_deref()
+                .and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().into_owned());
+            let url = match entry {
+                Entry::Bookmark(_) => path.as_deref().and_then(bookmark_url),
+                _ => None,
+            };
+            let (matched, confidence) = kinds
+                .iter()
+                .filter_map(|kind| {
+                    let candidate = match kind {
+                        Kind::Title => Some(entry.title()),
+                        Kind::Filename => filename.as_deref(),
+                        Kind::Url => url.as_deref(),
+                    }?;
+                    let confidence = match kind {
+                        Kind::Url => url_confidence(&args.query, candidate),
+                        _ => text_confidence(&args.query, candidate, *kind == Kind::Filename),
+                    }?;
+                    Some((*kind, confidence))
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1))?;
+            Some(Match {
+                id: entry.id().to_string(),
+                title: entry.title().to_string(),
+                filename,
+                url,
+                matched,
+                confidence,
+            })
+        })
+        .collect::<Vec<_>>();
+    matches.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    matches.truncate(MAX_MATCHES);
+    Ok(Resolution {
+        query: args.query.clone(),
+        matches,
+    })
+}
+
+/// Returns the kinds to match, inferring a URL query from its scheme.
+fn kinds(kind: Option<&str>, query: &str) -> Result<Vec<Kind>, NbError> {
+    match kind {
+        Some("title") => Ok(vec![Kind::Title]),
+        Some("filename") => Ok(vec![Kind::Filename]),
+        Some("url") => Ok(vec![Kind::Url]),
+        Some(other) => Err(NbError::CommandFailed(format!(
+            "unknown selector type {other:?}; expected title, filename, or url"
+        ))),
+        None if query.contains("://") => Ok(vec![Kind::Url]),
+        None => Ok(vec![Kind::Title, Kind::Filename]),
+    }
+}
+
+/// Scores a title or filename against the query, ignoring case (and, for
+/// filenames, the extension).
+fn text_confidence(query: &str, candidate: &str, filename: bool) -> Option<f64> {
+    let query = query.trim().to_lowercase();
+    let candidate = candidate.to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+    let stem = if filename {
+        candidate.split('.').next().unwrap_or(&candidate)
+    } else {
+        &candidate
+    };
+    if candidate == query || stem == query {
+        Some(1.0)
+    } else if candidate.starts_with(&query) {
+        Some(0.8)
+    } else if candidate.contains(&query) {
+        Some(0.6)
+    } else if query
+        .split_whitespace()
+        .all(|word| candidate.contains(word))
+    {
+        Some(0.4)
+    } else {
+        None
+    }
+}
+
+/// Scores a bookmarked URL against the query, ignoring the scheme, `www.`,
+/// and trailing slashes.
+fn url_confidence(query: &str, url: &str) -> Option<f64> {
+    let query = normalize_url(query);
+    let url = normalize_url(url);
+    if query.is_empty() {
+        None
+    } else if url == query {
+        Some(1.0)
+    } else if url.starts_with(&query) {
+        Some(0.7)
+    } else if url.contains(&query) {
+        Some(0.5)
+    } else {
+        None
+    }
+}
+
+fn normalize_url(url: &str) -> String {
+    let url = url.trim().to_lowercase();
+    let url = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+    url.strip_prefix("www.")
+        .unwrap_or(url)
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Returns the URL of a bookmark file: its first `<scheme://...>` line.
+fn bookmark_url(path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    content.lines().find_map(|line| {
+        let url = line.trim().strip_prefix('<')?.strip_suffix('>')?;
+        url.contains("://").then(|| url.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{resolve, text_confidence, url_confidence};
+    use crate::args::ResolveArgs;
+    use crate::executor::FakeExecutor;
+    use crate::nb::NbClient;
+
+    #[test]
+    fn text_confidence_ranks_exact_over_partial_matches() {
+        assert_eq!(text_confidence("Plan", "plan", false), Some(1.0));
+        assert_eq!(text_confidence("plan", "plan.md", true), Some(1.0));
+        assert_eq!(
+            text_confidence("quarter", "Quarterly plan", false),
+            Some(0.8)
+        );
+        assert_eq!(text_confidence("plan", "Quarterly plan", false), Some(0.6));
+        assert_eq!(
+            text_confidence("plan q3", "Q3 release plan", false),
+            Some(0.4)
+        );
+        assert_eq!(text_confidence("budget", "Quarterly plan", false), None);
+    }
+
+    #[test]
+    fn url_confidence_ignores_scheme_and_trailing_slash() {
+        assert_eq!(
+            url_confidence("http://www.example.com/post/", "https://example.com/post"),
+            Some(1.0)
+        );
+        assert_eq!(
+            url_confidence("example.com", "https://example.com/post"),
+            Some(0.7)
+        );
+        assert_eq!(
+            url_confidence("other.org", "https://example.com/post"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_matches_titles_filenames_and_urls() {
+        let root = std::env::temp_dir().join(format!("nb-mcp-resolve-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join(".index"),
+            "plan.md\n20240101000000.bookmark.md\nplanning-notes.md\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("20240101000000.bookmark.md"),
+            "# Post\n\n<https://example.com/post>\n",
+        )
+        .unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", root.to_str().unwrap())
+                .respond(
+                    &["list", "work:"],
+                    "[3] Meeting notes\n[2] 🔖 Post (example.com)\n[1] Plan\n",
+                ),
+        );
+        let nb = NbClient::with_executor(fake, Some("work".to_string()), false);
+
+        let args = ResolveArgs {
+            query: "plan".to_string(),
+            ..ResolveArgs::default()
+        };
+        let resolution = resolve(&nb, &args).await.unwrap();
+        let found = resolution
+            .matches
+            .iter()
+            .map(|m| (m.id.as_str(), m.confidence))
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec![("1", 1.0), ("3", 0.8)]);
+        assert_eq!(
+            resolution.matches[1].filename.as_deref(),
+            Some("planning-notes.md")
+        );
+
+        let args = ResolveArgs {
+            query: "https://example.com/post/".to_string(),
+            ..ResolveArgs::default()
+        };
+        let resolution = resolve(&nb, &args).await.unwrap();
+        assert_eq!(resolution.matches.len(), 1);
+        assert_eq!(resolution.matches[0].id, "2");
+        assert_eq!(
+            resolution.matches[0].url.as_deref(),
+            Some("https://example.com/post")
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
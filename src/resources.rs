@@ -0,0 +1,411 @@
+//! Notebook files and folders as MCP resources.
+//!
+//! `nb://{notebook}/{+path}` addresses any file or folder in a notebook, so
+//! clients can walk its tree without tool calls. Reading a folder (a URI
+//! ending in `/`, such as `nb://work/`) returns a JSON listing of its
+//! entries, each with its own URI and media type; reading a file returns its
+//! content, as text or base64. nb's own files (`.git`, `.index`, ...) are
+//! hidden, and reads never create notebooks. Listing follows the project
+//! policy for `list` and reading a file the one for `show`; as resource
+//! reads cannot be confirmed, a `confirm` permission refuses them too.
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+
+use base64::prelude::{BASE64_STANDARD, Engine as _};
+use rmcp::ErrorData as McpError;
+use rmcp::model::{
+    AnnotateAble, RawResource, RawResourceTemplate, Resource, ResourceContents, ResourceTemplate,
+};
+use serde::Serialize;
+
+use crate::file_type;
+use crate::nb::NbClient;
+use crate::percent;
+use crate::reload::Policy;
+
+/// URI template for notebook items.
+pub const URI_TEMPLATE: &str = "nb://{notebook}/{+path}";
+
+/// Media type reported for folders.
+pub const FOLDER_MEDIA_TYPE: &str = "inode/directory";
+
+/// Media type of folder listings.
+const LISTING_MEDIA_TYPE: &str = "application/json";
+
+/// Contents of a folder resource.
+#[derive(Debug, Serialize)]
+struct Listing {
+    uri: String,
+    entries: Vec<ListingEntry>,
+}
+
+/// A file or folder inside a folder resource.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListingEntry {
+    uri: String,
+    name: String,
+    mime_type: &'static str,
+    /// File size in bytes; absent for folders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+}
+
+/// Returns the resource template for notebook items.
+pub fn templates() -> Vec<ResourceTemplate> {
+    vec![
+        RawResourceTemplate {
+            uri_template: URI_TEMPLATE.to_string(),
+            name: "notebook-item".to_string(),
+            title: Some("Notebook file or folder".to_string()),
+            description: Some(
+                "A file or folder in an nb notebook. Folder URIs end in `/` and read as a \
+                 JSON listing of their entries; `nb://{notebook}/` is the notebook root."
+                    .to_string(),
+            ),
+            mime_type: None,
+        }
+        .no_annotation(),
+    ]
+}
+
+/// Lists the root folder of each notebook, limited to `served` if set.
+pub async fn list(
+    nb: &NbClient,
+    served: &[String],
+    policy: &RwLock<Policy>,
+) -> Result<Vec<Resource>, McpError> {
+    if !policy.read().expect("policy lock poisoned").permits("list") {
+        return Err(refused("listing notebooks", "list"));
+    }
+    let notebooks = nb
+        .notebook_list()
+        .await
+        .map_err(|err| McpError::internal_error(err.to_string(), None))?;
+    Ok(notebooks
+        .into_iter()
+        .filter(|notebook| served.is_empty() || served.contains(&notebook.name))
+        .map(|notebook| {
+            let mut resource = RawResource::new(folder_uri(&notebook.name, ""), &notebook.name);
+            resource.title = Some(format!("Notebook {}", notebook.name));
+            resource.mime_type = Some(FOLDER_MEDIA_TYPE.to_string());
+            resource.no_annotation()
+        })
+        .collect())
+}
+
+/// Reads the file or folder at `uri`, limited to `served` notebooks if set.
+pub async fn read(
+    nb: &NbClient,
+    served: &[String],
+    policy: &RwLock<Policy>,
+    uri: &str,
+) -> Result<ResourceContents, McpError> {
+    let access = {
+        let policy = policy.read().expect("policy lock poisoned");
+        Access {
+            list: policy.permits("list"),
+            show: policy.permits("show"),
+        }
+    };
+    let (notebook, path) = parse_uri(uri)?;
+    let not_found = || McpError::resource_not_found(format!("no such resource: {uri}"), None);
+    if !served.is_empty() && !served.contains(&notebook) {
+        return Err(not_found());
+    }
+    // `notebook_path` would create a missing notebook.
+    if !nb.notebook_exists(&notebook).await.unwrap_or(false) {
+        return Err(not_found());
+    }
+    let root = nb
+        .notebook_path(Some(&notebook))
+        .await
+        .map_err(|err| McpError::internal_error(err.to_string(), None))?;
+    let uri = uri.to_string();
+    tokio::task::spawn_blocking(move || read_path(&root, &notebook, &path, &uri, access))
+        .await
+        .map_err(|err| McpError::internal_error(format!("resource read failed: {err}"), None))?
+}
+
+/// Subcommands the policy lets a resource read stand for.
+#[derive(Debug, Clone, Copy)]
+struct Access {
+    /// Folder listings.
+    list: bool,
+    /// File contents.
+    show: bool,
+}
+
+fn refused(action: &str, subcommand: &str) -> McpError {
+    McpError::invalid_params(
+        format!("{action} is not allowed by project configuration"),
+        Some(serde_json::json!({"command": subcommand})),
+    )
+}
+
+/// Splits `nb://{notebook}/{path}` into the notebook and a relative path.
+fn parse_uri(uri: &str) -> Result<(String, PathBuf), McpError> {
+    let invalid = |reason: &str| McpError::invalid_params(format!("{reason}: {uri}"), None);
+    let rest = uri
+        .strip_prefix("nb://")
+        .ok_or_else(|| invalid("resource URIs start with nb://"))?;
+    let (notebook, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let notebook = percent::decode(notebook)
+        .filter(|notebook| !notebook.is_empty())
+        .ok_or_else(|| invalid("invalid notebook"))?;
+    let path = percent::decode(path).ok_or_else(|| invalid("invalid path"))?;
+    let path = PathBuf::from(path.trim_end_matches('/'));
+    let visible = path.components().all(|component| match component {
+        Component::Normal(name) => !name.to_string_lossy().starts_with('.'),
+        _ => false,
+    });
+    if !visible {
+        return Err(invalid(
+            "paths must not be absolute, hidden, or contain `..`",
+        ));
+    }
+    Ok((notebook, path))
+}
+
+fn read_path(
+    root: &Path,
+    notebook: &str,
+    path: &Path,
+    uri: &str,
+    access: Access,
+) -> Result<ResourceContents, McpError> {
+    let not_found = || McpError::resource_not_found(format!("no such resource: {uri}"), None);
+    let root = root.canonicalize().map_err(|_| not_found())?;
+    let target = root.join(path).canonicalize().map_err(|_| not_found())?;
+    // Symbolic links must not lead out of the notebook.
+    if !target.starts_with(&root) {
+        return Err(not_found());
+    }
+    let relative = path.to_string_lossy().replace('\\', "/");
+    if target.is_dir() {
+        if !access.list {
+            return Err(refused(&format!("listing {uri}"), "list"));
+        }
+        let listing = Listing {
+            uri: folder_uri(notebook, &relative),
+            entries: list_folder(&target, notebook, &relative).map_err(|_| not_found())?,
+        };
+        let text = serde_json::to_string_pretty(&listing)
+            .map_err(|err| McpError::internal_error(err.to_string(), None))?;
+        return Ok(ResourceContents::TextResourceContents {
+            uri: listing.uri,
+            mime_type: Some(LISTING_MEDIA_TYPE.to_string()),
+            text,
+            meta: None,
+        });
+    }
+    if !access.show {
+        return Err(refused(&format!("reading {uri}"), "show"));
+    }
+    let content = std::fs::read(&target).map_err(|_| not_found())?;
+    let mime_type = Some(file_type::media_type(&target, &content).to_string());
+    let uri = file_uri(notebook, &relative);
+    Ok(match String::from_utf8(content) {
+        Ok(text) if file_type::binary_type(&target, text.as_bytes()).is_none() => {
+            ResourceContents::TextResourceContents {
+                uri,
+                mime_type,
+                text,
+                meta: None,
+            }
+        }
+        Ok(text) => blob(uri, mime_type, text.as_bytes()),
+        Err(err) => blob(uri, mime_type, err.as_bytes()),
+    })
+}
+
+fn blob(uri: String, mime_type: Option<String>, content: &[u8]) -> ResourceContents {
+    ResourceContents::BlobResourceContents {
+        uri,
+        mime_type,
+        blob: BASE64_STANDARD.encode(content),
+        meta: None,
+    }
+}
+
+/// Lists the visible entries of `dir`, folders first, each by name.
+fn list_folder(dir: &Path, notebook: &str, relative: &str) -> std::io::Result<Vec<ListingEntry>> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        let child = if relative.is_empty() {
+            name.clone()
+        } else {
+            format!("{relative}/{name}")
+        };
+        let path = entry.path();
+        entries.push(if path.is_dir() {
+            ListingEntry {
+                uri: folder_uri(notebook, &child),
+                name,
+                mime_type: FOLDER_MEDIA_TYPE,
+                size: None,
+            }
+        } else {
+            ListingEntry {
+                uri: file_uri(notebook, &child),
+                name,
+                mime_type: file_type::sniff_media_type(&path)?,
+                size: Some(entry.metadata()?.len()),
+            }
+        });
+    }
+    entries.sort_by(|a, b| {
+        let is_file = |entry: &ListingEntry| entry.size.is_some();
+        is_file(a)
+            .cmp(&is_file(b))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(entries)
+}
+
+fn folder_uri(notebook: &str, relative: &str) -> String {
+    if relative.is_empty() {
+        format!("nb://{}/", percent::encode(notebook))
+    } else {
+        format!("{}/", file_uri(notebook, relative))
+    }
+}
+
+fn file_uri(notebook: &str, relative: &str) -> String {
+    format!(
+        "nb://{}/{}",
+        percent::encode(notebook),
+        percent::encode(relative)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, RwLock};
+
+    use rmcp::model::ResourceContents;
+
+    use super::{list, parse_uri, read};
+    use crate::project_config::Permission;
+    use crate::reload::Policy;
+    use crate::{FakeExecutor, NbClient};
+
+    fn notebook_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("nb-mcp-resources-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(dir.join(".index"), "plan.md\n").unwrap();
+        std::fs::write(dir.join("plan.md"), "# Plan\n").unwrap();
+        std::fs::write(dir.join("docs/logo.png"), [0x89, b'P', b'N', b'G', 0]).unwrap();
+        dir
+    }
+
+    fn text(contents: ResourceContents) -> String {
+        match contents {
+            ResourceContents::TextResourceContents { text, .. } => text,
+            other => panic!("expected text: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn read_lists_folders_and_files() {
+        let dir = notebook_dir("read");
+        let fake = Arc::new(FakeExecutor::new().notebook("work", dir.to_str().unwrap()));
+        let nb = NbClient::with_executor(fake, None, false);
+        let policy = RwLock::new(Policy::default());
+
+        let root: serde_json::Value =
+            serde_json::from_str(&text(read(&nb, &[], &policy, "nb://work/").await.unwrap()))
+                .unwrap();
+        let names: Vec<_> = root["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| {
+                (
+                    entry["uri"].as_str().unwrap(),
+                    entry["mimeType"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("nb://work/docs/", "inode/directory"),
+                ("nb://work/plan.md", "text/markdown"),
+            ]
+        );
+
+        assert_eq!(
+            text(read(&nb, &[], &policy, "nb://work/plan.md").await.unwrap()),
+            "# Plan\n"
+        );
+        let logo = read(&nb, &[], &policy, "nb://work/docs/logo.png")
+            .await
+            .unwrap();
+        assert!(matches!(
+            logo,
+            ResourceContents::BlobResourceContents { mime_type: Some(ref mime), .. }
+                if mime == "image/png"
+        ));
+        assert!(read(&nb, &[], &policy, "nb://home/").await.is_err());
+        assert!(
+            read(&nb, &["home".to_string()], &policy, "nb://work/plan.md")
+                .await
+                .is_err()
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn read_follows_the_show_and_list_policy() {
+        let dir = notebook_dir("policy");
+        std::fs::write(dir.join("secret.md"), "# Secret\n").unwrap();
+        let fake = Arc::new(FakeExecutor::new().notebook("work", dir.to_str().unwrap()));
+        let nb = NbClient::with_executor(fake, None, false);
+        let policy = RwLock::new(Policy {
+            permissions: BTreeMap::from([("show".to_string(), Permission::Deny)]),
+            ..Policy::default()
+        });
+        let error = read(&nb, &[], &policy, "nb://work/secret.md")
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("not allowed"), "{error:?}");
+        assert!(read(&nb, &[], &policy, "nb://work/").await.is_ok());
+
+        *policy.write().unwrap() = Policy {
+            allowed_commands: Some(vec!["show".to_string()]),
+            ..Policy::default()
+        };
+        assert!(read(&nb, &[], &policy, "nb://work/").await.is_err());
+        assert!(list(&nb, &[], &policy).await.is_err());
+        assert_eq!(
+            text(
+                read(&nb, &[], &policy, "nb://work/secret.md")
+                    .await
+                    .unwrap()
+            ),
+            "# Secret\n"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn parse_uri_rejects_escapes() {
+        let (notebook, path) = parse_uri("nb://my%20notes/docs/a%20b.md").unwrap();
+        assert_eq!(notebook, "my notes");
+        assert_eq!(path, std::path::Path::new("docs/a b.md"));
+        assert!(parse_uri("nb://work/../secret").is_err());
+        assert!(parse_uri("nb://work/.git/config").is_err());
+        assert!(parse_uri("file:///etc/passwd").is_err());
+    }
+}
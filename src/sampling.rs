@@ -0,0 +1,231 @@
+//! Note features that use the client's model through MCP sampling.
+//!
+//! Rather than calling an LLM itself, the server sends a
+//! `sampling/createMessage` request to the client, which runs it on whatever
+//! model the user has configured. Clients that do not advertise the sampling
+//! capability get a tool error explaining why the command is unavailable.
+
+use rmcp::{
+    Peer, RoleServer,
+    model::{Content, CreateMessageRequestParam, Role, SamplingMessage},
+};
+use serde::Serialize;
+
+use crate::args::{SuggestTagsArgs, SummarizeArgs};
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// Default summary length in words.
+const DEFAULT_SUMMARY_WORDS: u32 = 60;
+
+/// Default number of suggested tags.
+const DEFAULT_MAX_TAGS: u32 = 5;
+
+/// Result of summarizing a note.
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub id: String,
+    pub summary: String,
+    /// Model the client used for sampling.
+    pub model: String,
+    /// Whether the summary was written into the note's frontmatter.
+    pub written: bool,
+}
+
+/// Returns the peer if it advertised the sampling capability.
+fn sampling_peer(peer: Option<&Peer<RoleServer>>) -> Result<&Peer<RoleServer>, NbError> {
+    peer.filter(|peer| {
+        peer.peer_info()
+            .is_some_and(|info| info.capabilities.sampling.is_some())
+    })
+    .ok_or_else(|| NbError::CommandFailed("the MCP client does not support sampling".to_string()))
+}
+
+/// Sends one prompt to the client's model, returning the reply text and model.
+async fn complete(
+    peer: &Peer<RoleServer>,
+    system_prompt: &str,
+    prompt: String,
+    max_tokens: u32,
+) -> Result<(String, String), NbError> {
+    let result = peer
+        .create_message(CreateMessageRequestParam {
+            messages: vec![SamplingMessage {
+                role: Role::User,
+                content: Content::text(prompt),
+            }],
+            model_preferences: None,
+            system_prompt: Some(system_prompt.to_string()),
+            include_context: None,
+            temperature: None,
+            max_tokens,
+            stop_sequences: None,
+            metadata: None,
+        })
+        .await
+        .map_err(|err| NbError::CommandFailed(format!("sampling request failed: {err}")))?;
+    let text = result
+        .message
+        .content
+        .as_text()
+        .map(|text| text.text.trim().to_string())
+        .ok_or_else(|| {
+            NbError::CommandFailed("sampling response did not contain text".to_string())
+        })?;
+    Ok((text, result.model))
+}
+
+/// Reads a note's raw file content, rejecting binary files.
+async fn read_note(nb: &NbClient, id: &str, notebook: Option<&str>) -> Result<String, NbError> {
+    let path = nb.show_path(id, notebook).await?;
+    let content = tokio::fs::read(&path).await?;
+    String::from_utf8(content)
+        .map_err(|_| NbError::CommandFailed(format!("note {id} is not a text file")))
+}
+
+/// Summarizes a note, optionally storing the summary in its frontmatter.
+pub async fn summarize(
+    nb: &NbClient,
+    peer: Option<&Peer<RoleServer>>,
+    args: &SummarizeArgs,
+) -> Result<Summary, NbError> {
+    let peer = sampling_peer(peer)?;
+    let notebook = args.notebook.as_deref();
+    let content = read_note(nb, &args.id, notebook).await?;
+    let (_, body) = notes::split_frontmatter(&content);
+    let words = args.max_words.unwrap_or(DEFAULT_SUMMARY_WORDS).max(1);
+    let prompt = format!(
+        "Summarize the following note in at most {words} words. \
+         Reply with the summary only.\n\n{body}"
+    );
+    let (summary, model) = complete(
+        peer,
+        "You write concise, factual summaries of personal notes.",
+        prompt,
+        words.saturating_mul(4).max(64),
+    )
+    .await?;
+    let summary = summary.split_whitespace().collect::<Vec<_>>().join(" ");
+    if args.write {
+        let updated =
+            notes::set_frontmatter_field(&content, "summary", &notes::yaml_string(&summary));
+        nb.edit(&args.id, &updated, notebook).await?;
+    }
+    Ok(Summary {
+        id: args.id.clone(),
+        summary,
+        model,
+        written: args.write,
+    })
+}
+
+/// Result of suggesting tags for a note.
+#[derive(Debug, Serialize)]
+pub struct TagSuggestion {
+    pub id: String,
+    /// Suggested tags the note does not already have, without `#`.
+    pub suggested: Vec<String>,
+    /// Suggested tags that are not yet used anywhere in the notebook.
+    pub new_tags: Vec<String>,
+    /// Model the client used for sampling.
+    pub model: String,
+    /// Whether the suggested tags were added to the note.
+    pub applied: bool,
+}
+
+/// Suggests tags for a note, preferring the notebook's existing vocabulary.
+pub async fn suggest_tags(
+    nb: &NbClient,
+    peer: Option<&Peer<RoleServer>>,
+    args: &SuggestTagsArgs,
+) -> Result<TagSuggestion, NbError> {
+    let peer = sampling_peer(peer)?;
+    let notebook = args.notebook.as_deref();
+    let content = read_note(nb, &args.id, notebook).await?;
+    let root = nb.notebook_path(notebook).await?;
+    let vocabulary = tokio::task::spawn_blocking(move || notebook_tags(&root))
+        .await
+        .map_err(|err| NbError::CommandFailed(format!("tag scan task failed: {err}")))??;
+    let max_tags = args.max_tags.unwrap_or(DEFAULT_MAX_TAGS).max(1);
+    let prompt = format!(
+        "Suggest up to {max_tags} tags for the note below. Prefer tags from this \
+         existing vocabulary and only invent a new tag when none fits: {}.\n\
+         Reply with a comma-separated list of tags only.\n\n{}",
+        if vocabulary.is_empty() {
+            "(none yet)".to_string()
+        } else {
+            vocabulary.join(", ")
+        },
+        notes::split_frontmatter(&content).1
+    );
+    let (reply, model) = complete(
+        peer,
+        "You organize a personal knowledge base with consistent, lowercase tags.",
+        prompt,
+        128,
+    )
+    .await?;
+    let existing = notes::extract_tags(&content);
+    let suggested = parse_tags(&reply)
+        .into_iter()
+        .filter(|tag| !existing.contains(tag))
+        .take(max_tags as usize)
+        .collect::<Vec<_>>();
+    let new_tags = suggested
+        .iter()
+        .filter(|tag| !vocabulary.contains(tag))
+        .cloned()
+        .collect();
+    let applied = args.apply && !suggested.is_empty();
+    if applied {
+        let updated = notes::add_hashtags(&content, &suggested);
+        nb.edit(&args.id, &updated, notebook).await?;
+    }
+    Ok(TagSuggestion {
+        id: args.id.clone(),
+        suggested,
+        new_tags,
+        model,
+        applied,
+    })
+}
+
+/// Collects every hashtag used in the notebook, sorted.
+fn notebook_tags(root: &std::path::Path) -> Result<Vec<String>, NbError> {
+    let mut tags = notes::read_notes(root, None)?
+        .iter()
+        .flat_map(|note| notes::extract_tags(&note.content))
+        .collect::<Vec<_>>();
+    tags.sort();
+    tags.dedup();
+    Ok(tags)
+}
+
+/// Parses a model reply into normalized tags.
+///
+/// Accepts comma- or whitespace-separated lists, with or without `#`.
+fn parse_tags(reply: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in reply.split(|c: char| c == ',' || c.is_whitespace()) {
+        let tag = tag
+            .trim_matches(|c: char| !c.is_alphanumeric() && c != '-' && c != '_' && c != '/')
+            .to_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_tags;
+
+    #[test]
+    fn parse_tags_normalizes_model_replies() {
+        assert_eq!(
+            parse_tags("#API, design,  Design\nroadmap."),
+            vec!["api", "design", "roadmap"]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Canonical metadata for notes created or updated by a call.
+//!
+//! nb confirms `add` and `edit` with a line such as
+//! `Added: [work:12] 20240102120000.md "Plan"`. The server turns it into
+//! `{notebook, id, filename, path, commit, hash}` so follow-up calls can address
+//! the note directly instead of listing the notebook to find it.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::commit_message;
+use crate::content_hash;
+use crate::nb::NbClient;
+
+/// Matches nb's confirmation line: `Added: [id] filename "title"`.
+static SAVED_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^(?:Added|Updated):\s+\[([^\]]+)\]\s+(.+?)(?:\s+"(.*)")?$"#).unwrap()
+});
+
+/// A note as saved by `add` or `edit`.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct SavedNote {
+    pub notebook: String,
+    /// Selector for the note within the notebook (e.g., "12" or "docs/3").
+    pub id: String,
+    /// File name relative to the notebook root.
+    pub filename: String,
+    /// Absolute path of the note file.
+    pub path: String,
+    /// Notebook commit recording the change, if the notebook has one.
+    pub commit: Option<String>,
+    /// Content hash of the saved file, for a later `edit`'s `expected_hash`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// nb's confirmation output.
+    pub message: String,
+}
+
+/// Describes the note named in `output` as JSON, or returns `output` as is
+/// when nb's confirmation is not recognized.
+pub async fn describe(nb: &NbClient, output: String, notebook: Option<&str>) -> String {
+    let Some(line) = output
+        .lines()
+        .find_map(|line| SAVED_REGEX.captures(line.trim()))
+    else {
+        return output;
+    };
+    let Ok(notebook) = nb.resolve_notebook(notebook).await else {
+        return output;
+    };
+    let Ok(root) = nb.notebook_path(Some(&notebook)).await else {
+        return output;
+    };
+    // nb qualifies ids with the notebook when it is not the current one.
+    let id = line[1]
+        .strip_prefix(&format!("{notebook}:"))
+        .unwrap_or(&line[1]);
+    let filename = line[2].to_string();
+    let path = root.join(&filename);
+    let note = SavedNote {
+        id: id.to_string(),
+        hash: tokio::fs::read(&path)
+            .await
+            .ok()
+            .map(|content| content_hash::of(&content)),
+        path: path.display().to_string(),
+        filename,
+        commit: commit_message::head(nb, &root).await.ok().flatten(),
+        title: line.get(3).map(|title| title.as_str().to_string()),
+        notebook,
+        message: output.trim_end().to_string(),
+    };
+    serde_json::to_string_pretty(&note).unwrap_or(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::describe;
+    use crate::{FakeExecutor, NbClient};
+
+    #[tokio::test]
+    async fn describe_reads_qualified_ids() {
+        let fake = Arc::new(FakeExecutor::new().notebook("work", "/notes/work"));
+        let nb = NbClient::with_executor(fake, None, false);
+        let output = "Added: [work:docs/3] docs/20240102.md \"Q1 plan\"\n".to_string();
+        let note: serde_json::Value =
+            serde_json::from_str(&describe(&nb, output, Some("work")).await).unwrap();
+        assert_eq!(note["id"], "docs/3");
+        assert_eq!(note["filename"], "docs/20240102.md");
+        assert_eq!(note["path"], "/notes/work/docs/20240102.md");
+        assert_eq!(note["title"], "Q1 plan");
+        assert!(note["commit"].is_null());
+    }
+
+    #[tokio::test]
+    async fn describe_keeps_unrecognized_output() {
+        let fake = Arc::new(FakeExecutor::new());
+        let nb = NbClient::with_executor(fake, None, false);
+        assert_eq!(describe(&nb, "Done".to_string(), None).await, "Done");
+    }
+}
@@ -0,0 +1,129 @@
+//! Standard structure for newly created notebooks.
+//!
+//! When enabled, a notebook created automatically is seeded in one commit
+//! with folders, a README note, and saved views, so every project notebook
+//! starts with the same layout. Notebooks that already exist are never
+//! touched.
+//!
+//! ```toml
+//! [scaffold]
+//! folders = ["inbox", "journal", "projects", "decisions", ".templates"]
+//! readme = "# README\n\nProject notes for the team.\n"
+//!
+//! [scaffold.views.inbox]
+//! folder = "inbox"
+//! sort = "modified"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::args::SavedView;
+use crate::nb::NbError;
+use crate::views;
+
+/// File name of the README note.
+const README_FILE: &str = "README.md";
+
+/// Folders, README note, and views written into a new notebook.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scaffold {
+    /// Folders to create, relative to the notebook root.
+    pub folders: Vec<String>,
+    /// README note content; empty to skip the note.
+    pub readme: String,
+    /// Views written to the notebook views file.
+    pub views: BTreeMap<String, SavedView>,
+}
+
+impl Default for Scaffold {
+    fn default() -> Self {
+        Self {
+            folders: ["inbox", "journal", "projects", ".templates"]
+                .map(String::from)
+                .to_vec(),
+            readme: "# README\n\n\
+                     - `inbox/`: new notes waiting to be sorted\n\
+                     - `journal/`: dated entries\n\
+                     - `projects/`: one folder per project\n\
+                     - `.templates/`: note templates\n"
+                .to_string(),
+            views: BTreeMap::from([(
+                "inbox".to_string(),
+                SavedView {
+                    folder: Some("inbox".to_string()),
+                    sort: Some("modified".to_string()),
+                    ..SavedView::default()
+                },
+            )]),
+        }
+    }
+}
+
+impl Scaffold {
+    /// Writes the scaffold into the notebook directory at `root`.
+    ///
+    /// Existing files are left alone. Each folder gets an empty `.index`,
+    /// as nb creates for its own folders, so Git tracks it while empty.
+    pub async fn write(&self, root: &Path) -> Result<(), NbError> {
+        for folder in &self.folders {
+            let folder = folder.trim_matches('/');
+            if folder.is_empty() || folder.split('/').any(|part| part == "..") {
+                return Err(NbError::CommandFailed(format!(
+                    "invalid scaffold folder: {folder:?}"
+                )));
+            }
+            let dir = root.join(folder);
+            tokio::fs::create_dir_all(&dir).await?;
+            write_new(&dir.join(".index"), "").await?;
+        }
+        if !self.readme.trim().is_empty() {
+            write_new(&root.join(README_FILE), &self.readme).await?;
+        }
+        if !self.views.is_empty() {
+            let text = views::encode(self.views.clone())?;
+            write_new(&root.join(views::VIEWS_FILE), &text).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `content` to `path` unless the file already exists.
+async fn write_new(path: &Path, content: &str) -> Result<(), NbError> {
+    if tokio::fs::try_exists(path).await? {
+        return Ok(());
+    }
+    tokio::fs::write(path, content).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Scaffold;
+
+    #[tokio::test]
+    async fn write_creates_standard_structure() {
+        let root = std::env::temp_dir().join(format!("nb-mcp-scaffold-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("README.md"), "mine\n").unwrap();
+        Scaffold::default().write(&root).await.unwrap();
+        let readme = std::fs::read_to_string(root.join("README.md")).unwrap();
+        let views = std::fs::read_to_string(root.join(".nb-mcp-views.toml")).unwrap();
+        let templates = root.join(".templates/.index").is_file();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(readme, "mine\n");
+        assert!(views.contains("[views.inbox]"));
+        assert!(templates);
+    }
+
+    #[test]
+    fn parse_fills_unspecified_parts_with_defaults() {
+        let scaffold: Scaffold = toml::from_str(r#"folders = ["notes"]"#).unwrap();
+        assert_eq!(scaffold.folders, vec!["notes"]);
+        assert!(scaffold.readme.starts_with("# README"));
+        assert!(scaffold.views.contains_key("inbox"));
+    }
+}
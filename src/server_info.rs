@@ -0,0 +1,296 @@
+//! Environment report for the `server_info` tool.
+//!
+//! Agents call it before starting work to check that nb and git are
+//! installed (and whether optional pandoc is), which notebook their calls
+//! will land in, and whether earlier calls have been failing.
+
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::backup::{BackupMonitor, BackupStatus};
+use crate::nb::NbClient;
+use crate::nb_version::Compatibility;
+use crate::pandoc;
+
+/// Calls served since the server started.
+#[derive(Debug)]
+pub struct CallStats {
+    started: Instant,
+    counts: Mutex<CallCounts>,
+}
+
+/// Call counts reported by `server_info`.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct CallCounts {
+    pub total: u64,
+    /// Calls that returned a tool error.
+    pub failed: u64,
+    /// Calls per subcommand.
+    pub by_command: BTreeMap<String, u64>,
+    /// Call durations per subcommand.
+    pub latency: BTreeMap<String, Latency>,
+}
+
+/// Upper bounds of the latency histogram buckets, in milliseconds; slower
+/// calls fall into a final unbounded bucket.
+const BUCKET_BOUNDS_MS: [u64; 8] = [50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+/// Durations of the calls of one subcommand.
+#[derive(Debug, Default, Clone)]
+pub struct Latency {
+    calls: u64,
+    total_ms: u64,
+    max_ms: u64,
+    buckets: [u64; BUCKET_BOUNDS_MS.len() + 1],
+}
+
+/// Latency summary reported by `server_info`.
+#[derive(Debug, Serialize)]
+struct LatencySummary {
+    mean_ms: u64,
+    /// Estimated from the histogram: the bound of the bucket holding the
+    /// percentile, capped at the maximum.
+    p50_ms: u64,
+    p95_ms: u64,
+    max_ms: u64,
+    /// Non-empty buckets, fastest first.
+    histogram: Vec<Bucket>,
+}
+
+#[derive(Debug, Serialize)]
+struct Bucket {
+    /// Upper bound in milliseconds; absent for the slowest bucket.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    le_ms: Option<u64>,
+    calls: u64,
+}
+
+impl Latency {
+    fn record(&mut self, elapsed: Duration) {
+        let ms = elapsed.as_millis() as u64;
+        self.calls += 1;
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        let bucket = BUCKET_BOUNDS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.buckets[bucket] += 1;
+    }
+
+    /// Returns the estimated duration below which `quantile` of calls fall.
+    fn percentile(&self, quantile: f64) -> u64 {
+        let rank = ((self.calls as f64) * quantile).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound = BUCKET_BOUNDS_MS.get(index).copied().unwrap_or(u64::MAX);
+                return bound.min(self.max_ms);
+            }
+        }
+        self.max_ms
+    }
+
+    fn summary(&self) -> LatencySummary {
+        LatencySummary {
+            mean_ms: self.total_ms / self.calls.max(1),
+            p50_ms: self.percentile(0.5),
+            p95_ms: self.percentile(0.95),
+            max_ms: self.max_ms,
+            histogram: self
+                .buckets
+                .iter()
+                .enumerate()
+                .filter(|(_, calls)| **calls > 0)
+                .map(|(index, calls)| Bucket {
+                    le_ms: BUCKET_BOUNDS_MS.get(index).copied(),
+                    calls: *calls,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Serialize for Latency {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.summary().serialize(serializer)
+    }
+}
+
+impl Default for CallStats {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            counts: Mutex::default(),
+        }
+    }
+}
+
+impl CallStats {
+    /// Records one served call of `command` that took `elapsed`.
+    pub fn record(&self, command: &str, failed: bool, elapsed: Duration) {
+        let mut counts = self.counts.lock().expect("call stats lock poisoned");
+        counts.total += 1;
+        if failed {
+            counts.failed += 1;
+        }
+        *counts.by_command.entry(command.to_string()).or_default() += 1;
+        counts
+            .latency
+            .entry(command.to_string())
+            .or_default()
+            .record(elapsed);
+    }
+
+    fn snapshot(&self) -> CallCounts {
+        self.counts
+            .lock()
+            .expect("call stats lock poisoned")
+            .clone()
+    }
+}
+
+/// Report returned by the `server_info` tool.
+#[derive(Debug, Serialize)]
+pub struct ServerReport {
+    pub server_version: &'static str,
+    /// Installed nb version, if nb could be run.
+    pub nb_version: Option<String>,
+    /// Installed git version, if git could be run.
+    pub git_version: Option<String>,
+    /// Installed pandoc version, if pandoc could be run. Document formats of
+    /// `nb.import` and `nb.export` need it.
+    pub pandoc_version: Option<String>,
+    /// Installed nb checked against the versions the server requires.
+    pub nb_compatibility: Option<Compatibility>,
+    /// Notebook used by calls without a `notebook` argument.
+    pub default_notebook: Option<String>,
+    /// Whether the default notebook already exists.
+    pub default_notebook_exists: bool,
+    pub notebook_path: Option<String>,
+    pub commit_signing_disabled: bool,
+    /// nb and git invocations slower than this are logged as warnings.
+    pub slow_call_threshold_ms: Option<u64>,
+    pub uptime_seconds: u64,
+    pub calls: CallCounts,
+    /// Scheduled backup state, if backups are configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<BackupStatus>,
+    /// Problems found while probing the environment.
+    pub problems: Vec<String>,
+}
+
+/// Probes the environment and assembles the report.
+///
+/// Failed probes are listed under `problems` rather than failing the call,
+/// since diagnosing them is the point of the tool. Missing notebooks are not
+/// created.
+pub async fn report(
+    nb: &NbClient,
+    stats: &CallStats,
+    backup: Option<&BackupMonitor>,
+) -> ServerReport {
+    let mut problems = Vec::new();
+    let nb_version = nb
+        .version()
+        .await
+        .map_err(|err| problems.push(err.to_string()))
+        .ok();
+    let git_version = nb
+        .git_version()
+        .await
+        .map_err(|err| problems.push(format!("git unavailable: {err}")))
+        .ok();
+    let pandoc_version = pandoc::version().await;
+    let nb_compatibility = nb_version.as_deref().map(Compatibility::check);
+    if let Some(compatibility) = nb_compatibility.as_ref().filter(|c| !c.supported) {
+        problems.push(format!(
+            "nb {} is older than the minimum supported {}; unavailable commands: {}",
+            nb_version.as_deref().unwrap_or_default(),
+            compatibility.minimum,
+            compatibility.unavailable_commands.join(", ")
+        ));
+    }
+    let mut default_notebook = None;
+    let mut notebook_path = None;
+    if nb_version.is_some() {
+        match nb.existing_default_notebook().await {
+            Ok(Some(name)) => {
+                notebook_path = nb
+                    .notebook_path(Some(&name))
+                    .await
+                    .map(|path| path.display().to_string())
+                    .map_err(|err| problems.push(err.to_string()))
+                    .ok();
+                default_notebook = Some(name);
+            }
+            Ok(None) => {}
+            Err(err) => problems.push(err.to_string()),
+        }
+    }
+    let default_notebook_exists = default_notebook.is_some();
+    let default_notebook = default_notebook.or_else(|| nb.default_notebook());
+    if default_notebook.is_none() {
+        problems.push("no default notebook configured or derivable".to_string());
+    }
+    let backup = backup.map(BackupMonitor::snapshot);
+    if let Some(run) = backup
+        .as_ref()
+        .and_then(|status| status.last_run.as_ref())
+        .filter(|run| !run.success)
+    {
+        problems.push(format!("last backup failed: {}", run.detail));
+    }
+    ServerReport {
+        server_version: env!("CARGO_PKG_VERSION"),
+        nb_version,
+        git_version,
+        pandoc_version,
+        nb_compatibility,
+        default_notebook,
+        default_notebook_exists,
+        notebook_path,
+        commit_signing_disabled: nb.commit_signing_disabled(),
+        slow_call_threshold_ms: nb
+            .slow_call_threshold()
+            .map(|threshold| threshold.as_millis() as u64),
+        uptime_seconds: stats.started.elapsed().as_secs(),
+        calls: stats.snapshot(),
+        backup,
+        problems,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Latency;
+
+    #[test]
+    fn latency_summarizes_histogram_and_percentiles() {
+        let mut latency = Latency::default();
+        for ms in [20, 30, 40, 80, 7_000] {
+            latency.record(Duration::from_millis(ms));
+        }
+        let summary = serde_json::to_value(&latency).unwrap();
+        assert_eq!(summary["mean_ms"], 1_434);
+        assert_eq!(summary["p50_ms"], 50);
+        assert_eq!(summary["p95_ms"], 7_000);
+        assert_eq!(summary["max_ms"], 7_000);
+        assert_eq!(
+            summary["histogram"],
+            serde_json::json!([
+                {"le_ms": 50, "calls": 3},
+                {"le_ms": 100, "calls": 1},
+                {"le_ms": 10000, "calls": 1},
+            ])
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Session working folder.
+//!
+//! An agent working on one project would otherwise repeat
+//! `folder: "projects/acme"` on every call. `nb.use_folder` sets a folder
+//! that later calls of the session act in when they name none: notes and
+//! todos are created there, and listings, searches, and reports are scoped
+//! to it. The folder lives as long as the session; over stdio that is the
+//! server process.
+
+/// Subcommands whose `folder` argument the session folder fills in.
+///
+/// Commands where `folder` names a destination other than where the call's
+/// notes live (`copy_to`, `load`, `split`) or a special folder
+/// (`journal_stats`) are left alone.
+const FOLDER_COMMANDS: &[&str] = &[
+    "add",
+    "list",
+    "search",
+    "show_folder",
+    "todo",
+    "tasks",
+    "tasks_ical",
+    "todotxt_import",
+    "todotxt_export",
+    "board",
+    "resolve",
+    "pinned",
+    "bookmark",
+    "import",
+    "dump",
+    "tag_graph",
+    "dedupe",
+    "stale",
+    "note_stats",
+    "references",
+    "extract_todos",
+];
+
+/// Normalizes a folder passed to `nb.use_folder`; an empty folder clears the
+/// session folder.
+///
+/// Returns why the folder is refused if it leaves the notebook.
+pub fn normalize(folder: Option<&str>) -> Result<Option<String>, String> {
+    let Some(folder) = folder.map(|folder| folder.trim().trim_matches('/')) else {
+        return Ok(None);
+    };
+    if folder.split('/').any(|part| part == "..") {
+        return Err(format!("folder {folder} is outside the notebook"));
+    }
+    Ok((!folder.is_empty()).then(|| folder.to_string()))
+}
+
+/// Checks the `folder` argument of a folder-scoped call, so reads and
+/// writes cannot leave the notebook.
+///
+/// Returns why the folder is refused.
+pub fn check_folder(subcommand: &str, args: &serde_json::Value) -> Result<(), String> {
+    if !FOLDER_COMMANDS.contains(&subcommand) {
+        return Ok(());
+    }
+    match args.get("folder").and_then(|folder| folder.as_str()) {
+        Some(folder) => normalize(Some(folder)).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// Fills in the session `folder` for a call that names none.
+///
+/// Calls passing `no_defaults: true` are left untouched, as they are for the
+/// configured default folder.
+pub fn scope_args(
+    folder: Option<&str>,
+    subcommand: &str,
+    mut args: serde_json::Value,
+) -> serde_json::Value {
+    let Some(folder) = folder else {
+        return args;
+    };
+    if !FOLDER_COMMANDS.contains(&subcommand)
+        || args.get("folder").is_some_and(|value| !value.is_null())
+        || args.get("no_defaults").and_then(|value| value.as_bool()) == Some(true)
+    {
+        return args;
+    }
+    if args.is_null() {
+        args = serde_json::json!({});
+    }
+    if let Some(object) = args.as_object_mut() {
+        object.insert("folder".to_string(), folder.into());
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{check_folder, normalize, scope_args};
+
+    #[test]
+    fn scope_args_fills_in_the_folder_when_missing() {
+        assert_eq!(
+            scope_args(Some("projects/acme"), "list", serde_json::Value::Null),
+            json!({"folder": "projects/acme"})
+        );
+        assert_eq!(
+            scope_args(Some("projects/acme"), "add", json!({"folder": "inbox"})),
+            json!({"folder": "inbox"})
+        );
+        assert_eq!(
+            scope_args(Some("projects/acme"), "add", json!({"no_defaults": true})),
+            json!({"no_defaults": true})
+        );
+        assert_eq!(
+            scope_args(Some("projects/acme"), "split", json!({"id": "1"})),
+            json!({"id": "1"})
+        );
+        assert_eq!(scope_args(None, "list", json!({})), json!({}));
+    }
+
+    #[test]
+    fn normalize_trims_and_rejects_escapes() {
+        assert_eq!(
+            normalize(Some("/projects/acme/")).unwrap().as_deref(),
+            Some("projects/acme")
+        );
+        assert_eq!(normalize(Some("")).unwrap(), None);
+        assert_eq!(normalize(None).unwrap(), None);
+        assert!(normalize(Some("../other")).is_err());
+    }
+
+    #[test]
+    fn check_folder_refuses_escapes_of_scoped_commands() {
+        assert!(check_folder("show_folder", &json!({"folder": "../.."})).is_err());
+        assert!(check_folder("stale", &json!({"folder": "docs/../../x"})).is_err());
+        assert!(check_folder("references", &json!({"folder": "docs"})).is_ok());
+        assert!(check_folder("copy_to", &json!({"folder": "../x"})).is_ok());
+    }
+}
@@ -0,0 +1,267 @@
+//! Reading every note of a folder in one call.
+//!
+//! Agents summarizing a project would otherwise list a folder and then show
+//! each note. `nb.show_folder` reads the folder's notes (and optionally its
+//! subfolders') in name or date order, either as one structured list with
+//! each note's path, content, and hash, or concatenated into one Markdown
+//! text with a `<!-- path -->` comment before each note. All content shares
+//! one size budget: the note that crosses it is cut, and the paths of the
+//! notes after it are reported as omitted.
+
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use crate::archive;
+use crate::args::ShowFolderArgs;
+use crate::content_hash;
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+use crate::output;
+
+/// Hint attached to a note cut at the size budget.
+const CUT_HINT: &str = "Size budget reached. Read the rest of this note with nb.show and range.";
+
+/// Order notes are read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FolderOrder {
+    Name,
+    Created,
+    Modified,
+}
+
+impl FolderOrder {
+    pub fn parse(value: &str) -> Result<Self, NbError> {
+        match value {
+            "name" => Ok(Self::Name),
+            "created" => Ok(Self::Created),
+            "modified" => Ok(Self::Modified),
+            other => Err(NbError::CommandFailed(format!(
+                "unknown sort {other:?}; expected name, created, or modified"
+            ))),
+        }
+    }
+
+    /// Parses an `order` argument into "descending?"; names default to
+    /// ascending and dates to newest first.
+    pub fn descending(self, order: Option<&str>) -> Result<bool, NbError> {
+        match order {
+            None => Ok(self != Self::Name),
+            Some("asc") => Ok(false),
+            Some("desc") => Ok(true),
+            Some(other) => Err(NbError::CommandFailed(format!(
+                "unknown order {other:?}; expected asc or desc"
+            ))),
+        }
+    }
+}
+
+/// A note of a `nb.show_folder` result.
+#[derive(Debug, Serialize)]
+pub struct FolderNote {
+    /// Path relative to the notebook root.
+    pub path: String,
+    pub content: String,
+    /// Content hash to pass to `nb.edit` as `expected_hash`.
+    pub hash: String,
+}
+
+/// Result of `nb.show_folder`.
+#[derive(Debug, Serialize)]
+pub struct ShownFolder {
+    pub folder: String,
+    /// Notes read, in the requested order.
+    pub notes: Vec<FolderNote>,
+    /// Paths of notes not read because the size budget ran out.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub omitted: Vec<String>,
+    /// Whether any content was cut or omitted.
+    pub truncated: bool,
+}
+
+/// Reads the notes of `args.folder` within `budget` bytes of content.
+///
+/// Returns the JSON result, or the concatenated text with `concatenate`.
+pub async fn show_folder(
+    nb: &NbClient,
+    args: &ShowFolderArgs,
+    budget: usize,
+) -> Result<String, NbError> {
+    let order = FolderOrder::parse(args.sort.as_deref().unwrap_or("name"))?;
+    let descending = order.descending(args.order.as_deref())?;
+    let root = nb.notebook_path(args.notebook.as_deref()).await?;
+    let folder = args
+        .folder
+        .as_deref()
+        .map(|folder| folder.trim_matches('/').to_string())
+        .filter(|folder| !folder.is_empty());
+    let hide_archived = archive::hides(&root, folder.as_deref(), args.include_archived);
+    let recursive = args.recursive;
+    let scan_folder = folder.clone();
+    let files = tokio::task::spawn_blocking(move || {
+        let files = notes::read_notes(&root, scan_folder.as_deref())?;
+        let prefix = scan_folder.map_or(String::new(), |folder| format!("{folder}/"));
+        let mut files = files
+            .into_iter()
+            .filter(|note| {
+                let relative = note.path.strip_prefix(&prefix).unwrap_or(&note.path);
+                (recursive || !relative.contains('/'))
+                    && !(hide_archived && note.path.split('/').next() == Some(archive::FOLDER))
+            })
+            .map(|note| {
+                let metadata = std::fs::metadata(root.join(&note.path)).ok();
+                let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+                let created = metadata
+                    .as_ref()
+                    .and_then(|m| m.created().ok())
+                    .or(modified);
+                let date = match order {
+                    FolderOrder::Name => None,
+                    FolderOrder::Created => created,
+                    FolderOrder::Modified => modified,
+                };
+                (note, date)
+            })
+            .collect::<Vec<_>>();
+        sort(&mut files, order, descending);
+        Ok::<_, std::io::Error>(files)
+    })
+    .await
+    .map_err(|err| NbError::CommandFailed(format!("show_folder task failed: {err}")))?
+    .map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => NbError::CommandFailed(format!(
+            "no folder {} in the notebook",
+            folder.as_deref().unwrap_or("/")
+        )),
+        _ => err.into(),
+    })?;
+
+    let mut shown = ShownFolder {
+        folder: folder.unwrap_or_default(),
+        notes: Vec::new(),
+        omitted: Vec::new(),
+        truncated: false,
+    };
+    let mut remaining = budget;
+    for (note, _) in files {
+        if remaining == 0 {
+            shown.truncated = true;
+            shown.omitted.push(note.path);
+            continue;
+        }
+        let hash = content_hash::of(note.content.as_bytes());
+        let content = if note.content.len() > remaining {
+            shown.truncated = true;
+            let cut = output::window(&note.content, 0, None, remaining, CUT_HINT);
+            remaining = 0;
+            cut
+        } else {
+            remaining -= note.content.len();
+            note.content
+        };
+        shown.notes.push(FolderNote {
+            path: note.path,
+            content,
+            hash,
+        });
+    }
+    Ok(if args.concatenate {
+        concatenate(&shown)
+    } else {
+        serde_json::to_string_pretty(&shown).unwrap_or_default()
+    })
+}
+
+/// Orders `files` by name, or by date with undated files last.
+fn sort(files: &mut [(notes::NoteFile, Option<SystemTime>)], order: FolderOrder, descending: bool) {
+    files.sort_by(|(a, a_date), (b, b_date)| match order {
+        FolderOrder::Name if descending => b.path.cmp(&a.path),
+        FolderOrder::Name => a.path.cmp(&b.path),
+        FolderOrder::Created | FolderOrder::Modified => match (a_date, b_date) {
+            (Some(a_date), Some(b_date)) if descending => b_date.cmp(a_date),
+            (Some(a_date), Some(b_date)) => a_date.cmp(b_date),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.path.cmp(&b.path),
+        },
+    });
+}
+
+/// Joins the notes into one text, each after a comment naming its path.
+fn concatenate(shown: &ShownFolder) -> String {
+    let mut text = String::new();
+    for note in &shown.notes {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&format!("<!-- {} -->\n\n", note.path));
+        text.push_str(note.content.trim_end());
+        text.push('\n');
+    }
+    if !shown.omitted.is_empty() {
+        text.push_str(&format!(
+            "\n<!-- omitted at the size budget: {} -->\n",
+            shown.omitted.join(", ")
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{FolderNote, FolderOrder, ShownFolder, concatenate, sort};
+    use crate::notes::NoteFile;
+
+    fn file(path: &str, seconds: Option<u64>) -> (NoteFile, Option<SystemTime>) {
+        let note = NoteFile {
+            path: path.to_string(),
+            content: String::new(),
+        };
+        (
+            note,
+            seconds.map(|seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)),
+        )
+    }
+
+    fn paths(files: &[(NoteFile, Option<SystemTime>)]) -> Vec<&str> {
+        files.iter().map(|(note, _)| note.path.as_str()).collect()
+    }
+
+    #[test]
+    fn sort_orders_by_name_or_date() {
+        let mut files = vec![
+            file("b.md", Some(1)),
+            file("c.md", None),
+            file("a.md", Some(2)),
+        ];
+        sort(&mut files, FolderOrder::Name, false);
+        assert_eq!(paths(&files), ["a.md", "b.md", "c.md"]);
+        sort(&mut files, FolderOrder::Modified, true);
+        assert_eq!(paths(&files), ["a.md", "b.md", "c.md"]);
+        sort(&mut files, FolderOrder::Modified, false);
+        assert_eq!(paths(&files), ["b.md", "a.md", "c.md"]);
+    }
+
+    #[test]
+    fn concatenate_names_each_note() {
+        let shown = ShownFolder {
+            folder: "acme".to_string(),
+            notes: ["a", "b"]
+                .map(|name| FolderNote {
+                    path: format!("acme/{name}.md"),
+                    content: format!("# {name}\n\n"),
+                    hash: String::new(),
+                })
+                .into(),
+            omitted: vec!["acme/c.md".to_string()],
+            truncated: true,
+        };
+        assert_eq!(
+            concatenate(&shown),
+            "<!-- acme/a.md -->\n\n# a\n\n<!-- acme/b.md -->\n\n# b\n\n\
+             <!-- omitted at the size budget: acme/c.md -->\n"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Reading several notes in one call.
+//!
+//! An agent that needs a handful of related notes would otherwise call
+//! `nb.show` once per note. `nb.show_many` reads them in order into one
+//! response keyed by id, sharing one size budget: the note that crosses the
+//! budget is cut with a truncation marker, and the notes after it are listed
+//! as omitted rather than read.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::nb::{NbClient, NbError};
+use crate::output;
+use crate::session_folder;
+
+/// Most notes read in one call.
+pub const MAX_NOTES: usize = 50;
+
+/// Result of `nb.show_many`.
+#[derive(Debug, Default, Serialize)]
+pub struct ShownNotes {
+    /// Notes read, by the id they were requested with.
+    pub notes: BTreeMap<String, ShownNote>,
+    /// Ids not read because the size budget ran out.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub omitted: Vec<String>,
+    /// Whether any content was cut or omitted.
+    pub truncated: bool,
+    #[serde(skip)]
+    remaining: usize,
+}
+
+/// One note of a `nb.show_many` result.
+#[derive(Debug, Serialize)]
+pub struct ShownNote {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Content hash to pass to `nb.edit` as `expected_hash`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// Why the note could not be read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ShownNotes {
+    /// Starts a result that holds at most `budget` bytes of content.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            remaining: budget,
+            ..Self::default()
+        }
+    }
+
+    /// Returns whether content can still be added.
+    pub fn has_room(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// Records a note that was read, cutting it to the remaining budget.
+    pub fn push(&mut self, id: &str, content: String, hash: Option<String>, hint: &str) {
+        let content = if content.len() > self.remaining {
+            self.truncated = true;
+            let cut = output::window(&content, 0, None, self.remaining, hint);
+            self.remaining = 0;
+            cut
+        } else {
+            self.remaining -= content.len();
+            content
+        };
+        self.notes.insert(
+            id.to_string(),
+            ShownNote {
+                content: Some(content),
+                hash,
+                error: None,
+            },
+        );
+    }
+
+    /// Records a note that could not be read.
+    pub fn push_error(&mut self, id: &str, error: String) {
+        self.notes.insert(
+            id.to_string(),
+            ShownNote {
+                content: None,
+                hash: None,
+                error: Some(error),
+            },
+        );
+    }
+
+    /// Records a note skipped because the budget ran out.
+    pub fn omit(&mut self, id: &str) {
+        self.truncated = true;
+        self.omitted.push(id.to_string());
+    }
+}
+
+/// Returns the files matching `glob` (e.g., `clients/*.md`) as selectors
+/// relative to the notebook root, in name order.
+pub async fn glob_ids(
+    nb: &NbClient,
+    glob: &str,
+    notebook: Option<&str>,
+) -> Result<Vec<String>, NbError> {
+    let (folder, pattern) = split_glob(glob).map_err(NbError::CommandFailed)?;
+    let root = nb.notebook_path(notebook).await?;
+    let dir = folder.map_or(root.clone(), |folder| root.join(folder));
+    let mut entries = tokio::fs::read_dir(&dir).await.map_err(|err| {
+        NbError::CommandFailed(format!("cannot read folder {}: {err}", dir.display()))
+    })?;
+    let mut ids = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') || !entry.file_type().await?.is_file() {
+            continue;
+        }
+        if matches_glob(pattern, &name) {
+            ids.push(match folder {
+                Some(folder) => format!("{folder}/{name}"),
+                None => name,
+            });
+        }
+    }
+    ids.sort();
+    Ok(ids)
+}
+
+/// Splits `glob` into its folder and file name pattern.
+///
+/// Returns why the glob is refused if its folder leaves the notebook.
+pub fn split_glob(glob: &str) -> Result<(Option<&str>, &str), String> {
+    let glob = glob.trim().trim_matches('/');
+    let (folder, pattern) = match glob.rsplit_once('/') {
+        Some((folder, pattern)) => (Some(folder), pattern),
+        None => (None, glob),
+    };
+    session_folder::normalize(folder)?;
+    Ok((folder, pattern))
+}
+
+/// Matches `name` against a pattern with `*` (any run) and `?` (any one
+/// character) wildcards.
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{ShownNotes, glob_ids, matches_glob};
+    use crate::executor::FakeExecutor;
+    use crate::nb::NbClient;
+
+    #[test]
+    fn matches_glob_handles_wildcards() {
+        assert!(matches_glob("*.md", "plan.md"));
+        assert!(matches_glob("plan-?.md", "plan-1.md"));
+        assert!(matches_glob("*notes*", "meeting-notes.todo.md"));
+        assert!(!matches_glob("*.md", "plan.txt"));
+        assert!(!matches_glob("plan-?.md", "plan-10.md"));
+    }
+
+    #[test]
+    fn budget_cuts_the_crossing_note() {
+        let mut shown = ShownNotes::new(8);
+        shown.push("1", "abcde".to_string(), None, "hint");
+        assert!(shown.has_room());
+        shown.push("2", "fghij".to_string(), None, "hint");
+        assert!(!shown.has_room());
+        shown.omit("3");
+        let content = shown.notes["2"].content.as_deref().unwrap();
+        assert!(content.starts_with("fgh\n"));
+        assert!(shown.truncated);
+        assert_eq!(shown.omitted, ["3"]);
+    }
+
+    #[tokio::test]
+    async fn glob_ids_lists_matching_files() {
+        let root = std::env::temp_dir().join(format!("nb-mcp-show-many-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("clients/archive")).unwrap();
+        for name in ["b.md", "a.md", "c.txt", ".index"] {
+            std::fs::write(root.join("clients").join(name), "").unwrap();
+        }
+        let fake = Arc::new(FakeExecutor::new().notebook("work", root.to_str().unwrap()));
+        let nb = NbClient::with_executor(fake, Some("work".to_string()), false);
+        assert_eq!(
+            glob_ids(&nb, "clients/*.md", None).await.unwrap(),
+            ["clients/a.md", "clients/b.md"]
+        );
+        let error = glob_ids(&nb, "clients/../../*", None).await.unwrap_err();
+        assert!(error.to_string().contains("outside the notebook"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
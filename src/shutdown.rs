@@ -0,0 +1,126 @@
+//! Graceful shutdown on SIGTERM and SIGINT.
+//!
+//! When a supervisor stops the server, new tool calls are refused while calls
+//! already running (and the nb subprocesses behind them) get a bounded grace
+//! period to finish before the service is cancelled.
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use tokio::sync::Notify;
+
+/// How long in-flight calls may run after a shutdown signal.
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// Tracks in-flight tool calls and whether new ones are still accepted.
+#[derive(Debug, Default)]
+pub struct CallTracker {
+    shutting_down: AtomicBool,
+    active: AtomicUsize,
+    idle: Notify,
+}
+
+/// Marks a tool call as in flight until dropped.
+pub struct CallGuard<'a> {
+    tracker: &'a CallTracker,
+}
+
+impl CallTracker {
+    /// Registers a new call, or returns `None` once shutdown has begun.
+    pub fn begin(&self) -> Option<CallGuard<'_>> {
+        self.active.fetch_add(1, Ordering::SeqCst);
+        if self.shutting_down.load(Ordering::SeqCst) {
+            self.finish();
+            return None;
+        }
+        Some(CallGuard { tracker: self })
+    }
+
+    /// Stops accepting calls and waits up to `grace` for running ones.
+    ///
+    /// Returns whether every call finished in time.
+    pub async fn drain(&self, grace: Duration) -> bool {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        tokio::time::timeout(grace, async {
+            loop {
+                let idle = self.idle.notified();
+                if self.active.load(Ordering::SeqCst) == 0 {
+                    break;
+                }
+                idle.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+
+    /// Returns the number of calls currently running.
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    fn finish(&self) {
+        if self.active.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+}
+
+impl Drop for CallGuard<'_> {
+    fn drop(&mut self) {
+        self.tracker.finish();
+    }
+}
+
+/// Resolves when the process receives SIGINT or (on Unix) SIGTERM.
+pub async fn signal() {
+    let interrupt = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::warn!(error = %err, "failed to listen for SIGINT");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, "failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        () = interrupt => {}
+        () = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::CallTracker;
+
+    #[tokio::test]
+    async fn drain_waits_for_calls_and_refuses_new_ones() {
+        let tracker = std::sync::Arc::new(CallTracker::default());
+        let running = tracker.clone();
+        let call = tokio::spawn(async move {
+            let _guard = running.begin().unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(tracker.drain(Duration::from_secs(5)).await);
+        assert!(tracker.begin().is_none());
+        assert_eq!(tracker.active(), 0);
+        call.await.unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+//! Reusable text snippets.
+//!
+//! Snippets are short pieces of boilerplate (meeting headers, code block
+//! skeletons) kept as `.snippets/<name>.md` in the notebook, so they travel
+//! with it and are versioned like notes. `nb.save_snippet` writes and commits
+//! one, `nb.snippet` recalls or lists them, and `add` and `edit` take
+//! `insert_snippet` to append one to the note's content.
+
+use serde::Serialize;
+
+use crate::nb::{NbClient, NbError};
+
+/// Folder holding snippets, relative to the notebook root.
+pub const FOLDER: &str = ".snippets";
+
+/// A stored snippet, as listed by `nb.snippet` without a name.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SnippetSummary {
+    pub name: String,
+    /// First non-blank line of the snippet.
+    pub first_line: String,
+}
+
+/// Snippets available in a notebook.
+#[derive(Debug, Serialize)]
+pub struct SnippetList {
+    pub snippets: Vec<SnippetSummary>,
+}
+
+/// Returns the text of snippet `name`.
+pub async fn get(nb: &NbClient, name: &str, notebook: Option<&str>) -> Result<String, NbError> {
+    let name = validate(name)?;
+    let dir = nb.notebook_path(notebook).await?.join(FOLDER);
+    match tokio::fs::read_to_string(dir.join(format!("{name}.md"))).await {
+        Ok(text) => Ok(text),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            let available = list(nb, notebook)
+                .await?
+                .snippets
+                .into_iter()
+                .map(|snippet| snippet.name)
+                .collect::<Vec<_>>();
+            let available = if available.is_empty() {
+                "none".to_string()
+            } else {
+                available.join(", ")
+            };
+            Err(NbError::CommandFailed(format!(
+                "no snippet {name}; available: {available}"
+            )))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Lists the snippets of a notebook by name.
+pub async fn list(nb: &NbClient, notebook: Option<&str>) -> Result<SnippetList, NbError> {
+    let dir = nb.notebook_path(notebook).await?.join(FOLDER);
+    let mut snippets = Vec::new();
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(SnippetList { snippets });
+        }
+        Err(err) => return Err(err.into()),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some(name) = file_name.strip_suffix(".md") else {
+            continue;
+        };
+        let text = tokio::fs::read_to_string(entry.path())
+            .await
+            .unwrap_or_default();
+        snippets.push(SnippetSummary {
+            name: name.to_string(),
+            first_line: text
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .unwrap_or_default()
+                .to_string(),
+        });
+    }
+    snippets.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(SnippetList { snippets })
+}
+
+/// Stores snippet `name` in the notebook and commits it.
+pub async fn save(
+    nb: &NbClient,
+    name: &str,
+    text: &str,
+    notebook: Option<&str>,
+) -> Result<String, NbError> {
+    let name = validate(name)?;
+    let dir = nb.notebook_path(notebook).await?.join(FOLDER);
+    tokio::fs::create_dir_all(&dir).await?;
+    let path = dir.join(format!("{name}.md"));
+    let replaced = tokio::fs::try_exists(&path).await?;
+    let mut text = text.to_string();
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    tokio::fs::write(&path, text).await?;
+    nb.checkpoint(&format!("[nb-mcp] Save snippet: {name}"), notebook)
+        .await?;
+    Ok(format!(
+        "{} snippet: {}",
+        if replaced { "Updated" } else { "Saved" },
+        name
+    ))
+}
+
+/// Appends snippet `name` to `content`, separated by a blank line.
+pub async fn insert(
+    nb: &NbClient,
+    content: &str,
+    name: &str,
+    notebook: Option<&str>,
+) -> Result<String, NbError> {
+    let snippet = get(nb, name, notebook).await?;
+    Ok(append(content, &snippet))
+}
+
+fn append(content: &str, snippet: &str) -> String {
+    if content.trim().is_empty() {
+        snippet.to_string()
+    } else {
+        format!("{}\n\n{}", content.trim_end(), snippet)
+    }
+}
+
+/// Checks that `name` can be used as a snippet file name.
+fn validate(name: &str) -> Result<&str, NbError> {
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.starts_with('.');
+    if valid {
+        Ok(name.trim_end_matches(".md"))
+    } else {
+        Err(NbError::CommandFailed(format!(
+            "invalid snippet name {name:?}; use letters, digits, '-', '_', and '.'"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append, validate};
+
+    #[test]
+    fn append_separates_snippet_from_content() {
+        assert_eq!(
+            append("# Sync\n\nNotes\n", "## Actions\n"),
+            "# Sync\n\nNotes\n\n## Actions\n"
+        );
+        assert_eq!(append("", "## Actions\n"), "## Actions\n");
+    }
+
+    #[test]
+    fn validate_rejects_paths() {
+        assert_eq!(validate("meeting-header").unwrap(), "meeting-header");
+        assert!(validate("../secrets").is_err());
+        assert!(validate(".hidden").is_err());
+    }
+}
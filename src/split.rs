@@ -0,0 +1,217 @@
+//! Splitting a long note into child notes.
+//!
+//! `nb.split` moves each section at a chosen heading level into its own note
+//! in a folder (by default one named after the note, beside it), promoting
+//! the section's heading to the child's title. Each moved section is replaced
+//! in the original by a link to its child, so the original becomes an index,
+//! and the whole change is recorded as one git checkpoint.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::batch;
+use crate::links::{self, LinkStyle};
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+use crate::outline::{self, Heading};
+
+/// Heading level split at when the call does not choose one.
+pub const DEFAULT_LEVEL: usize = 2;
+
+/// A note created from a section.
+#[derive(Debug, Serialize)]
+pub struct SplitNote {
+    pub title: String,
+    /// Path relative to the notebook root.
+    pub path: String,
+}
+
+/// Result of `nb.split`.
+#[derive(Debug, Serialize)]
+pub struct Split {
+    pub id: String,
+    /// Folder holding the new notes, relative to the notebook root.
+    pub folder: String,
+    pub notes: Vec<SplitNote>,
+}
+
+/// Moves the level-`level` sections of note `id` into notes under `folder`
+/// and commits once.
+pub async fn split(
+    nb: &NbClient,
+    style: LinkStyle,
+    id: &str,
+    level: usize,
+    folder: Option<&str>,
+    notebook: Option<&str>,
+) -> Result<Split, NbError> {
+    let root = nb.notebook_path(notebook).await?;
+    let path = nb.show_path(id, notebook).await?;
+    let content = tokio::fs::read_to_string(&path).await?;
+    let sections = outline::headings(&content)
+        .into_iter()
+        .filter(|heading| heading.level == level)
+        .collect::<Vec<_>>();
+    if sections.is_empty() {
+        return Err(NbError::CommandFailed(format!(
+            "note {id} has no level-{level} headings to split at"
+        )));
+    }
+    let folder = match folder.map(|folder| folder.trim_matches('/')) {
+        Some(folder) if folder.split('/').any(|part| part == "..") => {
+            return Err(NbError::CommandFailed(format!(
+                "folder {folder} is outside the notebook"
+            )));
+        }
+        Some(folder) if !folder.is_empty() => folder.to_string(),
+        _ => default_folder(&notes::relative_path(&root, &path)),
+    };
+    let task_root = root.clone();
+    let task_folder = folder.clone();
+    let notes = tokio::task::spawn_blocking(move || -> std::io::Result<Vec<SplitNote>> {
+        let dir = task_root.join(&task_folder);
+        std::fs::create_dir_all(&dir)?;
+        let mut created = Vec::with_capacity(sections.len());
+        let mut items = Vec::with_capacity(sections.len());
+        for section in &sections {
+            let title = if section.text.is_empty() {
+                "Untitled".to_string()
+            } else {
+                section.text.clone()
+            };
+            let child = batch::unique_path(&dir.join(format!("{}.md", notes::slugify(&title))));
+            std::fs::write(&child, promote(&content, section))?;
+            let target = links::target(style, &task_root, &path, &child);
+            let text = match style {
+                LinkStyle::Markdown => title.as_str(),
+                LinkStyle::Wiki => "",
+            };
+            items.push(format!("- {}", links::format(style, &target, text)));
+            created.push(SplitNote {
+                title,
+                path: notes::relative_path(&task_root, &child),
+            });
+        }
+        std::fs::write(&path, replace_sections(&content, &sections, &items))?;
+        Ok(created)
+    })
+    .await
+    .map_err(|err| NbError::CommandFailed(format!("split task failed: {err}")))??;
+    nb.checkpoint(
+        &format!("[nb-mcp] Split {id} into {} notes", notes.len()),
+        notebook,
+    )
+    .await?;
+    Ok(Split {
+        id: id.to_string(),
+        folder,
+        notes,
+    })
+}
+
+/// Returns the folder named after the note at `path` (relative to the
+/// notebook root), beside it.
+fn default_folder(path: &str) -> String {
+    let stem = Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "split".to_string());
+    match path.rsplit_once('/') {
+        Some((parent, _)) => format!("{parent}/{stem}"),
+        None => stem,
+    }
+}
+
+/// Returns the text of `section` with its heading made the title (`#`) and
+/// the headings below it raised by as many levels.
+fn promote(content: &str, section: &Heading) -> String {
+    let text = &content[section.offset..section.offset + section.length];
+    let shift = section.level - 1;
+    let mut promoted = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for heading in outline::headings(text) {
+        let line_end = text[heading.offset..]
+            .find('\n')
+            .map_or(text.len(), |newline| heading.offset + newline);
+        promoted.push_str(&text[cursor..heading.offset]);
+        promoted.push_str(&"#".repeat(heading.level - shift));
+        if !heading.text.is_empty() {
+            promoted.push(' ');
+            promoted.push_str(&heading.text);
+        }
+        cursor = line_end;
+    }
+    promoted.push_str(&text[cursor..]);
+    let mut promoted = promoted.trim_end().to_string();
+    promoted.push('\n');
+    promoted
+}
+
+/// Replaces each of `sections` in `content` with the matching list item,
+/// keeping consecutive items together as one list.
+fn replace_sections(content: &str, sections: &[Heading], items: &[String]) -> String {
+    let mut replaced = String::with_capacity(content.len());
+    let mut cursor = 0;
+    for (section, item) in sections.iter().zip(items) {
+        let before = &content[cursor..section.offset];
+        replaced.push_str(before);
+        let in_list = cursor > 0 && before.is_empty();
+        if !in_list && !replaced.is_empty() && !replaced.ends_with("\n\n") {
+            replaced.push_str(if replaced.ends_with('\n') {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        replaced.push_str(item);
+        replaced.push('\n');
+        cursor = section.offset + section.length;
+        let next_is_item = sections
+            .iter()
+            .any(|next| next.offset == cursor && cursor < content.len());
+        if !next_is_item && cursor < content.len() {
+            replaced.push('\n');
+        }
+    }
+    replaced.push_str(&content[cursor..]);
+    replaced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_folder, promote, replace_sections};
+    use crate::outline::headings;
+
+    const NOTE: &str = "# Capture\n\nIntro\n\n## Ideas\n\nCache it.\n\n### Later\n\nMaybe.\n\n\
+                        ## Bugs\n\nCrash.\n\n# Appendix\n\nEnd.\n";
+
+    #[test]
+    fn promote_makes_the_heading_the_title() {
+        let sections = headings(NOTE);
+        assert_eq!(
+            promote(NOTE, &sections[1]),
+            "# Ideas\n\nCache it.\n\n## Later\n\nMaybe.\n"
+        );
+    }
+
+    #[test]
+    fn replace_sections_leaves_a_list_of_links() {
+        let sections = headings(NOTE)
+            .into_iter()
+            .filter(|heading| heading.level == 2)
+            .collect::<Vec<_>>();
+        let items = ["- [Ideas](capture/ideas.md)", "- [Bugs](capture/bugs.md)"].map(String::from);
+        assert_eq!(
+            replace_sections(NOTE, &sections, &items),
+            "# Capture\n\nIntro\n\n- [Ideas](capture/ideas.md)\n- [Bugs](capture/bugs.md)\n\n\
+             # Appendix\n\nEnd.\n"
+        );
+    }
+
+    #[test]
+    fn default_folder_sits_beside_the_note() {
+        assert_eq!(default_folder("projects/plan.md"), "projects/plan");
+        assert_eq!(default_folder("inbox.md"), "inbox");
+    }
+}
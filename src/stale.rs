@@ -0,0 +1,223 @@
+//! Notes due for review.
+//!
+//! `nb.stale` lists notes a maintenance agent should ask the user to refresh
+//! or archive: notes whose `expires` or `review_by` frontmatter date has
+//! passed, and, with `days`, notes whose files were not modified for more
+//! than that many days. A note with a review date still ahead is considered
+//! current however long it was left alone. Notes are listed most overdue
+//! first.
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
+
+use crate::archive;
+use crate::args::StaleArgs;
+use crate::nb::{NbClient, NbError};
+use crate::notes::{self, NoteFile};
+
+/// Default number of notes listed.
+const DEFAULT_LIMIT: usize = 100;
+
+/// Why a note is listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    /// Its `expires` date has passed.
+    Expired,
+    /// Its `review_by` date has passed.
+    ReviewDue,
+    /// It was not modified for more than `days` days.
+    Untouched,
+}
+
+/// Result of `nb.stale`.
+#[derive(Debug, Serialize)]
+pub struct StaleReport {
+    /// Date the report was made for.
+    pub today: String,
+    /// Number of notes scanned.
+    pub scanned: usize,
+    /// Number of stale notes, including those not listed.
+    pub total: usize,
+    /// Listed notes, most overdue first.
+    pub notes: Vec<StaleNote>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StaleNote {
+    /// Path relative to the notebook root.
+    pub path: String,
+    pub title: String,
+    pub reason: Reason,
+    /// The `expires` or `review_by` date that passed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    /// Date the file was last modified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    /// Days since `due`, or since `modified` for untouched notes.
+    pub overdue_days: i64,
+}
+
+/// Lists the notes of a notebook past their review date or left untouched.
+pub async fn stale(nb: &NbClient, args: &StaleArgs) -> Result<StaleReport, NbError> {
+    let root = nb.notebook_path(args.notebook.as_deref()).await?;
+    let folder = args
+        .folder
+        .as_deref()
+        .map(|folder| folder.trim_matches('/').to_string())
+        .filter(|folder| !folder.is_empty());
+    let hide_archived = archive::hides(&root, folder.as_deref(), args.include_archived);
+    let files = tokio::task::spawn_blocking(move || {
+        let files = notes::read_notes(&root, folder.as_deref())?;
+        Ok::<_, std::io::Error>(
+            files
+                .into_iter()
+                .filter(|note| {
+                    !(hide_archived && note.path.split('/').next() == Some(archive::FOLDER))
+                })
+                .map(|note| {
+                    let modified = std::fs::metadata(root.join(&note.path))
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .map(|modified| DateTime::<Local>::from(modified).date_naive());
+                    (note, modified)
+                })
+                .collect::<Vec<_>>(),
+        )
+    })
+    .await
+    .map_err(|err| NbError::CommandFailed(format!("stale report task failed: {err}")))??;
+    Ok(report(
+        &files,
+        Local::now().date_naive(),
+        args.days,
+        args.limit.map_or(DEFAULT_LIMIT, |limit| limit as usize),
+    ))
+}
+
+fn report(
+    files: &[(NoteFile, Option<NaiveDate>)],
+    today: NaiveDate,
+    days: Option<u32>,
+    limit: usize,
+) -> StaleReport {
+    let mut stale = files
+        .iter()
+        .filter_map(|(note, modified)| check(note, *modified, today, days))
+        .collect::<Vec<_>>();
+    stale.sort_by(|a, b| {
+        b.overdue_days
+            .cmp(&a.overdue_days)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    let total = stale.len();
+    stale.truncate(limit);
+    StaleReport {
+        today: today.to_string(),
+        scanned: files.len(),
+        total,
+        notes: stale,
+    }
+}
+
+/// Returns the note if it is stale on `today`.
+fn check(
+    note: &NoteFile,
+    modified: Option<NaiveDate>,
+    today: NaiveDate,
+    days: Option<u32>,
+) -> Option<StaleNote> {
+    let stale = |reason, due: Option<NaiveDate>, overdue_days| StaleNote {
+        path: note.path.clone(),
+        title: notes::derive_title(&note.content).unwrap_or_else(|| note.path.clone()),
+        reason,
+        due: due.map(|due| due.to_string()),
+        modified: modified.map(|modified| modified.to_string()),
+        overdue_days,
+    };
+    let mut scheduled = false;
+    for (key, reason) in [
+        ("expires", Reason::Expired),
+        ("review_by", Reason::ReviewDue),
+    ] {
+        let Some(due) = notes::frontmatter_field(&note.content, key)
+            .as_deref()
+            .and_then(parse_date)
+        else {
+            continue;
+        };
+        if due <= today {
+            return Some(stale(reason, Some(due), (today - due).num_days()));
+        }
+        scheduled = true;
+    }
+    let untouched = (today - modified?).num_days();
+    (!scheduled && days.is_some_and(|days| untouched > i64::from(days)))
+        .then(|| stale(Reason::Untouched, None, untouched))
+}
+
+/// Parses a frontmatter date, ignoring any time after it.
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..10).unwrap_or(value), "%Y-%m-%d").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{Reason, report};
+    use crate::notes::NoteFile;
+
+    fn note(path: &str, content: &str, modified: &str) -> (NoteFile, Option<NaiveDate>) {
+        let note = NoteFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        };
+        (note, modified.parse().ok())
+    }
+
+    #[test]
+    fn report_lists_notes_past_their_dates_or_untouched() {
+        let files = vec![
+            note("fresh.md", "# Fresh\n", "2024-05-30"),
+            note("old.md", "# Old\n", "2024-01-01"),
+            note(
+                "expired.md",
+                "---\nexpires: 2024-05-01\n---\n# Promo\n",
+                "2024-05-30",
+            ),
+            note(
+                "due.md",
+                "---\nreview_by: \"2024-05-31T09:00\"\n---\n# Policy\n",
+                "2024-05-30",
+            ),
+            note(
+                "scheduled.md",
+                "---\nreview_by: 2025-01-01\n---\n# Plan\n",
+                "2023-01-01",
+            ),
+        ];
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let full = report(&files, today, Some(90), 100);
+        assert_eq!(full.scanned, 5);
+        let listed = full
+            .notes
+            .iter()
+            .map(|note| (note.path.as_str(), note.reason, note.overdue_days))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            listed,
+            [
+                ("old.md", Reason::Untouched, 152),
+                ("expired.md", Reason::Expired, 31),
+                ("due.md", Reason::ReviewDue, 1),
+            ]
+        );
+        assert_eq!(full.notes[1].due.as_deref(), Some("2024-05-01"));
+
+        let dated = report(&files, today, None, 1);
+        assert_eq!(dated.total, 2);
+        assert_eq!(dated.notes[0].title, "Promo");
+    }
+}
@@ -0,0 +1,125 @@
+//! Guard on the bytes written to the stdio transport.
+//!
+//! Over stdio, stdout carries only JSON-RPC messages, one per line; any
+//! other output (a stray `println!`, a banner, a child process inheriting
+//! stdout) corrupts the stream for the client. Human-facing text goes to
+//! stderr and `print_stdout` is denied by clippy. On Unix, [`take_stdout`]
+//! also moves the transport to a duplicate of stdout and points file
+//! descriptor 1 at stderr, so anything else written to stdout lands in the
+//! log instead. [`ProtocolWriter`] checks the transport itself: a line that
+//! is not a JSON-RPC message panics in tests and is logged as an error
+//! otherwise.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::AsyncWrite;
+
+/// Returns a duplicate of stdout for the transport, pointing file
+/// descriptor 1 at stderr.
+#[cfg(unix)]
+pub fn take_stdout() -> io::Result<tokio::fs::File> {
+    use std::os::fd::AsFd;
+
+    let transport = io::stdout().as_fd().try_clone_to_owned()?;
+    // SAFETY: dup2 only swaps which open file descriptor 1 refers to; both
+    // descriptors stay valid, and std's stdout handle keeps using fd 1.
+    if unsafe { libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(tokio::fs::File::from_std(std::fs::File::from(transport)))
+}
+
+/// Writer that checks each line is a JSON-RPC message.
+#[derive(Debug)]
+pub struct ProtocolWriter<W> {
+    inner: W,
+    /// Bytes of the current, unfinished line.
+    line: Vec<u8>,
+}
+
+impl<W> ProtocolWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            line: Vec::new(),
+        }
+    }
+
+    fn check(&mut self, written: &[u8]) {
+        for byte in written {
+            if *byte == b'\n' {
+                let line = std::mem::take(&mut self.line);
+                if !is_message(&line) {
+                    let line = String::from_utf8_lossy(&line);
+                    if cfg!(test) {
+                        panic!("non-protocol output on stdout: {line:?}");
+                    }
+                    tracing::error!(%line, "non-protocol output on stdout");
+                }
+            } else {
+                self.line.push(*byte);
+            }
+        }
+    }
+}
+
+/// Returns whether `line` is a JSON-RPC 2.0 message (or blank).
+fn is_message(line: &[u8]) -> bool {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return true;
+    }
+    serde_json::from_slice::<serde_json::Value>(line)
+        .is_ok_and(|message| message.get("jsonrpc").and_then(|v| v.as_str()) == Some("2.0"))
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for ProtocolWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            self.check(&buf[..written]);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+
+    use super::ProtocolWriter;
+
+    #[tokio::test]
+    async fn protocol_lines_pass_through_in_pieces() {
+        let mut writer = ProtocolWriter::new(Vec::new());
+        writer.write_all(br#"{"jsonrpc":"2.0","#).await.unwrap();
+        writer
+            .write_all(b"\"id\":1,\"result\":{}}\n")
+            .await
+            .unwrap();
+        assert_eq!(
+            writer.inner,
+            b"{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n"
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "non-protocol output on stdout")]
+    async fn stray_text_panics_in_tests() {
+        let mut writer = ProtocolWriter::new(Vec::new());
+        writer.write_all(b"nb-mcp 1.0.0\n").await.unwrap();
+    }
+}
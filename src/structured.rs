@@ -0,0 +1,138 @@
+//! Typed results parsed from nb's text output.
+//!
+//! `list`, `search`, `tasks`, `folders`, `pinned`, `notebooks`, and `status` print
+//! human-oriented text. Their tool results keep that text and add
+//! `structuredContent` holding the [`model`](crate::model) values parsed from
+//! it, so clients can consume typed results instead of scraping. `add` and
+//! `edit` already answer with JSON metadata, which is passed through. The
+//! matching output schemas are published through the help tool.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::model::{self, Entry, Notebook, SearchHit, Task};
+use crate::saved_note::SavedNote;
+
+/// Structured result of `nb.list`, `nb.folders`, and `nb.pinned`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ListOutput {
+    pub items: Vec<ListItem>,
+}
+
+/// A listed entry with its dates, which only `nb.list` reports.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ListItem {
+    #[serde(flatten)]
+    pub entry: Entry,
+    /// When the item was first committed, or its file was created while
+    /// uncommitted (RFC 3339).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    /// When the item's file was last modified (RFC 3339).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+}
+
+/// Structured result of `nb.search`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SearchOutput {
+    pub results: Vec<SearchHit>,
+}
+
+/// Structured result of `nb.tasks`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TasksOutput {
+    pub tasks: Vec<Task>,
+}
+
+/// Structured result of `nb.notebooks`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NotebooksOutput {
+    pub notebooks: Vec<Notebook>,
+}
+
+/// Structured result of `nb.status`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StatusOutput {
+    /// Notebook name from the status header.
+    pub notebook: String,
+    /// Remaining status lines.
+    pub details: Vec<String>,
+}
+
+/// Parses the output of `command`, if it has a structured form.
+pub fn parse(command: &str, output: &str) -> Option<serde_json::Value> {
+    let value = match command {
+        // `count_only` results are already JSON.
+        "list" | "search" if output.starts_with('{') => return None,
+        "add" | "edit" => return serde_json::from_str(output).ok(),
+        "list" | "folders" | "pinned" => serde_json::to_value(ListOutput {
+            items: model::parse_entries(output)
+                .into_iter()
+                .map(|entry| ListItem {
+                    entry,
+                    created: None,
+                    modified: None,
+                })
+                .collect(),
+        }),
+        "search" => serde_json::to_value(SearchOutput {
+            results: model::parse_search(output),
+        }),
+        "tasks" => serde_json::to_value(TasksOutput {
+            tasks: model::parse_tasks(output),
+        }),
+        "notebooks" => serde_json::to_value(NotebooksOutput {
+            notebooks: model::parse_notebooks(output),
+        }),
+        "status" => serde_json::to_value(parse_status(output)),
+        _ => return None,
+    };
+    value.ok()
+}
+
+/// Returns the output schema for `command`, if it has a structured form.
+pub fn output_schema(command: &str) -> Option<serde_json::Value> {
+    let schema = match command {
+        "list" | "folders" | "pinned" => schemars::schema_for!(ListOutput),
+        "add" | "edit" => schemars::schema_for!(SavedNote),
+        "search" => schemars::schema_for!(SearchOutput),
+        "tasks" => schemars::schema_for!(TasksOutput),
+        "notebooks" => schemars::schema_for!(NotebooksOutput),
+        "status" => schemars::schema_for!(StatusOutput),
+        _ => return None,
+    };
+    serde_json::to_value(schema).ok()
+}
+
+fn parse_status(output: &str) -> StatusOutput {
+    let mut lines = model::lines(output)
+        // Drop the underlines nb prints beneath headers.
+        .filter(|line| !line.chars().all(|c| c == '-' || c == '─' || c == '='));
+    StatusOutput {
+        notebook: lines.next().unwrap_or_default().trim().to_string(),
+        details: lines.map(|line| line.trim().to_string()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn parse_wraps_entries_for_list_and_folders() {
+        let output = "[3] 📌 Plan\n[2] 📂 docs\n";
+        let list = parse("list", output).unwrap();
+        assert_eq!(list["items"][0]["kind"], "note");
+        assert_eq!(list["items"][1]["kind"], "folder");
+        assert_eq!(list["items"][0]["pinned"], true);
+        assert_eq!(parse("folders", output), Some(list));
+    }
+
+    #[test]
+    fn parse_status_drops_underlines() {
+        let status = parse("status", "home\n----\n3 items\n").unwrap();
+        assert_eq!(status["notebook"], "home");
+        assert_eq!(status["details"], serde_json::json!(["3 items"]));
+    }
+}
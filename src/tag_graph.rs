@@ -0,0 +1,215 @@
+//! Tag usage and co-occurrence.
+//!
+//! `nb.tag_graph` counts how often each hashtag is used and which tags
+//! appear in the same notes, so agents can propose taxonomy cleanups. Tags
+//! that look like variants of each other (`#Meeting` and `#meeting`,
+//! `#meeting` and `#meetings`, `#mtg` and `#meeting`) are reported as merge
+//! candidates; deciding whether to merge is left to the agent and the user.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::archive;
+use crate::args::TagGraphArgs;
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// Default number of tag pairs returned.
+const DEFAULT_LIMIT: usize = 50;
+
+/// Result of `nb.tag_graph`.
+#[derive(Debug, Serialize)]
+pub struct TagGraph {
+    /// Number of notes scanned.
+    pub notes: usize,
+    /// Number of scanned notes without tags.
+    pub untagged: usize,
+    /// Tags by number of notes using them, most used first.
+    pub tags: Vec<TagCount>,
+    /// Tags used together in the same notes, most frequent first.
+    pub pairs: Vec<TagPair>,
+    /// Pairs of tags that may name the same thing.
+    pub merge_candidates: Vec<MergeCandidate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub notes: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagPair {
+    pub tags: [String; 2],
+    /// Number of notes carrying both tags.
+    pub notes: usize,
+}
+
+/// A tag that may be a variant of a more used one.
+#[derive(Debug, Serialize)]
+pub struct MergeCandidate {
+    /// The less used tag.
+    pub tag: String,
+    /// The tag it may be merged into.
+    pub into: String,
+    /// `case`, `separator`, `plural`, or `abbreviation`.
+    pub reason: &'static str,
+}
+
+/// Counts tags and tag pairs across the notes of a notebook or folder.
+pub async fn tag_graph(nb: &NbClient, args: &TagGraphArgs) -> Result<TagGraph, NbError> {
+    let root = nb.notebook_path(args.notebook.as_deref()).await?;
+    let folder = args.folder.clone();
+    let hide_archived = archive::hides(&root, folder.as_deref(), args.include_archived);
+    let files = tokio::task::spawn_blocking(move || notes::read_notes(&root, folder.as_deref()))
+        .await
+        .map_err(|err| NbError::CommandFailed(format!("tag graph task failed: {err}")))??;
+    let note_tags = files
+        .iter()
+        .filter(|note| !(hide_archived && is_archived(&note.path)))
+        .map(|note| notes::extract_tags(&note.content))
+        .collect::<Vec<_>>();
+    Ok(build(
+        &note_tags,
+        args.min_count.unwrap_or(1).max(1) as usize,
+        args.limit.map_or(DEFAULT_LIMIT, |limit| limit as usize),
+    ))
+}
+
+fn is_archived(path: &str) -> bool {
+    path.split('/').next() == Some(archive::FOLDER)
+}
+
+/// Builds the graph from the tags of each note, keeping pairs seen in at
+/// least `min_count` notes.
+fn build(note_tags: &[Vec<String>], min_count: usize, limit: usize) -> TagGraph {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut pairs: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for tags in note_tags {
+        let mut tags = tags.iter().map(String::as_str).collect::<Vec<_>>();
+        tags.sort_unstable();
+        tags.dedup();
+        for (index, tag) in tags.iter().enumerate() {
+            *counts.entry(tag).or_default() += 1;
+            for other in &tags[index + 1..] {
+                *pairs.entry((tag, other)).or_default() += 1;
+            }
+        }
+    }
+
+    let mut tags = counts
+        .iter()
+        .map(|(tag, notes)| TagCount {
+            tag: tag.to_string(),
+            notes: *notes,
+        })
+        .collect::<Vec<_>>();
+    tags.sort_by(|a, b| b.notes.cmp(&a.notes).then_with(|| a.tag.cmp(&b.tag)));
+    let mut pairs = pairs
+        .into_iter()
+        .filter(|(_, notes)| *notes >= min_count)
+        .map(|((a, b), notes)| TagPair {
+            tags: [a.to_string(), b.to_string()],
+            notes,
+        })
+        .collect::<Vec<_>>();
+    pairs.sort_by(|a, b| b.notes.cmp(&a.notes).then_with(|| a.tags.cmp(&b.tags)));
+    pairs.truncate(limit);
+
+    let mut merge_candidates = Vec::new();
+    // `tags` is ordered most used first, so each candidate merges into the
+    // more used tag.
+    for (index, into) in tags.iter().enumerate() {
+        for tag in &tags[index + 1..] {
+            if let Some(reason) = variant_reason(&tag.tag, &into.tag) {
+                merge_candidates.push(MergeCandidate {
+                    tag: tag.tag.clone(),
+                    into: into.tag.clone(),
+                    reason,
+                });
+            }
+        }
+    }
+
+    TagGraph {
+        notes: note_tags.len(),
+        untagged: note_tags.iter().filter(|tags| tags.is_empty()).count(),
+        tags,
+        pairs,
+        merge_candidates,
+    }
+}
+
+/// Returns why `tag` may be a variant of `other`, if it looks like one.
+fn variant_reason(tag: &str, other: &str) -> Option<&'static str> {
+    let (a, b) = (tag.to_lowercase(), other.to_lowercase());
+    if a == b {
+        return Some("case");
+    }
+    let strip = |tag: &str| tag.replace(['-', '_'], "");
+    let (a, b) = (strip(&a), strip(&b));
+    if a == b {
+        return Some("separator");
+    }
+    if a.strip_suffix('s') == Some(&b) || b.strip_suffix('s') == Some(&a) {
+        return Some("plural");
+    }
+    let (short, long) = if a.len() < b.len() {
+        (&a, &b)
+    } else {
+        (&b, &a)
+    };
+    if short.len() >= 2 && short.len() * 2 <= long.len() && is_abbreviation(short, long) {
+        return Some("abbreviation");
+    }
+    None
+}
+
+/// Returns true if `short` starts like `long` and its letters appear in
+/// `long` in order, as in `mtg` for `meeting`.
+fn is_abbreviation(short: &str, long: &str) -> bool {
+    if short.chars().next() != long.chars().next() {
+        return false;
+    }
+    let mut rest = long.chars();
+    short.chars().all(|c| rest.any(|l| l == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build, variant_reason};
+
+    fn tags(list: &[&str]) -> Vec<String> {
+        list.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn build_counts_tags_and_pairs() {
+        let notes = vec![
+            tags(&["meeting", "client"]),
+            tags(&["meeting", "client", "urgent"]),
+            tags(&["mtg"]),
+            Vec::new(),
+        ];
+        let graph = build(&notes, 2, 10);
+        assert_eq!(graph.notes, 4);
+        assert_eq!(graph.untagged, 1);
+        assert_eq!(graph.tags[0].tag, "client");
+        assert_eq!(graph.tags[0].notes, 2);
+        assert_eq!(graph.pairs.len(), 1);
+        assert_eq!(graph.pairs[0].tags, ["client", "meeting"]);
+        assert_eq!(graph.merge_candidates.len(), 1);
+        assert_eq!(graph.merge_candidates[0].tag, "mtg");
+        assert_eq!(graph.merge_candidates[0].into, "meeting");
+    }
+
+    #[test]
+    fn variant_reason_recognizes_common_variants() {
+        assert_eq!(variant_reason("Meeting", "meeting"), Some("case"));
+        assert_eq!(variant_reason("follow_up", "follow-up"), Some("separator"));
+        assert_eq!(variant_reason("meetings", "meeting"), Some("plural"));
+        assert_eq!(variant_reason("mtg", "meeting"), Some("abbreviation"));
+        assert_eq!(variant_reason("client", "meeting"), None);
+    }
+}
@@ -0,0 +1,264 @@
+//! Completion log and throughput metrics for todos.
+//!
+//! `nb.do` and `nb.undo` append an event to `.nb-mcp-tasks.jsonl` at the
+//! notebook root before running nb, so nb's commit records the event with
+//! the change (the entry is removed again if nb fails). Each event carries
+//! the todo's path, title, and creation time, taken from nb's timestamped
+//! file name when it has one. `nb.task_metrics` replays the log to report
+//! completions and average todo age per ISO week.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{DateTime, Datelike, Days, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// Log file at the notebook root.
+pub const LOG_FILE: &str = ".nb-mcp-tasks.jsonl";
+
+/// Number of weeks reported when not specified.
+const DEFAULT_WEEKS: u32 = 8;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Event {
+    /// `done` or `undo`.
+    event: String,
+    /// Todo path relative to the notebook root.
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    /// RFC 3339 time of the event.
+    at: String,
+    /// RFC 3339 creation time of the todo, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created: Option<String>,
+}
+
+/// Result of `nb.task_metrics`.
+#[derive(Debug, Serialize)]
+pub struct TaskMetrics {
+    /// Todos completed within the reported weeks.
+    pub completed: usize,
+    /// Weeks from oldest to current.
+    pub weeks: Vec<Week>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Week {
+    /// ISO week, e.g. `2024-W18`.
+    pub week: String,
+    /// Monday the week starts on.
+    pub start: String,
+    pub completed: usize,
+    /// Mean days from creation to completion, over todos with a known
+    /// creation time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_age_days: Option<f64>,
+}
+
+/// Marks a todo done, logging its completion.
+pub async fn complete(nb: &NbClient, id: &str, notebook: Option<&str>) -> Result<String, NbError> {
+    record(nb, "done", id, notebook).await
+}
+
+/// Reopens a todo, logging that it is no longer done.
+pub async fn reopen(nb: &NbClient, id: &str, notebook: Option<&str>) -> Result<String, NbError> {
+    record(nb, "undo", id, notebook).await
+}
+
+async fn record(
+    nb: &NbClient,
+    event: &str,
+    id: &str,
+    notebook: Option<&str>,
+) -> Result<String, NbError> {
+    let root = nb.notebook_path(notebook).await?;
+    let path = nb.show_path(id, notebook).await?;
+    let content = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+    let entry = Event {
+        event: event.to_string(),
+        id: notes::relative_path(&root, &path),
+        title: todo_title(&content),
+        at: Local::now().to_rfc3339(),
+        created: created_from_name(&path).map(|created| created.to_rfc3339()),
+    };
+    let log = root.join(LOG_FILE);
+    let previous = match tokio::fs::read(&log).await {
+        Ok(previous) => Some(previous),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+    let mut appended = previous.clone().unwrap_or_default();
+    if appended.last().is_some_and(|byte| *byte != b'\n') {
+        appended.push(b'\n');
+    }
+    appended.extend(serde_json::to_vec(&entry).unwrap_or_default());
+    appended.push(b'\n');
+    tokio::fs::write(&log, appended).await?;
+    let result = if event == "done" {
+        nb.do_task(id, notebook).await
+    } else {
+        nb.undo_task(id, notebook).await
+    };
+    if result.is_err() {
+        let _ = match previous {
+            Some(previous) => tokio::fs::write(&log, previous).await,
+            None => tokio::fs::remove_file(&log).await,
+        };
+    }
+    result
+}
+
+/// Reports completions per week over the last `weeks` weeks.
+pub async fn metrics(
+    nb: &NbClient,
+    weeks: Option<u32>,
+    notebook: Option<&str>,
+) -> Result<TaskMetrics, NbError> {
+    let log = nb.notebook_path(notebook).await?.join(LOG_FILE);
+    let text = match tokio::fs::read_to_string(&log).await {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.into()),
+    };
+    let weeks = weeks.unwrap_or(DEFAULT_WEEKS).max(1);
+    Ok(summarize(&text, weeks, Local::now().date_naive()))
+}
+
+fn summarize(log: &str, weeks: u32, today: NaiveDate) -> TaskMetrics {
+    // Last event per todo; a todo reopened after completion is not counted.
+    let mut latest = BTreeMap::new();
+    for event in log
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Event>(line).ok())
+    {
+        latest.insert(event.id.clone(), event);
+    }
+    let current = week_start(today);
+    let first = current - Days::new(7 * u64::from(weeks - 1));
+    let mut buckets = (0..weeks)
+        .map(|index| (first + Days::new(7 * u64::from(index)), Vec::new()))
+        .collect::<BTreeMap<_, Vec<Option<f64>>>>();
+    for event in latest.values().filter(|event| event.event == "done") {
+        let Some(at) = parse_time(&event.at) else {
+            continue;
+        };
+        let age = event
+            .created
+            .as_deref()
+            .and_then(parse_time)
+            .map(|created| (at - created).num_seconds() as f64 / 86_400.0);
+        if let Some(ages) = buckets.get_mut(&week_start(at.date_naive())) {
+            ages.push(age);
+        }
+    }
+    let weeks = buckets
+        .into_iter()
+        .map(|(start, ages)| {
+            let known = ages.iter().flatten().collect::<Vec<_>>();
+            let average_age_days = (!known.is_empty()).then(|| {
+                let mean = known.iter().copied().sum::<f64>() / known.len() as f64;
+                (mean * 10.0).round() / 10.0
+            });
+            let iso = start.iso_week();
+            Week {
+                week: format!("{}-W{:02}", iso.year(), iso.week()),
+                start: start.to_string(),
+                completed: ages.len(),
+                average_age_days,
+            }
+        })
+        .collect::<Vec<_>>();
+    TaskMetrics {
+        completed: weeks.iter().map(|week| week.completed).sum(),
+        weeks,
+    }
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Days::new(u64::from(date.weekday().num_days_from_monday()))
+}
+
+fn parse_time(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value).ok()
+}
+
+/// Reads the creation time from nb's `YYYYMMDDHHMMSS` file names.
+fn created_from_name(path: &Path) -> Option<DateTime<Local>> {
+    let name = path.file_name()?.to_str()?;
+    let stamp = name.get(..14)?;
+    let naive = NaiveDateTime::parse_from_str(stamp, "%Y%m%d%H%M%S").ok()?;
+    Local.from_local_datetime(&naive).single()
+}
+
+fn todo_title(content: &str) -> Option<String> {
+    let heading = content.lines().find(|line| !line.trim().is_empty())?;
+    let title = heading
+        .trim()
+        .trim_start_matches("# ")
+        .trim_start_matches("[ ]")
+        .trim_start_matches("[x]")
+        .trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::NaiveDate;
+
+    use super::{LOG_FILE, complete, summarize};
+    use crate::executor::FakeExecutor;
+    use crate::nb::NbClient;
+
+    #[test]
+    fn summarize_counts_completions_and_ages_by_week() {
+        let log = [
+            r#"{"event":"done","id":"a.todo.md","at":"2024-05-02T10:00:00+00:00","created":"2024-04-30T10:00:00+00:00"}"#,
+            r#"{"event":"done","id":"b.todo.md","at":"2024-05-03T10:00:00+00:00","created":"2024-05-02T10:00:00+00:00"}"#,
+            r#"{"event":"done","id":"c.todo.md","at":"2024-04-24T10:00:00+00:00"}"#,
+            r#"{"event":"done","id":"d.todo.md","at":"2024-05-01T10:00:00+00:00"}"#,
+            r#"{"event":"undo","id":"d.todo.md","at":"2024-05-01T11:00:00+00:00"}"#,
+        ]
+        .join("\n");
+        let metrics = summarize(&log, 2, NaiveDate::from_ymd_opt(2024, 5, 4).unwrap());
+        assert_eq!(metrics.completed, 3);
+        assert_eq!(metrics.weeks[0].week, "2024-W17");
+        assert_eq!(metrics.weeks[0].completed, 1);
+        assert_eq!(metrics.weeks[0].average_age_days, None);
+        assert_eq!(metrics.weeks[1].start, "2024-04-29");
+        assert_eq!(metrics.weeks[1].completed, 2);
+        assert_eq!(metrics.weeks[1].average_age_days, Some(1.5));
+    }
+
+    #[tokio::test]
+    async fn complete_logs_before_nb_commits_and_rolls_back_on_failure() {
+        let root = std::env::temp_dir().join(format!("nb-mcp-task-log-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let todo = root.join("20240430100000.todo.md");
+        std::fs::write(&todo, "# [ ] Ship it\n").unwrap();
+        let fake = Arc::new(
+            FakeExecutor::new()
+                .notebook("work", root.to_str().unwrap())
+                .respond(&["show", "work:1", "--path"], todo.to_str().unwrap())
+                .respond(&["show", "work:2", "--path"], todo.to_str().unwrap())
+                .respond(&["do", "work:1"], "Done: [1] Ship it"),
+        );
+        let nb = NbClient::with_executor(fake, Some("work".to_string()), false);
+
+        complete(&nb, "1", None).await.unwrap();
+        let log = std::fs::read_to_string(root.join(LOG_FILE)).unwrap();
+        assert!(
+            log.starts_with(r#"{"event":"done","id":"20240430100000.todo.md","title":"Ship it","#)
+        );
+        assert!(log.contains(r#""created":"2024-04-30T10:00:00"#));
+
+        assert!(complete(&nb, "2", None).await.is_err());
+        assert_eq!(std::fs::read_to_string(root.join(LOG_FILE)).unwrap(), log);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
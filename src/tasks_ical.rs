@@ -0,0 +1,221 @@
+//! iCalendar feed of todos with due dates.
+//!
+//! nb records a todo's due date (`nb todo add --due`) as a `## Due` section
+//! of the todo file. `nb.tasks_ical` renders each open todo that has one as
+//! an event, all-day unless the date includes a time, so users can subscribe
+//! their calendar app to the tasks agents manage. Done todos are left out.
+
+use std::path::Path;
+
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use serde::Serialize;
+
+use crate::archive;
+use crate::args::TasksIcalArgs;
+use crate::nb::{NbClient, NbError};
+use crate::notes::{self, NoteFile};
+
+/// Suffix nb gives todo files.
+const TODO_SUFFIX: &str = ".todo.md";
+
+/// Longest content line, in bytes, before folding (RFC 5545).
+const MAX_LINE_BYTES: usize = 75;
+
+/// An open todo with a due date.
+#[derive(Debug, PartialEq)]
+struct DueTodo {
+    /// Path relative to the notebook root.
+    path: String,
+    title: String,
+    due: Due,
+}
+
+#[derive(Debug, PartialEq)]
+enum Due {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+/// Result of `nb.tasks_ical` when the feed is written to a file.
+#[derive(Debug, Serialize)]
+pub struct WrittenFeed {
+    pub path: String,
+    pub events: usize,
+}
+
+/// Renders the feed of open todos with due dates, writing it to `path` if
+/// given.
+pub async fn tasks_ical(
+    nb: &NbClient,
+    args: &TasksIcalArgs,
+    path: Option<&Path>,
+) -> Result<String, NbError> {
+    let notebook = nb.resolve_notebook(args.notebook.as_deref()).await?;
+    let root = nb.notebook_path(Some(&notebook)).await?;
+    let folder = args.folder.clone();
+    let hide_archived = archive::hides(&root, folder.as_deref(), false);
+    let files = tokio::task::spawn_blocking(move || notes::read_notes(&root, folder.as_deref()))
+        .await
+        .map_err(|err| NbError::CommandFailed(format!("tasks feed task failed: {err}")))??;
+    let todos = files
+        .iter()
+        .filter(|file| !(hide_archived && file.path.split('/').next() == Some(archive::FOLDER)))
+        .filter_map(due_todo)
+        .collect::<Vec<_>>();
+    let calendar = render(&notebook, &todos, Utc::now().naive_utc());
+    match path {
+        Some(path) => {
+            tokio::fs::write(path, &calendar).await?;
+            Ok(serde_json::to_string_pretty(&WrittenFeed {
+                path: path.display().to_string(),
+                events: todos.len(),
+            })
+            .unwrap_or_default())
+        }
+        None => Ok(calendar),
+    }
+}
+
+/// Reads an open todo's title and due date from its file.
+fn due_todo(file: &NoteFile) -> Option<DueTodo> {
+    if !file.path.ends_with(TODO_SUFFIX) {
+        return None;
+    }
+    let mut lines = file.content.lines().map(str::trim);
+    let title = lines
+        .by_ref()
+        .find(|line| !line.is_empty())?
+        .strip_prefix("# [ ]")?
+        .trim()
+        .to_string();
+    lines
+        .by_ref()
+        .find(|line| line.eq_ignore_ascii_case("## Due"))?;
+    let due = lines.find(|line| !line.is_empty())?;
+    Some(DueTodo {
+        path: file.path.clone(),
+        title,
+        due: parse_due(due)?,
+    })
+}
+
+fn parse_due(value: &str) -> Option<Due> {
+    for format in ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(Due::DateTime(datetime));
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .map(Due::Date)
+}
+
+/// Renders `todos` as an iCalendar document with CRLF line endings.
+fn render(notebook: &str, todos: &[DueTodo], now: NaiveDateTime) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:-//nb-mcp//{}//EN", env!("CARGO_PKG_VERSION")),
+        format!("X-WR-CALNAME:{}", escape(&format!("nb {notebook} todos"))),
+    ];
+    for todo in todos {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!(
+            "UID:{}",
+            escape(&format!("{notebook}/{}@nb-mcp", todo.path))
+        ));
+        lines.push(format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")));
+        match todo.due {
+            Due::Date(date) => {
+                lines.push(format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")));
+                let next = date.succ_opt().unwrap_or(date);
+                lines.push(format!("DTEND;VALUE=DATE:{}", next.format("%Y%m%d")));
+            }
+            Due::DateTime(datetime) => {
+                lines.push(format!("DTSTART:{}", datetime.format("%Y%m%dT%H%M%S")));
+                lines.push("DURATION:PT30M".to_string());
+            }
+        }
+        lines.push(format!("SUMMARY:{}", escape(&todo.title)));
+        lines.push(format!(
+            "DESCRIPTION:{}",
+            escape(&format!("{notebook}:{}", todo.path))
+        ));
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+/// Escapes a text value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line into 75-byte pieces joined by CRLF and a space.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_BYTES {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{Due, due_todo, fold, render};
+    use crate::notes::NoteFile;
+
+    fn file(path: &str, content: &str) -> NoteFile {
+        NoteFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn due_todo_reads_open_todos_with_due_sections() {
+        let todo = due_todo(&file(
+            "work/1.todo.md",
+            "# [ ] Send report, v2\n\n## Due\n\n2024-05-01\n\n## Tags\n\n#client\n",
+        ))
+        .unwrap();
+        assert_eq!(todo.title, "Send report, v2");
+        assert_eq!(
+            todo.due,
+            Due::Date(NaiveDate::from_ymd_opt(2024, 5, 1).unwrap())
+        );
+        assert!(due_todo(&file("2.todo.md", "# [x] Done\n\n## Due\n\n2024-05-01\n")).is_none());
+        assert!(due_todo(&file("3.todo.md", "# [ ] No date\n")).is_none());
+
+        let now = NaiveDate::from_ymd_opt(2024, 4, 1)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+        let calendar = render("home", &[todo], now);
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(calendar.contains("DTSTART;VALUE=DATE:20240501\r\nDTEND;VALUE=DATE:20240502\r\n"));
+        assert!(calendar.contains("SUMMARY:Send report\\, v2\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn fold_splits_long_lines() {
+        let line = format!("SUMMARY:{}", "é".repeat(40));
+        let folded = fold(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+}
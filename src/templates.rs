@@ -0,0 +1,311 @@
+//! Note templates with declared variables.
+//!
+//! Templates are Markdown files in the notebook's `.templates/` folder. A
+//! template declares the variables it uses in a `variables` frontmatter list,
+//! each with a `name`, an optional `description`, and an optional `default`:
+//!
+//! ```markdown
+//! ---
+//! title: "Bug: {{summary}}"
+//! variables:
+//!   - name: summary
+//!     description: One-line summary of the bug
+//!   - name: severity
+//!     default: medium
+//! ---
+//! # {{summary}}
+//!
+//! Severity: {{severity}}
+//! ```
+//!
+//! `nb.add` with `template` replaces each `{{name}}` with the value from
+//! `vars`, or the default. Variables without a default are required, and
+//! `{{date}}` is always today's date.
+
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use regex::{Captures, Regex};
+use serde::Serialize;
+
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+
+/// Folder holding templates, relative to the notebook root.
+pub const FOLDER: &str = ".templates";
+
+/// Matches a placeholder: `{{name}}` or `{{ name }}`.
+static PLACEHOLDER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([A-Za-z0-9_-]+)\s*\}\}").unwrap());
+
+/// A variable declared by a template.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Variable {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+/// A template read from the notebook.
+#[derive(Debug, PartialEq)]
+pub struct Template {
+    pub name: String,
+    pub variables: Vec<Variable>,
+    /// Template text with the `variables` declaration removed.
+    text: String,
+}
+
+/// Why a template could not be filled in.
+#[derive(Debug, PartialEq)]
+pub enum RenderError {
+    /// Required variables without a value.
+    Missing(Vec<Variable>),
+    /// Values for variables the template does not declare.
+    Unknown(Vec<String>),
+}
+
+impl RenderError {
+    /// Formats the error for the caller, naming what the template expects.
+    pub fn message(&self, template: &Template) -> String {
+        match self {
+            Self::Missing(missing) => {
+                let variables = missing
+                    .iter()
+                    .map(|variable| match &variable.description {
+                        Some(description) => format!("`{}` ({description})", variable.name),
+                        None => format!("`{}`", variable.name),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "template {} needs a value in `vars` for {variables}",
+                    template.name
+                )
+            }
+            Self::Unknown(unknown) => format!(
+                "template {} has no variable {}; it declares {}",
+                template.name,
+                unknown
+                    .iter()
+                    .map(|name| format!("`{name}`"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                declared(template)
+            ),
+        }
+    }
+}
+
+/// Reads template `name` from the `.templates` folder of `notebook`.
+pub async fn load(nb: &NbClient, name: &str, notebook: Option<&str>) -> Result<Template, NbError> {
+    let dir = nb.notebook_path(notebook).await?.join(FOLDER);
+    let name = name.trim().trim_end_matches(".md");
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(NbError::CommandFailed(format!(
+            "invalid template name {name:?}"
+        )));
+    }
+    match std::fs::read_to_string(dir.join(format!("{name}.md"))) {
+        Ok(content) => Ok(parse(name, &content)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            let mut available = std::fs::read_dir(&dir)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(|entry| {
+                    let file_name = entry.file_name().to_string_lossy().into_owned();
+                    file_name.strip_suffix(".md").map(String::from)
+                })
+                .collect::<Vec<_>>();
+            available.sort();
+            let available = if available.is_empty() {
+                "none".to_string()
+            } else {
+                available.join(", ")
+            };
+            Err(NbError::CommandFailed(format!(
+                "no template {name} in {FOLDER}/; available: {available}"
+            )))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Parses a template file, separating its variable declarations.
+pub fn parse(name: &str, content: &str) -> Template {
+    let (frontmatter, body) = notes::split_frontmatter(content);
+    let Some(frontmatter) = frontmatter else {
+        return Template {
+            name: name.to_string(),
+            variables: Vec::new(),
+            text: content.to_string(),
+        };
+    };
+    let mut variables: Vec<Variable> = Vec::new();
+    let mut kept = Vec::new();
+    let mut in_variables = false;
+    for line in frontmatter.lines() {
+        if in_variables && (line.starts_with([' ', '\t', '-']) || line.trim().is_empty()) {
+            let item = line.trim();
+            let (item, new) = match item.strip_prefix("- ") {
+                Some(item) => (item.trim(), true),
+                None => (item, false),
+            };
+            if new {
+                variables.push(Variable::default());
+            }
+            if let (Some(variable), Some((key, value))) =
+                (variables.last_mut(), item.split_once(':'))
+            {
+                let value = unquote(value.trim());
+                match key.trim() {
+                    "name" => variable.name = value,
+                    "description" => variable.description = Some(value),
+                    "default" => variable.default = Some(value),
+                    _ => {}
+                }
+            }
+            continue;
+        }
+        in_variables = line.trim_end() == "variables:";
+        if !in_variables {
+            kept.push(line);
+        }
+    }
+    variables.retain(|variable| !variable.name.is_empty());
+    let frontmatter = kept.join("\n");
+    let text = if frontmatter.trim().is_empty() {
+        body.trim_start_matches(['\n', '\r']).to_string()
+    } else {
+        notes::join_frontmatter(Some(&frontmatter), body)
+    };
+    Template {
+        name: name.to_string(),
+        variables,
+        text,
+    }
+}
+
+impl Template {
+    /// Fills in the template with `vars`, falling back to declared defaults.
+    ///
+    /// Placeholders for undeclared names other than `date` are left as they
+    /// are, so templates can contain literal braces.
+    pub fn render(
+        &self,
+        vars: &BTreeMap<String, String>,
+        date: &str,
+    ) -> Result<String, RenderError> {
+        let unknown = vars
+            .keys()
+            .filter(|name| {
+                !self
+                    .variables
+                    .iter()
+                    .any(|variable| &variable.name == *name)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            return Err(RenderError::Unknown(unknown));
+        }
+        let missing = self
+            .variables
+            .iter()
+            .filter(|variable| variable.default.is_none() && !vars.contains_key(&variable.name))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(RenderError::Missing(missing));
+        }
+        let rendered = PLACEHOLDER_REGEX.replace_all(&self.text, |captures: &Captures| {
+            let name = &captures[1];
+            let declared = self.variables.iter().find(|variable| variable.name == name);
+            match (vars.get(name), declared) {
+                (Some(value), _) => value.clone(),
+                (None, Some(variable)) => variable.default.clone().unwrap_or_default(),
+                (None, None) if name == "date" => date.to_string(),
+                (None, None) => captures[0].to_string(),
+            }
+        });
+        Ok(rendered.into_owned())
+    }
+}
+
+fn declared(template: &Template) -> String {
+    if template.variables.is_empty() {
+        return "no variables".to_string();
+    }
+    template
+        .variables
+        .iter()
+        .map(|variable| format!("`{}`", variable.name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn unquote(value: &str) -> String {
+    let quoted = |quote| value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote);
+    if quoted('"') || quoted('\'') {
+        value[1..value.len() - 1].to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{RenderError, parse};
+
+    const BUG_REPORT: &str = "---\ntitle: \"Bug: {{summary}}\"\nvariables:\n  \
+        - name: summary\n    description: One-line summary\n  \
+        - name: severity\n    default: medium\n---\n# {{summary}}\n\n\
+        Severity: {{ severity }}\nReported: {{date}}\n";
+
+    #[test]
+    fn parse_reads_variable_declarations() {
+        let template = parse("bug-report", BUG_REPORT);
+        let names = template
+            .variables
+            .iter()
+            .map(|variable| variable.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["summary", "severity"]);
+        assert_eq!(
+            template.variables[0].description.as_deref(),
+            Some("One-line summary")
+        );
+        assert_eq!(template.variables[1].default.as_deref(), Some("medium"));
+    }
+
+    #[test]
+    fn render_fills_values_and_defaults() {
+        let template = parse("bug-report", BUG_REPORT);
+        let vars = BTreeMap::from([("summary".to_string(), "Crash on save".to_string())]);
+        assert_eq!(
+            template.render(&vars, "2025-06-01").unwrap(),
+            "---\ntitle: \"Bug: Crash on save\"\n---\n# Crash on save\n\n\
+             Severity: medium\nReported: 2025-06-01\n"
+        );
+    }
+
+    #[test]
+    fn render_reports_missing_and_unknown_variables() {
+        let template = parse("bug-report", BUG_REPORT);
+        let error = template.render(&BTreeMap::new(), "2025-06-01").unwrap_err();
+        assert_eq!(
+            error.message(&template),
+            "template bug-report needs a value in `vars` for `summary` (One-line summary)"
+        );
+        let vars = BTreeMap::from([("sevrity".to_string(), "high".to_string())]);
+        let error = template.render(&vars, "2025-06-01").unwrap_err();
+        assert_eq!(error, RenderError::Unknown(vec!["sevrity".to_string()]));
+        assert_eq!(
+            error.message(&template),
+            "template bug-report has no variable `sevrity`; it declares `summary`, `severity`"
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Tables of contents for notes.
+//!
+//! `nb.toc` renders a note's headings as a nested Markdown list linking to
+//! each heading's anchor (GitHub style). With `insert`, the list is written
+//! between `<!-- toc -->` and `<!-- tocstop -->` markers in the note,
+//! replacing what was there; a note without markers gets them after its
+//! first top-level heading, or at the top of its body.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::nb::{NbClient, NbError};
+use crate::notes;
+use crate::outline::{self, Heading};
+
+/// Marker opening the table of contents.
+const START_MARKER: &str = "<!-- toc -->";
+
+/// Marker closing the table of contents.
+const END_MARKER: &str = "<!-- tocstop -->";
+
+/// Deepest heading level listed when the call does not choose one.
+pub const DEFAULT_MAX_LEVEL: usize = 3;
+
+/// Result of `nb.toc`.
+#[derive(Debug, Serialize)]
+pub struct Toc {
+    pub id: String,
+    /// The table of contents as a Markdown list.
+    pub toc: String,
+    /// True when the note was updated with the table of contents.
+    pub inserted: bool,
+}
+
+/// Builds the table of contents of note `id`, writing it into the note when
+/// `insert` is set.
+pub async fn toc(
+    nb: &NbClient,
+    id: &str,
+    max_level: usize,
+    insert: bool,
+    notebook: Option<&str>,
+) -> Result<Toc, NbError> {
+    let path = nb.show_path(id, notebook).await?;
+    let content = tokio::fs::read_to_string(&path).await?;
+    let toc = render(&outline::headings(&content), max_level);
+    let mut inserted = false;
+    if insert {
+        let updated = with_toc(&content, &toc);
+        if updated != content {
+            nb.edit(id, &updated, notebook).await?;
+            inserted = true;
+        }
+    }
+    Ok(Toc {
+        id: id.to_string(),
+        toc,
+        inserted,
+    })
+}
+
+/// Renders `headings` up to `max_level` as a nested list of anchor links,
+/// indented relative to the shallowest listed level.
+fn render(headings: &[Heading], max_level: usize) -> String {
+    let listed = headings
+        .iter()
+        .filter(|heading| heading.level <= max_level && !heading.text.is_empty())
+        .collect::<Vec<_>>();
+    let top = listed
+        .iter()
+        .map(|heading| heading.level)
+        .min()
+        .unwrap_or(1);
+    // Anchors count every heading, listed or not, as GitHub does.
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let anchors = headings
+        .iter()
+        .map(|heading| {
+            let slug = slug(&heading.text);
+            let count = seen.entry(slug.clone()).or_default();
+            let anchor = match *count {
+                0 => slug,
+                n => format!("{slug}-{n}"),
+            };
+            *count += 1;
+            (heading.offset, anchor)
+        })
+        .collect::<HashMap<_, _>>();
+    listed
+        .iter()
+        .map(|heading| {
+            format!(
+                "{}- [{}](#{})\n",
+                "  ".repeat(heading.level - top),
+                heading.text,
+                anchors[&heading.offset]
+            )
+        })
+        .collect()
+}
+
+/// Returns the GitHub-style anchor of a heading: lowercase, punctuation
+/// dropped, spaces as hyphens.
+fn slug(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+/// Places `toc` between the markers in `content`, adding the markers after
+/// the first top-level heading (or at the top of the body) if missing.
+fn with_toc(content: &str, toc: &str) -> String {
+    let block = format!("{START_MARKER}\n\n{toc}\n{END_MARKER}");
+    if let Some(start) = content.find(START_MARKER) {
+        let end = content[start..]
+            .find(END_MARKER)
+            .map_or(start + START_MARKER.len(), |end| {
+                start + end + END_MARKER.len()
+            });
+        return format!("{}{block}{}", &content[..start], &content[end..]);
+    }
+    let (frontmatter, body) = notes::split_frontmatter(content);
+    let title = outline::headings(body).into_iter().find(|heading| {
+        heading.level == 1 && heading.offset == body.len() - body.trim_start().len()
+    });
+    let body = match title {
+        Some(title) => {
+            let end = body[title.offset..]
+                .find('\n')
+                .map_or(body.len(), |newline| title.offset + newline + 1);
+            let rest = body[end..].trim_start_matches('\n');
+            format!("{}\n{block}\n\n{rest}", &body[..end])
+        }
+        None => format!("{block}\n\n{}", body.trim_start_matches('\n')),
+    };
+    notes::join_frontmatter(frontmatter, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, with_toc};
+    use crate::outline::headings;
+
+    const NOTE: &str = "# Plan\n\nIntro\n\n## Goals\n\n### Ship v2!\n\n## Goals\n\n#### Deep\n";
+
+    #[test]
+    fn render_nests_headings_with_unique_anchors() {
+        assert_eq!(
+            render(&headings(NOTE), 3),
+            "- [Plan](#plan)\n  - [Goals](#goals)\n    - [Ship v2!](#ship-v2)\n  \
+             - [Goals](#goals-1)\n"
+        );
+    }
+
+    #[test]
+    fn with_toc_adds_markers_after_title_then_replaces_between_them() {
+        let toc = "- [Goals](#goals)\n";
+        let inserted = with_toc("# Plan\n\nIntro\n\n## Goals\n", toc);
+        assert_eq!(
+            inserted,
+            "# Plan\n\n<!-- toc -->\n\n- [Goals](#goals)\n\n<!-- tocstop -->\n\nIntro\n\n## Goals\n"
+        );
+        let updated = with_toc(&inserted, "- [Aims](#aims)\n");
+        assert_eq!(
+            updated,
+            "# Plan\n\n<!-- toc -->\n\n- [Aims](#aims)\n\n<!-- tocstop -->\n\nIntro\n\n## Goals\n"
+        );
+        assert_eq!(with_toc(&updated, "- [Aims](#aims)\n"), updated);
+    }
+}
@@ -0,0 +1,307 @@
+//! todo.txt import and export.
+//!
+//! Bridges nb todos and the [todo.txt](https://github.com/todotxt/todo.txt)
+//! format used by many CLI task tools. Priorities, `+projects`, and
+//! `@contexts` become namespaced tags (`#priority/a`, `#project/garden`,
+//! `#context/phone`) so they survive a round trip, other `#tags` stay plain
+//! tags, and `due:YYYY-MM-DD` maps to nb's `## Due` section. Creation and
+//! completion dates are not kept.
+
+use std::path::Path;
+
+use chrono::Local;
+use serde::Serialize;
+
+use crate::archive;
+use crate::args::{TodotxtExportArgs, TodotxtImportArgs};
+use crate::batch::{self, BatchSummary, PendingNote};
+use crate::nb::{NbClient, NbError};
+use crate::notes::{self, NoteFile};
+
+const PRIORITY_TAG: &str = "priority/";
+const PROJECT_TAG: &str = "project/";
+const CONTEXT_TAG: &str = "context/";
+
+/// A task in either format.
+#[derive(Debug, Default, PartialEq)]
+struct Task {
+    done: bool,
+    description: String,
+    /// Tags without `#`, including the namespaced ones.
+    tags: Vec<String>,
+    due: Option<String>,
+}
+
+/// Result of `nb.todotxt_export` when the list is written to a file.
+#[derive(Debug, Serialize)]
+pub struct WrittenList {
+    pub path: String,
+    pub tasks: usize,
+}
+
+/// Creates an nb todo for each line of a todo.txt list, with one commit. The
+/// list is `content` if given, or else read from `path`.
+pub async fn import(
+    nb: &NbClient,
+    args: &TodotxtImportArgs,
+    path: Option<&Path>,
+) -> Result<BatchSummary, NbError> {
+    let text = match (&args.content, path) {
+        (Some(content), _) => content.clone(),
+        (None, Some(path)) => tokio::fs::read_to_string(path).await?,
+        (None, None) => String::new(),
+    };
+    let stamp = Local::now().format("%Y%m%d%H%M%S");
+    let pending = text
+        .lines()
+        .filter_map(parse_line)
+        .enumerate()
+        .map(|(index, task)| PendingNote {
+            path: format!("{stamp}-{}.todo.md", index + 1),
+            content: render_todo(&task).into_bytes(),
+            title: Some(task.description),
+        })
+        .collect::<Vec<_>>();
+    let message = format!("[nb] Import {} todos from todo.txt", pending.len());
+    batch::write_notes(
+        nb,
+        pending,
+        args.folder.as_deref(),
+        &message,
+        args.notebook.as_deref(),
+    )
+    .await
+}
+
+/// Renders the notebook's todos as a todo.txt list, writing it to `path` if
+/// given.
+pub async fn export(
+    nb: &NbClient,
+    args: &TodotxtExportArgs,
+    path: Option<&Path>,
+) -> Result<String, NbError> {
+    let root = nb.notebook_path(args.notebook.as_deref()).await?;
+    let folder = args.folder.clone();
+    let hide_archived = archive::hides(&root, folder.as_deref(), false);
+    let files = tokio::task::spawn_blocking(move || notes::read_notes(&root, folder.as_deref()))
+        .await
+        .map_err(|err| NbError::CommandFailed(format!("todo.txt export task failed: {err}")))??;
+    let lines = files
+        .iter()
+        .filter(|file| !(hide_archived && file.path.split('/').next() == Some(archive::FOLDER)))
+        .filter_map(parse_todo)
+        .filter(|task| args.include_done || !task.done)
+        .map(|task| render_line(&task))
+        .collect::<Vec<_>>();
+    let list = lines
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+    match path {
+        Some(path) => {
+            tokio::fs::write(path, &list).await?;
+            Ok(serde_json::to_string_pretty(&WrittenList {
+                path: path.display().to_string(),
+                tasks: lines.len(),
+            })
+            .unwrap_or_default())
+        }
+        None => Ok(list),
+    }
+}
+
+/// Parses one todo.txt line; blank lines yield `None`.
+fn parse_line(line: &str) -> Option<Task> {
+    let mut words = line.split_whitespace().peekable();
+    words.peek()?;
+    let mut task = Task::default();
+    if words.peek() == Some(&"x") {
+        task.done = true;
+        words.next();
+    }
+    if let Some(priority) = words.peek().and_then(|word| parse_priority(word)) {
+        task.tags.push(format!("{PRIORITY_TAG}{priority}"));
+        words.next();
+    }
+    // Completion and creation dates.
+    while words.peek().is_some_and(|word| is_date(word)) {
+        words.next();
+    }
+    let mut description = Vec::new();
+    for word in words {
+        let tag = if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+            Some(format!("{PROJECT_TAG}{project}"))
+        } else if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+            Some(format!("{CONTEXT_TAG}{context}"))
+        } else {
+            word.strip_prefix('#')
+                .filter(|t| !t.is_empty())
+                .map(String::from)
+        };
+        if let Some(tag) = tag {
+            if !task.tags.contains(&tag) {
+                task.tags.push(tag);
+            }
+        } else if let Some(due) = word.strip_prefix("due:").filter(|d| is_date(d)) {
+            task.due = Some(due.to_string());
+        } else if let Some(priority) = word.strip_prefix("pri:").and_then(parse_priority) {
+            task.tags.push(format!("{PRIORITY_TAG}{priority}"));
+        } else {
+            description.push(word);
+        }
+    }
+    task.description = description.join(" ");
+    Some(task)
+}
+
+/// Returns the lowercase letter of a `(A)` priority or `pri:A` value.
+fn parse_priority(word: &str) -> Option<char> {
+    let letter = word
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+        .unwrap_or(word);
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_uppercase() => Some(c.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+fn is_date(word: &str) -> bool {
+    chrono::NaiveDate::parse_from_str(word, "%Y-%m-%d").is_ok()
+}
+
+/// Reads a task from an nb todo file.
+fn parse_todo(file: &NoteFile) -> Option<Task> {
+    if !file.path.ends_with(".todo.md") {
+        return None;
+    }
+    let mut lines = file.content.lines().map(str::trim);
+    let heading = lines.by_ref().find(|line| !line.is_empty())?;
+    let (done, description) = if let Some(rest) = heading.strip_prefix("# [ ]") {
+        (false, rest)
+    } else {
+        (true, heading.strip_prefix("# [x]")?)
+    };
+    let mut task = Task {
+        done,
+        description: description.trim().to_string(),
+        tags: notes::extract_tags(&file.content),
+        due: None,
+    };
+    if lines
+        .by_ref()
+        .any(|line| line.eq_ignore_ascii_case("## Due"))
+    {
+        task.due = lines
+            .find(|line| !line.is_empty())
+            .and_then(|line| line.get(..10))
+            .filter(|date| is_date(date))
+            .map(String::from);
+    }
+    Some(task)
+}
+
+/// Renders a task as an nb todo file.
+fn render_todo(task: &Task) -> String {
+    let mark = if task.done { 'x' } else { ' ' };
+    let mut todo = format!("# [{mark}] {}\n", task.description);
+    if let Some(due) = &task.due {
+        todo.push_str(&format!("\n## Due\n\n{due}\n"));
+    }
+    if !task.tags.is_empty() {
+        let tags = task
+            .tags
+            .iter()
+            .map(|tag| format!("#{tag}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        todo.push_str(&format!("\n## Tags\n\n{tags}\n"));
+    }
+    todo
+}
+
+/// Renders a task as a todo.txt line.
+fn render_line(task: &Task) -> String {
+    let priority = task
+        .tags
+        .iter()
+        .find_map(|tag| tag.strip_prefix(PRIORITY_TAG).and_then(parse_priority_tag));
+    let mut words = Vec::new();
+    match (task.done, priority) {
+        (true, _) => words.push("x".to_string()),
+        (false, Some(priority)) => words.push(format!("({priority})")),
+        (false, None) => {}
+    }
+    words.push(task.description.clone());
+    for tag in &task.tags {
+        if let Some(project) = tag.strip_prefix(PROJECT_TAG) {
+            words.push(format!("+{project}"));
+        } else if let Some(context) = tag.strip_prefix(CONTEXT_TAG) {
+            words.push(format!("@{context}"));
+        } else if !tag.starts_with(PRIORITY_TAG) {
+            words.push(format!("#{tag}"));
+        }
+    }
+    if let Some(due) = &task.due {
+        words.push(format!("due:{due}"));
+    }
+    if let (true, Some(priority)) = (task.done, priority) {
+        words.push(format!("pri:{priority}"));
+    }
+    words.join(" ")
+}
+
+fn parse_priority_tag(letter: &str) -> Option<char> {
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => Some(c.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Task, parse_line, parse_todo, render_line, render_todo};
+    use crate::notes::NoteFile;
+
+    #[test]
+    fn parse_line_maps_priorities_projects_and_contexts_to_tags() {
+        let task = parse_line("(A) 2024-04-01 Call Mom +Family @phone due:2024-05-01").unwrap();
+        assert_eq!(
+            task,
+            Task {
+                done: false,
+                description: "Call Mom".to_string(),
+                tags: vec![
+                    "priority/a".to_string(),
+                    "project/Family".to_string(),
+                    "context/phone".to_string(),
+                ],
+                due: Some("2024-05-01".to_string()),
+            }
+        );
+        assert!(parse_line("x 2024-04-02 2024-04-01 Pay rent").unwrap().done);
+        assert!(parse_line("   ").is_none());
+    }
+
+    #[test]
+    fn todo_files_round_trip_through_todo_txt() {
+        let line = "(B) Water plants +garden @home #weekly due:2024-05-01";
+        let task = parse_line(line).unwrap();
+        let file = NoteFile {
+            path: "1.todo.md".to_string(),
+            content: render_todo(&task),
+        };
+        assert_eq!(
+            file.content,
+            "# [ ] Water plants\n\n## Due\n\n2024-05-01\n\n## Tags\n\n\
+             #priority/b #project/garden #context/home #weekly\n"
+        );
+        assert_eq!(render_line(&parse_todo(&file).unwrap()), line);
+
+        let done = parse_line("x (C) Sweep").unwrap();
+        assert_eq!(render_line(&done), "x Sweep pri:C");
+        assert_eq!(parse_line(&render_line(&done)).unwrap(), done);
+    }
+}
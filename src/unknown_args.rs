@@ -0,0 +1,115 @@
+//! Rejection of arguments a command does not define.
+//!
+//! serde ignores unknown fields, so a typo such as `foldr` used to be
+//! dropped silently and the note landed in the default folder. Arguments are
+//! checked against the command's schema before parsing, and each unknown one
+//! is reported with the closest valid name.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// An argument the command does not define.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct UnknownArg {
+    pub field: String,
+    /// Closest valid argument name, if one is near enough to be a typo.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+/// Returns the arguments in `args` that `schema` does not declare.
+///
+/// Schemas without declared properties (e.g., free-form maps) accept any
+/// argument.
+pub fn find(args: &serde_json::Map<String, Value>, schema: &Value) -> Vec<UnknownArg> {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    if schema.get("additionalProperties") == Some(&Value::Bool(true)) {
+        return Vec::new();
+    }
+    args.keys()
+        .filter(|field| !properties.contains_key(*field))
+        .map(|field| UnknownArg {
+            field: field.clone(),
+            suggestion: closest(field, properties.keys()),
+        })
+        .collect()
+}
+
+/// Formats `unknown` as a one-line error message.
+pub fn message(unknown: &[UnknownArg]) -> String {
+    let fields = unknown
+        .iter()
+        .map(|arg| match &arg.suggestion {
+            Some(suggestion) => format!("`{}` (did you mean `{suggestion}`?)", arg.field),
+            None => format!("`{}`", arg.field),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("unknown argument {fields}")
+}
+
+/// Returns the candidate nearest to `field`, if it is within a typo's reach.
+fn closest<'a>(field: &str, candidates: impl Iterator<Item = &'a String>) -> Option<String> {
+    let field = field.to_lowercase();
+    let reach = (field.chars().count() / 3).max(1);
+    candidates
+        .map(|candidate| (distance(&field, candidate), candidate))
+        .filter(|(distance, _)| *distance <= reach)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.clone())
+}
+
+/// Levenshtein distance between `a` and `b`, in characters.
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{UnknownArg, find, message};
+
+    #[test]
+    fn suggests_the_closest_argument() {
+        let schema = json!({"properties": {"folder": {}, "tags": {}, "title": {}}});
+        let args = json!({"foldr": "x", "tags": [], "colour": "red"});
+        let unknown = find(args.as_object().unwrap(), &schema);
+        assert_eq!(
+            unknown,
+            vec![
+                UnknownArg {
+                    field: "colour".to_string(),
+                    suggestion: None,
+                },
+                UnknownArg {
+                    field: "foldr".to_string(),
+                    suggestion: Some("folder".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            message(&unknown),
+            "unknown argument `colour`, `foldr` (did you mean `folder`?)"
+        );
+    }
+
+    #[test]
+    fn accepts_any_argument_without_declared_properties() {
+        let args = json!({"anything": 1});
+        let schema = json!({"type": "object", "additionalProperties": true});
+        assert!(find(args.as_object().unwrap(), &schema).is_empty());
+    }
+}
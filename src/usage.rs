@@ -0,0 +1,187 @@
+//! Per-command usage statistics for the `server_usage` tool.
+//!
+//! Counts calls and failures per subcommand so users can see what their
+//! agents actually do with the notebook. Counts are kept in memory; with
+//! `--persist-usage` they are also written to the state directory after each
+//! call and reloaded at startup, so they accumulate across sessions.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Usage counts since `since`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Usage {
+    /// When counting started (RFC 3339).
+    pub since: String,
+    pub total: u64,
+    pub failed: u64,
+    pub by_command: BTreeMap<String, CommandUsage>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CommandUsage {
+    pub calls: u64,
+    pub failed: u64,
+    /// When the command was last called (RFC 3339).
+    pub last_used: String,
+}
+
+/// Report returned by the `server_usage` tool.
+#[derive(Debug, Serialize)]
+pub struct UsageReport {
+    /// File the counts are persisted to, if persistence is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub persisted_to: Option<String>,
+    pub since: String,
+    pub total: u64,
+    pub failed: u64,
+    /// Commands by number of calls, most used first.
+    pub commands: Vec<CommandReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommandReport {
+    pub command: String,
+    pub calls: u64,
+    pub failed: u64,
+    /// Fraction of calls that failed.
+    pub error_rate: f64,
+    pub last_used: String,
+}
+
+/// Usage counts, optionally backed by a file.
+#[derive(Debug)]
+pub struct UsageTracker {
+    usage: Mutex<Usage>,
+    path: Option<PathBuf>,
+}
+
+impl Default for UsageTracker {
+    fn default() -> Self {
+        Self {
+            usage: Mutex::new(Usage {
+                since: Local::now().to_rfc3339(),
+                ..Usage::default()
+            }),
+            path: None,
+        }
+    }
+}
+
+impl UsageTracker {
+    /// Returns a tracker persisted to `path`, starting from its counts.
+    ///
+    /// A missing or unreadable file starts fresh counts.
+    pub fn persisted(path: PathBuf) -> Self {
+        let usage = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|err| {
+                warn!(path = %path.display(), error = %err, "ignoring unreadable usage file");
+                None
+            }),
+            Err(_) => None,
+        };
+        let tracker = Self::default();
+        if let Some(usage) = usage {
+            *tracker.usage.lock().expect("usage lock poisoned") = usage;
+        }
+        Self {
+            path: Some(path),
+            ..tracker
+        }
+    }
+
+    /// Records one call of `command`.
+    pub fn record(&self, command: &str, failed: bool) {
+        let mut usage = self.usage.lock().expect("usage lock poisoned");
+        usage.total += 1;
+        let entry = usage.by_command.entry(command.to_string()).or_default();
+        entry.calls += 1;
+        entry.last_used = Local::now().to_rfc3339();
+        if failed {
+            entry.failed += 1;
+            usage.failed += 1;
+        }
+    }
+
+    /// Writes the counts to the usage file, if persistence is enabled.
+    pub async fn persist(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let text = serde_json::to_string_pretty(&*self.usage.lock().expect("usage lock poisoned"))
+            .unwrap_or_default();
+        if let Err(err) = write(path, text).await {
+            warn!(path = %path.display(), error = %err, "failed to persist usage counts");
+        }
+    }
+
+    pub fn report(&self) -> UsageReport {
+        let usage = self.usage.lock().expect("usage lock poisoned").clone();
+        let mut commands = usage
+            .by_command
+            .into_iter()
+            .map(|(command, counts)| CommandReport {
+                command,
+                calls: counts.calls,
+                failed: counts.failed,
+                error_rate: (counts.failed as f64 / counts.calls.max(1) as f64 * 1000.0).round()
+                    / 1000.0,
+                last_used: counts.last_used,
+            })
+            .collect::<Vec<_>>();
+        commands.sort_by(|a, b| {
+            b.calls
+                .cmp(&a.calls)
+                .then_with(|| a.command.cmp(&b.command))
+        });
+        UsageReport {
+            persisted_to: self.path.as_ref().map(|path| path.display().to_string()),
+            since: usage.since,
+            total: usage.total,
+            failed: usage.failed,
+            commands,
+        }
+    }
+}
+
+/// Replaces the file at `path` with `text`, creating its directory.
+async fn write(path: &Path, text: String) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let partial = path.with_extension("json.partial");
+    tokio::fs::write(&partial, text).await?;
+    tokio::fs::rename(&partial, path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UsageTracker;
+
+    #[tokio::test]
+    async fn persisted_counts_accumulate_across_trackers() {
+        let path = std::env::temp_dir().join(format!("nb-mcp-usage-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let tracker = UsageTracker::persisted(path.clone());
+        tracker.record("add", false);
+        tracker.record("show", true);
+        tracker.record("show", false);
+        tracker.persist().await;
+
+        let reloaded = UsageTracker::persisted(path.clone());
+        reloaded.record("show", false);
+        let report = reloaded.report();
+        assert_eq!(report.total, 4);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.commands[0].command, "show");
+        assert_eq!(report.commands[0].calls, 3);
+        assert_eq!(report.commands[0].error_rate, 0.333);
+        assert_eq!(report.commands[1].command, "add");
+        std::fs::remove_file(path).unwrap();
+    }
+}
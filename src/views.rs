@@ -0,0 +1,79 @@
+//! Saved searches ("views").
+//!
+//! A view is a named list or search query. Views come from two places: the
+//! `[views]` table of `.nb-mcp.toml`, and `.nb-mcp-views.toml` at the root of
+//! the notebook, which `nb.save_view` writes and commits. Notebook views
+//! override project views of the same name, so a saved view is never
+//! shadowed.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::args::SavedView;
+use crate::nb::{NbClient, NbError};
+
+/// Name of the views file at the notebook root.
+pub const VIEWS_FILE: &str = ".nb-mcp-views.toml";
+
+/// Contents of the notebook views file.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ViewsFile {
+    #[serde(default)]
+    views: BTreeMap<String, SavedView>,
+}
+
+/// Returns all views available in a notebook.
+pub async fn load(
+    nb: &NbClient,
+    configured: &BTreeMap<String, SavedView>,
+    notebook: Option<&str>,
+) -> Result<BTreeMap<String, SavedView>, NbError> {
+    let mut views = configured.clone();
+    views.extend(read_file(nb, notebook).await?.views);
+    Ok(views)
+}
+
+/// Stores a view in the notebook views file and commits it.
+pub async fn save(
+    nb: &NbClient,
+    name: &str,
+    view: SavedView,
+    notebook: Option<&str>,
+) -> Result<String, NbError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(NbError::CommandFailed(
+            "view name must be non-empty".to_string(),
+        ));
+    }
+    let mut views = read_file(nb, notebook).await?.views;
+    let replaced = views.insert(name.to_string(), view).is_some();
+    let text = encode(views)?;
+    let path = nb.notebook_path(notebook).await?.join(VIEWS_FILE);
+    tokio::fs::write(&path, text).await?;
+    nb.checkpoint(&format!("[nb-mcp] Save view: {name}"), notebook)
+        .await?;
+    Ok(format!(
+        "{} view: {}",
+        if replaced { "Updated" } else { "Saved" },
+        name
+    ))
+}
+
+/// Encodes views in the format of the notebook views file.
+pub fn encode(views: BTreeMap<String, SavedView>) -> Result<String, NbError> {
+    toml::to_string_pretty(&ViewsFile { views })
+        .map_err(|err| NbError::CommandFailed(format!("failed to encode views: {err}")))
+}
+
+async fn read_file(nb: &NbClient, notebook: Option<&str>) -> Result<ViewsFile, NbError> {
+    let path = nb.notebook_path(notebook).await?.join(VIEWS_FILE);
+    let text = match tokio::fs::read_to_string(&path).await {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(ViewsFile::default()),
+        Err(err) => return Err(err.into()),
+    };
+    toml::from_str(&text)
+        .map_err(|err| NbError::CommandFailed(format!("invalid {}: {err}", path.display())))
+}